@@ -5,7 +5,11 @@ use quote::quote;
 use std::collections::HashMap;
 use std::sync::LazyLock;
 use std::sync::Mutex;
-use syn::{ImplItem, ItemFn, ItemImpl, ItemStruct, parse_macro_input, parse_quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    Expr, ExprLit, ImplItem, ItemFn, ItemImpl, ItemStruct, Lit, LitStr, MetaNameValue, Token,
+    parse_macro_input, parse_quote,
+};
 
 static PLUGIN_METHODS: LazyLock<Mutex<HashMap<String, String>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
@@ -44,9 +48,81 @@ pub fn plugin_method(_attr: TokenStream, item: TokenStream) -> TokenStream {
     TokenStream::new()
 }
 
+/// The dependency fields of the plugin metadata, given as arguments of `plugin_impl`
+#[derive(Default)]
+struct PluginDependencies {
+    depends: Vec<LitStr>,
+    soft_depends: Vec<LitStr>,
+    load_before: Vec<LitStr>,
+    api_version: Option<LitStr>,
+}
+
+fn parse_names(value: &Expr) -> Vec<LitStr> {
+    let Expr::Array(array) = value else {
+        abort!(value, "expected a list of plugin names like `[\"name\"]`");
+    };
+    array.elems.iter().map(parse_string).collect()
+}
+
+fn parse_string(value: &Expr) -> LitStr {
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(string),
+            ..
+        }) => string.clone(),
+        _ => abort!(value, "expected a string literal"),
+    }
+}
+
+fn parse_dependencies(args: Punctuated<MetaNameValue, Token![,]>) -> PluginDependencies {
+    let mut dependencies = PluginDependencies::default();
+    for arg in args {
+        let Some(key) = arg.path.get_ident() else {
+            abort!(arg.path, "expected a metadata field");
+        };
+        match key.to_string().as_str() {
+            "depends" => dependencies.depends = parse_names(&arg.value),
+            "soft_depends" => dependencies.soft_depends = parse_names(&arg.value),
+            "load_before" => dependencies.load_before = parse_names(&arg.value),
+            "api_version" => dependencies.api_version = Some(parse_string(&arg.value)),
+            other => abort!(
+                key,
+                format!(
+                    "unknown metadata field `{other}`, expected `depends`, `soft_depends`, `load_before` or `api_version`"
+                )
+            ),
+        }
+    }
+    dependencies
+}
+
+/// Turns a struct into the plugin of the crate.
+///
+/// The metadata is taken from the crate manifest. The plugins it is loaded with are given as
+/// arguments, all of them optional:
+///
+/// ```ignore
+/// #[plugin_impl(
+///     depends = ["economy"],
+///     soft_depends = ["permissions"],
+///     load_before = ["shop"],
+///     api_version = "^0.1",
+/// )]
+/// pub struct MyPlugin;
+/// ```
 #[proc_macro_error]
 #[proc_macro_attribute]
-pub fn plugin_impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn plugin_impl(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args =
+        parse_macro_input!(attr with Punctuated::<MetaNameValue, Token![,]>::parse_terminated);
+    let PluginDependencies {
+        depends,
+        soft_depends,
+        load_before,
+        api_version,
+    } = parse_dependencies(args);
+    let api_version = api_version.map_or_else(|| quote! { "" }, |version| quote! { #version });
+
     // Parse the input struct
     let input_struct = parse_macro_input!(item as ItemStruct);
     let struct_ident = &input_struct.ident;
@@ -78,6 +154,10 @@ pub fn plugin_impl(_attr: TokenStream, item: TokenStream) -> TokenStream {
             version: env!("CARGO_PKG_VERSION"),
             authors: env!("CARGO_PKG_AUTHORS"),
             description: env!("CARGO_PKG_DESCRIPTION"),
+            depends: &[#(#depends),*],
+            soft_depends: &[#(#soft_depends),*],
+            load_before: &[#(#load_before),*],
+            api_version: #api_version,
         };
 
         #input_struct
@@ -39,7 +39,7 @@ impl Default for ChunkCompression {
     }
 }
 
-#[derive(Deserialize, Serialize, Clone, Copy)]
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Compression {
     /// GZip Compression
     GZip,
@@ -15,6 +15,7 @@ pub mod resource_pack;
 
 pub use chat::ChatConfig;
 pub use commands::CommandsConfig;
+pub use movement::MovementConfig;
 pub use networking::auth::AuthenticationConfig;
 pub use networking::compression::CompressionConfig;
 pub use networking::lan_broadcast::LANBroadcastConfig;
@@ -26,8 +27,10 @@ mod commands;
 
 mod chat;
 pub mod chunk;
+mod movement;
 pub mod op;
 mod player_data;
+pub mod plugins;
 mod pvp;
 mod server_links;
 pub mod whitelist;
@@ -35,6 +38,7 @@ pub mod world;
 
 use networking::NetworkingConfig;
 use player_data::PlayerDataConfig;
+use plugins::PluginsConfig;
 use resource_pack::ResourcePackConfig;
 use world::LevelConfig;
 
@@ -53,9 +57,11 @@ pub struct AdvancedConfiguration {
     pub commands: CommandsConfig,
     pub chat: ChatConfig,
     pub pvp: PVPConfig,
+    pub movement: MovementConfig,
     pub server_links: ServerLinksConfig,
     pub player_data: PlayerDataConfig,
     pub fun: FunConfig,
+    pub plugins: PluginsConfig,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// Server-side checks of player movement and interactions. The defaults match vanilla.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct MovementConfig {
+    /// Whether player movement and interaction reach is validated.
+    pub enabled: bool,
+    /// How far (squared, in blocks) a player may move per movement packet beyond their velocity
+    /// before being corrected with "moved too quickly".
+    pub max_speed_squared: f64,
+    /// The same as `max_speed_squared`, but while gliding with an elytra.
+    pub max_elytra_speed_squared: f64,
+    /// How far (squared, in blocks) the client may end up from where collisions allow before being
+    /// corrected with "moved wrongly".
+    pub max_error_squared: f64,
+    /// Whether players are teleported back when they move into blocks.
+    pub prevent_no_clip: bool,
+    /// Whether players floating in the air without being able to fly are kicked.
+    pub kick_for_flying: bool,
+    /// For how many ticks a player may float before being kicked.
+    pub max_floating_ticks: u32,
+    /// How much further than their block interaction range players may reach blocks.
+    pub block_reach_buffer: f64,
+    /// How much further than their entity interaction range players may reach entities.
+    pub entity_reach_buffer: f64,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_speed_squared: 100.0,
+            max_elytra_speed_squared: 300.0,
+            max_error_squared: 0.0625,
+            prevent_no_clip: true,
+            kick_for_flying: true,
+            max_floating_ticks: 80,
+            block_reach_buffer: 1.0,
+            entity_reach_buffer: 3.0,
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Whether server metrics are served in the OpenMetrics format for Prometheus to scrape.
+    pub enabled: bool,
+    /// The network address and port of the HTTP listener, metrics are served under `/metrics`.
+    pub address: SocketAddr,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 9225),
+        }
+    }
+}
@@ -1,4 +1,7 @@
 use auth::AuthenticationConfig;
+use metrics::MetricsConfig;
+use protection::ProtectionConfig;
+use protocol::ProtocolConfig;
 use proxy::ProxyConfig;
 use query::QueryConfig;
 use rcon::RCONConfig;
@@ -9,6 +12,9 @@ use crate::{CompressionConfig, LANBroadcastConfig};
 pub mod auth;
 pub mod compression;
 pub mod lan_broadcast;
+pub mod metrics;
+pub mod protection;
+pub mod protocol;
 pub mod proxy;
 pub mod query;
 pub mod rcon;
@@ -21,4 +27,7 @@ pub struct NetworkingConfig {
     pub proxy: ProxyConfig,
     pub packet_compression: CompressionConfig,
     pub lan_broadcast: LANBroadcastConfig,
+    pub protocol: ProtocolConfig,
+    pub protection: ProtectionConfig,
+    pub metrics: MetricsConfig,
 }
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+/// Limits that keep a single misbehaving client from overloading the server.
+/// Setting a limit to `0` disables it.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ProtectionConfig {
+    /// Whether connections and packets are limited at all.
+    pub enabled: bool,
+    /// How many new connections a single IP may open within `connection_window`.
+    pub connections_per_ip: u32,
+    /// The window for `connections_per_ip` and `login_attempts_per_ip`, in seconds.
+    pub connection_window: u64,
+    /// How many connections a single IP may have open at the same time.
+    pub max_connections_per_ip: u32,
+    /// How many logins a single IP may start within `connection_window`.
+    pub login_attempts_per_ip: u32,
+    /// How many unconnected pings (Bedrock server list) a single IP may send per second.
+    pub pings_per_second: u32,
+    /// How many packets a connection may send per second on average.
+    pub packets_per_second: u32,
+    /// How many bytes a connection may send per second on average, after decompression.
+    pub bytes_per_second: u64,
+    /// For how many seconds a connection may go over its packet and byte budget before being
+    /// kicked.
+    pub kick_threshold: u32,
+    /// The largest packet a client may send, after decompression.
+    pub max_packet_size: usize,
+}
+
+impl Default for ProtectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            connections_per_ip: 10,
+            connection_window: 10,
+            max_connections_per_ip: 5,
+            login_attempts_per_ip: 5,
+            pings_per_second: 5,
+            packets_per_second: 500,
+            bytes_per_second: 4 * 1024 * 1024,
+            kick_threshold: 5,
+            max_packet_size: 2 * 1024 * 1024,
+        }
+    }
+}
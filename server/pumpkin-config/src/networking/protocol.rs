@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProtocolConfig {
+    /// The oldest protocol version clients may join with
+    pub min_version: u32,
+    /// The newest protocol version clients may join with
+    pub max_version: u32,
+    /// Folder in the config folder holding a `<protocol>.json` mappings file for each version
+    /// other than the server's own, generated with pumpkin-protocol's `generate_mappings` example
+    pub mappings_folder: String,
+}
+
+impl Default for ProtocolConfig {
+    fn default() -> Self {
+        Self {
+            // 1.21 and 1.21.1
+            min_version: 767,
+            max_version: u32::MAX,
+            mappings_folder: "protocol".to_string(),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::net::{Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
 use serde::{Deserialize, Serialize};
 
@@ -7,6 +7,9 @@ use serde::{Deserialize, Serialize};
 pub struct QueryConfig {
     pub enabled: bool,
     pub address: SocketAddr,
+    /// The IP players connect to, as reported to query clients. Defaults to the address the
+    /// server is bound to, set this when it is bound to all interfaces
+    pub host_ip: Option<IpAddr>,
 }
 
 impl Default for QueryConfig {
@@ -14,6 +17,7 @@ impl Default for QueryConfig {
         Self {
             enabled: true,
             address: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 25565),
+            host_ip: None,
         }
     }
 }
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct PluginsConfig {
+    pub wasm: WasmPluginConfig,
+}
+
+/// Limits for every WebAssembly plugin, each one gets its own
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct WasmPluginConfig {
+    /// Whether `.wasm` plugins are loaded.
+    pub enabled: bool,
+    /// How much fuel a single call into a plugin may use, roughly one per instruction. A plugin
+    /// that runs out is disabled.
+    pub fuel_per_call: u64,
+    /// How much memory a plugin may use in MiB.
+    pub max_memory_mib: u32,
+}
+
+impl Default for WasmPluginConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            fuel_per_call: 100_000_000,
+            max_memory_mib: 64,
+        }
+    }
+}
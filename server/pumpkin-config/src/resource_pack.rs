@@ -1,10 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr};
 
-#[derive(Deserialize, Serialize, Default)]
+#[derive(Deserialize, Serialize)]
 #[serde(default)]
 pub struct ResourcePackConfig {
     pub enabled: bool,
-    /// The path to the resource pack.
+    /// The path to an externally hosted resource pack; leave blank for none.
     pub url: String,
     /// The SHA1 hash (40) of the resource pack.
     pub sha1: String,
@@ -12,10 +13,30 @@ pub struct ResourcePackConfig {
     pub prompt_message: String,
     /// Force players to accept the resource pack.
     pub force: bool,
+    /// Folder with packs to send to every player: `.zip` files for Java, `.mcpack` files for
+    /// Bedrock.
+    pub folder: String,
+    /// The built-in HTTP server Java clients download the packs in `folder` from.
+    pub host: ResourcePackHostConfig,
+}
+
+impl Default for ResourcePackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "".to_string(),
+            sha1: "".to_string(),
+            prompt_message: "".to_string(),
+            force: false,
+            folder: "resource_packs".to_string(),
+            host: Default::default(),
+        }
+    }
 }
 
 impl ResourcePackConfig {
     pub fn validate(&self) {
+        self.host.validate();
         if !self.enabled {
             return;
         }
@@ -26,10 +47,58 @@ impl ResourcePackConfig {
             "Resource pack path or SHA1 hash is missing"
         );
 
-        let hash_len = self.sha1.len();
+        if !self.sha1.is_empty() {
+            let hash_len = self.sha1.len();
+            assert!(
+                hash_len == 40,
+                "Resource pack SHA1 hash is the wrong length (should be 40, is {hash_len})"
+            )
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ResourcePackHostConfig {
+    /// Whether the packs in the resource pack folder are hosted for Java clients.
+    pub enabled: bool,
+    /// The network address and port the HTTP server listens on.
+    pub address: SocketAddr,
+    /// The URL clients download packs from, e.g. `http://example.com:8080`. May only be left
+    /// blank if `address` is one clients can reach, not `0.0.0.0` or `::`.
+    pub public_url: String,
+}
+
+impl Default for ResourcePackHostConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 8080),
+            public_url: "".to_string(),
+        }
+    }
+}
+
+impl ResourcePackHostConfig {
+    pub fn validate(&self) {
+        if !self.enabled {
+            return;
+        }
+
         assert!(
-            hash_len == 40,
-            "Resource pack SHA1 hash is the wrong length (should be 40, is {hash_len})"
-        )
+            !self.public_url.is_empty() || !self.address.ip().is_unspecified(),
+            "Resource pack hosting binds to {}, which clients can't download from; set public_url to the address clients reach the server at",
+            self.address
+        );
+    }
+
+    /// The URL a hosted file with this name can be downloaded from
+    #[must_use]
+    pub fn url_for(&self, file_name: &str) -> String {
+        if self.public_url.is_empty() {
+            format!("http://{}/{file_name}", self.address)
+        } else {
+            format!("{}/{file_name}", self.public_url.trim_end_matches('/'))
+        }
     }
 }
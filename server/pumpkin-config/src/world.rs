@@ -5,5 +5,28 @@ use crate::chunk::ChunkConfig;
 #[derive(Deserialize, Serialize, Default)]
 pub struct LevelConfig {
     pub chunk: ChunkConfig,
+    #[serde(default)]
+    pub pregen: PregenConfig,
     // TODO: More options
 }
+
+#[derive(Deserialize, Serialize)]
+#[serde(default)]
+pub struct PregenConfig {
+    /// The most chunks that are generated at once while the server has spare tick time
+    pub max_concurrent_chunks: usize,
+    /// Time interval in seconds to save the progress of a pre-generation
+    pub save_interval: u64,
+    /// The largest radius in blocks a pre-generation may cover, larger radii are clamped to it
+    pub max_radius: u32,
+}
+
+impl Default for PregenConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_chunks: 8,
+            save_interval: 30,
+            max_radius: 10_000,
+        }
+    }
+}
@@ -37,6 +37,7 @@ pub(crate) fn build() -> TokenStream {
 
 pub(crate) fn parse_packets(packets: BTreeMap<String, Vec<String>>) -> proc_macro2::TokenStream {
     let mut consts = TokenStream::new();
+    let mut all = Vec::new();
 
     for packet in packets {
         let phase = packet.0;
@@ -45,11 +46,17 @@ pub(crate) fn parse_packets(packets: BTreeMap<String, Vec<String>>) -> proc_macr
             let packet_id = id as i32;
             let packet_name = packet_name.replace("/", "_");
             let name = format!("{phase}_{packet_name}").to_uppercase();
-            let name = format_ident!("{}", name);
+            let ident = format_ident!("{}", name);
             consts.extend([quote! {
-                pub const #name: i32 = #packet_id;
+                pub const #ident: i32 = #packet_id;
             }]);
+            all.push(quote! { (#name, #ident) });
         }
     }
+    consts.extend([quote! {
+        /// The name of every packet constant above together with its id, used to match packets
+        /// by name against the packet reports of other versions
+        pub const ALL: &[(&str, i32)] = &[#(#all),*];
+    }]);
     consts
 }
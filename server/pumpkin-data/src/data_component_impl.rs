@@ -43,6 +43,7 @@ pub fn read_data(id: DataComponent, data: &NbtTag) -> Option<Box<dyn DataCompone
         MaxStackSize => Some(MaxStackSizeImpl::read_data(data)?.to_dyn()),
         Enchantments => Some(EnchantmentsImpl::read_data(data)?.to_dyn()),
         Damage => Some(DamageImpl::read_data(data)?.to_dyn()),
+        CustomData => Some(CustomDataImpl::read_data(data)?.to_dyn()),
         _ => todo!(),
     }
 }
@@ -98,9 +99,24 @@ pub fn get<T: DataComponentImpl + 'static>(value: &dyn DataComponentImpl) -> &T
 pub fn get_mut<T: DataComponentImpl + 'static>(value: &mut dyn DataComponentImpl) -> &mut T {
     value.as_mut_any().downcast_mut::<T>().unwrap()
 }
-#[derive(Clone, Debug, Hash, PartialEq)]
-pub struct CustomDataImpl;
+/// Arbitrary data the game itself ignores, e.g. the data plugins attach to items
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomDataImpl {
+    pub data: NbtCompound,
+}
+impl CustomDataImpl {
+    fn read_data(data: &NbtTag) -> Option<Self> {
+        data.extract_compound()
+            .map(|data| Self { data: data.clone() })
+    }
+}
 impl DataComponentImpl for CustomDataImpl {
+    fn write_data(&self) -> NbtTag {
+        NbtTag::Compound(self.data.clone())
+    }
+    fn get_hash(&self) -> i32 {
+        get_nbt_hash(&NbtTag::Compound(self.data.clone())) as i32
+    }
     default_impl!(CustomData);
 }
 
@@ -192,10 +208,78 @@ impl EnchantmentsImpl {
 fn get_str_hash(val: &str) -> u32 {
     let mut digest = Digest::new(Crc32Iscsi);
     digest.update(&[12u8]);
-    digest.update(&(val.len() as u32).to_le_bytes());
-    let byte = val.as_bytes();
-    for i in byte {
-        digest.update(&[*i, 0u8]);
+    // Java strings are hashed as their UTF-16 code units
+    digest.update(&(val.encode_utf16().count() as u32).to_le_bytes());
+    for unit in val.encode_utf16() {
+        digest.update(&unit.to_le_bytes());
+    }
+    digest.finalize() as u32
+}
+
+/// Hashes a tag the way vanilla's `HashOps` does, compound entries are sorted by their hashes
+fn get_nbt_hash(tag: &NbtTag) -> u32 {
+    let mut digest = Digest::new(Crc32Iscsi);
+    match tag {
+        NbtTag::End => digest.update(&[1u8]),
+        NbtTag::Byte(value) => digest.update(&[6u8, *value as u8]),
+        NbtTag::Short(value) => {
+            digest.update(&[7u8]);
+            digest.update(&value.to_le_bytes());
+        }
+        NbtTag::Int(value) => return get_i32_hash(*value),
+        NbtTag::Long(value) => {
+            digest.update(&[9u8]);
+            digest.update(&value.to_le_bytes());
+        }
+        NbtTag::Float(value) => {
+            digest.update(&[10u8]);
+            digest.update(&value.to_bits().to_le_bytes());
+        }
+        NbtTag::Double(value) => {
+            digest.update(&[11u8]);
+            digest.update(&value.to_bits().to_le_bytes());
+        }
+        NbtTag::String(value) => return get_str_hash(value),
+        NbtTag::ByteArray(values) => {
+            digest.update(&[14u8]);
+            digest.update(values);
+            digest.update(&[15u8]);
+        }
+        NbtTag::IntArray(values) => {
+            digest.update(&[16u8]);
+            for value in values {
+                digest.update(&value.to_le_bytes());
+            }
+            digest.update(&[17u8]);
+        }
+        NbtTag::LongArray(values) => {
+            digest.update(&[18u8]);
+            for value in values {
+                digest.update(&value.to_le_bytes());
+            }
+            digest.update(&[19u8]);
+        }
+        NbtTag::List(values) => {
+            digest.update(&[4u8]);
+            for value in values {
+                digest.update(&get_nbt_hash(value).to_le_bytes());
+            }
+            digest.update(&[5u8]);
+        }
+        NbtTag::Compound(compound) => {
+            let mut entries: Vec<_> = compound
+                .child_tags
+                .iter()
+                .map(|(key, value)| (get_str_hash(key), get_nbt_hash(value)))
+                .collect();
+            entries.sort_unstable();
+            digest.update(&[2u8]);
+            for (key, value) in entries {
+                digest.update(&key.to_le_bytes());
+                digest.update(&value.to_le_bytes());
+            }
+            digest.update(&[3u8]);
+        }
     }
     digest.finalize() as u32
 }
@@ -219,6 +303,12 @@ fn test_hash() {
         -1580618251i32
     );
     assert_eq!(MaxStackSizeImpl { size: 99 }.get_hash(), -1632321551i32);
+    let mut sharpness = NbtCompound::new();
+    sharpness.put_int("minecraft:sharpness", 2);
+    assert_eq!(
+        get_nbt_hash(&NbtTag::Compound(sharpness)) as i32,
+        -1580618251i32
+    );
 }
 
 impl DataComponentImpl for EnchantmentsImpl {
@@ -84,7 +84,8 @@ pub struct GenericContainerScreenHandler {
 }
 
 impl GenericContainerScreenHandler {
-    async fn new(
+    /// Shows the first `rows * columns` slots of `inventory` followed by the player's inventory
+    pub async fn new(
         screen_type: WindowType,
         sync_id: u8,
         player_inventory: &Arc<PlayerInventory>,
@@ -0,0 +1,45 @@
+//! Prints a packet recording of the server's `/pumpkin debug packets` command.
+//!
+//! `cargo run -p pumpkin-protocol --example decode_recording -- <file>`
+
+use std::{fs::File, io::BufReader, process::ExitCode};
+
+use pumpkin_data::packet::CURRENT_MC_PROTOCOL;
+use pumpkin_protocol::recording::{Platform, RecordingReader, describe_packet};
+
+fn main() -> ExitCode {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("Usage: decode_recording <file>");
+        return ExitCode::FAILURE;
+    };
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("Failed to open {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut reader = match RecordingReader::new(BufReader::new(file)) {
+        Ok(reader) => reader,
+        Err(err) => {
+            eprintln!("Failed to read {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let header = reader.header();
+    println!("{:?} client, protocol {}", header.platform, header.protocol);
+    if header.platform == Platform::Java && header.protocol != CURRENT_MC_PROTOCOL {
+        println!("The packet names are those of protocol {CURRENT_MC_PROTOCOL} and may be wrong");
+    }
+    loop {
+        match reader.next_packet() {
+            Ok(Some(packet)) => println!("{}", describe_packet(header.platform, &packet)),
+            Ok(None) => return ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("Failed to read packet: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+}
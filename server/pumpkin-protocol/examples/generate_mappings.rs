@@ -0,0 +1,40 @@
+//! Generates the mappings file that lets clients of another version join, from that version's
+//! extractor reports and ours in `assets/`. Put the file into the server's mappings folder.
+//!
+//! `cargo run -p pumpkin-protocol --example generate_mappings -- <their assets> <name> <output>`
+
+use std::{fs, path::Path, process::ExitCode};
+
+use pumpkin_protocol::java::translation::generate::{VersionReports, generate_mappings};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [their_assets, name, output] = args.as_slice() else {
+        eprintln!("Usage: generate_mappings <their assets> <name, like 1.21.10> <output>");
+        return ExitCode::FAILURE;
+    };
+
+    let our_assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("../assets");
+    let reports = VersionReports::read(&our_assets)
+        .and_then(|ours| Ok((ours, VersionReports::read(Path::new(their_assets))?)));
+    let (ours, theirs) = match reports {
+        Ok(reports) => reports,
+        Err(err) => {
+            eprintln!("Failed to read the reports: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mappings = match generate_mappings(name, &ours, &theirs) {
+        Ok(mappings) => mappings,
+        Err(err) => {
+            eprintln!("Failed to generate the mappings: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = fs::write(output, mappings) {
+        eprintln!("Failed to write {output}: {err}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,186 @@
+//! Hashing for the client blob cache, where clients keep chunk data they were sent before and
+//! only download what they don't have yet.
+//!
+//! Blobs are identified by their XXH64 hash (seed 0), the same id the vanilla server uses, so
+//! clients can reuse blobs across servers.
+
+use std::collections::HashMap;
+
+use bytes::Bytes;
+
+const PRIME_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+const fn round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME_1)
+}
+
+const fn merge_round(acc: u64, val: u64) -> u64 {
+    (acc ^ round(0, val))
+        .wrapping_mul(PRIME_1)
+        .wrapping_add(PRIME_4)
+}
+
+fn read_u64(data: &[u8]) -> u64 {
+    u64::from_le_bytes(data[..8].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    u32::from_le_bytes(data[..4].try_into().unwrap())
+}
+
+/// The id of a blob
+#[must_use]
+pub fn blob_hash(data: &[u8]) -> u64 {
+    let mut rest = data;
+    let mut hash = if data.len() >= 32 {
+        let mut v1 = PRIME_1.wrapping_add(PRIME_2);
+        let mut v2 = PRIME_2;
+        let mut v3 = 0;
+        let mut v4 = 0u64.wrapping_sub(PRIME_1);
+        while rest.len() >= 32 {
+            v1 = round(v1, read_u64(rest));
+            v2 = round(v2, read_u64(&rest[8..]));
+            v3 = round(v3, read_u64(&rest[16..]));
+            v4 = round(v4, read_u64(&rest[24..]));
+            rest = &rest[32..];
+        }
+        let hash = v1
+            .rotate_left(1)
+            .wrapping_add(v2.rotate_left(7))
+            .wrapping_add(v3.rotate_left(12))
+            .wrapping_add(v4.rotate_left(18));
+        let hash = merge_round(hash, v1);
+        let hash = merge_round(hash, v2);
+        let hash = merge_round(hash, v3);
+        merge_round(hash, v4)
+    } else {
+        PRIME_5
+    };
+    hash = hash.wrapping_add(data.len() as u64);
+
+    while rest.len() >= 8 {
+        hash ^= round(0, read_u64(rest));
+        hash = hash
+            .rotate_left(27)
+            .wrapping_mul(PRIME_1)
+            .wrapping_add(PRIME_4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        hash ^= u64::from(read_u32(rest)).wrapping_mul(PRIME_1);
+        hash = hash
+            .rotate_left(23)
+            .wrapping_mul(PRIME_2)
+            .wrapping_add(PRIME_3);
+        rest = &rest[4..];
+    }
+    for byte in rest {
+        hash ^= u64::from(*byte).wrapping_mul(PRIME_5);
+        hash = hash.rotate_left(11).wrapping_mul(PRIME_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME_3);
+    hash ^ (hash >> 32)
+}
+
+/// Blobs whose ids were sent to a client, kept until it tells us whether it has them.
+///
+/// A blob shared by several chunks, like an empty sub chunk, is referenced once per chunk it was
+/// sent with and only dropped once the client answered for every one of them.
+#[derive(Default)]
+pub struct PendingBlobs {
+    blobs: HashMap<u64, (usize, Bytes)>,
+}
+
+impl PendingBlobs {
+    /// How many different blobs are waiting for an answer
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+
+    /// Adds a reference to a blob sent with a chunk
+    pub fn add(&mut self, hash: u64, blob: &Bytes) {
+        self.blobs
+            .entry(hash)
+            .or_insert_with(|| (0, blob.clone()))
+            .0 += 1;
+    }
+
+    /// Drops one reference to each blob the client answered for, returning the missing ones
+    /// that have to be sent
+    pub fn resolve(&mut self, hits: &[u64], missing: &[u64]) -> Vec<(u64, Bytes)> {
+        for hash in hits {
+            self.release(*hash);
+        }
+        missing
+            .iter()
+            .filter_map(|hash| self.release(*hash).map(|blob| (*hash, blob)))
+            .collect()
+    }
+
+    fn release(&mut self, hash: u64) -> Option<Bytes> {
+        let (references, blob) = self.blobs.get_mut(&hash)?;
+        *references -= 1;
+        let blob = blob.clone();
+        if *references == 0 {
+            self.blobs.remove(&hash);
+        }
+        Some(blob)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+
+    use super::{PendingBlobs, blob_hash};
+
+    #[test]
+    fn matches_xxh64() {
+        assert_eq!(blob_hash(b""), 0xEF46_DB37_51D8_E999);
+        assert_eq!(blob_hash(b"abc"), 0x44BC_2CF5_AD77_0999);
+    }
+
+    #[test]
+    fn long_input() {
+        // Goes through the 32 byte stripes as well as every tail step, a changed length has to change
+        // the hash
+        let data = (0..=200u8).collect::<Vec<_>>();
+        for len in [31, 32, 33, 36, 40, 45, 64, 201] {
+            assert_ne!(blob_hash(&data[..len]), blob_hash(&data[..len - 1]));
+        }
+    }
+
+    #[test]
+    fn shared_blobs_stay_pending() {
+        let mut pending = PendingBlobs::default();
+        let empty = Bytes::from_static(b"empty");
+        let stone = Bytes::from_static(b"stone");
+        // Two chunks in flight share the empty sub chunk
+        pending.add(1, &empty);
+        pending.add(2, &stone);
+        pending.add(1, &empty);
+        assert_eq!(pending.len(), 2);
+
+        // The answer for the first chunk keeps the blob the second one still needs
+        assert!(pending.resolve(&[1, 2], &[]).is_empty());
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending.resolve(&[], &[1, 3]), vec![(1, empty)]);
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,24 @@
+use std::io::{Error, Write};
+
+use bytes::Bytes;
+use pumpkin_macros::packet;
+
+use crate::{codec::var_uint::VarUInt, serial::PacketWrite};
+
+#[packet(136)]
+pub struct CClientCacheMissResponse {
+    // https://mojang.github.io/bedrock-protocol-docs/html/ClientCacheMissResponsePacket.html
+    pub blobs: Vec<(u64, Bytes)>,
+}
+
+impl PacketWrite for CClientCacheMissResponse {
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        VarUInt(self.blobs.len() as u32).write(writer)?;
+        for (hash, blob) in &self.blobs {
+            hash.write(writer)?;
+            VarUInt(blob.len() as u32).write(writer)?;
+            writer.write_all(blob)?;
+        }
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use pumpkin_macros::packet;
 use pumpkin_world::chunk::{ChunkData, palette::NetworkPalette};
 
 use crate::{
+    bedrock::blob_cache::blob_hash,
     codec::{var_int::VarInt, var_uint::VarUInt},
     serial::PacketWrite,
 };
@@ -12,32 +13,75 @@ const VERSION: u8 = 9;
 #[packet(58)]
 pub struct CLevelChunk<'a> {
     // https://mojang.github.io/bedrock-protocol-docs/html/LevelChunkPacket.html
+    pub x: i32,
+    pub z: i32,
     pub dimension: i32,
-    pub cache_enabled: bool,
+    pub data: LevelChunkData<'a>,
+}
 
-    // https://gist.github.com/Tomcc/a96af509e275b1af483b25c543cfbf37
-    // https://github.com/Mojang/bedrock-protocol-docs/blob/main/additional_docs/SubChunk%20Request%20System%20v1.18.10.md
-    pub chunk: &'a ChunkData,
+pub enum LevelChunkData<'a> {
+    /// Every blob sent inline
+    Blobs(&'a ChunkBlobs),
+    /// Only the ids of the blobs, sub chunks first and biomes last. The client asks for the
+    /// blobs it doesn't have
+    BlobIds(&'a [u64]),
 }
 
-impl<'a> PacketWrite for CLevelChunk<'a> {
+impl PacketWrite for CLevelChunk<'_> {
     fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
-        VarInt(self.chunk.x).write(writer)?;
-        VarInt(self.chunk.z).write(writer)?;
+        VarInt(self.x).write(writer)?;
+        VarInt(self.z).write(writer)?;
 
         VarInt(self.dimension).write(writer)?;
-        let sub_chunk_count = self.chunk.section.sections.len() as u32;
-        assert_eq!(sub_chunk_count, 24);
-        VarUInt(sub_chunk_count).write(writer)?;
-        self.cache_enabled.write(writer)?;
 
         let mut chunk_data = Vec::new();
-        let data_write = &mut chunk_data;
+        match self.data {
+            LevelChunkData::Blobs(blobs) => {
+                VarUInt(blobs.sub_chunks.len() as u32).write(writer)?;
+                false.write(writer)?;
+                for sub_chunk in &blobs.sub_chunks {
+                    chunk_data.extend_from_slice(sub_chunk);
+                }
+                chunk_data.extend_from_slice(&blobs.biomes);
+            }
+            LevelChunkData::BlobIds(hashes) => {
+                // Every blob but the last one is a sub chunk
+                VarUInt(hashes.len().saturating_sub(1) as u32).write(writer)?;
+                true.write(writer)?;
+                VarUInt(hashes.len() as u32).write(writer)?;
+                for hash in hashes {
+                    hash.write(writer)?;
+                }
+            }
+        }
+        // Border blocks, always sent inline
+        chunk_data.push(0);
+
+        VarUInt(chunk_data.len() as u32).write(writer)?;
+        writer.write_all(&chunk_data)
+    }
+}
+
+/// A chunk split into the blobs the client caches: one per sub chunk and one for all biomes
+pub struct ChunkBlobs {
+    pub sub_chunks: Vec<Vec<u8>>,
+    pub biomes: Vec<u8>,
+}
+
+impl ChunkBlobs {
+    // https://gist.github.com/Tomcc/a96af509e275b1af483b25c543cfbf37
+    // https://github.com/Mojang/bedrock-protocol-docs/blob/main/additional_docs/SubChunk%20Request%20System%20v1.18.10.md
+    pub fn new(chunk: &ChunkData) -> Result<Self, Error> {
+        let sub_chunk_count = chunk.section.sections.len();
+        assert_eq!(sub_chunk_count, 24);
 
-        let min_y = (self.chunk.section.min_y >> 4) as i8;
+        let min_y = (chunk.section.min_y >> 4) as i8;
 
         // Blocks
-        for (i, sub_chunk) in self.chunk.section.sections.iter().enumerate() {
+        let mut sub_chunks = Vec::with_capacity(sub_chunk_count);
+        for (i, sub_chunk) in chunk.section.sections.iter().enumerate() {
+            let mut blob = Vec::new();
+            let data_write = &mut blob;
             // Version 9
             // [version:byte][num_storages:byte][sub_chunk_index:byte][block storage1]...[blockStorageN]
             let y = i as i8 + min_y;
@@ -62,23 +106,31 @@ impl<'a> PacketWrite for CLevelChunk<'a> {
                 }
                 NetworkPalette::Direct => (),
             }
+            sub_chunks.push(blob);
         }
 
         // Biomes
+        let mut biomes = Vec::new();
         for i in 0..sub_chunk_count {
             let num_storages = 1;
             let y = i as i8 + min_y;
-            data_write.write_all(&[VERSION, num_storages, y as _])?;
+            biomes.write_all(&[VERSION, num_storages, y as _])?;
 
             for _ in 0..num_storages {
-                1u8.write(data_write)?;
-                VarInt(0).write(data_write)?;
+                1u8.write(&mut biomes)?;
+                VarInt(0).write(&mut biomes)?;
             }
         }
 
-        data_write.write_all(&[0])?;
+        Ok(Self { sub_chunks, biomes })
+    }
 
-        VarUInt(chunk_data.len() as u32).write(writer)?;
-        writer.write_all(&chunk_data)
+    /// Every blob together with its id, in the order the client expects them: sub chunks
+    /// first, biomes last
+    pub fn blobs(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        self.sub_chunks
+            .iter()
+            .chain(std::iter::once(&self.biomes))
+            .map(|blob| (blob_hash(blob), blob.as_slice()))
     }
 }
@@ -1,4 +1,5 @@
 pub mod chunk_radius_update;
+pub mod client_cache_miss_response;
 pub mod container_open;
 pub mod correct_player_move;
 pub mod creative_content;
@@ -13,11 +14,14 @@ pub mod network_settings;
 pub mod play_status;
 pub mod player_hotbar;
 pub mod raknet;
+pub mod resource_pack_chunk_data;
+pub mod resource_pack_data_info;
 pub mod resource_pack_stack;
 pub mod resource_packs_info;
 pub mod set_actor_motion;
 pub mod set_player_gamemode;
 pub mod set_time;
 pub mod start_game;
+pub mod transfer;
 pub mod update_abilities;
 pub mod update_artributes;
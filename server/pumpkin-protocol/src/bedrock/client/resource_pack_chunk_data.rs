@@ -0,0 +1,26 @@
+use std::io::{Error, Write};
+
+use pumpkin_macros::packet;
+
+use crate::{codec::var_uint::VarUInt, serial::PacketWrite};
+
+#[packet(83)]
+pub struct CResourcePackChunkData<'a> {
+    // https://mojang.github.io/bedrock-protocol-docs/html/ResourcePackChunkDataPacket.html
+    /// The pack as `<uuid>_<version>`
+    pub pack_id: String,
+    pub chunk_index: u32,
+    /// The offset of this chunk in the pack
+    pub progress: u64,
+    pub data: &'a [u8],
+}
+
+impl PacketWrite for CResourcePackChunkData<'_> {
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        self.pack_id.write(writer)?;
+        self.chunk_index.write(writer)?;
+        self.progress.write(writer)?;
+        VarUInt(self.data.len() as u32).write(writer)?;
+        writer.write_all(self.data)
+    }
+}
@@ -0,0 +1,35 @@
+use std::io::{Error, Write};
+
+use pumpkin_macros::packet;
+
+use crate::{codec::var_uint::VarUInt, serial::PacketWrite};
+
+/// The pack type of a resource pack
+pub const PACK_TYPE_RESOURCES: u8 = 6;
+
+#[packet(82)]
+pub struct CResourcePackDataInfo<'a> {
+    // https://mojang.github.io/bedrock-protocol-docs/html/ResourcePackDataInfoPacket.html
+    /// The pack as `<uuid>_<version>`
+    pub pack_id: String,
+    pub max_chunk_size: u32,
+    pub chunk_count: u32,
+    pub size: u64,
+    /// SHA-256 of the whole pack
+    pub hash: &'a [u8],
+    pub is_premium: bool,
+    pub pack_type: u8,
+}
+
+impl PacketWrite for CResourcePackDataInfo<'_> {
+    fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        self.pack_id.write(writer)?;
+        self.max_chunk_size.write(writer)?;
+        self.chunk_count.write(writer)?;
+        self.size.write(writer)?;
+        VarUInt(self.hash.len() as u32).write(writer)?;
+        writer.write_all(self.hash)?;
+        self.is_premium.write(writer)?;
+        self.pack_type.write(writer)
+    }
+}
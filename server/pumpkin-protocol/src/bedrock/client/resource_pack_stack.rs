@@ -11,6 +11,7 @@ pub struct CResourcePackStackPacket {
     resource_pack_required: bool,
     addons_list_size: VarUInt,
     texture_pack_list_size: VarUInt,
+    texture_packs: Vec<StackEntry>,
     game_version: String,
     experiments: Experiments,
     /// When connecting to an Editor world, include the vanilla editor packs in the stack
@@ -21,7 +22,7 @@ impl CResourcePackStackPacket {
     pub fn new(
         resource_pack_required: bool,
         addons_list_size: VarUInt,
-        texture_pack_list_size: VarUInt,
+        texture_packs: Vec<StackEntry>,
         game_version: String,
         experiments: Experiments,
         include_editor_packs: bool,
@@ -29,10 +30,29 @@ impl CResourcePackStackPacket {
         Self {
             resource_pack_required,
             addons_list_size,
-            texture_pack_list_size,
+            texture_pack_list_size: VarUInt(texture_packs.len() as u32),
+            texture_packs,
             game_version,
             experiments,
             include_editor_packs,
         }
     }
 }
+
+/// A pack in the order the client should apply them
+#[derive(PacketWrite)]
+pub struct StackEntry {
+    pack_id: String,
+    version: String,
+    subpack_name: String,
+}
+
+impl StackEntry {
+    pub fn new(pack_id: String, version: String) -> Self {
+        Self {
+            pack_id,
+            version,
+            subpack_name: String::new(),
+        }
+    }
+}
@@ -50,3 +50,20 @@ impl CResourcePacksInfo {
         }
     }
 }
+
+impl ResourcePack {
+    pub fn new(pack_id: uuid::Uuid, version: String, size: u64) -> Self {
+        Self {
+            pack_id,
+            version,
+            size,
+            content_key: String::new(),
+            subpack_name: String::new(),
+            content_identity: String::new(),
+            has_scripts: false,
+            is_addon_pack: false,
+            is_raytracing_capable: false,
+            cdn_url: String::new(),
+        }
+    }
+}
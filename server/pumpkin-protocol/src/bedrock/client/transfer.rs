@@ -0,0 +1,22 @@
+use pumpkin_macros::packet;
+
+use crate::serial::PacketWrite;
+
+#[derive(PacketWrite)]
+#[packet(85)]
+pub struct CTransfer {
+    // https://mojang.github.io/bedrock-protocol-docs/html/TransferPacket.html
+    pub address: String,
+    pub port: u16,
+    pub reload_world: bool,
+}
+
+impl CTransfer {
+    pub fn new(address: String, port: u16) -> Self {
+        Self {
+            address,
+            port,
+            reload_world: false,
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod ack;
+pub mod blob_cache;
 pub mod client;
 pub mod frame_set;
 pub mod network_item;
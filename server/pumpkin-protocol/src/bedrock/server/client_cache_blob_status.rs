@@ -0,0 +1,36 @@
+use std::io::{Error, Read};
+
+use pumpkin_macros::packet;
+
+use crate::{codec::var_uint::VarUInt, serial::PacketRead};
+
+/// Upper bound for a single list, the client never reports more than a few chunks worth of blobs
+const MAX_BLOBS: u32 = 4096;
+
+#[derive(Debug)]
+#[packet(135)]
+pub struct SClientCacheBlobStatus {
+    // https://mojang.github.io/bedrock-protocol-docs/html/ClientCacheBlobStatusPacket.html
+    /// Blobs the client doesn't have and wants us to send
+    pub missing: Vec<u64>,
+    /// Blobs the client already had cached
+    pub hits: Vec<u64>,
+}
+
+impl PacketRead for SClientCacheBlobStatus {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let missing_count = VarUInt::read(reader)?.0;
+        let hit_count = VarUInt::read(reader)?.0;
+        if missing_count > MAX_BLOBS || hit_count > MAX_BLOBS {
+            return Err(Error::other("Too many blobs in cache status"));
+        }
+
+        let missing = (0..missing_count)
+            .map(|_| u64::read(reader))
+            .collect::<Result<_, _>>()?;
+        let hits = (0..hit_count)
+            .map(|_| u64::read(reader))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { missing, hits })
+    }
+}
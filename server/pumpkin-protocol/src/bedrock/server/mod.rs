@@ -1,3 +1,4 @@
+pub mod client_cache_blob_status;
 pub mod client_cache_status;
 pub mod command_request;
 pub mod container_close;
@@ -8,5 +9,6 @@ pub mod player_auth_input;
 pub mod raknet;
 pub mod request_chunk_radius;
 pub mod request_network_settings;
+pub mod resource_pack_chunk_request;
 pub mod resource_pack_response;
 pub mod text;
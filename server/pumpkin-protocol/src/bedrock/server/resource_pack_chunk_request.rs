@@ -0,0 +1,11 @@
+use crate::serial::PacketRead;
+use pumpkin_macros::packet;
+
+#[derive(PacketRead)]
+#[packet(84)]
+pub struct SResourcePackChunkRequest {
+    // https://mojang.github.io/bedrock-protocol-docs/html/ResourcePackChunkRequestPacket.html
+    /// The pack as `<uuid>_<version>`
+    pub pack_id: String,
+    pub chunk_index: u32,
+}
@@ -1,9 +1,36 @@
+use std::io::{Error, Read};
+
 use crate::serial::PacketRead;
 use pumpkin_macros::packet;
 
-#[derive(PacketRead)]
+/// Upper bound for the requested packs, the client can't ask for more than we offered
+const MAX_PACK_IDS: u16 = 1024;
+
 #[packet(8)]
 pub struct SResourcePackResponse {
+    // https://mojang.github.io/bedrock-protocol-docs/html/ResourcePackClientResponsePacket.html
     pub response: u8,
-    pub download_size: u16,
+    /// The packs the response refers to, as `<uuid>_<version>`
+    pub pack_ids: Vec<String>,
+}
+
+impl SResourcePackResponse {
+    pub const REFUSED: u8 = 1;
+    pub const SEND_PACKS: u8 = 2;
+    pub const HAVE_ALL_PACKS: u8 = 3;
+    pub const COMPLETED: u8 = 4;
+}
+
+impl PacketRead for SResourcePackResponse {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let response = u8::read(reader)?;
+        let count = u16::read(reader)?;
+        if count > MAX_PACK_IDS {
+            return Err(Error::other("Too many resource packs in response"));
+        }
+        let pack_ids = (0..count)
+            .map(|_| String::read(reader))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { response, pack_ids })
+    }
 }
@@ -1,13 +1,17 @@
 use crate::codec::var_int::VarInt;
+use crate::ser::network_serialize_no_prefix;
 use pumpkin_data::Enchantment;
 use pumpkin_data::data_component::DataComponent;
 use pumpkin_data::data_component_impl::{
-    DamageImpl, DataComponentImpl, EnchantmentsImpl, MaxStackSizeImpl, get,
+    CustomDataImpl, DamageImpl, DataComponentImpl, EnchantmentsImpl, MaxStackSizeImpl, get,
 };
-use serde::de;
+use pumpkin_nbt::Nbt;
+use pumpkin_nbt::deserializer::NbtReadHelper;
 use serde::de::SeqAccess;
 use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer, de};
 use std::borrow::Cow;
+use std::io::Cursor;
 
 trait DataComponentCodec<Impl: DataComponentImpl> {
     fn serialize<T: SerializeStruct>(&self, seq: &mut T) -> Result<(), T::Error>;
@@ -79,14 +83,50 @@ impl DataComponentCodec<Self> for EnchantmentsImpl {
     }
 }
 
+/// Raw bytes written as they are, without a length prefix
+struct UnprefixedBytes<'a>(&'a [u8]);
+
+impl Serialize for UnprefixedBytes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        network_serialize_no_prefix(self.0, serializer)
+    }
+}
+
+fn serialize_custom_data<T: SerializeStruct>(
+    custom_data: &CustomDataImpl,
+    seq: &mut T,
+) -> Result<(), T::Error> {
+    let nbt = Nbt::new(String::new(), custom_data.data.clone()).write_unnamed();
+    seq.serialize_field("", &UnprefixedBytes(&nbt))
+}
+
+/// Unlike the other components, NBT can't be read without knowing its length up front
+fn deserialize_custom_data<'a, A: SeqAccess<'a>>(
+    seq: &mut A,
+    byte_len: usize,
+) -> Result<CustomDataImpl, A::Error> {
+    let mut bytes = Vec::new();
+    for _ in 0..byte_len {
+        bytes.push(
+            seq.next_element::<u8>()?
+                .ok_or(de::Error::custom("CustomData NBT ended early!"))?,
+        );
+    }
+    let nbt = Nbt::read_unnamed(&mut NbtReadHelper::new(Cursor::new(bytes)))
+        .map_err(|err| de::Error::custom(format!("Invalid CustomData NBT: {err}")))?;
+    Ok(CustomDataImpl { data: nbt.root_tag })
+}
+
 pub fn deserialize<'a, A: SeqAccess<'a>>(
     id: DataComponent,
+    byte_len: usize,
     seq: &mut A,
 ) -> Result<Box<dyn DataComponentImpl>, A::Error> {
     match id {
         DataComponent::MaxStackSize => Ok(MaxStackSizeImpl::deserialize(seq)?.to_dyn()),
         DataComponent::Enchantments => Ok(EnchantmentsImpl::deserialize(seq)?.to_dyn()),
         DataComponent::Damage => Ok(DamageImpl::deserialize(seq)?.to_dyn()),
+        DataComponent::CustomData => Ok(deserialize_custom_data(seq, byte_len)?.to_dyn()),
         _ => todo!("{} not yet implemented", id.to_name()),
     }
 }
@@ -99,6 +139,7 @@ pub fn serialize<T: SerializeStruct>(
         DataComponent::MaxStackSize => get::<MaxStackSizeImpl>(value).serialize(seq),
         DataComponent::Enchantments => get::<EnchantmentsImpl>(value).serialize(seq),
         DataComponent::Damage => get::<DamageImpl>(value).serialize(seq),
+        DataComponent::CustomData => serialize_custom_data(get::<CustomDataImpl>(value), seq),
         _ => todo!("{} not yet implemented", id.to_name()),
     }
 }
@@ -54,10 +54,12 @@ impl<'de> Deserialize<'de> for ItemStackSerializer<'static> {
                             .map_err(|_| de::Error::custom("Unknown component id VarInt!"))?;
                         let id = DataComponent::try_from_id(id)
                             .ok_or(de::Error::custom("Unknown component id VarInt!"))?;
-                        let _byte_len = seq
+                        let byte_len = seq
                             .next_element::<VarInt>()?
                             .ok_or(de::Error::custom("No data len VarInt!"))?;
-                        patch.push((id, Some(deserialize(id, &mut seq)?)))
+                        let byte_len = usize::try_from(byte_len.0)
+                            .map_err(|_| de::Error::custom("Negative data len VarInt!"))?;
+                        patch.push((id, Some(deserialize(id, byte_len, &mut seq)?)))
                     }
                     for _ in 0..num_components_to_remove {
                         let id = seq
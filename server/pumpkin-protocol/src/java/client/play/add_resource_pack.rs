@@ -0,0 +1,34 @@
+use pumpkin_data::packet::clientbound::PLAY_RESOURCE_PACK_PUSH;
+use pumpkin_macros::packet;
+use pumpkin_util::text::TextComponent;
+use serde::Serialize;
+
+#[derive(Serialize)]
+#[packet(PLAY_RESOURCE_PACK_PUSH)]
+/// Tells the client to download and apply a resource pack on top of the ones it already has.
+pub struct CAddResourcePack<'a> {
+    #[serde(with = "uuid::serde::compact")]
+    pub uuid: &'a uuid::Uuid,
+    pub url: &'a str,
+    pub hash: &'a str, // max 40
+    pub forced: bool,
+    pub prompt_message: Option<TextComponent>,
+}
+
+impl<'a> CAddResourcePack<'a> {
+    pub fn new(
+        uuid: &'a uuid::Uuid,
+        url: &'a str,
+        hash: &'a str,
+        forced: bool,
+        prompt_message: Option<TextComponent>,
+    ) -> Self {
+        Self {
+            uuid,
+            url,
+            hash,
+            forced,
+            prompt_message,
+        }
+    }
+}
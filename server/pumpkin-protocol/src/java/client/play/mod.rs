@@ -1,5 +1,6 @@
 mod acknowledge_block;
 mod actionbar;
+mod add_resource_pack;
 mod block_destroy_stage;
 mod block_entity_data;
 mod block_event;
@@ -17,6 +18,7 @@ mod combat_death;
 mod command_suggestions;
 mod commands;
 mod cookie_request;
+mod custom_payload;
 mod damage_event;
 mod disconnect;
 mod disguised_chat_message;
@@ -49,6 +51,7 @@ mod player_remove;
 mod player_spawn_position;
 mod remove_entities;
 mod remove_mob_effect;
+mod remove_resource_pack;
 mod reset_score;
 mod respawn;
 mod server_links;
@@ -91,6 +94,7 @@ mod worldevent;
 
 pub use acknowledge_block::*;
 pub use actionbar::*;
+pub use add_resource_pack::*;
 pub use block_destroy_stage::*;
 pub use block_entity_data::*;
 pub use block_event::*;
@@ -108,6 +112,7 @@ pub use combat_death::*;
 pub use command_suggestions::*;
 pub use commands::*;
 pub use cookie_request::*;
+pub use custom_payload::*;
 pub use damage_event::*;
 pub use disconnect::*;
 pub use disguised_chat_message::*;
@@ -140,6 +145,7 @@ pub use player_remove::*;
 pub use player_spawn_position::*;
 pub use remove_entities::*;
 pub use remove_mob_effect::*;
+pub use remove_resource_pack::*;
 pub use reset_score::*;
 pub use respawn::*;
 pub use server_links::*;
@@ -0,0 +1,25 @@
+use std::io::Write;
+
+use crate::ser::NetworkWriteExt;
+use crate::{ClientPacket, WritingError};
+use pumpkin_data::packet::clientbound::PLAY_RESOURCE_PACK_POP;
+use pumpkin_macros::packet;
+
+#[packet(PLAY_RESOURCE_PACK_POP)]
+/// Removes a resource pack the server sent before, or all of them if no UUID is given.
+pub struct CRemoveResourcePack {
+    pub uuid: Option<uuid::Uuid>,
+}
+
+impl CRemoveResourcePack {
+    pub fn new(uuid: Option<uuid::Uuid>) -> Self {
+        Self { uuid }
+    }
+}
+
+impl ClientPacket for CRemoveResourcePack {
+    fn write_packet_data(&self, write: impl Write) -> Result<(), WritingError> {
+        let mut write = write;
+        write.write_option(&self.uuid, |write, uuid| write.write_uuid(uuid))
+    }
+}
@@ -73,6 +73,7 @@ impl<R: AsyncRead + Unpin> AsyncRead for DecryptionReader<R> {
 pub struct TCPNetworkDecoder<R: AsyncRead + Unpin> {
     reader: DecryptionReader<R>,
     compression: Option<CompressionThreshold>,
+    max_packet_data_size: usize,
 }
 
 impl<R: AsyncRead + Unpin> TCPNetworkDecoder<R> {
@@ -80,6 +81,7 @@ impl<R: AsyncRead + Unpin> TCPNetworkDecoder<R> {
         Self {
             reader: DecryptionReader::None(reader),
             compression: None,
+            max_packet_data_size: MAX_PACKET_DATA_SIZE,
         }
     }
 
@@ -87,6 +89,12 @@ impl<R: AsyncRead + Unpin> TCPNetworkDecoder<R> {
         self.compression = Some(threshold);
     }
 
+    /// Limits how large a packet may be after decompression, can not go above
+    /// `MAX_PACKET_DATA_SIZE`
+    pub fn set_max_packet_data_size(&mut self, size: usize) {
+        self.max_packet_data_size = size.min(MAX_PACKET_DATA_SIZE);
+    }
+
     /// NOTE: Encryption can only be set; a minecraft stream cannot go back to being unencrypted
     pub fn set_encryption(&mut self, key: &[u8; 16]) {
         if matches!(self.reader, DecryptionReader::Decrypt(_)) {
@@ -112,16 +120,19 @@ impl<R: AsyncRead + Unpin> TCPNetworkDecoder<R> {
 
         let mut bounded_reader = (&mut self.reader).take(packet_len);
 
-        let mut reader = if let Some(threshold) = self.compression {
+        // Zlib can inflate far beyond the packet length, so never read more than was declared
+        let mut expected_length = None;
+        let reader = if let Some(threshold) = self.compression {
             let decompressed_length = VarInt::decode_async(&mut bounded_reader).await?;
             let raw_packet_length = packet_len - decompressed_length.written_size() as u64;
             let decompressed_length = decompressed_length.0 as usize;
 
-            if !(0..=MAX_PACKET_DATA_SIZE).contains(&decompressed_length) {
+            if !(0..=self.max_packet_data_size).contains(&decompressed_length) {
                 Err(PacketDecodeError::TooLong)?
             }
 
             if decompressed_length > 0 {
+                expected_length = Some(decompressed_length);
                 DecompressionReader::Decompress(ZlibDecoder::new(BufReader::new(bounded_reader)))
             } else {
                 // Validate that we are not less than the compression threshold
@@ -135,13 +146,20 @@ impl<R: AsyncRead + Unpin> TCPNetworkDecoder<R> {
             DecompressionReader::None(bounded_reader)
         };
 
+        if packet_len as usize > self.max_packet_data_size {
+            Err(PacketDecodeError::TooLong)?
+        }
+
+        // One byte over the declared length is enough to tell the client lied about it
+        let mut reader =
+            reader.take(expected_length.map_or(packet_len, |length| length as u64 + 1));
+
         // TODO: Serde is sync so we need to write to a buffer here :(
         // Is there a way to deserialize in an asynchronous manner?
 
         let packet_id = VarInt::decode_async(&mut reader)
             .await
-            .map_err(|_| PacketDecodeError::DecodeID)?
-            .0;
+            .map_err(|_| PacketDecodeError::DecodeID)?;
 
         let mut payload = Vec::new();
         reader
@@ -149,6 +167,16 @@ impl<R: AsyncRead + Unpin> TCPNetworkDecoder<R> {
             .await
             .map_err(|err| PacketDecodeError::FailedDecompression(err.to_string()))?;
 
+        if let Some(expected_length) = expected_length {
+            let length = packet_id.written_size() + payload.len();
+            if length != expected_length {
+                Err(PacketDecodeError::FailedDecompression(format!(
+                    "declared length {expected_length} but decompressed to {length} bytes"
+                )))?
+            }
+        }
+        let packet_id = packet_id.0;
+
         Ok(RawPacket {
             id: packet_id,
             payload: payload.into(),
@@ -367,6 +395,44 @@ mod tests {
         }
     }
 
+    /// Test decoding a packet that decompresses to more than it declared
+    #[tokio::test]
+    async fn test_decode_with_wrong_decompressed_length() {
+        let mut data = Vec::new();
+        data.write_var_int(&VarInt(6)).unwrap();
+        data.write_slice(&[0u8; 4096]).unwrap();
+
+        let mut buffer = Vec::new();
+        // Declares far less than the compressed data inflates to
+        buffer.write_var_int(&VarInt(16)).unwrap();
+        buffer.write_slice(&compress_zlib(&data)).unwrap();
+
+        let mut packet = Vec::new();
+        packet.write_var_int(&VarInt(buffer.len() as i32)).unwrap();
+        packet.write_slice(&buffer).unwrap();
+
+        let mut decoder = TCPNetworkDecoder::new(packet.as_slice());
+        decoder.set_compression(256);
+        assert!(matches!(
+            decoder.get_raw_packet().await,
+            Err(PacketDecodeError::FailedDecompression(_))
+        ));
+    }
+
+    /// Test decoding a packet above the configured size limit
+    #[tokio::test]
+    async fn test_decode_above_max_packet_data_size() {
+        let packet = build_packet(9, &[0x41u8; 2048], true, None, None);
+
+        let mut decoder = TCPNetworkDecoder::new(packet.as_slice());
+        decoder.set_compression(256);
+        decoder.set_max_packet_data_size(1024);
+        assert!(matches!(
+            decoder.get_raw_packet().await,
+            Err(PacketDecodeError::TooLong)
+        ));
+    }
+
     /// Test decoding with a zero-length packet
     #[tokio::test]
     async fn test_decode_with_zero_length_packet() {
@@ -263,6 +263,50 @@ impl<W: AsyncWrite + Unpin> TCPNetworkEncoder<W> {
             .map_err(|err| PacketEncodeError::Message(err.to_string()))?;
         Ok(())
     }
+
+    /// The compression threshold and level, if compression is enabled
+    pub fn compression(&self) -> Option<(CompressionThreshold, CompressionLevel)> {
+        self.compression
+    }
+
+    /// Writes a packet compressed ahead of time, so one compressed packet can be sent to many
+    /// clients.
+    ///
+    /// `compressed` is everything after the `Packet Length` of a compressed packet: the
+    /// `Data Length` followed by the zlib-compressed `Packet ID` and `Data`. Only use this while
+    /// compression is enabled and for packets at or above the threshold.
+    pub async fn write_precompressed_packet(
+        &mut self,
+        compressed: &[u8],
+    ) -> Result<(), PacketEncodeError> {
+        debug_assert!(self.compression.is_some());
+        let full_packet_len_var_int: VarInt = compressed.len().try_into().map_err(|_| {
+            PacketEncodeError::Message(format!(
+                "Full packet length is too large to fit in VarInt! ({})",
+                compressed.len()
+            ))
+        })?;
+
+        let complete_serialization_length =
+            full_packet_len_var_int.written_size() + compressed.len();
+        if complete_serialization_length > MAX_PACKET_SIZE as usize {
+            return Err(PacketEncodeError::TooLong(complete_serialization_length));
+        }
+
+        full_packet_len_var_int
+            .encode_async(&mut self.writer)
+            .await
+            .map_err(|err| PacketEncodeError::Message(err.to_string()))?;
+        self.writer
+            .write_all(compressed)
+            .await
+            .map_err(|err| PacketEncodeError::Message(err.to_string()))?;
+        self.writer
+            .flush()
+            .await
+            .map_err(|err| PacketEncodeError::Message(err.to_string()))?;
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -643,4 +687,23 @@ mod tests {
 
         assert_eq!(buffer, expected_payload);
     }
+
+    /// Test writing a packet compressed ahead of time
+    #[tokio::test]
+    async fn test_encode_precompressed() {
+        let compressed = [0x05, 0x78, 0x9C, 0x01, 0x02, 0x03];
+
+        let mut buf = Vec::new();
+        let mut encoder = TCPNetworkEncoder::new(&mut buf);
+        encoder.set_compression((0, 6));
+        encoder
+            .write_precompressed_packet(&compressed)
+            .await
+            .unwrap();
+
+        let mut buffer = &buf[..];
+        let packet_length = decode_varint(&mut buffer).expect("Failed to decode packet length");
+        assert_eq!(packet_length as usize, compressed.len());
+        assert_eq!(buffer, compressed);
+    }
 }
@@ -1,7 +1,30 @@
+use std::io::Read;
+
 use pumpkin_data::packet::serverbound::PLAY_CUSTOM_PAYLOAD;
 use pumpkin_macros::packet;
+use pumpkin_util::resource_location::ResourceLocation;
+
+use crate::{
+    ServerPacket,
+    ser::{NetworkReadExt, ReadingError},
+};
+
+/// The Notchian server does not accept larger payloads from the client
+const MAX_PAYLOAD_SIZE: usize = 32767;
 
+/// Data the client sent on a plugin channel
 #[packet(PLAY_CUSTOM_PAYLOAD)]
 pub struct SCustomPayload {
-    // TODO
+    pub channel: ResourceLocation,
+    pub data: Box<[u8]>,
+}
+
+impl ServerPacket for SCustomPayload {
+    fn read(read: impl Read) -> Result<Self, ReadingError> {
+        let mut read = read;
+        Ok(Self {
+            channel: read.get_resource_location()?,
+            data: read.read_remaining_to_boxed_slice(MAX_PAYLOAD_SIZE)?,
+        })
+    }
 }
@@ -25,6 +25,7 @@ mod player_position;
 mod player_position_rotation;
 mod player_rotation;
 mod player_session;
+mod resource_pack_response;
 mod set_command_block;
 mod set_creative_slot;
 mod set_held_item;
@@ -60,6 +61,7 @@ pub use player_position::*;
 pub use player_position_rotation::*;
 pub use player_rotation::*;
 pub use player_session::*;
+pub use resource_pack_response::*;
 pub use set_command_block::*;
 pub use set_creative_slot::*;
 pub use set_held_item::*;
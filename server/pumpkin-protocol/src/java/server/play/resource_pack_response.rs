@@ -0,0 +1,43 @@
+use std::io::Read;
+
+use pumpkin_data::packet::serverbound::PLAY_RESOURCE_PACK;
+use pumpkin_macros::packet;
+
+use crate::{
+    ServerPacket,
+    codec::var_int::VarInt,
+    java::server::config::ResourcePackResponseResult,
+    ser::{NetworkReadExt, ReadingError},
+};
+
+#[packet(PLAY_RESOURCE_PACK)]
+/// Progress of a resource pack that was pushed to the client while playing.
+pub struct SResourcePackResponse {
+    pub uuid: uuid::Uuid,
+    result: VarInt,
+}
+
+impl SResourcePackResponse {
+    pub fn response_result(&self) -> ResourcePackResponseResult {
+        match self.result.0 {
+            0 => ResourcePackResponseResult::DownloadSuccess,
+            1 => ResourcePackResponseResult::Declined,
+            2 => ResourcePackResponseResult::DownloadFail,
+            3 => ResourcePackResponseResult::Accepted,
+            4 => ResourcePackResponseResult::Downloaded,
+            5 => ResourcePackResponseResult::InvalidUrl,
+            6 => ResourcePackResponseResult::ReloadFailed,
+            7 => ResourcePackResponseResult::Discarded,
+            x => ResourcePackResponseResult::Unknown(x),
+        }
+    }
+}
+
+impl ServerPacket for SResourcePackResponse {
+    fn read(mut read: impl Read) -> Result<Self, ReadingError> {
+        Ok(Self {
+            uuid: read.get_uuid()?,
+            result: read.get_var_int()?,
+        })
+    }
+}
@@ -0,0 +1,319 @@
+//! Generates the mappings file of another version from the reports of both versions, as written
+//! by the extractor into `assets/`.
+//!
+//! `cargo run -p pumpkin-protocol --example generate_mappings -- <their assets> <name> <output>`
+
+use std::collections::{BTreeMap, HashMap};
+use std::{fs, io, path::Path};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, de::IgnoredAny};
+use serde_json::json;
+
+/// The reports of one version, of which only `packets.json` is required. Ids of items or block
+/// states are left as they are if either version lacks their report
+pub struct VersionReports {
+    pub packets: String,
+    pub items: Option<String>,
+    pub blocks: Option<String>,
+    pub registries: Option<String>,
+}
+
+impl VersionReports {
+    /// Reads the reports from a folder laid out like `assets/`
+    pub fn read(folder: &Path) -> io::Result<Self> {
+        let optional = |file: &str| match fs::read_to_string(folder.join(file)) {
+            Ok(json) => Ok(Some(json)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        };
+        Ok(Self {
+            packets: fs::read_to_string(folder.join("packets.json"))?,
+            items: optional("items.json")?,
+            blocks: optional("blocks.json")?,
+            registries: optional("synced_registries.json")?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct PacketsReport {
+    version: u32,
+    serverbound: BTreeMap<String, Vec<String>>,
+    clientbound: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct ItemReport {
+    id: u16,
+}
+
+#[derive(Deserialize)]
+struct BlocksReport {
+    blocks: Vec<BlockReport>,
+}
+
+#[derive(Deserialize)]
+struct BlockReport {
+    name: String,
+    properties: Vec<i32>,
+    default_state_id: u16,
+    states: Vec<StateReport>,
+}
+
+#[derive(Deserialize)]
+struct StateReport {
+    id: u16,
+}
+
+type RegistriesReport = IndexMap<String, IndexMap<String, IgnoredAny>>;
+
+/// Generates the contents of the mappings file for the version of `theirs`, named `name`
+pub fn generate_mappings(
+    name: &str,
+    ours: &VersionReports,
+    theirs: &VersionReports,
+) -> serde_json::Result<String> {
+    let packets: PacketsReport = serde_json::from_str(&theirs.packets)?;
+    let mut mappings = json!({
+        "protocol": packets.version,
+        "name": name,
+        "packets": {
+            "serverbound": packets.serverbound,
+            "clientbound": packets.clientbound,
+        },
+    });
+
+    if let (Some(ours), Some(theirs)) = (&ours.items, &theirs.items) {
+        let items = map_items(&serde_json::from_str(ours)?, &serde_json::from_str(theirs)?);
+        if let Some(items) = items {
+            mappings["items"] = json!(items);
+        }
+    }
+    if let (Some(ours), Some(theirs)) = (&ours.blocks, &theirs.blocks) {
+        let theirs: BlocksReport = serde_json::from_str(theirs)?;
+        let block_states = map_block_states(&serde_json::from_str(ours)?, &theirs);
+        if let Some(block_states) = block_states {
+            mappings["block_states"] = json!(block_states);
+            mappings["block_state_count"] = json!(
+                theirs
+                    .blocks
+                    .iter()
+                    .map(|block| block.states.len())
+                    .sum::<usize>()
+            );
+        }
+    }
+    if let (Some(ours), Some(theirs)) = (&ours.registries, &theirs.registries) {
+        let ours: RegistriesReport = serde_json::from_str(ours)?;
+        let theirs: RegistriesReport = serde_json::from_str(theirs)?;
+        // Only the registries whose entries differ, the others are sent as they are
+        let registries: BTreeMap<&String, Vec<&String>> = theirs
+            .iter()
+            .filter(|(registry, entries)| {
+                ours.get(*registry)
+                    .is_none_or(|our_entries| !our_entries.keys().eq(entries.keys()))
+            })
+            .map(|(registry, entries)| (registry, entries.keys().collect()))
+            .collect();
+        if !registries.is_empty() {
+            mappings["registries"] = json!(registries);
+        }
+    }
+
+    serde_json::to_string_pretty(&mappings)
+}
+
+/// Their id of each of our items by name, `None` if the ids are the same
+fn map_items(
+    ours: &HashMap<String, ItemReport>,
+    theirs: &HashMap<String, ItemReport>,
+) -> Option<Vec<u16>> {
+    let mut items = vec![0; ours.values().map(|item| item.id as usize + 1).max()?];
+    for (name, item) in ours {
+        // Items they don't have become air
+        items[item.id as usize] = theirs.get(name).map_or(0, |item| item.id);
+    }
+    (items.len() != theirs.len() || !is_identity(&items)).then_some(items)
+}
+
+/// Their id of each of our block states, `None` if the ids are the same. States of blocks whose
+/// properties changed become the block's default state, as they can't be matched up
+fn map_block_states(ours: &BlocksReport, theirs: &BlocksReport) -> Option<Vec<u16>> {
+    let their_blocks: HashMap<&str, &BlockReport> = theirs
+        .blocks
+        .iter()
+        .map(|block| (block.name.as_str(), block))
+        .collect();
+    let count = ours
+        .blocks
+        .iter()
+        .flat_map(|block| &block.states)
+        .map(|state| state.id as usize + 1)
+        .max()?;
+    let mut block_states = vec![0; count];
+    for block in &ours.blocks {
+        let theirs = their_blocks.get(block.name.as_str());
+        for (index, state) in block.states.iter().enumerate() {
+            block_states[state.id as usize] = match theirs {
+                Some(theirs) if theirs.properties == block.properties => theirs
+                    .states
+                    .get(index)
+                    .map_or(theirs.default_state_id, |state| state.id),
+                Some(theirs) => theirs.default_state_id,
+                None => 0,
+            };
+        }
+    }
+    let their_count = theirs.blocks.iter().map(|block| block.states.len()).sum();
+    (count != their_count || !is_identity(&block_states)).then_some(block_states)
+}
+
+fn is_identity(ids: &[u16]) -> bool {
+    ids.iter()
+        .enumerate()
+        .all(|(index, id)| index == *id as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use bytes::Bytes;
+    use pumpkin_data::item::Item;
+    use pumpkin_data::packet::{
+        CURRENT_MC_PROTOCOL, clientbound::PLAY_SET_CURSOR_ITEM,
+        serverbound::PLAY_SET_CREATIVE_MODE_SLOT,
+    };
+    use pumpkin_world::item::ItemStack;
+    use serde_json::{Map, Value, json};
+
+    use super::{VersionReports, generate_mappings};
+    use crate::{
+        ClientPacket, ConnectionState, RawPacket, ServerPacket,
+        codec::{item_stack_seralizer::ItemStackSerializer, var_int::VarInt},
+        java::{
+            client::play::CSetCursorItem, server::play::SSetCreativeSlot,
+            translation::VersionMappings,
+        },
+        packet::Packet,
+        ser::{NetworkReadExt, NetworkWriteExt},
+    };
+
+    fn our_reports() -> VersionReports {
+        VersionReports::read(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../assets")).unwrap()
+    }
+
+    /// Our reports, as if the next version added a packet in front of the others and removed
+    /// stone, shifting every id after them
+    fn next_version(ours: &VersionReports) -> VersionReports {
+        let mut packets: Value = serde_json::from_str(&ours.packets).unwrap();
+        packets["version"] = json!(CURRENT_MC_PROTOCOL + 1);
+        for direction in ["clientbound", "serverbound"] {
+            packets[direction]["play"]
+                .as_array_mut()
+                .unwrap()
+                .insert(0, json!("new_packet"));
+        }
+
+        let mut items: Map<String, Value> =
+            serde_json::from_str(ours.items.as_ref().unwrap()).unwrap();
+        items.remove("stone");
+        for item in items.values_mut() {
+            let id = item["id"].as_u64().unwrap();
+            if id > u64::from(Item::STONE.id) {
+                item["id"] = json!(id - 1);
+            }
+        }
+
+        VersionReports {
+            packets: packets.to_string(),
+            items: Some(Value::Object(items).to_string()),
+            blocks: None,
+            registries: None,
+        }
+    }
+
+    #[test]
+    fn translates_real_packets_both_ways() {
+        let ours = our_reports();
+        let json = generate_mappings("next", &ours, &next_version(&ours)).unwrap();
+        let mappings = VersionMappings::from_json(&json).unwrap();
+        assert_eq!(mappings.protocol, CURRENT_MC_PROTOCOL + 1);
+        assert_eq!(mappings.remap_item(Item::STONE.id), 0);
+
+        let stack = ItemStackSerializer::from(ItemStack::new(3, &Item::DIRT));
+        let mut packet = Vec::new();
+        packet
+            .write_var_int(&VarInt(CSetCursorItem::PACKET_ID))
+            .unwrap();
+        CSetCursorItem::new(&stack)
+            .write_packet_data(&mut packet)
+            .unwrap();
+        let translated = mappings
+            .translate_clientbound(ConnectionState::Play, &Bytes::from(packet))
+            .unwrap()
+            .unwrap();
+        let mut read = &translated[..];
+        assert_eq!(read.get_var_int().unwrap().0, PLAY_SET_CURSOR_ITEM + 1);
+        let their_stack = read;
+        assert_eq!(read.get_var_int().unwrap().0, 3);
+        assert_eq!(read.get_var_int().unwrap().0, i32::from(Item::DIRT.id) - 1);
+
+        // The client puts the stack back into its inventory
+        let mut payload = Vec::new();
+        payload.write_i16_be(36).unwrap();
+        payload.write_slice(their_stack).unwrap();
+        let packet = mappings
+            .translate_serverbound(
+                ConnectionState::Play,
+                RawPacket {
+                    id: PLAY_SET_CREATIVE_MODE_SLOT + 1,
+                    payload: payload.into(),
+                },
+            )
+            .unwrap();
+        assert_eq!(packet.id, PLAY_SET_CREATIVE_MODE_SLOT);
+        let packet = SSetCreativeSlot::read(&packet.payload[..]).unwrap();
+        assert_eq!(packet.slot, 36);
+        let stack = packet.clicked_item.to_stack();
+        assert_eq!(stack.item.id, Item::DIRT.id);
+        assert_eq!(stack.item_count, 3);
+    }
+
+    #[test]
+    fn maps_block_states_by_name_and_properties() {
+        let reports = |blocks: Value| VersionReports {
+            packets: r#"{"version": 1, "serverbound": {}, "clientbound": {}}"#.to_string(),
+            items: None,
+            blocks: Some(json!({ "blocks": blocks }).to_string()),
+            registries: None,
+        };
+        let ours = reports(json!([
+            {"name": "air", "properties": [], "default_state_id": 0, "states": [{"id": 0}]},
+            {"name": "lever", "properties": [1], "default_state_id": 1,
+                "states": [{"id": 1}, {"id": 2}]},
+            {"name": "door", "properties": [2], "default_state_id": 4,
+                "states": [{"id": 3}, {"id": 4}]},
+            {"name": "new_block", "properties": [], "default_state_id": 5, "states": [{"id": 5}]},
+        ]));
+        let theirs = reports(json!([
+            {"name": "air", "properties": [], "default_state_id": 0, "states": [{"id": 0}]},
+            {"name": "door", "properties": [3], "default_state_id": 1,
+                "states": [{"id": 1}, {"id": 2}, {"id": 3}]},
+            {"name": "lever", "properties": [1], "default_state_id": 4,
+                "states": [{"id": 4}, {"id": 5}]},
+        ]));
+
+        let json = generate_mappings("test", &ours, &theirs).unwrap();
+        let mappings: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(mappings["block_states"], json!([0, 4, 5, 1, 1, 0]));
+        assert_eq!(mappings["block_state_count"], json!(6));
+        assert!(mappings.get("items").is_none());
+
+        // The same version needs no block state mappings
+        let json = generate_mappings("test", &ours, &ours).unwrap();
+        let mappings: Value = serde_json::from_str(&json).unwrap();
+        assert!(mappings.get("block_states").is_none());
+    }
+}
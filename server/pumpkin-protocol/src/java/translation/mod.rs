@@ -0,0 +1,575 @@
+//! Lets clients on other protocol versions of the same minor release join.
+//!
+//! Every other version is described by a [`VersionMappings`], loaded from a mappings file holding
+//! that version's packet report and its block state, item and registry ids. The mappings files
+//! are generated from the extractor's reports of both versions, see [`generate`].
+//!
+//! Packets are written and read in the server's own format and translated right at the
+//! connection: packet ids are matched by name, and packets whose fields changed go through a
+//! [`PacketRemapper`]. Block states are remapped in block updates and chunk sections, and items
+//! in every packet carrying item stacks.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use pumpkin_data::packet::{CURRENT_MC_PROTOCOL, clientbound, serverbound};
+use pumpkin_util::encompassing_bits;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    ConnectionState, RawPacket,
+    codec::var_int::VarInt,
+    ser::{NetworkReadExt, NetworkWriteExt, ReadingError, WritingError},
+};
+
+pub mod generate;
+mod remappers;
+
+/// Rewrites the payload of a packet whose fields differ between versions, reading it from the
+/// first argument and writing the translated payload to the second
+pub type PacketRemapper =
+    fn(&mut &[u8], &mut Vec<u8>, &VersionMappings) -> Result<(), TranslationError>;
+
+#[derive(Error, Debug)]
+pub enum TranslationError {
+    #[error("No packet with id {id} in state {state:?} exists in the other version")]
+    UnknownPacket { state: ConnectionState, id: i32 },
+    #[error("Failed to read packet: {0}")]
+    Reading(#[from] ReadingError),
+    #[error("Failed to write packet: {0}")]
+    Writing(#[from] WritingError),
+    #[error("Data component {0} can't be translated")]
+    UnsupportedComponent(i32),
+}
+
+/// The contents of a mappings file
+#[derive(Deserialize)]
+struct MappingsFile {
+    protocol: u32,
+    name: String,
+    packets: PacketReport,
+    /// The id of each of our block states in the other version, indexed by our id
+    #[serde(default)]
+    block_states: Vec<u16>,
+    /// How many block states the other version has, the highest mapped id + 1 if missing
+    #[serde(default)]
+    block_state_count: Option<usize>,
+    /// The id of each of our items in the other version, indexed by our id
+    #[serde(default)]
+    items: Vec<u16>,
+    /// The entries the other version knows of each synced registry, in its order
+    #[serde(default)]
+    registries: BTreeMap<String, Vec<String>>,
+}
+
+/// Same layout as `packets.json`
+#[derive(Deserialize)]
+struct PacketReport {
+    serverbound: BTreeMap<String, Vec<String>>,
+    clientbound: BTreeMap<String, Vec<String>>,
+}
+
+/// How to talk to clients on one other protocol version
+pub struct VersionMappings {
+    pub protocol: u32,
+    /// The game version, like `1.21.9`
+    pub name: String,
+    /// Our clientbound id to their id and our packet name
+    clientbound: HashMap<(&'static str, i32), (i32, &'static str)>,
+    /// Their serverbound id to our id and our packet name
+    serverbound: HashMap<(&'static str, i32), (i32, &'static str)>,
+    clientbound_remappers: HashMap<&'static str, PacketRemapper>,
+    serverbound_remappers: HashMap<&'static str, PacketRemapper>,
+    block_states: Vec<u16>,
+    /// Bits per entry of chunk sections storing block states directly in the other version
+    direct_block_state_bits: u8,
+    items: Vec<u16>,
+    /// Our id of each of the other version's items, indexed by their id
+    unmapped_items: Vec<u16>,
+    registries: BTreeMap<String, Vec<String>>,
+}
+
+impl VersionMappings {
+    /// Reads the mappings of a version from the contents of its mappings file
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let file: MappingsFile = serde_json::from_str(json)?;
+
+        let block_state_count = file.block_state_count.unwrap_or_else(|| {
+            file.block_states
+                .iter()
+                .max()
+                .map_or(0, |max| *max as usize + 1)
+        });
+        let mut unmapped_items =
+            vec![0; file.items.iter().max().map_or(0, |max| *max as usize + 1)];
+        // Backwards, so the first of our items wins when several map to the same one
+        for (our_id, their_id) in file.items.iter().enumerate().rev() {
+            unmapped_items[*their_id as usize] = our_id as u16;
+        }
+
+        let mut mappings = Self {
+            protocol: file.protocol,
+            name: file.name,
+            clientbound: HashMap::new(),
+            serverbound: HashMap::new(),
+            clientbound_remappers: HashMap::new(),
+            serverbound_remappers: HashMap::new(),
+            block_states: file.block_states,
+            direct_block_state_bits: encompassing_bits(block_state_count.max(1)),
+            items: file.items,
+            unmapped_items,
+            registries: file.registries,
+        };
+
+        let their_clientbound = packet_ids_by_name(&file.packets.clientbound);
+        for (name, id) in clientbound::ALL {
+            if let Some(their_id) = their_clientbound.get(*name) {
+                mappings
+                    .clientbound
+                    .insert((phase_of(*name), *id), (*their_id, *name));
+            }
+        }
+        let their_serverbound = packet_ids_by_name(&file.packets.serverbound);
+        for (name, id) in serverbound::ALL {
+            if let Some(their_id) = their_serverbound.get(*name) {
+                mappings
+                    .serverbound
+                    .insert((phase_of(*name), *their_id), (*id, *name));
+            }
+        }
+
+        remappers::register(&mut mappings);
+        Ok(mappings)
+    }
+
+    /// Runs `remapper` on the clientbound packet with the constant name `packet`, like
+    /// `PLAY_BLOCK_UPDATE`, replacing any remapper already registered for it
+    pub fn register_clientbound_remapper(
+        &mut self,
+        packet: &'static str,
+        remapper: PacketRemapper,
+    ) {
+        self.clientbound_remappers.insert(packet, remapper);
+    }
+
+    /// Runs `remapper` on the serverbound packet with the constant name `packet`
+    pub fn register_serverbound_remapper(
+        &mut self,
+        packet: &'static str,
+        remapper: PacketRemapper,
+    ) {
+        self.serverbound_remappers.insert(packet, remapper);
+    }
+
+    /// The id the other version uses for our block state, states it doesn't have become air
+    #[must_use]
+    pub fn remap_block_state(&self, state_id: u16) -> u16 {
+        if self.block_states.is_empty() {
+            return state_id;
+        }
+        self.block_states
+            .get(state_id as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The id the other version uses for our item, items it doesn't have become air
+    #[must_use]
+    pub fn remap_item(&self, item_id: u16) -> u16 {
+        if self.items.is_empty() {
+            return item_id;
+        }
+        self.items.get(item_id as usize).copied().unwrap_or(0)
+    }
+
+    /// Our id of an item of the other version, items we don't have become air
+    #[must_use]
+    pub fn unmap_item(&self, item_id: u16) -> u16 {
+        if self.items.is_empty() {
+            return item_id;
+        }
+        self.unmapped_items
+            .get(item_id as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Bits per entry of a chunk section holding the other version's block states directly,
+    /// `None` if it matches ours
+    #[must_use]
+    pub fn direct_block_state_bits(&self) -> Option<u8> {
+        (!self.block_states.is_empty()).then_some(self.direct_block_state_bits)
+    }
+
+    /// The entries the other version knows of a synced registry, `None` if they match ours
+    #[must_use]
+    pub fn registry_entries(&self, registry: &str) -> Option<&[String]> {
+        self.registries.get(registry).map(Vec::as_slice)
+    }
+
+    /// Translates a serialized clientbound packet (id followed by payload) into the other
+    /// version's format, `None` if the other version doesn't have this packet
+    pub fn translate_clientbound(
+        &self,
+        state: ConnectionState,
+        packet: &Bytes,
+    ) -> Result<Option<Bytes>, TranslationError> {
+        let mut read = &packet[..];
+        let id = read.get_var_int()?.0;
+        let Some((their_id, name)) = self.clientbound.get(&(state_phase(state), id)) else {
+            return Ok(None);
+        };
+
+        let mut translated = Vec::with_capacity(packet.len());
+        translated.write_var_int(&VarInt(*their_id))?;
+        match self.clientbound_remappers.get(name) {
+            Some(remapper) => remapper(&mut read, &mut translated, self)?,
+            None => translated.extend_from_slice(read),
+        }
+        Ok(Some(translated.into()))
+    }
+
+    /// Translates a packet received from the client into our own format
+    pub fn translate_serverbound(
+        &self,
+        state: ConnectionState,
+        packet: RawPacket,
+    ) -> Result<RawPacket, TranslationError> {
+        let Some((our_id, name)) = self.serverbound.get(&(state_phase(state), packet.id)) else {
+            return Err(TranslationError::UnknownPacket {
+                state,
+                id: packet.id,
+            });
+        };
+
+        let payload = match self.serverbound_remappers.get(name) {
+            Some(remapper) => {
+                let mut translated = Vec::with_capacity(packet.payload.len());
+                remapper(&mut &packet.payload[..], &mut translated, self)?;
+                translated.into()
+            }
+            None => packet.payload,
+        };
+        Ok(RawPacket {
+            id: *our_id,
+            payload,
+        })
+    }
+}
+
+/// The protocol versions clients may join with
+pub struct SupportedVersions {
+    min_version: u32,
+    max_version: u32,
+    versions: HashMap<u32, Arc<VersionMappings>>,
+}
+
+/// Why a client can't join with its protocol version
+#[derive(Debug, PartialEq, Eq)]
+pub enum UnsupportedVersion {
+    Outdated,
+    Incompatible,
+}
+
+impl SupportedVersions {
+    /// Only our own version is supported until mappings are added, and only those in
+    /// `min_version..=max_version` will be accepted
+    #[must_use]
+    pub fn new(min_version: u32, max_version: u32) -> Self {
+        Self {
+            min_version,
+            max_version,
+            versions: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, mappings: VersionMappings) {
+        self.versions.insert(mappings.protocol, Arc::new(mappings));
+    }
+
+    /// The names of the supported versions other than ours, oldest first
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        let mut versions = self
+            .versions
+            .values()
+            .filter(|mappings| self.in_range(mappings.protocol))
+            .collect::<Vec<_>>();
+        versions.sort_by_key(|mappings| mappings.protocol);
+        versions
+            .into_iter()
+            .map(|mappings| mappings.name.as_str())
+            .collect()
+    }
+
+    fn in_range(&self, protocol: u32) -> bool {
+        (self.min_version..=self.max_version).contains(&protocol)
+    }
+
+    /// The mappings a client on `protocol` needs, `None` if it speaks our protocol
+    pub fn get(&self, protocol: i32) -> Result<Option<Arc<VersionMappings>>, UnsupportedVersion> {
+        let unsupported = if protocol < CURRENT_MC_PROTOCOL as i32 {
+            UnsupportedVersion::Outdated
+        } else {
+            UnsupportedVersion::Incompatible
+        };
+        let Ok(protocol) = u32::try_from(protocol) else {
+            return Err(unsupported);
+        };
+        if protocol == CURRENT_MC_PROTOCOL {
+            return Ok(None);
+        }
+        if !self.in_range(protocol) {
+            return Err(unsupported);
+        }
+        self.versions
+            .get(&protocol)
+            .map(|mappings| Some(mappings.clone()))
+            .ok_or(unsupported)
+    }
+}
+
+/// The prefix of the packet constant names of a connection state
+pub(crate) fn state_phase(state: ConnectionState) -> &'static str {
+    match state {
+        ConnectionState::HandShake => "HANDSHAKE",
+        ConnectionState::Status => "STATUS",
+        ConnectionState::Login | ConnectionState::Transfer => "LOGIN",
+        ConnectionState::Config => "CONFIG",
+        ConnectionState::Play => "PLAY",
+    }
+}
+
+fn phase_of(name: &'static str) -> &'static str {
+    name.split_once('_').map_or(name, |(phase, _)| phase)
+}
+
+/// Names the packets of a report the same way our packet constants are named
+fn packet_ids_by_name(report: &BTreeMap<String, Vec<String>>) -> HashMap<String, i32> {
+    let mut ids = HashMap::new();
+    for (phase, packets) in report {
+        for (id, packet) in packets.iter().enumerate() {
+            let name = format!("{phase}_{}", packet.replace('/', "_")).to_uppercase();
+            ids.insert(name, id as i32);
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+    use pumpkin_data::data_component::DataComponent;
+    use pumpkin_data::packet::clientbound::{
+        PLAY_BLOCK_UPDATE, PLAY_CONTAINER_SET_SLOT, PLAY_LEVEL_CHUNK_WITH_LIGHT,
+    };
+
+    use super::{SupportedVersions, UnsupportedVersion, VersionMappings};
+    use crate::{
+        ConnectionState,
+        codec::var_int::VarInt,
+        ser::{NetworkReadExt, NetworkWriteExt},
+    };
+
+    fn play_packet(id: i32) -> String {
+        let (name, _) = pumpkin_data::packet::clientbound::ALL
+            .iter()
+            .find(|(name, packet_id)| *packet_id == id && name.starts_with("PLAY_"))
+            .unwrap();
+        name["PLAY_".len()..].to_lowercase()
+    }
+
+    fn mappings(protocol: u32) -> VersionMappings {
+        let block_update = play_packet(PLAY_BLOCK_UPDATE);
+        let chunk = play_packet(PLAY_LEVEL_CHUNK_WITH_LIGHT);
+        let set_slot = play_packet(PLAY_CONTAINER_SET_SLOT);
+        // Pretend the other version has one packet before block_update, and swaps two states
+        // and two items
+        let json = format!(
+            r#"{{
+                "protocol": {protocol},
+                "name": "test",
+                "packets": {{
+                    "serverbound": {{}},
+                    "clientbound": {{
+                        "play": ["something_else", "{block_update}", "{chunk}", "{set_slot}"]
+                    }}
+                }},
+                "block_states": [0, 2, 1],
+                "items": [0, 2, 1]
+            }}"#
+        );
+        VersionMappings::from_json(&json).unwrap()
+    }
+
+    #[test]
+    fn remaps_block_update() {
+        let mappings = mappings(1);
+
+        let mut packet = Vec::new();
+        packet.write_var_int(&VarInt(PLAY_BLOCK_UPDATE)).unwrap();
+        packet.write_i64_be(42).unwrap();
+        packet.write_var_int(&VarInt(1)).unwrap();
+
+        let translated = mappings
+            .translate_clientbound(ConnectionState::Play, &Bytes::from(packet))
+            .unwrap()
+            .unwrap();
+        let mut read = &translated[..];
+        assert_eq!(read.get_var_int().unwrap().0, 1);
+        assert_eq!(read.get_i64_be().unwrap(), 42);
+        assert_eq!(read.get_var_int().unwrap().0, 2);
+        assert!(read.is_empty());
+
+        // Packets the other version doesn't know are dropped
+        let mut unknown = Vec::new();
+        unknown.write_var_int(&VarInt(PLAY_BLOCK_UPDATE)).unwrap();
+        assert!(
+            mappings
+                .translate_clientbound(ConnectionState::Config, &Bytes::from(unknown))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn remaps_chunk_sections() {
+        let mappings = mappings(1);
+
+        let mut packet = Vec::new();
+        packet
+            .write_var_int(&VarInt(PLAY_LEVEL_CHUNK_WITH_LIGHT))
+            .unwrap();
+        packet.write_i32_be(3).unwrap();
+        packet.write_i32_be(-4).unwrap();
+        // No heightmaps
+        packet.write_var_int(&VarInt(0)).unwrap();
+
+        let mut sections = Vec::new();
+        // A palette of air and state 1
+        sections.write_i16_be(1).unwrap();
+        sections.write_u8(4).unwrap();
+        sections.write_var_int(&VarInt(2)).unwrap();
+        sections.write_var_int(&VarInt(0)).unwrap();
+        sections.write_var_int(&VarInt(1)).unwrap();
+        sections.write_i64_be(0b0001).unwrap();
+        for _ in 1..256 {
+            sections.write_i64_be(0).unwrap();
+        }
+        sections.write_u8(0).unwrap();
+        sections.write_var_int(&VarInt(0)).unwrap();
+        // The same, stored directly with 15 bits per state
+        sections.write_i16_be(1).unwrap();
+        sections.write_u8(15).unwrap();
+        sections.write_i64_be(1).unwrap();
+        for _ in 1..1024 {
+            sections.write_i64_be(0).unwrap();
+        }
+        sections.write_u8(0).unwrap();
+        sections.write_var_int(&VarInt(0)).unwrap();
+
+        packet
+            .write_var_int(&VarInt(sections.len() as i32))
+            .unwrap();
+        packet.write_slice(&sections).unwrap();
+        // Block entities and light
+        packet.write_slice(&[7, 7]).unwrap();
+
+        let translated = mappings
+            .translate_clientbound(ConnectionState::Play, &Bytes::from(packet))
+            .unwrap()
+            .unwrap();
+        let mut read = &translated[..];
+        assert_eq!(read.get_var_int().unwrap().0, 2);
+        assert_eq!(read.get_i32_be().unwrap(), 3);
+        assert_eq!(read.get_i32_be().unwrap(), -4);
+        assert_eq!(read.get_var_int().unwrap().0, 0);
+        read.get_var_int().unwrap();
+
+        assert_eq!(read.get_i16_be().unwrap(), 1);
+        assert_eq!(read.get_u8().unwrap(), 4);
+        assert_eq!(read.get_var_int().unwrap().0, 2);
+        assert_eq!(read.get_var_int().unwrap().0, 0);
+        assert_eq!(read.get_var_int().unwrap().0, 2);
+        assert_eq!(read.get_i64_be().unwrap(), 0b0001);
+        read.read_boxed_slice(255 * 8).unwrap();
+        assert_eq!(read.get_u8().unwrap(), 0);
+        assert_eq!(read.get_var_int().unwrap().0, 0);
+
+        // The other version has 3 states, which fit in 2 bits
+        assert_eq!(read.get_i16_be().unwrap(), 1);
+        assert_eq!(read.get_u8().unwrap(), 2);
+        assert_eq!(read.get_i64_be().unwrap(), 2);
+        read.read_boxed_slice(127 * 8).unwrap();
+        assert_eq!(read.get_u8().unwrap(), 0);
+        assert_eq!(read.get_var_int().unwrap().0, 0);
+
+        assert_eq!(read, &[7, 7]);
+    }
+
+    #[test]
+    fn remaps_slots() {
+        let mappings = mappings(1);
+
+        let mut packet = Vec::new();
+        packet
+            .write_var_int(&VarInt(PLAY_CONTAINER_SET_SLOT))
+            .unwrap();
+        packet.write_i8(0).unwrap();
+        packet.write_var_int(&VarInt(9)).unwrap();
+        packet.write_i16_be(5).unwrap();
+        packet.write_var_int(&VarInt(16)).unwrap();
+        packet.write_var_int(&VarInt(1)).unwrap();
+        packet.write_var_int(&VarInt(1)).unwrap();
+        packet.write_var_int(&VarInt(0)).unwrap();
+        packet
+            .write_var_int(&VarInt(i32::from(DataComponent::MaxStackSize.to_id())))
+            .unwrap();
+        packet.write_var_int(&VarInt(16)).unwrap();
+
+        let translated = mappings
+            .translate_clientbound(ConnectionState::Play, &Bytes::from(packet))
+            .unwrap()
+            .unwrap();
+        let mut read = &translated[..];
+        assert_eq!(read.get_var_int().unwrap().0, 3);
+        assert_eq!(read.get_i8().unwrap(), 0);
+        assert_eq!(read.get_var_int().unwrap().0, 9);
+        assert_eq!(read.get_i16_be().unwrap(), 5);
+        assert_eq!(read.get_var_int().unwrap().0, 16);
+        assert_eq!(read.get_var_int().unwrap().0, 2);
+        assert_eq!(read.get_var_int().unwrap().0, 1);
+        assert_eq!(read.get_var_int().unwrap().0, 0);
+        assert_eq!(
+            read.get_var_int().unwrap().0,
+            i32::from(DataComponent::MaxStackSize.to_id())
+        );
+        assert_eq!(read.get_var_int().unwrap().0, 16);
+        assert!(read.is_empty());
+
+        assert_eq!(mappings.unmap_item(2), 1);
+        assert_eq!(mappings.unmap_item(7), 0);
+    }
+
+    #[test]
+    fn version_range() {
+        let mut versions = SupportedVersions::new(2, u32::MAX);
+        versions.add(mappings(1));
+        versions.add(mappings(3));
+
+        assert!(
+            versions
+                .get(super::CURRENT_MC_PROTOCOL as i32)
+                .unwrap()
+                .is_none()
+        );
+        assert!(versions.get(3).unwrap().is_some());
+        // Known but outside the configured range
+        assert_eq!(versions.get(1).unwrap_err(), UnsupportedVersion::Outdated);
+        assert_eq!(versions.get(-5).unwrap_err(), UnsupportedVersion::Outdated);
+        assert_eq!(versions.get(2).unwrap_err(), UnsupportedVersion::Outdated);
+        assert_eq!(versions.names(), vec!["test"]);
+    }
+}
@@ -0,0 +1,378 @@
+//! Remappers for the packets carrying block state ids and item stacks
+
+use std::io::Cursor;
+
+use pumpkin_data::data_component::DataComponent;
+use pumpkin_nbt::{Nbt, deserializer::NbtReadHelper};
+
+use crate::{
+    codec::{var_int::VarInt, var_long::VarLong},
+    ser::{NetworkReadExt, NetworkWriteExt, ReadingError},
+};
+
+use super::{TranslationError, VersionMappings};
+
+pub(super) fn register(mappings: &mut VersionMappings) {
+    mappings.register_clientbound_remapper("PLAY_BLOCK_UPDATE", block_update);
+    mappings.register_clientbound_remapper("PLAY_SECTION_BLOCKS_UPDATE", section_blocks_update);
+    mappings.register_clientbound_remapper("PLAY_LEVEL_CHUNK_WITH_LIGHT", level_chunk_with_light);
+    mappings.register_clientbound_remapper("PLAY_CONTAINER_SET_CONTENT", container_set_content);
+    mappings.register_clientbound_remapper("PLAY_CONTAINER_SET_SLOT", container_set_slot);
+    mappings.register_clientbound_remapper("PLAY_SET_CURSOR_ITEM", slot);
+    mappings.register_clientbound_remapper("PLAY_SET_PLAYER_INVENTORY", set_player_inventory);
+    mappings.register_clientbound_remapper("PLAY_SET_EQUIPMENT", set_equipment);
+    mappings.register_clientbound_remapper("PLAY_SET_ENTITY_DATA", set_entity_data);
+    mappings.register_serverbound_remapper("PLAY_SET_CREATIVE_MODE_SLOT", set_creative_mode_slot);
+}
+
+/// Entries in the block states of a chunk section
+const SECTION_BLOCKS: usize = 16 * 16 * 16;
+/// Entries in the biomes of a chunk section
+const SECTION_BIOMES: usize = 4 * 4 * 4;
+/// Block states are stored directly above this many bits per entry
+const MAX_INDIRECT_BLOCK_BITS: u8 = 8;
+/// Biomes are stored directly above this many bits per entry
+const MAX_INDIRECT_BIOME_BITS: u8 = 3;
+
+fn remap_state(mappings: &VersionMappings, state_id: i64) -> i64 {
+    i64::from(mappings.remap_block_state(state_id as u16))
+}
+
+/// Position followed by a block state id
+fn block_update(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    write.write_i64_be(read.get_i64_be()?)?;
+    let state_id = read.get_var_int()?.0;
+    write.write_var_int(&VarInt(remap_state(mappings, i64::from(state_id)) as i32))?;
+    Ok(())
+}
+
+/// Section position followed by a list of block state ids packed with their position
+fn section_blocks_update(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    write.write_i64_be(read.get_i64_be()?)?;
+    let count = read.get_var_int()?;
+    write.write_var_int(&count)?;
+    for _ in 0..count.0 {
+        let packed = read.get_var_long()?.0;
+        let state_id = remap_state(mappings, packed >> 12);
+        write.write_var_long(&VarLong((state_id << 12) | (packed & 0xFFF)))?;
+    }
+    Ok(())
+}
+
+fn copy_var_int(read: &mut &[u8], write: &mut Vec<u8>) -> Result<i32, TranslationError> {
+    let value = read.get_var_int()?;
+    write.write_var_int(&value)?;
+    Ok(value.0)
+}
+
+fn copy_bytes(read: &mut &[u8], write: &mut Vec<u8>, count: usize) -> Result<(), TranslationError> {
+    write.write_slice(&read.read_boxed_slice(count)?)?;
+    Ok(())
+}
+
+fn copy_remaining(read: &mut &[u8], write: &mut Vec<u8>) -> Result<(), TranslationError> {
+    write.write_slice(read)?;
+    *read = &[];
+    Ok(())
+}
+
+fn length(value: i32) -> Result<usize, TranslationError> {
+    usize::try_from(value)
+        .map_err(|_| ReadingError::Message(format!("Negative length {value}")).into())
+}
+
+/// How many longs the entries of a paletted container are packed into
+fn packed_longs(entries: usize, bits: u8) -> usize {
+    entries.div_ceil(64 / bits as usize)
+}
+
+/// Position, heightmaps and the sections of a chunk, followed by its block entities and light
+fn level_chunk_with_light(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    write.write_i32_be(read.get_i32_be()?)?;
+    write.write_i32_be(read.get_i32_be()?)?;
+
+    let heightmaps = copy_var_int(read, write)?;
+    for _ in 0..heightmaps {
+        copy_var_int(read, write)?;
+        let longs = length(copy_var_int(read, write)?)?;
+        copy_bytes(read, write, longs * 8)?;
+    }
+
+    // Palettes change size with their ids, so the sections are length prefixed again
+    let size = length(read.get_var_int()?.0)?;
+    let sections = read.read_boxed_slice(size)?;
+    let mut sections = &sections[..];
+    let mut translated = Vec::with_capacity(size);
+    while !sections.is_empty() {
+        translated.write_i16_be(sections.get_i16_be()?)?;
+        block_states(&mut sections, &mut translated, mappings)?;
+        biomes(&mut sections, &mut translated)?;
+    }
+    write.write_var_int(&VarInt(translated.len() as i32))?;
+    write.write_slice(&translated)?;
+
+    copy_remaining(read, write)
+}
+
+/// The block states of a chunk section, as a single state, a palette or directly stored states
+fn block_states(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    let bits = read.get_u8()?;
+    if bits == 0 {
+        write.write_u8(bits)?;
+        let state_id = read.get_var_int()?.0;
+        write.write_var_int(&VarInt(remap_state(mappings, i64::from(state_id)) as i32))?;
+        return Ok(());
+    }
+    if bits <= MAX_INDIRECT_BLOCK_BITS {
+        write.write_u8(bits)?;
+        let palette = copy_var_int(read, write)?;
+        for _ in 0..palette {
+            let state_id = read.get_var_int()?.0;
+            write.write_var_int(&VarInt(remap_state(mappings, i64::from(state_id)) as i32))?;
+        }
+        return copy_bytes(read, write, packed_longs(SECTION_BLOCKS, bits) * 8);
+    }
+
+    let Some(their_bits) = mappings.direct_block_state_bits() else {
+        write.write_u8(bits)?;
+        return copy_bytes(read, write, packed_longs(SECTION_BLOCKS, bits) * 8);
+    };
+    let mask = (1 << bits) - 1;
+    let per_long = 64 / bits as usize;
+    let mut states = Vec::with_capacity(SECTION_BLOCKS);
+    for _ in 0..packed_longs(SECTION_BLOCKS, bits) {
+        let packed = read.get_i64_be()?;
+        for index in 0..per_long {
+            if states.len() < SECTION_BLOCKS {
+                states.push((packed >> (index * bits as usize)) & mask);
+            }
+        }
+    }
+
+    write.write_u8(their_bits)?;
+    for chunk in states.chunks(64 / their_bits as usize) {
+        let packed = chunk.iter().enumerate().fold(0, |acc, (index, state_id)| {
+            acc | (remap_state(mappings, *state_id) << (index * their_bits as usize))
+        });
+        write.write_i64_be(packed)?;
+    }
+    Ok(())
+}
+
+/// The biomes of a chunk section, registry ids are synced per version and left as they are
+fn biomes(read: &mut &[u8], write: &mut Vec<u8>) -> Result<(), TranslationError> {
+    let bits = read.get_u8()?;
+    write.write_u8(bits)?;
+    if bits == 0 {
+        copy_var_int(read, write)?;
+        return Ok(());
+    }
+    if bits <= MAX_INDIRECT_BIOME_BITS {
+        let palette = copy_var_int(read, write)?;
+        for _ in 0..palette {
+            copy_var_int(read, write)?;
+        }
+    }
+    copy_bytes(read, write, packed_longs(SECTION_BIOMES, bits) * 8)
+}
+
+/// An item stack: count, item id and the components added and removed
+fn slot(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    if copy_var_int(read, write)? <= 0 {
+        return Ok(());
+    }
+    let item_id = read.get_var_int()?.0;
+    write.write_var_int(&VarInt(i32::from(mappings.remap_item(item_id as u16))))?;
+
+    let to_add = copy_var_int(read, write)?;
+    let to_remove = copy_var_int(read, write)?;
+    for _ in 0..to_add {
+        let id = copy_var_int(read, write)?;
+        component(read, write, id)?;
+    }
+    for _ in 0..to_remove {
+        copy_var_int(read, write)?;
+    }
+    Ok(())
+}
+
+/// The data of an added component, which isn't length prefixed in clientbound stacks, so only
+/// the components we write can be copied
+fn component(read: &mut &[u8], write: &mut Vec<u8>, id: i32) -> Result<(), TranslationError> {
+    let component = u8::try_from(id)
+        .ok()
+        .and_then(DataComponent::try_from_id)
+        .ok_or(TranslationError::UnsupportedComponent(id))?;
+    match component {
+        DataComponent::MaxStackSize | DataComponent::Damage => {
+            copy_var_int(read, write)?;
+        }
+        DataComponent::Enchantments => {
+            let enchantments = copy_var_int(read, write)?;
+            for _ in 0..enchantments {
+                copy_var_int(read, write)?;
+                copy_var_int(read, write)?;
+            }
+        }
+        DataComponent::CustomData => {
+            let mut cursor = Cursor::new(*read);
+            Nbt::read_unnamed(&mut NbtReadHelper::new(&mut cursor))
+                .map_err(|err| ReadingError::Message(format!("Invalid CustomData NBT: {err}")))?;
+            copy_bytes(read, write, cursor.position() as usize)?;
+        }
+        _ => return Err(TranslationError::UnsupportedComponent(id)),
+    }
+    Ok(())
+}
+
+/// Window and state id, the slots of the window and the item carried by the cursor
+fn container_set_content(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    copy_var_int(read, write)?;
+    copy_var_int(read, write)?;
+    let slots = copy_var_int(read, write)?;
+    for _ in 0..slots {
+        slot(read, write, mappings)?;
+    }
+    slot(read, write, mappings)
+}
+
+/// Window, state id and slot index followed by the stack in the slot
+fn container_set_slot(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    write.write_i8(read.get_i8()?)?;
+    copy_var_int(read, write)?;
+    write.write_i16_be(read.get_i16_be()?)?;
+    slot(read, write, mappings)
+}
+
+/// Slot index followed by the stack in the slot
+fn set_player_inventory(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    copy_var_int(read, write)?;
+    slot(read, write, mappings)
+}
+
+/// Entity id followed by equipment slots and their stacks, the top bit of a slot marks that
+/// another one follows
+fn set_equipment(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    copy_var_int(read, write)?;
+    while !read.is_empty() {
+        let equipment_slot = read.get_i8()?;
+        write.write_i8(equipment_slot)?;
+        slot(read, write, mappings)?;
+        if equipment_slot >= 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Entity id followed by typed metadata entries up to the `0xFF` index. Entries after one we
+/// can't tell the size of are copied as they are
+fn set_entity_data(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    copy_var_int(read, write)?;
+    loop {
+        let index = read.get_u8()?;
+        write.write_u8(index)?;
+        if index == 0xFF {
+            return Ok(());
+        }
+        match copy_var_int(read, write)? {
+            // Byte, boolean
+            0 | 8 => write.write_u8(read.get_u8()?)?,
+            // Integer, facing, optional integer, pose and the variants and states
+            1 | 12 | 19..=27 | 30..=33 => {
+                copy_var_int(read, write)?;
+            }
+            2 => write.write_var_long(&read.get_var_long()?)?,
+            3 => copy_bytes(read, write, 4)?,
+            7 => slot(read, write, mappings)?,
+            // Rotation, vector
+            9 | 34 => copy_bytes(read, write, 12)?,
+            10 => copy_bytes(read, write, 8)?,
+            11 => {
+                let present = read.get_bool()?;
+                write.write_bool(present)?;
+                if present {
+                    copy_bytes(read, write, 8)?;
+                }
+            }
+            // Optional block states use 0 for absent, which is air either way
+            14 | 15 => {
+                let state_id = read.get_var_int()?.0;
+                write.write_var_int(&VarInt(remap_state(mappings, i64::from(state_id)) as i32))?;
+            }
+            18 => {
+                for _ in 0..3 {
+                    copy_var_int(read, write)?;
+                }
+            }
+            35 => copy_bytes(read, write, 16)?,
+            _ => return copy_remaining(read, write),
+        }
+    }
+}
+
+/// Slot index followed by a stack, whose components are length prefixed when sent by the client
+fn set_creative_mode_slot(
+    read: &mut &[u8],
+    write: &mut Vec<u8>,
+    mappings: &VersionMappings,
+) -> Result<(), TranslationError> {
+    write.write_i16_be(read.get_i16_be()?)?;
+    if copy_var_int(read, write)? <= 0 {
+        return Ok(());
+    }
+    let item_id = read.get_var_int()?.0;
+    write.write_var_int(&VarInt(i32::from(mappings.unmap_item(item_id as u16))))?;
+
+    let to_add = copy_var_int(read, write)?;
+    let to_remove = copy_var_int(read, write)?;
+    for _ in 0..to_add {
+        copy_var_int(read, write)?;
+        let byte_len = length(copy_var_int(read, write)?)?;
+        copy_bytes(read, write, byte_len)?;
+    }
+    for _ in 0..to_remove {
+        copy_var_int(read, write)?;
+    }
+    Ok(())
+}
@@ -28,6 +28,7 @@ pub mod java;
 pub mod packet;
 #[cfg(feature = "query")]
 pub mod query;
+pub mod recording;
 pub mod ser;
 pub mod serial;
 
@@ -0,0 +1,359 @@
+//! A compact file format for the packets exchanged with a single client, used to debug clients
+//! that misbehave. Packets are stored after decompression and before encryption, in the ids of
+//! our protocol version.
+//!
+//! The file starts with [`MAGIC`], the format version, the platform and the protocol version.
+//! Every packet follows as the time since the previous one in microseconds (`VarULong`), the
+//! direction and connection state in one byte, the packet id (`VarInt`), the payload length
+//! (`VarUInt`) and the payload.
+
+use std::{
+    fmt::Write as _,
+    io::{Read, Write},
+    time::Duration,
+};
+
+use pumpkin_data::packet::{clientbound, serverbound};
+
+use crate::{
+    ConnectionState,
+    codec::{var_int::VarInt, var_uint::VarUInt, var_ulong::VarULong},
+    java::translation::state_phase,
+    ser::{NetworkReadExt, NetworkWriteExt, ReadingError, WritingError},
+};
+
+pub const MAGIC: &[u8; 4] = b"PKPR";
+pub const FORMAT_VERSION: u8 = 1;
+/// How many bytes of the payload are shown when describing a packet
+const PREVIEW_BYTES: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Java,
+    Bedrock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Serverbound,
+    Clientbound,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordingHeader {
+    pub platform: Platform,
+    pub protocol: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RecordedPacket {
+    /// The time since the recording started
+    pub time: Duration,
+    pub direction: Direction,
+    /// The state of a Java connection, Bedrock packets are always recorded as `Play`
+    pub state: ConnectionState,
+    pub id: i32,
+    pub payload: Box<[u8]>,
+}
+
+fn encode_state(state: ConnectionState) -> u8 {
+    match state {
+        ConnectionState::HandShake => 0,
+        ConnectionState::Status => 1,
+        ConnectionState::Login => 2,
+        ConnectionState::Transfer => 3,
+        ConnectionState::Config => 4,
+        ConnectionState::Play => 5,
+    }
+}
+
+fn decode_state(state: u8) -> Result<ConnectionState, ReadingError> {
+    Ok(match state {
+        0 => ConnectionState::HandShake,
+        1 => ConnectionState::Status,
+        2 => ConnectionState::Login,
+        3 => ConnectionState::Transfer,
+        4 => ConnectionState::Config,
+        5 => ConnectionState::Play,
+        _ => return Err(ReadingError::Message(format!("Unknown state {state}"))),
+    })
+}
+
+/// Writes packets in the recording format
+pub struct RecordingWriter<W: Write> {
+    write: W,
+    last_time: Duration,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    pub fn new(mut write: W, header: RecordingHeader) -> Result<Self, WritingError> {
+        write.write_slice(MAGIC)?;
+        write.write_u8(FORMAT_VERSION)?;
+        write.write_u8(match header.platform {
+            Platform::Java => 0,
+            Platform::Bedrock => 1,
+        })?;
+        write.write_slice(&header.protocol.to_le_bytes())?;
+        Ok(Self {
+            write,
+            last_time: Duration::ZERO,
+        })
+    }
+
+    /// Appends a packet, `time` is the time since the recording started
+    pub fn write_packet(
+        &mut self,
+        time: Duration,
+        direction: Direction,
+        state: ConnectionState,
+        id: i32,
+        payload: &[u8],
+    ) -> Result<(), WritingError> {
+        let delta = time.saturating_sub(self.last_time);
+        self.last_time = self.last_time.max(time);
+        let direction = match direction {
+            Direction::Serverbound => 0,
+            Direction::Clientbound => 1,
+        };
+
+        VarULong(delta.as_micros() as u64).encode(&mut self.write)?;
+        self.write.write_u8(direction << 4 | encode_state(state))?;
+        VarInt(id).encode(&mut self.write)?;
+        VarUInt(payload.len() as u32).encode(&mut self.write)?;
+        self.write.write_slice(payload)
+    }
+
+    pub fn flush(&mut self) -> Result<(), WritingError> {
+        self.write.flush().map_err(WritingError::IoError)
+    }
+}
+
+/// Reads back what a [`RecordingWriter`] wrote
+pub struct RecordingReader<R: Read> {
+    read: R,
+    header: RecordingHeader,
+    time: Duration,
+}
+
+impl<R: Read> RecordingReader<R> {
+    pub fn new(mut read: R) -> Result<Self, ReadingError> {
+        let magic = read.read_boxed_slice(MAGIC.len())?;
+        if magic.as_ref() != MAGIC {
+            return Err(ReadingError::Message("Not a packet recording".to_string()));
+        }
+        let version = read.get_u8()?;
+        if version != FORMAT_VERSION {
+            return Err(ReadingError::Message(format!(
+                "Unsupported recording version {version}"
+            )));
+        }
+        let platform = match read.get_u8()? {
+            0 => Platform::Java,
+            1 => Platform::Bedrock,
+            platform => {
+                return Err(ReadingError::Message(format!(
+                    "Unknown platform {platform}"
+                )));
+            }
+        };
+        let protocol = read.read_boxed_slice(4)?;
+        let protocol = u32::from_le_bytes([protocol[0], protocol[1], protocol[2], protocol[3]]);
+        Ok(Self {
+            read,
+            header: RecordingHeader { platform, protocol },
+            time: Duration::ZERO,
+        })
+    }
+
+    #[must_use]
+    pub fn header(&self) -> RecordingHeader {
+        self.header
+    }
+
+    /// Reads the next packet, `None` at the end of the recording. A recording cut off in the
+    /// middle of a packet, e.g. because the server crashed, ends before that packet
+    pub fn next_packet(&mut self) -> Result<Option<RecordedPacket>, ReadingError> {
+        let mut first = [0];
+        if self
+            .read
+            .read(&mut first)
+            .map_err(|err| ReadingError::Incomplete(err.to_string()))?
+            == 0
+        {
+            return Ok(None);
+        }
+        let delta = match VarULong::decode(&mut (&first[..]).chain(&mut self.read)) {
+            Ok(delta) => delta.0,
+            Err(ReadingError::Incomplete(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let packet = match self.read_rest(Duration::from_micros(delta)) {
+            Err(ReadingError::Incomplete(_)) => return Ok(None),
+            packet => packet?,
+        };
+        Ok(Some(packet))
+    }
+
+    fn read_rest(&mut self, delta: Duration) -> Result<RecordedPacket, ReadingError> {
+        let flags = self.read.get_u8()?;
+        let direction = if flags >> 4 == 0 {
+            Direction::Serverbound
+        } else {
+            Direction::Clientbound
+        };
+        let state = decode_state(flags & 0x0F)?;
+        let id = VarInt::decode(&mut self.read)?.0;
+        let len = VarUInt::decode(&mut self.read)?.0 as usize;
+        let payload = self.read.read_boxed_slice(len)?;
+        self.time += delta;
+        Ok(RecordedPacket {
+            time: self.time,
+            direction,
+            state,
+            id,
+            payload,
+        })
+    }
+}
+
+/// The name of a Java packet like our packet constants, e.g. `PLAY_MOVE_PLAYER_POS`. We don't
+/// have names for Bedrock packets
+#[must_use]
+pub fn packet_name(
+    platform: Platform,
+    direction: Direction,
+    state: ConnectionState,
+    id: i32,
+) -> Option<&'static str> {
+    if platform == Platform::Bedrock {
+        return None;
+    }
+    let packets = match direction {
+        Direction::Serverbound => serverbound::ALL,
+        Direction::Clientbound => clientbound::ALL,
+    };
+    let phase = state_phase(state);
+    packets
+        .iter()
+        .find(|(name, packet_id)| {
+            *packet_id == id
+                && name
+                    .strip_prefix(phase)
+                    .is_some_and(|name| name.starts_with('_'))
+        })
+        .map(|(name, _)| *name)
+}
+
+/// One line describing a packet: its time, direction, name, id, size and the start of its
+/// payload
+#[must_use]
+pub fn describe_packet(platform: Platform, packet: &RecordedPacket) -> String {
+    let arrow = match packet.direction {
+        Direction::Serverbound => "C->S",
+        Direction::Clientbound => "S->C",
+    };
+    let name =
+        packet_name(platform, packet.direction, packet.state, packet.id).unwrap_or("UNKNOWN");
+    let mut line = format!(
+        "[{:>10.3}s] {arrow} {name} (0x{:02x}) {} bytes:",
+        packet.time.as_secs_f64(),
+        packet.id,
+        packet.payload.len()
+    );
+    for byte in packet.payload.iter().take(PREVIEW_BYTES) {
+        let _ = write!(line, " {byte:02x}");
+    }
+    if packet.payload.len() > PREVIEW_BYTES {
+        line.push_str(" ...");
+    }
+    line
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use pumpkin_data::packet::serverbound::PLAY_CHAT;
+
+    use super::{
+        Direction, Platform, RecordedPacket, RecordingHeader, RecordingReader, RecordingWriter,
+        packet_name,
+    };
+    use crate::ConnectionState;
+
+    #[test]
+    fn round_trip() {
+        let mut data = Vec::new();
+        let header = RecordingHeader {
+            platform: Platform::Java,
+            protocol: 774,
+        };
+        let mut writer = RecordingWriter::new(&mut data, header).unwrap();
+        writer
+            .write_packet(
+                Duration::from_millis(5),
+                Direction::Serverbound,
+                ConnectionState::Login,
+                0,
+                b"Notch",
+            )
+            .unwrap();
+        writer
+            .write_packet(
+                Duration::from_millis(1500),
+                Direction::Clientbound,
+                ConnectionState::Play,
+                300,
+                &[],
+            )
+            .unwrap();
+        writer
+            .write_packet(
+                Duration::from_secs(2),
+                Direction::Clientbound,
+                ConnectionState::Play,
+                1,
+                &[1, 2, 3],
+            )
+            .unwrap();
+        // The server stopped while writing the last packet
+        data.truncate(data.len() - 5);
+
+        let mut reader = RecordingReader::new(&data[..]).unwrap();
+        assert_eq!(reader.header(), header);
+        assert_eq!(
+            reader.next_packet().unwrap(),
+            Some(RecordedPacket {
+                time: Duration::from_millis(5),
+                direction: Direction::Serverbound,
+                state: ConnectionState::Login,
+                id: 0,
+                payload: b"Notch".as_slice().into(),
+            })
+        );
+        let second = reader.next_packet().unwrap().unwrap();
+        assert_eq!(second.time, Duration::from_millis(1500));
+        assert_eq!(second.id, 300);
+        assert_eq!(reader.next_packet().unwrap(), None);
+    }
+
+    #[test]
+    fn names_packets_by_state() {
+        let name = packet_name(
+            Platform::Java,
+            Direction::Serverbound,
+            ConnectionState::Play,
+            PLAY_CHAT,
+        );
+        assert_eq!(name, Some("PLAY_CHAT"));
+        assert_eq!(
+            packet_name(
+                Platform::Bedrock,
+                Direction::Serverbound,
+                ConnectionState::Play,
+                PLAY_CHAT
+            ),
+            None
+        );
+    }
+}
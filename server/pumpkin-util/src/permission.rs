@@ -1,5 +1,5 @@
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -70,6 +70,14 @@ impl PermissionRegistry {
         Ok(())
     }
 
+    /// Remove every permission in a namespace, e.g. the permissions of an unloaded plugin
+    pub fn unregister_namespace(&mut self, namespace: &str) {
+        self.permissions.retain(|node, _| {
+            node.split_once(':')
+                .is_none_or(|(node_namespace, _)| node_namespace != namespace)
+        });
+    }
+
     /// Get a registered permission by node
     pub fn get_permission(&self, node: &str) -> Option<&Permission> {
         self.permissions.get(node)
@@ -116,6 +124,208 @@ impl PermissionAttachment {
     }
 }
 
+/// The group users are in when they aren't in any other group
+pub const DEFAULT_GROUP: &str = "default";
+
+/// Permission nodes set on a group or user, globally and per world
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionNodes {
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub permissions: HashMap<String, bool>,
+    /// Overrides of the global nodes, keyed by the dimension (e.g. "minecraft:the_nether")
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub worlds: HashMap<String, HashMap<String, bool>>,
+}
+
+impl PermissionNodes {
+    /// Set a permission value, globally if `world` is `None`
+    pub fn set_permission(&mut self, node: &str, value: bool, world: Option<&str>) {
+        let permissions = match world {
+            Some(world) => self.worlds.entry(world.to_string()).or_default(),
+            None => &mut self.permissions,
+        };
+        permissions.insert(node.to_string(), value);
+    }
+
+    /// Unset a permission, returns whether it was set
+    pub fn unset_permission(&mut self, node: &str, world: Option<&str>) -> bool {
+        let Some(world) = world else {
+            return self.permissions.remove(node).is_some();
+        };
+        let Some(permissions) = self.worlds.get_mut(world) else {
+            return false;
+        };
+        let removed = permissions.remove(node).is_some();
+        if permissions.is_empty() {
+            self.worlds.remove(world);
+        }
+        removed
+    }
+
+    /// Check if a permission is directly set, without looking at wildcards
+    pub fn has_permission_set(&self, node: &str, world: Option<&str>) -> Option<bool> {
+        match world {
+            Some(world) => self.worlds.get(world)?.get(node).copied(),
+            None => self.permissions.get(node).copied(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.permissions.is_empty() && self.worlds.is_empty()
+    }
+
+    /// World overrides take precedence over global nodes, and more specific nodes over wildcards
+    fn lookup(&self, candidates: &[String], world: Option<&str>) -> Option<bool> {
+        let find = |permissions: &HashMap<String, bool>| {
+            candidates
+                .iter()
+                .find_map(|node| permissions.get(node).copied())
+        };
+        world
+            .and_then(|world| self.worlds.get(world))
+            .and_then(find)
+            .or_else(|| find(&self.permissions))
+    }
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionGroup {
+    /// Groups whose nodes this group inherits, earlier groups take precedence
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inherits: Vec<String>,
+    #[serde(flatten)]
+    pub nodes: PermissionNodes,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionUser {
+    /// The groups the user is in, earlier groups take precedence. Without any the user is in the
+    /// [`DEFAULT_GROUP`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+    #[serde(flatten)]
+    pub nodes: PermissionNodes,
+}
+
+/// The permissions of groups and users which are kept across restarts
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct PermissionStorage {
+    #[serde(default)]
+    pub groups: HashMap<String, PermissionGroup>,
+    #[serde(default)]
+    pub users: HashMap<uuid::Uuid, PermissionUser>,
+}
+
+impl PermissionStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The groups a user is in, including the [`DEFAULT_GROUP`] if they aren't in any other
+    pub fn user_groups(&self, user_id: &uuid::Uuid) -> Vec<&str> {
+        match self.users.get(user_id) {
+            Some(user) if !user.groups.is_empty() => {
+                user.groups.iter().map(String::as_str).collect()
+            }
+            _ => vec![DEFAULT_GROUP],
+        }
+    }
+
+    /// Whether `group` is `ancestor` or inherits from it, directly or through other groups
+    pub fn inherits_from(&self, group: &str, ancestor: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut todo = vec![group];
+        while let Some(group) = todo.pop() {
+            if group == ancestor {
+                return true;
+            }
+            if visited.insert(group)
+                && let Some(group) = self.groups.get(group)
+            {
+                todo.extend(group.inherits.iter().map(String::as_str));
+            }
+        }
+        false
+    }
+
+    /// Resolves the value of a permission for a user, `None` if neither the user nor one of their
+    /// groups sets it.
+    ///
+    /// The user's own nodes take precedence over the ones of their groups, and a group's own nodes
+    /// over the ones it inherits. Wildcard nodes (e.g. `minecraft:command.*` or `*`) apply to
+    /// every node below them, more specific nodes taking precedence.
+    pub fn lookup(
+        &self,
+        user_id: &uuid::Uuid,
+        permission_node: &str,
+        world: Option<&str>,
+    ) -> Option<bool> {
+        let candidates = wildcard_candidates(permission_node);
+        if let Some(user) = self.users.get(user_id)
+            && let Some(value) = user.nodes.lookup(&candidates, world)
+        {
+            return Some(value);
+        }
+
+        let mut visited = HashSet::new();
+        self.user_groups(user_id)
+            .into_iter()
+            .find_map(|group| self.lookup_inherited(group, &candidates, world, &mut visited))
+    }
+
+    /// Resolves the value of a permission for a group like [`Self::lookup`] does for users
+    pub fn lookup_group(
+        &self,
+        group: &str,
+        permission_node: &str,
+        world: Option<&str>,
+    ) -> Option<bool> {
+        let candidates = wildcard_candidates(permission_node);
+        self.lookup_inherited(group, &candidates, world, &mut HashSet::new())
+    }
+
+    fn lookup_inherited<'a>(
+        &'a self,
+        group: &'a str,
+        candidates: &[String],
+        world: Option<&str>,
+        visited: &mut HashSet<&'a str>,
+    ) -> Option<bool> {
+        // Guards against groups inheriting from each other
+        if !visited.insert(group) {
+            return None;
+        }
+        let group = self.groups.get(group)?;
+        group.nodes.lookup(candidates, world).or_else(|| {
+            group
+                .inherits
+                .iter()
+                .find_map(|parent| self.lookup_inherited(parent, candidates, world, visited))
+        })
+    }
+}
+
+/// The node itself followed by the wildcards matching it, most specific first: `ns:a.b` is
+/// matched by `ns:a.b`, `ns:a.*`, `ns:*` and `*`
+fn wildcard_candidates(node: &str) -> Vec<String> {
+    let (prefix, path) = match node.split_once(':') {
+        Some((namespace, path)) => (format!("{namespace}:"), path),
+        None => (String::new(), node),
+    };
+
+    let mut candidates = vec![node.to_string()];
+    let mut end = path.len();
+    while let Some(dot) = path[..end].rfind('.') {
+        candidates.push(format!("{prefix}{}.*", &path[..dot]));
+        end = dot;
+    }
+    if !prefix.is_empty() {
+        candidates.push(format!("{prefix}*"));
+    }
+    candidates.push("*".to_string());
+    candidates
+}
+
 /// Manager for player permissions
 #[derive(Default)]
 pub struct PermissionManager {
@@ -123,6 +333,8 @@ pub struct PermissionManager {
     pub registry: Arc<RwLock<PermissionRegistry>>,
     /// Player permission attachments
     pub attachments: HashMap<uuid::Uuid, Arc<RwLock<PermissionAttachment>>>,
+    /// Permissions of groups and users, which are saved
+    pub storage: PermissionStorage,
 }
 
 impl PermissionManager {
@@ -130,6 +342,7 @@ impl PermissionManager {
         Self {
             registry,
             attachments: HashMap::new(),
+            storage: PermissionStorage::new(),
         }
     }
 
@@ -146,19 +359,25 @@ impl PermissionManager {
         self.attachments.remove(player_id);
     }
 
-    /// Check if a player has a permission, considering defaults and op status
+    /// Check if a player has a permission, considering defaults and op status.
+    ///
+    /// `world` is the dimension the player is in, for per world overrides in the storage
     pub async fn has_permission(
         &self,
         player_id: &uuid::Uuid,
         permission_node: &str,
         player_op_level: PermissionLvl,
+        world: Option<&str>,
     ) -> bool {
         let reg = self.registry.read().await;
 
-        // Check explicitly set permissions
-        if let Some(attachment) = self.attachments.get(player_id) {
-            let attachment = attachment.read().await;
+        let attachment = match self.attachments.get(player_id) {
+            Some(attachment) => Some(attachment.read().await),
+            None => None,
+        };
 
+        // Check explicitly set permissions
+        if let Some(attachment) = &attachment {
             // Check for exact permission match
             if let Some(value) = attachment.has_permission_set(permission_node) {
                 return value;
@@ -194,7 +413,14 @@ impl PermissionManager {
                     }
                 }
             }
+        }
+
+        // Check the permissions of the player and their groups
+        if let Some(value) = self.storage.lookup(player_id, permission_node, world) {
+            return value;
+        }
 
+        if let Some(attachment) = &attachment {
             // Check for inherited permissions from parent nodes
             for (node, value) in attachment.get_permissions() {
                 if let Some(permission) = reg.get_permission(node)
@@ -273,3 +499,99 @@ impl<'de> Deserialize<'de> for PermissionLvl {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{DEFAULT_GROUP, PermissionGroup, PermissionStorage, PermissionUser};
+
+    fn group(inherits: &[&str], nodes: &[(&str, bool)]) -> PermissionGroup {
+        let mut group = PermissionGroup {
+            inherits: inherits.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        };
+        for (node, value) in nodes {
+            group.nodes.set_permission(node, *value, None);
+        }
+        group
+    }
+
+    #[test]
+    fn wildcards() {
+        let user = uuid::Uuid::new_v4();
+        let mut storage = PermissionStorage::new();
+        storage.groups.insert(
+            DEFAULT_GROUP.to_string(),
+            group(
+                &[],
+                &[
+                    ("minecraft:*", true),
+                    ("minecraft:command.*", false),
+                    ("minecraft:command.help", true),
+                ],
+            ),
+        );
+
+        assert_eq!(
+            storage.lookup(&user, "minecraft:command.help", None),
+            Some(true)
+        );
+        assert_eq!(
+            storage.lookup(&user, "minecraft:command.stop", None),
+            Some(false)
+        );
+        assert_eq!(
+            storage.lookup(&user, "minecraft:broadcast", None),
+            Some(true)
+        );
+        assert_eq!(storage.lookup(&user, "pumpkin:command.plugin", None), None);
+    }
+
+    #[test]
+    fn inheritance_and_worlds() {
+        let user = uuid::Uuid::new_v4();
+        let mut storage = PermissionStorage::new();
+        storage.groups.insert(
+            "builder".to_string(),
+            group(&["member"], &[("minecraft:command.fill", true)]),
+        );
+        // Cycles must not make the lookup loop forever
+        storage.groups.insert(
+            "member".to_string(),
+            group(
+                &["builder"],
+                &[
+                    ("minecraft:command.fill", false),
+                    ("minecraft:command.me", true),
+                ],
+            ),
+        );
+        let mut member = PermissionUser {
+            groups: vec!["builder".to_string()],
+            ..Default::default()
+        };
+        member
+            .nodes
+            .set_permission("minecraft:command.fill", false, Some("minecraft:the_end"));
+        storage.users.insert(user, member);
+
+        assert_eq!(
+            storage.lookup(&user, "minecraft:command.me", None),
+            Some(true)
+        );
+        assert_eq!(
+            storage.lookup(&user, "minecraft:command.fill", None),
+            Some(true)
+        );
+        assert_eq!(
+            storage.lookup(&user, "minecraft:command.fill", Some("minecraft:the_end")),
+            Some(false)
+        );
+        assert_eq!(storage.lookup(&user, "minecraft:command.stop", None), None);
+        assert_eq!(
+            storage.lookup_group("member", "minecraft:command.fill", None),
+            Some(false)
+        );
+        assert!(storage.inherits_from("builder", "member"));
+        assert!(!storage.inherits_from("builder", DEFAULT_GROUP));
+    }
+}
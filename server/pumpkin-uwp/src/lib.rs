@@ -3,6 +3,7 @@ use pumpkin::command::CommandSender;
 use pumpkin::entity::player::Player;
 use pumpkin::net::ClientPlatform;
 use pumpkin::server::Server;
+use pumpkin::server::pregen::PregenStatus;
 use pumpkin_config::LoadConfiguration;
 use pumpkin_protocol::java::client::play::CommandSuggestion;
 use serde::Serialize;
@@ -106,6 +107,7 @@ struct SerializedMetrics {
     tick_count: i32,
     loaded_chunks: usize,
     player_count: usize,
+    pregen: Vec<PregenStatus>,
 }
 
 //  LOGGING BRIDGE
@@ -209,6 +211,7 @@ pub extern "C" fn pumpkin_get_metrics_json() -> *mut c_char {
             tick_count: tick_count as i32,
             loaded_chunks,
             player_count,
+            pregen: server.pregen.status(),
         };
 
         let json = serde_json::to_string(&metrics).unwrap_or_else(|_| "{}".to_string());
@@ -0,0 +1,770 @@
+//! Offline conversion of region files between chunk formats and compressions.
+//!
+//! Every region file of a world is streamed through the [`ChunkSerializer`] of its source format,
+//! optionally pruned, and re-encoded with the serializer of the target format. Chunks are never
+//! parsed into [`ChunkData`](super::ChunkData); they are carried around as raw NBT so that data
+//! Pumpkin does not understand yet survives the conversion.
+//!
+//! The state of a conversion is persisted to [`JOB_FILE_NAME`] in the world folder after every
+//! region, so an interrupted conversion picks up where it left off.
+
+use std::{
+    collections::BTreeSet,
+    io::Cursor,
+    path::{Path, PathBuf},
+    pin::Pin,
+};
+
+use bytes::Bytes;
+use pumpkin_config::chunk::{AnvilChunkConfig, ChunkConfig};
+use pumpkin_nbt::compound::NbtCompound;
+use pumpkin_util::math::vector2::Vector2;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::chunk::{
+    ChunkReadingError, ChunkSerializingError, ChunkWritingError,
+    format::{
+        anvil::{AnvilChunkFile, REGION_SIZE, SingleChunkDataSerializer, remove_external_chunk},
+        linear::LinearFile,
+    },
+    io::{ChunkSerializer, Dirtiable, LoadedData},
+};
+
+/// The file in the world folder that holds a pending or interrupted conversion.
+pub const JOB_FILE_NAME: &str = "convert.json";
+
+/// The dimension folders of a world, relative to the world folder.
+const DIMENSION_FOLDERS: [&str; 3] = ["", "DIM-1", "DIM1"];
+
+#[derive(Error, Debug)]
+pub enum ConversionError {
+    #[error("Io error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Failed to read region {0}: {1}")]
+    Reading(PathBuf, ChunkReadingError),
+    #[error("Failed to read chunk {1},{2} in region {0}: {3}")]
+    ReadingChunk(PathBuf, i32, i32, ChunkReadingError),
+    #[error("Failed to write region {0}: {1}")]
+    Writing(PathBuf, ChunkWritingError),
+    #[error("Invalid conversion job file: {0}")]
+    InvalidJob(String),
+    #[error(
+        "The pending conversion targets {0}, but the server is configured for {1}. Update `world.chunk` in features.toml first"
+    )]
+    TargetMismatch(String, String),
+}
+
+/// The on-disk layout of a region file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionFormat {
+    Anvil,
+    Linear,
+}
+
+impl RegionFormat {
+    pub fn of(config: &ChunkConfig) -> Self {
+        match config {
+            ChunkConfig::Anvil(_) => Self::Anvil,
+            ChunkConfig::Linear(_) => Self::Linear,
+        }
+    }
+
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Anvil => "mca",
+            Self::Linear => "linear",
+        }
+    }
+
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "mca" => Some(Self::Anvil),
+            "linear" => Some(Self::Linear),
+            _ => None,
+        }
+    }
+}
+
+/// A human readable description of a chunk config, e.g. `anvil (LZ4)`.
+pub fn describe_target(config: &ChunkConfig) -> String {
+    match config {
+        ChunkConfig::Anvil(anvil) => format!(
+            "anvil ({:?}, level {})",
+            anvil.compression.algorithm, anvil.compression.level
+        ),
+        ChunkConfig::Linear(_) => "linear (zstd)".to_string(),
+    }
+}
+
+fn same_target(a: &ChunkConfig, b: &ChunkConfig) -> bool {
+    match (a, b) {
+        (ChunkConfig::Anvil(a), ChunkConfig::Anvil(b)) => {
+            a.compression.algorithm == b.compression.algorithm
+                && a.compression.level == b.compression.level
+        }
+        (ChunkConfig::Linear(_), ChunkConfig::Linear(_)) => true,
+        _ => false,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct ConvertOptions {
+    /// Drop terrain chunks that never finished generating and entity chunks without entities.
+    /// The server simply regenerates them when they are needed again.
+    pub drop_empty: bool,
+    /// Drop terrain chunks in which players spent fewer ticks than this (`InhabitedTime`).
+    pub min_inhabited_time: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+#[serde(default)]
+pub struct ConversionProgress {
+    pub regions_total: usize,
+    pub regions_done: usize,
+    pub chunks_kept: u64,
+    pub chunks_dropped: u64,
+    /// Size of the converted region files before the conversion
+    pub bytes_before: u64,
+    /// Size of the converted region files after the conversion
+    pub bytes_after: u64,
+}
+
+impl ConversionProgress {
+    pub fn bytes_saved(&self) -> i64 {
+        self.bytes_before as i64 - self.bytes_after as i64
+    }
+
+    pub fn percent_done(&self) -> f32 {
+        if self.regions_total == 0 {
+            100.0
+        } else {
+            self.regions_done as f32 * 100.0 / self.regions_total as f32
+        }
+    }
+}
+
+/// A conversion that has been requested for a world and is persisted in [`JOB_FILE_NAME`].
+#[derive(Serialize, Deserialize)]
+pub struct ConversionJob {
+    pub target: ChunkConfig,
+    #[serde(default)]
+    pub options: ConvertOptions,
+    /// Region files (relative to the world folder) that are already converted
+    #[serde(default)]
+    pub completed: BTreeSet<String>,
+    #[serde(default)]
+    pub progress: ConversionProgress,
+}
+
+impl ConversionJob {
+    pub fn new(target: ChunkConfig, options: ConvertOptions) -> Self {
+        Self {
+            target,
+            options,
+            completed: BTreeSet::new(),
+            progress: ConversionProgress::default(),
+        }
+    }
+
+    /// Reads the pending job of a world, if there is one.
+    pub fn read(world_folder: &Path) -> Result<Option<Self>, ConversionError> {
+        let path = world_folder.join(JOB_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|err| ConversionError::InvalidJob(err.to_string()))
+    }
+
+    pub fn write(&self, world_folder: &Path) -> Result<(), ConversionError> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|err| ConversionError::InvalidJob(err.to_string()))?;
+        // Write then rename so an interruption never leaves a half written job behind
+        let path = world_folder.join(JOB_FILE_NAME);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, content)?;
+        std::fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    pub fn remove(world_folder: &Path) -> Result<bool, ConversionError> {
+        let path = world_folder.join(JOB_FILE_NAME);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+/// A chunk that is passed through a [`ChunkSerializer`] as raw (uncompressed) NBT.
+pub struct RawChunk {
+    pub x: i32,
+    pub z: i32,
+    pub data: Bytes,
+}
+
+impl Dirtiable for RawChunk {
+    #[inline]
+    fn is_dirty(&self) -> bool {
+        // Raw chunks only exist to be written
+        true
+    }
+
+    #[inline]
+    fn mark_dirty(&mut self, _flag: bool) {}
+}
+
+impl SingleChunkDataSerializer for RawChunk {
+    #[inline]
+    fn from_bytes(bytes: &Bytes, pos: Vector2<i32>) -> Result<Self, ChunkReadingError> {
+        Ok(Self {
+            x: pos.x,
+            z: pos.y,
+            data: bytes.clone(),
+        })
+    }
+
+    #[inline]
+    fn to_bytes(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<Bytes, ChunkSerializingError>> + Send + '_>> {
+        Box::pin(async move { Ok(self.data.clone()) })
+    }
+
+    #[inline]
+    fn position(&self) -> (i32, i32) {
+        (self.x, self.z)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RegionKind {
+    Terrain,
+    Entities,
+}
+
+/// The few fields of a chunk we need to decide whether to keep it.
+#[derive(Deserialize)]
+struct ChunkSummary {
+    #[serde(rename = "Status")]
+    status: Option<String>,
+    #[serde(rename = "InhabitedTime", default)]
+    inhabited_time: i64,
+    #[serde(rename = "Entities", default)]
+    entities: Vec<NbtCompound>,
+}
+
+impl ConvertOptions {
+    fn keep(&self, kind: RegionKind, chunk: &RawChunk) -> bool {
+        if !self.drop_empty && self.min_inhabited_time.is_none() {
+            return true;
+        }
+        let Ok(summary) = pumpkin_nbt::from_bytes::<ChunkSummary>(Cursor::new(&chunk.data)) else {
+            // Never throw away what we don't understand
+            return true;
+        };
+        match kind {
+            RegionKind::Terrain => {
+                if self.drop_empty
+                    && summary
+                        .status
+                        .as_deref()
+                        .is_some_and(|status| status != "minecraft:full")
+                {
+                    return false;
+                }
+                self.min_inhabited_time
+                    .is_none_or(|min| summary.inhabited_time >= min)
+            }
+            RegionKind::Entities => !self.drop_empty || !summary.entities.is_empty(),
+        }
+    }
+}
+
+struct RegionFile {
+    path: PathBuf,
+    /// Path relative to the world folder, used as key in the job
+    key: String,
+    format: RegionFormat,
+    kind: RegionKind,
+    region: (i32, i32),
+}
+
+/// Parses `r.<x>.<z>.<extension>`
+fn parse_region_name(name: &str) -> Option<((i32, i32), RegionFormat)> {
+    let mut parts = name.split('.');
+    if parts.next()? != "r" {
+        return None;
+    }
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    let format = RegionFormat::from_extension(parts.next()?)?;
+    parts.next().is_none().then_some(((x, z), format))
+}
+
+/// Strips the extension of a region key, so keys of the same region in different formats match.
+fn region_stem(key: &str) -> &str {
+    key.rsplit_once('.').map_or(key, |(stem, _)| stem)
+}
+
+/// Converts all region files of a world to the format and compression of `target`.
+pub struct WorldConverter {
+    world_folder: PathBuf,
+    target: ChunkConfig,
+    options: ConvertOptions,
+}
+
+impl WorldConverter {
+    pub fn new(world_folder: PathBuf, target: ChunkConfig, options: ConvertOptions) -> Self {
+        Self {
+            world_folder,
+            target,
+            options,
+        }
+    }
+
+    /// Runs the conversion persisted in the world folder, if there is one.
+    ///
+    /// The job only runs when its target matches `configured`, otherwise the server would be
+    /// unable to read the converted world.
+    pub async fn resume_pending(
+        world_folder: &Path,
+        configured: &ChunkConfig,
+        on_progress: impl Fn(&ConversionProgress),
+    ) -> Result<Option<ConversionProgress>, ConversionError> {
+        let Some(job) = ConversionJob::read(world_folder)? else {
+            return Ok(None);
+        };
+        if !same_target(&job.target, configured) {
+            return Err(ConversionError::TargetMismatch(
+                describe_target(&job.target),
+                describe_target(configured),
+            ));
+        }
+        let converter = Self::new(
+            world_folder.to_path_buf(),
+            job.target.clone(),
+            job.options.clone(),
+        );
+        converter.run(job, on_progress).await.map(Some)
+    }
+
+    /// Converts the world, calling `on_progress` after every region.
+    pub async fn convert(
+        &self,
+        on_progress: impl Fn(&ConversionProgress),
+    ) -> Result<ConversionProgress, ConversionError> {
+        let job = match ConversionJob::read(&self.world_folder)? {
+            // Resume an interrupted conversion with the same target
+            Some(job) if same_target(&job.target, &self.target) => job,
+            _ => ConversionJob::new(self.target.clone(), self.options.clone()),
+        };
+        self.run(job, on_progress).await
+    }
+
+    async fn run(
+        &self,
+        mut job: ConversionJob,
+        on_progress: impl Fn(&ConversionProgress),
+    ) -> Result<ConversionProgress, ConversionError> {
+        let regions = self.pending_regions(&job)?;
+        job.progress.regions_total = job.progress.regions_done + regions.len();
+        job.write(&self.world_folder)?;
+
+        for region in regions {
+            self.convert_region(&region, &mut job.progress).await?;
+            job.progress.regions_done += 1;
+            job.completed.insert(region.key);
+            job.write(&self.world_folder)?;
+            on_progress(&job.progress);
+        }
+
+        ConversionJob::remove(&self.world_folder)?;
+        Ok(job.progress)
+    }
+
+    /// The regions that still have to be converted for `job`.
+    ///
+    /// Region files already in the target format are skipped when they were written by this job,
+    /// or when a region in another format is going to be merged into them.
+    fn pending_regions(&self, job: &ConversionJob) -> Result<Vec<RegionFile>, ConversionError> {
+        let regions = self.collect_regions()?;
+        let target_format = RegionFormat::of(&self.target);
+        let merged = regions
+            .iter()
+            .filter(|region| region.format != target_format)
+            .map(|region| region_stem(&region.key))
+            .chain(job.completed.iter().map(|key| region_stem(key)))
+            .map(str::to_string)
+            .collect::<BTreeSet<_>>();
+        Ok(regions
+            .into_iter()
+            .filter(|region| {
+                !job.completed.contains(&region.key)
+                    && (region.format != target_format
+                        || !merged.contains(region_stem(&region.key)))
+            })
+            .collect())
+    }
+
+    fn collect_regions(&self) -> Result<Vec<RegionFile>, ConversionError> {
+        let mut regions = Vec::new();
+        for dimension in DIMENSION_FOLDERS {
+            for (folder, kind) in [
+                ("region", RegionKind::Terrain),
+                ("entities", RegionKind::Entities),
+            ] {
+                let relative = Path::new(dimension).join(folder);
+                let Ok(entries) = std::fs::read_dir(self.world_folder.join(&relative)) else {
+                    continue;
+                };
+                for entry in entries {
+                    let entry = entry?;
+                    let name = entry.file_name();
+                    let Some((region, format)) = name.to_str().and_then(parse_region_name) else {
+                        continue;
+                    };
+                    regions.push(RegionFile {
+                        path: entry.path(),
+                        key: relative.join(&name).to_string_lossy().replace('\\', "/"),
+                        format,
+                        kind,
+                        region,
+                    });
+                }
+            }
+        }
+        regions.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(regions)
+    }
+
+    async fn convert_region(
+        &self,
+        region: &RegionFile,
+        progress: &mut ConversionProgress,
+    ) -> Result<(), ConversionError> {
+        let target_format = RegionFormat::of(&self.target);
+        let target_path = region.path.with_extension(target_format.extension());
+        let bytes_before = tokio::fs::metadata(&region.path).await?.len();
+
+        let mut chunks = self
+            .read_region(region.format, &region.path, region.region)
+            .await?;
+        if target_path != region.path && target_path.exists() {
+            // Keep chunks that only exist in a region file that is already in the target format
+            let existing = self
+                .read_region(target_format, &target_path, region.region)
+                .await?;
+            let missing = existing
+                .into_iter()
+                .filter(|existing| {
+                    !chunks
+                        .iter()
+                        .any(|chunk| chunk.x == existing.x && chunk.z == existing.z)
+                })
+                .collect::<Vec<_>>();
+            chunks.extend(missing);
+        }
+
+        let (chunks, dropped): (Vec<_>, Vec<_>) = chunks
+            .into_iter()
+            .partition(|chunk| self.options.keep(region.kind, chunk));
+        progress.chunks_dropped += dropped.len() as u64;
+        progress.chunks_kept += chunks.len() as u64;
+
+        if chunks.is_empty() {
+            log::debug!("Removing empty region {}", region.key);
+            if target_path.exists() {
+                tokio::fs::remove_file(&target_path).await?;
+            }
+        } else {
+            self.write_region(&chunks, &target_path).await?;
+            progress.bytes_after += tokio::fs::metadata(&target_path).await?.len();
+        }
+        progress.bytes_before += bytes_before;
+
+        // Oversized Anvil chunks keep their data in external files next to the region
+        if region.format == RegionFormat::Anvil || target_format == RegionFormat::Anvil {
+            let region_folder = region.path.parent().unwrap_or(Path::new(""));
+            let moved = (target_format != RegionFormat::Anvil).then_some(&chunks);
+            for chunk in dropped.iter().chain(moved.into_iter().flatten()) {
+                remove_external_chunk(region_folder, chunk.x, chunk.z).await?;
+            }
+        }
+
+        if target_path != region.path && region.path.exists() {
+            tokio::fs::remove_file(&region.path).await?;
+        }
+        Ok(())
+    }
+
+    async fn read_region(
+        &self,
+        format: RegionFormat,
+        path: &Path,
+        region: (i32, i32),
+    ) -> Result<Vec<RawChunk>, ConversionError> {
+        match format {
+            RegionFormat::Anvil => read_region::<AnvilChunkFile<RawChunk>>(path, region).await,
+            RegionFormat::Linear => read_region::<LinearFile<RawChunk>>(path, region).await,
+        }
+    }
+
+    async fn write_region(&self, chunks: &[RawChunk], path: &Path) -> Result<(), ConversionError> {
+        match &self.target {
+            ChunkConfig::Anvil(config) => {
+                // Regions are always written as a whole
+                let config = AnvilChunkConfig {
+                    write_in_place: false,
+                    ..config.clone()
+                };
+                let written =
+                    write_region::<AnvilChunkFile<RawChunk>>(chunks, &config, path).await?;
+
+                // The chunks were previously read from scratch, so stale external files have to
+                // be looked for here
+                let region_folder = path.parent().unwrap_or(Path::new(""));
+                for chunk in chunks {
+                    if !written.is_external_chunk(chunk.x, chunk.z) {
+                        remove_external_chunk(region_folder, chunk.x, chunk.z).await?;
+                    }
+                }
+                Ok(())
+            }
+            ChunkConfig::Linear(config) => {
+                write_region::<LinearFile<RawChunk>>(chunks, config, path).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn read_region<S>(path: &Path, region: (i32, i32)) -> Result<Vec<RawChunk>, ConversionError>
+where
+    S: ChunkSerializer<Data = RawChunk, WriteBackend = PathBuf>,
+{
+    let bytes = tokio::fs::read(path).await?;
+    let serializer = S::read(bytes.into(), &path.to_path_buf())
+        .await
+        .map_err(|err| ConversionError::Reading(path.to_path_buf(), err))?;
+
+    let (region_x, region_z) = region;
+    let positions = (0..REGION_SIZE as i32)
+        .flat_map(|z| {
+            (0..REGION_SIZE as i32).map(move |x| {
+                Vector2::new(
+                    region_x * REGION_SIZE as i32 + x,
+                    region_z * REGION_SIZE as i32 + z,
+                )
+            })
+        })
+        .collect();
+
+    let (send, mut recv) = tokio::sync::mpsc::channel(16);
+    let mut chunks = Vec::new();
+    let mut error = None;
+    let collect = async {
+        while let Some(data) = recv.recv().await {
+            match data {
+                LoadedData::Loaded(chunk) => chunks.push(chunk),
+                LoadedData::Missing(_) => {}
+                // The source region is deleted after conversion, so a single unreadable chunk
+                // has to abort the whole region
+                LoadedData::Error((pos, err)) => {
+                    error.get_or_insert(ConversionError::ReadingChunk(
+                        path.to_path_buf(),
+                        pos.x,
+                        pos.y,
+                        err,
+                    ));
+                }
+            }
+        }
+    };
+    tokio::join!(serializer.get_chunks(positions, send), collect);
+
+    error.map_or(Ok(chunks), Err)
+}
+
+async fn write_region<S>(
+    chunks: &[RawChunk],
+    config: &S::ChunkConfig,
+    path: &Path,
+) -> Result<S, ConversionError>
+where
+    S: ChunkSerializer<Data = RawChunk, WriteBackend = PathBuf>,
+{
+    let mut serializer = S::default();
+    for chunk in chunks {
+        serializer
+            .update_chunk(chunk, config)
+            .await
+            .map_err(|err| ConversionError::Writing(path.to_path_buf(), err))?;
+    }
+    serializer.write(&path.to_path_buf()).await?;
+    Ok(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use pumpkin_config::chunk::{AnvilChunkConfig, ChunkConfig, Compression, LinearChunkConfig};
+    use pumpkin_nbt::compound::NbtCompound;
+    use temp_dir::TempDir;
+
+    use super::*;
+
+    fn chunk_nbt(x: i32, z: i32, status: &str, inhabited_time: i64) -> Bytes {
+        let mut nbt = NbtCompound::new();
+        nbt.put_int("xPos", x);
+        nbt.put_int("zPos", z);
+        nbt.put_string("Status", status.to_string());
+        nbt.put_long("InhabitedTime", inhabited_time);
+        let mut bytes = Vec::new();
+        pumpkin_nbt::to_bytes(&nbt, &mut bytes).unwrap();
+        bytes.into()
+    }
+
+    fn anvil(algorithm: Compression) -> ChunkConfig {
+        let mut config = AnvilChunkConfig::default();
+        config.compression.algorithm = algorithm;
+        ChunkConfig::Anvil(config)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn converts_and_prunes() {
+        let temp_dir = TempDir::new().unwrap();
+        let world = temp_dir.path().to_path_buf();
+        std::fs::create_dir_all(world.join("region")).unwrap();
+
+        let chunks = vec![
+            RawChunk {
+                x: 0,
+                z: 0,
+                data: chunk_nbt(0, 0, "minecraft:full", 500),
+            },
+            RawChunk {
+                x: 1,
+                z: 0,
+                data: chunk_nbt(1, 0, "minecraft:features", 0),
+            },
+            RawChunk {
+                x: 2,
+                z: 3,
+                data: chunk_nbt(2, 3, "minecraft:full", 0),
+            },
+        ];
+        let source = world.join("region").join("r.0.0.mca");
+        write_region::<AnvilChunkFile<RawChunk>>(&chunks, &AnvilChunkConfig::default(), &source)
+            .await
+            .unwrap();
+
+        let converter = WorldConverter::new(
+            world.clone(),
+            ChunkConfig::Linear(LinearChunkConfig::default()),
+            ConvertOptions {
+                drop_empty: true,
+                min_inhabited_time: Some(1),
+            },
+        );
+        let progress = converter.convert(|_| {}).await.unwrap();
+
+        assert_eq!(progress.regions_done, 1);
+        assert_eq!(progress.chunks_kept, 1);
+        assert_eq!(progress.chunks_dropped, 2);
+        assert!(!source.exists());
+        assert!(!world.join(JOB_FILE_NAME).exists());
+
+        let converted =
+            read_region::<LinearFile<RawChunk>>(&source.with_extension("linear"), (0, 0))
+                .await
+                .unwrap();
+        assert_eq!(converted.len(), 1);
+        assert_eq!((converted[0].x, converted[0].z), (0, 0));
+
+        // And back again with a different compression
+        let converter = WorldConverter::new(
+            world.clone(),
+            anvil(Compression::ZLib),
+            ConvertOptions::default(),
+        );
+        converter.convert(|_| {}).await.unwrap();
+        let converted = read_region::<AnvilChunkFile<RawChunk>>(&source, (0, 0))
+            .await
+            .unwrap();
+        assert_eq!(converted.len(), 1);
+        assert_eq!(converted[0].data, chunk_nbt(0, 0, "minecraft:full", 500));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn keeps_unreadable_regions_and_resumes() {
+        let temp_dir = TempDir::new().unwrap();
+        let world = temp_dir.path().to_path_buf();
+        std::fs::create_dir_all(world.join("region")).unwrap();
+
+        let write = |region_x: i32| {
+            let x = region_x * REGION_SIZE as i32;
+            let chunks = vec![RawChunk {
+                x,
+                z: 0,
+                data: chunk_nbt(x, 0, "minecraft:full", 0),
+            }];
+            let path = world.join("region").join(format!("r.{region_x}.0.mca"));
+            async move {
+                write_region::<AnvilChunkFile<RawChunk>>(
+                    &chunks,
+                    &AnvilChunkConfig::default(),
+                    &path,
+                )
+                .await
+                .unwrap();
+                path
+            }
+        };
+        let first = write(0).await;
+        let second = write(1).await;
+
+        // Give the only chunk of the second region an unknown compression
+        let mut bytes = std::fs::read(&second).unwrap();
+        let sector = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]) as usize;
+        bytes[sector * 4096 + 4] = 0x7F;
+        std::fs::write(&second, bytes).unwrap();
+
+        let converter = WorldConverter::new(
+            world.clone(),
+            ChunkConfig::Linear(LinearChunkConfig::default()),
+            ConvertOptions::default(),
+        );
+        assert!(converter.convert(|_| {}).await.is_err());
+        assert!(!first.exists());
+        assert!(second.exists());
+        assert!(world.join(JOB_FILE_NAME).exists());
+
+        // The converted first region must not be counted again
+        write(1).await;
+        let progress = converter.convert(|_| {}).await.unwrap();
+        assert_eq!(progress.regions_done, 2);
+        assert_eq!(progress.regions_total, 2);
+        assert_eq!(progress.chunks_kept, 2);
+        assert!(!second.exists());
+    }
+
+    #[test]
+    fn region_names() {
+        assert_eq!(
+            parse_region_name("r.-1.2.mca"),
+            Some(((-1, 2), RegionFormat::Anvil))
+        );
+        assert_eq!(
+            parse_region_name("r.0.0.linear"),
+            Some(((0, 0), RegionFormat::Linear))
+        );
+        assert_eq!(parse_region_name("r.0.0.tmp"), None);
+        assert_eq!(parse_region_name("r.0.mca"), None);
+    }
+}
@@ -37,6 +37,12 @@ pub const CHUNK_COUNT: usize = REGION_SIZE * REGION_SIZE;
 /// The number of bytes in a sector (4 KiB)
 const SECTOR_BYTES: usize = 4096;
 
+/// The most sectors a chunk can take up inside a region file, as the count is stored in one byte
+const MAX_CHUNK_SECTORS: usize = 255;
+
+/// Set in the compression byte if the chunk data is stored in an external `.mcc` file
+const EXTERNAL_CHUNK_FLAG: u8 = 0x80;
+
 // 1.21.11
 pub const WORLD_DATA_VERSION: i32 = 4671;
 
@@ -102,6 +108,9 @@ impl WriteAction {
 struct AnvilChunkMetadata {
     serialized_data: AnvilChunkData,
     timestamp: u32,
+    // The chunk was stored in an external file before, which has to be removed once it fits into
+    // the region again
+    stale_external: bool,
 
     // NOTE: This is only valid if our WriteAction is `Parts`
     file_sector_offset: u32,
@@ -228,14 +237,81 @@ impl From<pumpkin_config::chunk::Compression> for Compression {
     }
 }
 
+/// Returns the path of the external file that holds the data of an oversized chunk
+pub fn external_chunk_path(region_folder: &Path, x: i32, z: i32) -> PathBuf {
+    region_folder.join(format!("c.{x}.{z}.mcc"))
+}
+
+/// Removes the external file of a chunk, if there is one
+pub async fn remove_external_chunk(
+    region_folder: &Path,
+    x: i32,
+    z: i32,
+) -> Result<(), std::io::Error> {
+    match tokio::fs::remove_file(external_chunk_path(region_folder, x, z)).await {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the absolute position of the chunk at `index` of the region file at `region_path`
+fn chunk_position(region_path: &Path, index: usize) -> Option<(i32, i32)> {
+    let name = region_path.file_name()?.to_str()?;
+    let mut parts = name.split('.');
+    if parts.next()? != "r" {
+        return None;
+    }
+    let region_x: i32 = parts.next()?.parse().ok()?;
+    let region_z: i32 = parts.next()?.parse().ok()?;
+
+    let local_x = (index % REGION_SIZE) as i32;
+    let local_z = (index / REGION_SIZE) as i32;
+    Some((
+        (region_x << SUBREGION_BITS) + local_x,
+        (region_z << SUBREGION_BITS) + local_z,
+    ))
+}
+
+/// Returns the path of the external file of the chunk at `index`, next to the region file
+fn external_chunk_path_of(region_path: &Path, index: usize) -> Result<PathBuf, std::io::Error> {
+    let (x, z) = chunk_position(region_path, index).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Can't locate external chunks of {}, it is not named like a region",
+                region_path.display()
+            ),
+        )
+    })?;
+    let region_folder = region_path.parent().unwrap_or(Path::new(""));
+    Ok(external_chunk_path(region_folder, x, z))
+}
+
 impl AnvilChunkData {
-    /// Raw size of serialized chunk
+    /// Size of the serialized chunk if it is stored inside the region
     #[inline]
-    fn raw_write_size(&self) -> usize {
+    fn inline_size(&self) -> usize {
         // 4 bytes for the *length* and 1 byte for the *compression* method
         self.compressed_data.len() + 4 + 1
     }
 
+    /// Whether the chunk is too large for the region and has to be stored in an external file
+    #[inline]
+    fn is_external(&self) -> bool {
+        self.inline_size() > MAX_CHUNK_SECTORS * SECTOR_BYTES
+    }
+
+    /// Raw size of serialized chunk
+    #[inline]
+    fn raw_write_size(&self) -> usize {
+        if self.is_external() {
+            // Only the header is kept in the region
+            4 + 1
+        } else {
+            self.inline_size()
+        }
+    }
+
     /// Size of serialized chunk with padding
     #[inline]
     fn padded_size(&self) -> usize {
@@ -249,7 +325,15 @@ impl AnvilChunkData {
         total_size.div_ceil(SECTOR_BYTES) as u32
     }
 
-    fn from_bytes(bytes: Bytes) -> Result<Self, ChunkReadingError> {
+    /// Whether the chunk at the start of `bytes` is stored in an external file
+    fn is_external_bytes(bytes: &[u8]) -> bool {
+        bytes
+            .get(4)
+            .is_some_and(|compression| compression & EXTERNAL_CHUNK_FLAG != 0)
+    }
+
+    /// `external` holds the contents of the external file for chunks that are stored in one
+    fn from_bytes(bytes: Bytes, external: Option<Bytes>) -> Result<Self, ChunkReadingError> {
         let mut bytes = bytes;
         // Minus one for the compression byte
         let length = bytes.get_u32() as usize - 1;
@@ -265,28 +349,39 @@ impl AnvilChunkData {
         }
 
         let compression_method = bytes.get_u8();
-        let compression = Compression::from_byte(compression_method)
+        let compression = Compression::from_byte(compression_method & !EXTERNAL_CHUNK_FLAG)
             .map_err(|_| ChunkReadingError::Compression(CompressionError::UnknownCompression))?;
 
+        let compressed_data = if compression_method & EXTERNAL_CHUNK_FLAG != 0 {
+            external.ok_or(ChunkReadingError::IoError(std::io::ErrorKind::NotFound))?
+        } else {
+            // If this has padding, we need to trim it
+            bytes.slice(..length)
+        };
+
         Ok(AnvilChunkData {
             compression,
-            // If this has padding, we need to trim it
-            compressed_data: bytes.slice(..length),
+            compressed_data,
         })
     }
 
     async fn write(&self, w: &mut (impl AsyncWrite + Unpin + Send)) -> Result<(), std::io::Error> {
         let padded_size = self.padded_size();
+        let compression_id = self
+            .compression
+            .map_or(Compression::NO_COMPRESSION_ID, |c| c as u8);
+
+        if self.is_external() {
+            // The data itself is written by `write_external`
+            w.write_u32(1).await?;
+            w.write_u8(compression_id | EXTERNAL_CHUNK_FLAG).await?;
+        } else {
+            w.write_u32((self.compressed_data.remaining() + 1) as u32)
+                .await?;
+            w.write_u8(compression_id).await?;
+            w.write_all(&self.compressed_data).await?;
+        }
 
-        w.write_u32((self.compressed_data.remaining() + 1) as u32)
-            .await?;
-        w.write_u8(
-            self.compression
-                .map_or(Compression::NO_COMPRESSION_ID, |c| c as u8),
-        )
-        .await?;
-
-        w.write_all(&self.compressed_data).await?;
         for _ in 0..(padded_size - self.raw_write_size()) {
             w.write_u8(0).await?;
         }
@@ -294,6 +389,15 @@ impl AnvilChunkData {
         Ok(())
     }
 
+    /// Writes the data of an oversized chunk to its external file
+    async fn write_external(&self, path: &Path) -> Result<(), std::io::Error> {
+        let temp_path = path.with_extension("mcc.tmp");
+        log::trace!("Writing external chunk to disk: {}", path.display());
+
+        tokio::fs::write(&temp_path, &self.compressed_data).await?;
+        tokio::fs::rename(temp_path, path).await
+    }
+
     fn to_chunk<S>(&self, pos: Vector2<i32>) -> Result<S, ChunkReadingError>
     where
         S: SingleChunkDataSerializer,
@@ -349,8 +453,60 @@ impl<S: SingleChunkDataSerializer> AnvilChunkFile<S> {
         index as usize
     }
 
+    /// Whether the chunk at the given position is stored in an external `.mcc` file
+    pub fn is_external_chunk(&self, x: i32, z: i32) -> bool {
+        self.chunks_data[Self::get_chunk_index(x, z)]
+            .as_ref()
+            .is_some_and(|chunk| chunk.serialized_data.is_external())
+    }
+
+    /// Writes the external files of the oversized chunks at `indices`.
+    ///
+    /// This has to happen before the region is written, so that it never points to a missing file
+    async fn write_external_chunks(
+        &self,
+        path: &Path,
+        indices: impl Iterator<Item = usize>,
+    ) -> Result<(), std::io::Error> {
+        for index in indices {
+            if let Some(chunk) = &self.chunks_data[index]
+                && chunk.serialized_data.is_external()
+            {
+                chunk
+                    .serialized_data
+                    .write_external(&external_chunk_path_of(path, index)?)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the external files of the chunks at `indices` that fit into the region again
+    async fn remove_stale_external_chunks(
+        &self,
+        path: &Path,
+        indices: impl Iterator<Item = usize>,
+    ) -> Result<(), std::io::Error> {
+        for index in indices {
+            if let Some(chunk) = &self.chunks_data[index]
+                && chunk.stale_external
+                && !chunk.serialized_data.is_external()
+            {
+                let external_path = external_chunk_path_of(path, index)?;
+                log::trace!("Removing external chunk {}", external_path.display());
+                match tokio::fs::remove_file(external_path).await {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
     async fn write_indices(&self, path: &Path, indices: &[usize]) -> Result<(), std::io::Error> {
         log::trace!("Writing in place: {}", path.display());
+        self.write_external_chunks(path, indices.iter().copied())
+            .await?;
 
         let file = tokio::fs::OpenOptions::new()
             .read(false)
@@ -444,13 +600,16 @@ impl<S: SingleChunkDataSerializer> AnvilChunkFile<S> {
             chunk.serialized_data.write(&mut write).await?;
         }
 
-        write.flush().await
+        write.flush().await?;
+        self.remove_stale_external_chunks(path, indices.iter().copied())
+            .await
     }
 
     /// Write entire file, disregarding saved offsets
     async fn write_all(&self, path: &Path) -> Result<(), std::io::Error> {
         let temp_path = path.with_extension("tmp");
         log::trace!("Writing tmp file to disk: {temp_path:?}");
+        self.write_external_chunks(path, 0..CHUNK_COUNT).await?;
 
         let file = tokio::fs::OpenOptions::new()
             .read(false)
@@ -495,6 +654,8 @@ impl<S: SingleChunkDataSerializer> AnvilChunkFile<S> {
         // The rename of the file works like an atomic operation ensuring
         // that the data is not corrupted before the rename is completed
         tokio::fs::rename(temp_path, path).await?;
+        self.remove_stale_external_chunks(path, 0..CHUNK_COUNT)
+            .await?;
 
         log::trace!("Wrote file to Disk: {}", path.display());
         Ok(())
@@ -558,7 +719,7 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for AnvilChunkFile<S> {
         Ok(())
     }
 
-    fn read(r: Bytes) -> Result<Self, ChunkReadingError> {
+    async fn read(r: Bytes, path: &PathBuf) -> Result<Self, ChunkReadingError> {
         let mut raw_file_bytes = r;
 
         if raw_file_bytes.len() < SECTOR_BYTES * 2 {
@@ -604,13 +765,34 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for AnvilChunkFile<S> {
                 ));
             }
 
-            let serialized_data = AnvilChunkData::from_bytes(
-                raw_file_bytes.slice(bytes_offset..bytes_offset + bytes_count),
-            )?;
+            let chunk_bytes = raw_file_bytes.slice(bytes_offset..bytes_offset + bytes_count);
+            let external = if AnvilChunkData::is_external_bytes(&chunk_bytes) {
+                let external = match external_chunk_path_of(path, i) {
+                    Ok(external_path) => tokio::fs::read(external_path).await,
+                    Err(err) => Err(err),
+                };
+                match external {
+                    Ok(external) => Some(Bytes::from(external)),
+                    Err(err) => {
+                        // Like vanilla, a missing external file only loses that chunk
+                        log::error!(
+                            "Failed to read the external file of chunk {} in {}: {}",
+                            i,
+                            path.display(),
+                            err
+                        );
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+            let serialized_data = AnvilChunkData::from_bytes(chunk_bytes, external)?;
 
             chunk_file.chunks_data[i] = Some(AnvilChunkMetadata {
                 serialized_data,
                 timestamp,
+                stale_external: false,
                 file_sector_offset: sector_offset as u32,
             });
         }
@@ -636,6 +818,9 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for AnvilChunkFile<S> {
             .and_then(|chunk_data| chunk_data.serialized_data.compression);
         let new_chunk_data =
             AnvilChunkData::from_chunk(chunk, compression_type, chunk_config).await?;
+        let stale_external = self.chunks_data[index].as_ref().is_some_and(|chunk_data| {
+            chunk_data.stale_external || chunk_data.serialized_data.is_external()
+        }) && !new_chunk_data.is_external();
 
         let mut write_action = self.write_action.lock().await;
         if !chunk_config.write_in_place {
@@ -649,6 +834,7 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for AnvilChunkFile<S> {
                 self.chunks_data[index] = Some(AnvilChunkMetadata {
                     serialized_data: new_chunk_data,
                     timestamp: epoch,
+                    stale_external,
                     file_sector_offset: 0,
                 });
             }
@@ -666,6 +852,7 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for AnvilChunkFile<S> {
                         self.chunks_data[index] = Some(AnvilChunkMetadata {
                             serialized_data: new_chunk_data,
                             timestamp: epoch,
+                            stale_external,
                             file_sector_offset: self.end_sector,
                         });
                         self.end_sector = new_eof;
@@ -684,6 +871,7 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for AnvilChunkFile<S> {
                             self.chunks_data[index] = Some(AnvilChunkMetadata {
                                 serialized_data: new_chunk_data,
                                 timestamp: epoch,
+                                stale_external,
                                 file_sector_offset: old_chunk.file_sector_offset,
                             });
                             write_action.maybe_update_chunk_index(index);
@@ -731,6 +919,7 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for AnvilChunkFile<S> {
                                 self.chunks_data[index] = Some(AnvilChunkMetadata {
                                     serialized_data: new_chunk_data,
                                     timestamp: epoch,
+                                    stale_external,
                                     file_sector_offset: 0,
                                 });
                             } else {
@@ -752,6 +941,7 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for AnvilChunkFile<S> {
                                 self.chunks_data[index] = Some(AnvilChunkMetadata {
                                     serialized_data: new_chunk_data,
                                     timestamp: epoch,
+                                    stale_external,
                                     file_sector_offset: swap.1.file_sector_offset,
                                 });
                                 write_action.maybe_update_chunk_index(index);
@@ -911,6 +1101,7 @@ mod tests {
                     root_folder: PathBuf::from(""),
                     region_folder: region_path,
                     entities_folder: PathBuf::from(""),
+                    poi_folder: PathBuf::from(""),
                 },
                 &[Vector2::new(0, 0)],
                 send,
@@ -940,6 +1131,7 @@ mod tests {
             root_folder: temp_dir.path().to_path_buf(),
             region_folder: temp_dir.path().join("region"),
             entities_folder: PathBuf::from("entities"),
+            poi_folder: PathBuf::from("poi"),
         };
         fs::create_dir(&level_folder.region_folder).expect("couldn't create region folder");
         let chunk_saver = ChunkFileManager::<AnvilChunkFile<ChunkData>>::default();
@@ -1216,6 +1408,7 @@ mod tests {
             root_folder: temp_dir.path().to_path_buf(),
             region_folder: temp_dir.path().join("region"),
             entities_folder: PathBuf::from("entities"),
+            poi_folder: PathBuf::from("poi"),
         };
         fs::create_dir(&level_folder.region_folder).expect("couldn't create region folder");
         let chunk_saver = ChunkFileManager::<AnvilChunkFile<ChunkData>>::default();
@@ -1334,3 +1527,185 @@ mod tests {
     */
 }
  */
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use pumpkin_config::chunk::AnvilChunkConfig;
+    use pumpkin_util::math::vector2::Vector2;
+    use std::path::{Path, PathBuf};
+    use temp_dir::TempDir;
+
+    use super::{
+        AnvilChunkData, AnvilChunkFile, AnvilChunkMetadata, MAX_CHUNK_SECTORS, SECTOR_BYTES,
+        WriteAction, external_chunk_path,
+    };
+    use crate::chunk::convert::RawChunk;
+    use crate::chunk::io::{ChunkSerializer, LoadedData};
+
+    /// Bytes that don't compress, so the size on disk is predictable
+    fn noise(len: usize) -> Bytes {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn raw_chunk_data(len: usize) -> AnvilChunkData {
+        AnvilChunkData {
+            compression: None,
+            compressed_data: noise(len),
+        }
+    }
+
+    async fn read_chunk(path: &PathBuf, x: i32, z: i32) -> Option<RawChunk> {
+        let bytes = tokio::fs::read(path).await.unwrap();
+        let file = AnvilChunkFile::<RawChunk>::read(bytes.into(), path)
+            .await
+            .unwrap();
+
+        let (send, mut recv) = tokio::sync::mpsc::channel(1);
+        let (_, data) = tokio::join!(file.get_chunks(vec![Vector2::new(x, z)], send), async {
+            recv.recv().await
+        });
+        match data.unwrap() {
+            LoadedData::Loaded(chunk) => Some(chunk),
+            LoadedData::Missing(_) => None,
+            LoadedData::Error((_, err)) => panic!("Failed to read chunk: {err}"),
+        }
+    }
+
+    #[test]
+    fn sector_boundaries() {
+        // Length and compression byte are stored in front of the data
+        let largest_inline = MAX_CHUNK_SECTORS * SECTOR_BYTES - 5;
+
+        let chunk = raw_chunk_data(largest_inline);
+        assert!(!chunk.is_external());
+        assert_eq!(chunk.sector_count(), MAX_CHUNK_SECTORS as u32);
+
+        let chunk = raw_chunk_data(largest_inline + 1);
+        assert!(chunk.is_external());
+        assert_eq!(chunk.sector_count(), 1);
+
+        let chunk = raw_chunk_data(SECTOR_BYTES - 5);
+        assert_eq!(chunk.sector_count(), 1);
+        let chunk = raw_chunk_data(SECTOR_BYTES - 4);
+        assert_eq!(chunk.sector_count(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn boundary_sizes_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("r.-1.2.mca");
+        let largest_inline = MAX_CHUNK_SECTORS * SECTOR_BYTES - 5;
+
+        for (len, external) in [(largest_inline, false), (largest_inline + 1, true)] {
+            let mut file = AnvilChunkFile::<RawChunk>::default();
+            file.chunks_data[33] = Some(AnvilChunkMetadata {
+                serialized_data: raw_chunk_data(len),
+                timestamp: 0,
+                stale_external: false,
+                file_sector_offset: 0,
+            });
+            *file.write_action.get_mut() = WriteAction::All;
+            file.write(&path).await.unwrap();
+
+            // Index 33 is local chunk 1,1 of region -1,2
+            let external_path = external_chunk_path(temp_dir.path(), -31, 65);
+            assert_eq!(external_path.exists(), external);
+            let chunk = read_chunk(&path, -31, 65).await.unwrap();
+            assert_eq!(chunk.data, noise(len));
+            if external {
+                assert_eq!(
+                    std::fs::metadata(&path).unwrap().len() as usize,
+                    3 * SECTOR_BYTES
+                );
+                std::fs::remove_file(external_path).unwrap();
+            }
+        }
+    }
+
+    async fn write_shrinking_chunk(region_folder: &Path, write_in_place: bool) {
+        let path = region_folder.join("r.0.0.mca");
+        let config = AnvilChunkConfig {
+            write_in_place,
+            ..Default::default()
+        };
+        let small = RawChunk {
+            x: 3,
+            z: 4,
+            data: noise(100),
+        };
+        let mut big = RawChunk {
+            x: 5,
+            z: 6,
+            // Larger than the 1 MiB a region can hold for a single chunk
+            data: noise(2 * 1024 * 1024),
+        };
+
+        let mut file = AnvilChunkFile::<RawChunk>::default();
+        file.update_chunk(&small, &config).await.unwrap();
+        file.update_chunk(&big, &config).await.unwrap();
+        file.write(&path).await.unwrap();
+
+        let external_path = external_chunk_path(region_folder, 5, 6);
+        assert!(file.is_external_chunk(5, 6));
+        assert!(!file.is_external_chunk(3, 4));
+        assert!(external_path.exists());
+        assert_eq!(read_chunk(&path, 5, 6).await.unwrap().data, big.data);
+        assert_eq!(read_chunk(&path, 3, 4).await.unwrap().data, small.data);
+
+        // Shrink the chunk on a freshly read file, like after a restart
+        let mut file =
+            AnvilChunkFile::<RawChunk>::read(tokio::fs::read(&path).await.unwrap().into(), &path)
+                .await
+                .unwrap();
+        big.data = noise(1000);
+        file.update_chunk(&big, &config).await.unwrap();
+        file.write(&path).await.unwrap();
+
+        assert!(!external_path.exists());
+        assert_eq!(read_chunk(&path, 5, 6).await.unwrap().data, big.data);
+        assert_eq!(read_chunk(&path, 3, 4).await.unwrap().data, small.data);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn external_chunk_is_removed_when_shrinking() {
+        let temp_dir = TempDir::new().unwrap();
+        write_shrinking_chunk(temp_dir.path(), false).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn external_chunk_is_removed_when_shrinking_in_place() {
+        let temp_dir = TempDir::new().unwrap();
+        write_shrinking_chunk(temp_dir.path(), true).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn missing_external_file_only_loses_its_chunk() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("r.0.0.mca");
+        let config = AnvilChunkConfig::default();
+
+        let mut file = AnvilChunkFile::<RawChunk>::default();
+        for (x, len) in [(0, 100), (1, 2 * 1024 * 1024)] {
+            let chunk = RawChunk {
+                x,
+                z: 0,
+                data: noise(len),
+            };
+            file.update_chunk(&chunk, &config).await.unwrap();
+        }
+        file.write(&path).await.unwrap();
+        std::fs::remove_file(external_chunk_path(temp_dir.path(), 1, 0)).unwrap();
+
+        assert!(read_chunk(&path, 1, 0).await.is_none());
+        assert_eq!(read_chunk(&path, 0, 0).await.unwrap().data, noise(100));
+    }
+}
@@ -241,7 +241,7 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for LinearFile<S> {
         Ok(())
     }
 
-    fn read(raw_file: Bytes) -> Result<Self, ChunkReadingError> {
+    async fn read(raw_file: Bytes, _path: &PathBuf) -> Result<Self, ChunkReadingError> {
         let Some((signature, raw_file_bytes)) = raw_file.split_at_checked(SIGNATURE.len()) else {
             return Err(ChunkReadingError::IoError(ErrorKind::UnexpectedEof));
         };
@@ -275,6 +275,10 @@ impl<S: SingleChunkDataSerializer> ChunkSerializer for LinearFile<S> {
             .map_err(|err| ChunkReadingError::IoError(err.kind()))?;
         let mut buffer: Bytes = buffer.into();
 
+        // A truncated region must not panic the reader
+        if buffer.len() < LinearChunkHeader::CHUNK_HEADER_SIZE * CHUNK_COUNT {
+            return Err(ChunkReadingError::InvalidHeader);
+        }
         let headers_buffer = buffer.split_to(LinearChunkHeader::CHUNK_HEADER_SIZE * CHUNK_COUNT);
 
         // Parse the chunk headers
@@ -425,6 +429,7 @@ mod tests {
                     root_folder: PathBuf::from(""),
                     region_folder: region_path,
                     entities_folder: PathBuf::from(""),
+                    poi_folder: PathBuf::from(""),
                 },
                 &[Vector2::new(0, 0)],
                 send,
@@ -449,6 +454,7 @@ mod tests {
             root_folder: temp_dir.path().to_path_buf(),
             region_folder: temp_dir.path().join("region"),
             entities_folder: PathBuf::from("entities"),
+            poi_folder: PathBuf::from("poi"),
         };
         fs::create_dir(&level_folder.region_folder).expect("couldn't create region folder");
         let chunk_saver = ChunkFileManager::<LinearFile<ChunkData>>::default();
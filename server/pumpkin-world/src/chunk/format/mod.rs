@@ -15,6 +15,7 @@ use crate::{
     },
     generation::section_coords,
     level::LevelFolder,
+    persistent_data::PersistentDataContainer,
     tick::{ScheduledTick, scheduler::ChunkTickScheduler},
 };
 use pumpkin_util::math::vector2::Vector2;
@@ -58,6 +59,9 @@ impl PathFromLevelFolder for ChunkData {
 impl Dirtiable for ChunkData {
     #[inline]
     fn mark_dirty(&mut self, flag: bool) {
+        if flag {
+            self.generation = self.generation.wrapping_add(1);
+        }
         self.dirty = flag;
     }
 
@@ -181,6 +185,9 @@ impl ChunkData {
             },
             light_engine,
             status: chunk_data.status,
+            generation: 0,
+            packet_cache: Default::default(),
+            persistent_data: chunk_data.persistent_data,
         })
     }
 
@@ -223,6 +230,7 @@ impl ChunkData {
             .await,
             // we have not implemented light engine
             light_correct: false,
+            persistent_data: self.persistent_data.clone(),
         };
 
         let mut result = Vec::new();
@@ -468,6 +476,12 @@ struct ChunkNbt {
     block_entities: Vec<NbtCompound>,
     #[serde(rename = "isLightOn")]
     light_correct: bool,
+    #[serde(
+        rename = "PumpkinData",
+        default,
+        skip_serializing_if = "PersistentDataContainer::is_empty"
+    )]
+    persistent_data: PersistentDataContainer,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
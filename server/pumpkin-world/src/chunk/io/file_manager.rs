@@ -112,7 +112,7 @@ impl<S: ChunkSerializer<WriteBackend = PathBuf>> ChunkSerializerLazyLoader<S> {
                 file.read_to_end(&mut file_bytes)
                     .await
                     .map_err(|err| ChunkReadingError::IoError(err.kind()))?;
-                S::read(file_bytes.into())?
+                S::read(file_bytes.into(), &self.path).await?
             }
             Err(ChunkReadingError::ChunkNotExist) => S::default(),
             Err(err) => return Err(err),
@@ -112,8 +112,11 @@ pub trait ChunkSerializer: Send + Sync + Default {
         backend: &Self::WriteBackend,
     ) -> impl Future<Output = Result<(), std::io::Error>> + Send;
 
-    /// Create a new instance from bytes
-    fn read(r: Bytes) -> Result<Self, ChunkReadingError>;
+    /// Create a new instance from bytes read from the backend
+    fn read(
+        r: Bytes,
+        backend: &Self::WriteBackend,
+    ) -> impl Future<Output = Result<Self, ChunkReadingError>> + Send;
 
     /// Add the chunk data to the serializer
     fn update_chunk(
@@ -1,7 +1,9 @@
 use crate::BlockStateId;
 use crate::block::entities::BlockEntity;
 use crate::chunk::format::LightContainer;
+use crate::persistent_data::PersistentDataContainer;
 use crate::tick::scheduler::ChunkTickScheduler;
+use packet_cache::ChunkPacketCache;
 use palette::{BiomePalette, BlockPalette};
 use pumpkin_data::block_properties::blocks_movement;
 use pumpkin_data::chunk::ChunkStatus;
@@ -17,8 +19,10 @@ use std::ops::BitAnd;
 use std::{collections::HashMap, sync::Arc};
 use thiserror::Error;
 
+pub mod convert;
 pub mod format;
 pub mod io;
+pub mod packet_cache;
 pub mod palette;
 
 // TODO
@@ -80,6 +84,11 @@ pub struct ChunkData {
     pub light_engine: ChunkLight,
     pub status: ChunkStatus,
     pub dirty: bool,
+    /// Bumped every time the chunk is marked dirty, tells cached packets apart
+    pub generation: u64,
+    pub packet_cache: ChunkPacketCache,
+    /// Data plugins attached to the chunk, only saved once the chunk is marked dirty
+    pub persistent_data: PersistentDataContainer,
 }
 
 #[derive(Clone)]
@@ -0,0 +1,135 @@
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+
+/// The encodings of a chunk sent to clients
+#[derive(Clone, Copy)]
+pub enum ChunkPacketKind {
+    /// Java chunk and light packet, id included
+    Java,
+    /// The Java packet zlib-compressed, prefixed with its uncompressed length
+    JavaCompressed,
+    /// Bedrock level chunk payload
+    Bedrock,
+}
+
+impl ChunkPacketKind {
+    const COUNT: usize = 3;
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The blobs a chunk is split into for the Bedrock client blob cache, each with its id
+pub type ChunkBlobSet = Arc<[(u64, Bytes)]>;
+
+/// Encoded packets of a chunk, so a chunk sent to many players is only encoded once.
+///
+/// Entries are tagged with the chunk generation they were encoded from; once the chunk is
+/// modified its generation changes and the old entries are never handed out again.
+#[derive(Default)]
+pub struct ChunkPacketCache {
+    entries: Mutex<[Option<(u64, Bytes)>; ChunkPacketKind::COUNT]>,
+    blobs: Mutex<Option<(u64, ChunkBlobSet)>>,
+}
+
+impl ChunkPacketCache {
+    /// The cached packet of `generation`, encoding it with `encode` on a miss.
+    ///
+    /// The lock is not held while encoding, so two players may both encode a chunk they
+    /// request at the same time; that is cheaper than making one wait on the other.
+    pub fn get_or_encode<E>(
+        &self,
+        kind: ChunkPacketKind,
+        generation: u64,
+        encode: impl FnOnce() -> Result<Bytes, E>,
+    ) -> Result<Bytes, E> {
+        if let Some((cached_generation, data)) = &self.entries.lock().unwrap()[kind.index()]
+            && *cached_generation == generation
+        {
+            return Ok(data.clone());
+        }
+
+        let data = encode()?;
+        self.entries.lock().unwrap()[kind.index()] = Some((generation, data.clone()));
+        Ok(data)
+    }
+
+    /// The cached blob set of `generation`, encoding it with `encode` on a miss, the same way
+    /// as [`Self::get_or_encode`]
+    pub fn get_or_encode_blobs<E>(
+        &self,
+        generation: u64,
+        encode: impl FnOnce() -> Result<ChunkBlobSet, E>,
+    ) -> Result<ChunkBlobSet, E> {
+        if let Some((cached_generation, blobs)) = &*self.blobs.lock().unwrap()
+            && *cached_generation == generation
+        {
+            return Ok(blobs.clone());
+        }
+
+        let blobs = encode()?;
+        *self.blobs.lock().unwrap() = Some((generation, blobs.clone()));
+        Ok(blobs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{convert::Infallible, sync::Arc};
+
+    use bytes::Bytes;
+
+    use super::{ChunkPacketCache, ChunkPacketKind};
+
+    #[test]
+    fn encodes_once_per_generation() {
+        let cache = ChunkPacketCache::default();
+        let mut encoded = 0;
+        let mut get = |generation| {
+            cache
+                .get_or_encode(ChunkPacketKind::Java, generation, || {
+                    encoded += 1;
+                    Ok::<_, Infallible>(Bytes::from(vec![generation as u8]))
+                })
+                .unwrap()
+        };
+
+        assert_eq!(get(0), Bytes::from_static(&[0]));
+        assert_eq!(get(0), Bytes::from_static(&[0]));
+        assert_eq!(get(1), Bytes::from_static(&[1]));
+        drop(get);
+        assert_eq!(encoded, 2);
+
+        // Kinds are cached separately
+        let bedrock = cache
+            .get_or_encode(ChunkPacketKind::Bedrock, 1, || {
+                Ok::<_, Infallible>(Bytes::from_static(&[9]))
+            })
+            .unwrap();
+        assert_eq!(bedrock, Bytes::from_static(&[9]));
+    }
+
+    #[test]
+    fn encodes_blobs_once_per_generation() {
+        let cache = ChunkPacketCache::default();
+        let mut encoded = 0;
+        let mut get = |generation: u64| {
+            cache
+                .get_or_encode_blobs(generation, || {
+                    encoded += 1;
+                    Ok::<_, Infallible>(
+                        vec![(generation, Bytes::from(vec![generation as u8]))].into(),
+                    )
+                })
+                .unwrap()
+        };
+
+        let first = get(0);
+        assert!(Arc::ptr_eq(&first, &get(0)));
+        assert_eq!(get(1)[0].0, 1);
+        drop(get);
+        assert_eq!(encoded, 2);
+    }
+}
@@ -267,6 +267,15 @@ impl<V: Hash + Eq + Copy + Default, const DIM: usize> PalettedContainer<V, DIM>
         }
     }
 
+    /// Whether any distinct value of the container matches `predicate`, without visiting every
+    /// entry
+    pub fn palette_any(&self, mut predicate: impl FnMut(V) -> bool) -> bool {
+        match self {
+            Self::Homogeneous(value) => predicate(*value),
+            Self::Heterogeneous(data) => data.palette.iter().any(|value| predicate(*value)),
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         match self {
             Self::Homogeneous(value) => *value == V::default(),
@@ -245,6 +245,9 @@ impl ChunkLoading {
     // pub const FULL_CHUNK_LEVEL: i8 = 33;
     pub const FULL_CHUNK_LEVEL: i8 = 43;
     pub const MAX_LEVEL: i8 = 46; // level 46 will be unloaded.
+    /// Level of `/forceload` tickets, keeps the surrounding chunks fully loaded so the forced
+    /// chunk itself is simulated like one next to a player
+    pub const FORCED_CHUNK_LEVEL: i8 = Self::FULL_CHUNK_LEVEL - 2;
     fn debug_check_error(&self) -> bool {
         let mut temp = ChunkLevel::default();
         for (ticket_pos, levels) in &self.ticket {
@@ -816,6 +819,9 @@ impl Chunk {
             fluid_ticks: Default::default(),
             block_entities: Default::default(),
             status: proto_chunk.stage.into(),
+            generation: 0,
+            packet_cache: Default::default(),
+            persistent_data: Default::default(),
         };
 
         chunk.heightmap = chunk.calculate_heightmap();
@@ -1707,7 +1713,7 @@ impl GenerationSchedule {
         );
     }
 
-    fn unload_chunk(&mut self) {
+    fn unload_chunk(&mut self, level: &Level) {
         let mut unload_chunks = HashSetType::default();
         swap(&mut unload_chunks, &mut self.unload_chunks);
         let mut chunks = Vec::with_capacity(unload_chunks.len());
@@ -1751,6 +1757,9 @@ impl GenerationSchedule {
         if chunks.is_empty() {
             return;
         }
+        level
+            .unloaded_chunks
+            .fetch_add(chunks.len() as u64, Relaxed);
         let mut data = self.io_lock.0.lock().unwrap();
         for (pos, _chunk) in &chunks {
             *data.entry(*pos).or_insert(0) += 1;
@@ -1920,7 +1929,7 @@ impl GenerationSchedule {
         loop {
             if level.should_unload.load(Relaxed) {
                 // log::debug!("unload chunk signal");
-                self.unload_chunk();
+                self.unload_chunk(&level);
                 level.should_unload.store(false, Relaxed);
             }
             if level.should_save.load(Relaxed) {
@@ -1934,6 +1943,7 @@ impl GenerationSchedule {
             }
 
             'out2: while let Some(task) = self.queue.pop() {
+                level.generation_queue_len.store(self.queue.len(), Relaxed);
                 if self.resort_work(self.send_level.get()) {
                     self.queue.push(task);
                     break 'out2;
@@ -2051,6 +2061,7 @@ impl GenerationSchedule {
                 }
             }
 
+            level.generation_queue_len.store(self.queue.len(), Relaxed);
             if self.queue.is_empty() {
                 // debug!("the queue is empty. thread sleep");
                 'out: while self.running_task_count > 0 {
@@ -0,0 +1,140 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use pumpkin_nbt::nbt_long_array;
+use pumpkin_util::math::vector2::Vector2;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::chunk::format::anvil::WORLD_DATA_VERSION;
+
+pub const FORCED_CHUNKS_FILE_NAME: &str = "chunks.dat";
+
+/// The vanilla `data/chunks.dat` saved data, holding the chunks forced by `/forceload`
+#[derive(Serialize, Deserialize)]
+struct ForcedChunksDat {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    data: ForcedChunksData,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ForcedChunksData {
+    #[serde(rename = "Forced", serialize_with = "nbt_long_array", default)]
+    forced: Vec<i64>,
+}
+
+#[derive(Error, Debug)]
+pub enum ForcedChunksError {
+    #[error("Io error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Deserialization error: {0}")]
+    DeserializationError(String),
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+}
+
+/// Packs a chunk position the same way vanilla's `ChunkPos.toLong` does
+#[must_use]
+pub const fn pack_chunk_pos(pos: Vector2<i32>) -> i64 {
+    (pos.x as u32 as i64) | ((pos.y as i64) << 32)
+}
+
+#[must_use]
+pub const fn unpack_chunk_pos(packed: i64) -> Vector2<i32> {
+    Vector2::new(packed as i32, (packed >> 32) as i32)
+}
+
+fn forced_chunks_path(root_folder: &Path) -> PathBuf {
+    root_folder.join("data").join(FORCED_CHUNKS_FILE_NAME)
+}
+
+/// Reads the forced chunks of the dimension stored in `root_folder`, a missing file means none
+pub fn read_forced_chunks(root_folder: &Path) -> Result<Vec<Vector2<i32>>, ForcedChunksError> {
+    let file = match OpenOptions::new()
+        .read(true)
+        .open(forced_chunks_path(root_folder))
+    {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut buf = Vec::new();
+    GzDecoder::new(file).read_to_end(&mut buf)?;
+    let dat: ForcedChunksDat = pumpkin_nbt::from_bytes(Cursor::new(buf))
+        .map_err(|err| ForcedChunksError::DeserializationError(err.to_string()))?;
+
+    Ok(dat.data.forced.into_iter().map(unpack_chunk_pos).collect())
+}
+
+/// Writes the forced chunks of the dimension stored in `root_folder`
+pub fn write_forced_chunks(
+    root_folder: &Path,
+    chunks: impl IntoIterator<Item = Vector2<i32>>,
+) -> Result<(), ForcedChunksError> {
+    let dat = ForcedChunksDat {
+        data_version: WORLD_DATA_VERSION,
+        data: ForcedChunksData {
+            forced: chunks.into_iter().map(pack_chunk_pos).collect(),
+        },
+    };
+
+    let path = forced_chunks_path(root_folder);
+    fs::create_dir_all(path.parent().expect("chunks.dat always has a parent"))?;
+    // Write next to the old file first so a crash never leaves a truncated chunks.dat behind
+    let temp_path = path.with_extension("dat_tmp");
+    let file = OpenOptions::new()
+        .truncate(true)
+        .create(true)
+        .write(true)
+        .open(&temp_path)?;
+    let mut writer = GzEncoder::new(file, Compression::default());
+    pumpkin_nbt::to_bytes(&dat, &mut writer)
+        .map_err(|err| ForcedChunksError::SerializationError(err.to_string()))?;
+    writer.finish()?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use pumpkin_util::math::vector2::Vector2;
+    use temp_dir::TempDir;
+
+    use super::{pack_chunk_pos, read_forced_chunks, unpack_chunk_pos, write_forced_chunks};
+
+    #[test]
+    fn packs_like_vanilla() {
+        assert_eq!(pack_chunk_pos(Vector2::new(1, 2)), (2 << 32) | 1);
+        assert_eq!(pack_chunk_pos(Vector2::new(-1, 0)), 0xFFFF_FFFF);
+        for pos in [
+            Vector2::new(0, 0),
+            Vector2::new(-1, -1),
+            Vector2::new(1_874_999, -1_874_999),
+        ] {
+            assert_eq!(unpack_chunk_pos(pack_chunk_pos(pos)), pos);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(read_forced_chunks(temp_dir.path()).unwrap().is_empty());
+
+        let chunks = vec![
+            Vector2::new(0, 0),
+            Vector2::new(-3, 7),
+            Vector2::new(12, -40),
+        ];
+        write_forced_chunks(temp_dir.path(), chunks.clone()).unwrap();
+        assert_eq!(read_forced_chunks(temp_dir.path()).unwrap(), chunks);
+
+        write_forced_chunks(temp_dir.path(), []).unwrap();
+        assert!(read_forced_chunks(temp_dir.path()).unwrap().is_empty());
+    }
+}
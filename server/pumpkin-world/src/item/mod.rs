@@ -1,7 +1,7 @@
 use pumpkin_data::data_component::DataComponent;
 use pumpkin_data::data_component::DataComponent::Enchantments;
 use pumpkin_data::data_component_impl::{
-    BlocksAttacksImpl, ConsumableImpl, DataComponentImpl, EnchantmentsImpl, IDSet,
+    BlocksAttacksImpl, ConsumableImpl, CustomDataImpl, DataComponentImpl, EnchantmentsImpl, IDSet,
     MaxStackSizeImpl, ToolImpl, get, get_mut, read_data,
 };
 use pumpkin_data::item::Item;
@@ -13,6 +13,8 @@ use pumpkin_util::GameMode;
 use std::borrow::Cow;
 use std::cmp::{max, min};
 
+use crate::persistent_data::{PERSISTENT_DATA_KEY, PersistentDataContainer};
+
 mod categories;
 
 #[derive(Clone)]
@@ -196,6 +198,35 @@ impl ItemStack {
             ));
         }
     }
+    /// The data plugins attached to the item, kept in its `custom_data` component
+    pub fn persistent_data(&self) -> PersistentDataContainer {
+        self.get_data_component::<CustomDataImpl>()
+            .map(|custom_data| PersistentDataContainer::read(&custom_data.data))
+            .unwrap_or_default()
+    }
+
+    /// Replaces the data plugins attached to the item, leaving the rest of its `custom_data` alone.
+    /// Items with different data don't stack.
+    pub fn set_persistent_data(&mut self, data: &PersistentDataContainer) {
+        let mut custom_data = self
+            .get_data_component::<CustomDataImpl>()
+            .map(|custom_data| custom_data.data.clone())
+            .unwrap_or_default();
+        custom_data
+            .child_tags
+            .retain(|(name, _)| name != PERSISTENT_DATA_KEY);
+        data.write(&mut custom_data);
+
+        self.patch
+            .retain(|(id, _)| *id != DataComponent::CustomData);
+        if !custom_data.is_empty() {
+            self.patch.push((
+                DataComponent::CustomData,
+                Some(CustomDataImpl { data: custom_data }.to_dyn()),
+            ));
+        }
+    }
+
     pub fn are_items_and_components_equal(&self, other: &Self) -> bool {
         if self.item != other.item || self.patch.len() != other.patch.len() {
             return false;
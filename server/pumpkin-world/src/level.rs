@@ -9,15 +9,23 @@ use crate::{
         io::{Dirtiable, FileIO, LoadedData, file_manager::ChunkFileManager},
     },
     dimension::Dimension,
+    forced_chunks::{read_forced_chunks, write_forced_chunks},
     generation::get_world_gen,
+    persistent_data::{
+        PersistentDataContainer, read_world_persistent_data, write_world_persistent_data,
+    },
+    poi::{PoiStorage, PoiType},
     tick::{OrderedTick, ScheduledTick, TickPriority},
     world::BlockRegistryExt,
 };
-use crossbeam::channel::Sender;
+use crossbeam::channel::{Receiver, Sender};
 use dashmap::{DashMap, Entry};
 use log::trace;
 use num_traits::Zero;
-use pumpkin_config::{chunk::ChunkConfig, world::LevelConfig};
+use pumpkin_config::{
+    chunk::{AnvilChunkConfig, ChunkConfig},
+    world::LevelConfig,
+};
 use pumpkin_data::biome::Biome;
 use pumpkin_data::{Block, block_properties::has_random_ticks, fluid::Fluid};
 use pumpkin_util::math::{position::BlockPos, vector2::Vector2};
@@ -26,11 +34,11 @@ use rand::{Rng, SeedableRng, rngs::SmallRng};
 use std::sync::Mutex;
 // use std::time::Duration;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     path::PathBuf,
     sync::{
         Arc,
-        atomic::{AtomicBool, AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
     },
     thread,
 };
@@ -72,11 +80,19 @@ pub struct Level {
     pub loaded_chunks: Arc<DashMap<Vector2<i32>, SyncChunk>>,
     loaded_entity_chunks: Arc<DashMap<Vector2<i32>, SyncEntityChunk>>,
     pub chunk_loading: Mutex<ChunkLoading>,
+    /// Chunks kept loaded by `/forceload`, persisted in `data/chunks.dat`
+    forced_chunks: Mutex<HashSet<Vector2<i32>>>,
+    /// Data plugins attached to the dimension, persisted in `data/pumpkin_data.dat`
+    pub persistent_data: Mutex<PersistentDataContainer>,
 
     chunk_watchers: Arc<DashMap<Vector2<i32>, usize>>,
 
     pub chunk_saver: Arc<dyn FileIO<Data = SyncChunk>>,
     entity_saver: Arc<dyn FileIO<Data = SyncEntityChunk>>,
+    /// Points of interest like portals, beds and workstations
+    pub poi: PoiStorage,
+    /// Chunks that finished loading and still have to be scanned for points of interest
+    poi_listener: Receiver<(Vector2<i32>, SyncChunk)>,
 
     pub world_gen: Arc<VanillaGenerator>,
 
@@ -91,6 +107,13 @@ pub struct Level {
     pub should_save: AtomicBool,
     pub should_unload: AtomicBool,
 
+    /// Tasks waiting in the queue of the generation schedule
+    pub generation_queue_len: AtomicUsize,
+    /// Chunks written out and dropped from memory because nobody watched them anymore
+    pub unloaded_chunks: AtomicU64,
+    /// Entity chunks dropped from memory because nobody watched them anymore
+    pub unloaded_entity_chunks: AtomicU64,
+
     gen_entity_request_tx: Sender<Vector2<i32>>,
     pending_entity_generations: Arc<DashMap<Vector2<i32>, Vec<oneshot::Sender<SyncEntityChunk>>>>,
 
@@ -111,6 +134,7 @@ pub struct LevelFolder {
     pub root_folder: PathBuf,
     pub region_folder: PathBuf,
     pub entities_folder: PathBuf,
+    pub poi_folder: PathBuf,
 }
 
 #[ignore]
@@ -143,10 +167,23 @@ impl Level {
         if !entities_folder.exists() {
             std::fs::create_dir_all(&region_folder).expect("Failed to create Entities folder");
         }
+        let poi_folder = root_folder.join("poi");
+        if !poi_folder.exists() {
+            std::fs::create_dir_all(&poi_folder).expect("Failed to create POI folder");
+        }
+        let forced_chunks = read_forced_chunks(&root_folder).unwrap_or_else(|err| {
+            log::error!("Failed to read the forced chunks, none will be loaded: {err}");
+            Vec::new()
+        });
+        let persistent_data = read_world_persistent_data(&root_folder).unwrap_or_else(|err| {
+            log::error!("Failed to read the persistent data of plugins, it will be reset: {err}");
+            PersistentDataContainer::new()
+        });
         let level_folder = LevelFolder {
             root_folder,
             region_folder,
             entities_folder,
+            poi_folder,
         };
 
         // TODO: Load info correctly based on world format type
@@ -170,12 +207,19 @@ impl Level {
             >::new(chunk_config.clone())),
         };
 
+        // Points of interest are always stored in the vanilla region format
+        let poi = PoiStorage::new(match &level_config.chunk {
+            ChunkConfig::Anvil(chunk_config) => chunk_config.clone(),
+            ChunkConfig::Linear(_) => AnvilChunkConfig::default(),
+        });
+
         let (gen_entity_request_tx, gen_entity_request_rx) = crossbeam::channel::unbounded();
         let pending_entity_generations = Arc::new(DashMap::new());
 
         let level_channel = Arc::new(LevelChannel::new());
         let thread_tracker = Mutex::new(Vec::new());
         let listener = Arc::new(ChunkListener::new());
+        let poi_listener = listener.add_global_chunk_listener();
 
         let level_ref = Arc::new(Self {
             seed,
@@ -184,10 +228,14 @@ impl Level {
             level_folder,
             chunk_saver,
             entity_saver,
+            poi,
+            poi_listener,
             schedule_tick_counts: AtomicU64::new(0),
             loaded_chunks: Arc::new(DashMap::new()),
             loaded_entity_chunks: Arc::new(DashMap::new()),
             chunk_loading: Mutex::new(ChunkLoading::new(level_channel.clone())),
+            forced_chunks: Mutex::new(HashSet::new()),
+            persistent_data: Mutex::new(persistent_data),
             chunk_watchers: Arc::new(DashMap::new()),
             tasks: TaskTracker::new(),
             chunk_system_tasks: TaskTracker::new(),
@@ -196,6 +244,9 @@ impl Level {
             shut_down_chunk_system: AtomicBool::new(false),
             should_save: AtomicBool::new(false),
             should_unload: AtomicBool::new(false),
+            generation_queue_len: AtomicUsize::new(0),
+            unloaded_chunks: AtomicU64::new(0),
+            unloaded_entity_chunks: AtomicU64::new(0),
             gen_entity_request_tx,
             pending_entity_generations: pending_entity_generations.clone(),
             level_channel: level_channel.clone(),
@@ -259,6 +310,7 @@ impl Level {
             // );
         }
         // drop(tracker);
+        level_ref.add_forced_tickets(&forced_chunks);
         // level_ref
         //     .chunk_loading
         //     .lock()
@@ -359,6 +411,135 @@ impl Level {
         // TODO: I think the chunk_saver should be at the server level
         self.entity_saver.clear_watched_chunks().await;
         self.write_entity_chunks(chunks_to_write).await;
+
+        self.poi.shutdown(&self.level_folder).await;
+        self.save_persistent_data();
+    }
+
+    /// Adds the tickets of newly forced chunks, returns the ones that weren't forced before
+    fn add_forced_tickets(&self, chunks: &[Vector2<i32>]) -> Vec<Vector2<i32>> {
+        let mut forced_chunks = self.forced_chunks.lock().unwrap();
+        let added = chunks
+            .iter()
+            .copied()
+            .filter(|pos| forced_chunks.insert(*pos))
+            .collect::<Vec<_>>();
+        drop(forced_chunks);
+
+        let mut chunk_loading = self.chunk_loading.lock().unwrap();
+        for pos in &added {
+            chunk_loading.add_ticket(*pos, ChunkLoading::FORCED_CHUNK_LEVEL);
+        }
+        chunk_loading.send_change();
+        added
+    }
+
+    /// Forces (or stops forcing) the given chunks to stay loaded and ticking without players
+    /// nearby. Returns the chunks whose state actually changed, the new set is saved right away.
+    pub fn set_chunks_forced(&self, chunks: &[Vector2<i32>], forced: bool) -> Vec<Vector2<i32>> {
+        let changed = if forced {
+            self.add_forced_tickets(chunks)
+        } else {
+            let mut forced_chunks = self.forced_chunks.lock().unwrap();
+            let removed = chunks
+                .iter()
+                .copied()
+                .filter(|pos| forced_chunks.remove(pos))
+                .collect::<Vec<_>>();
+            drop(forced_chunks);
+
+            let mut chunk_loading = self.chunk_loading.lock().unwrap();
+            for pos in &removed {
+                chunk_loading.remove_ticket(*pos, ChunkLoading::FORCED_CHUNK_LEVEL);
+            }
+            chunk_loading.send_change();
+            removed
+        };
+
+        if !changed.is_empty() {
+            self.save_forced_chunks();
+        }
+        changed
+    }
+
+    pub fn is_chunk_forced(&self, pos: &Vector2<i32>) -> bool {
+        self.forced_chunks.lock().unwrap().contains(pos)
+    }
+
+    /// All forced chunks, sorted by x then z
+    pub fn forced_chunks(&self) -> Vec<Vector2<i32>> {
+        let mut chunks = self
+            .forced_chunks
+            .lock()
+            .unwrap()
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        chunks.sort_unstable_by_key(|pos| (pos.x, pos.y));
+        chunks
+    }
+
+    pub fn save_forced_chunks(&self) {
+        let chunks = self.forced_chunks();
+        if let Err(err) = write_forced_chunks(&self.level_folder.root_folder, chunks) {
+            log::error!("Failed to save the forced chunks: {err}");
+        }
+    }
+
+    pub fn save_persistent_data(&self) {
+        let data = self.persistent_data.lock().unwrap().clone();
+        if let Err(err) = write_world_persistent_data(&self.level_folder.root_folder, &data) {
+            log::error!("Failed to save the persistent data of plugins: {err}");
+        }
+    }
+
+    /// Keeps the points of interest in sync with a block change, like vanilla's
+    /// `ServerWorld::onBlockStateChanged`
+    pub async fn update_poi(
+        &self,
+        pos: BlockPos,
+        old_state: BlockStateId,
+        new_state: BlockStateId,
+    ) {
+        let old_type = PoiType::from_state(old_state);
+        let new_type = PoiType::from_state(new_state);
+        if old_type == new_type {
+            return;
+        }
+        if old_type.is_some() {
+            self.poi.remove(&self.level_folder, &pos).await;
+        }
+        if let Some(new_type) = new_type {
+            self.poi.add(&self.level_folder, pos, new_type).await;
+        }
+    }
+
+    /// Scans the chunks loaded since the last call for points of interest the storage doesn't
+    /// know about yet, e.g. portals of worlds from before it existed
+    pub fn init_loaded_poi(self: &Arc<Self>) {
+        let chunks = self
+            .poi_listener
+            .try_iter()
+            .map(|(_, chunk)| chunk)
+            .collect::<Vec<_>>();
+        if chunks.is_empty() {
+            return;
+        }
+        let level = self.clone();
+        self.spawn_task(async move {
+            for chunk in chunks {
+                level.poi.init_chunk(&level.level_folder, &chunk).await;
+            }
+        });
+    }
+
+    /// Writes changed points of interest and unloads those of unloaded chunks
+    pub async fn save_poi(&self) {
+        self.poi
+            .save(&self.level_folder, |pos| {
+                self.loaded_chunks.contains_key(pos)
+            })
+            .await;
     }
 
     pub fn loaded_chunk_count(&self) -> usize {
@@ -459,6 +640,7 @@ impl Level {
                     .chunk_watchers
                     .get(pos)
                     .is_none_or(|count| count.is_zero())
+                    && !self.is_chunk_forced(pos)
                 {
                     self.loaded_entity_chunks
                         .get(pos)
@@ -482,6 +664,7 @@ impl Level {
                         .chunk_watchers
                         .get(&pos)
                         .is_none_or(|count| count.is_zero())
+                        && !level.is_chunk_forced(&pos)
                 });
             }
         });
@@ -573,8 +756,13 @@ impl Level {
 
     pub fn clean_memory(&self) {
         self.chunk_watchers.retain(|_, watcher| !watcher.is_zero());
+        let entity_chunks = self.loaded_entity_chunks.len();
         self.loaded_entity_chunks
-            .retain(|at, _| self.chunk_watchers.get(at).is_some());
+            .retain(|at, _| self.chunk_watchers.get(at).is_some() || self.is_chunk_forced(at));
+        self.unloaded_entity_chunks.fetch_add(
+            entity_chunks.saturating_sub(self.loaded_entity_chunks.len()) as u64,
+            Ordering::Relaxed,
+        );
 
         // if the difference is too big, we can shrink the loaded chunks
         // (1024 chunks is the equivalent to a 32x32 chunks area)
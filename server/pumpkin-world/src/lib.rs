@@ -11,11 +11,14 @@ pub mod cylindrical_chunk_iterator;
 pub mod data;
 pub mod dimension;
 pub mod entity;
+pub mod forced_chunks;
 pub mod generation;
 pub mod inventory;
 pub mod item;
 pub mod level;
 pub mod lock;
+pub mod persistent_data;
+pub mod poi;
 pub mod tick;
 pub mod world;
 pub mod world_info;
@@ -25,6 +28,7 @@ pub type BlockStateId = u16;
 
 pub const CURRENT_MC_VERSION: &str = "1.21.11";
 pub const CURRENT_BEDROCK_MC_VERSION: &str = "1.21.111";
+pub const CURRENT_BEDROCK_MC_PROTOCOL: u32 = 827;
 
 #[macro_export]
 macro_rules! global_path {
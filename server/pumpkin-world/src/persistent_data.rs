@@ -0,0 +1,382 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+use pumpkin_nbt::{compound::NbtCompound, tag::NbtTag};
+use pumpkin_util::resource_location::ResourceLocation;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::chunk::format::anvil::WORLD_DATA_VERSION;
+
+/// The key the data of plugins is stored under in entities, chunks and the `custom_data` of items
+pub const PERSISTENT_DATA_KEY: &str = "PumpkinData";
+pub const PERSISTENT_DATA_FILE_NAME: &str = "pumpkin_data.dat";
+
+/// A value that can be kept in a [`PersistentDataContainer`]
+pub trait PersistentDataType: Sized {
+    fn to_tag(self) -> NbtTag;
+    /// Returns `None` if the tag holds another type
+    fn from_tag(tag: &NbtTag) -> Option<Self>;
+}
+
+macro_rules! persistent_data_type {
+    ($type:ty, $variant:ident, $extract:ident) => {
+        impl PersistentDataType for $type {
+            fn to_tag(self) -> NbtTag {
+                NbtTag::$variant(self)
+            }
+
+            fn from_tag(tag: &NbtTag) -> Option<Self> {
+                tag.$extract()
+            }
+        }
+    };
+}
+
+persistent_data_type!(i8, Byte, extract_byte);
+persistent_data_type!(i16, Short, extract_short);
+persistent_data_type!(i32, Int, extract_int);
+persistent_data_type!(i64, Long, extract_long);
+persistent_data_type!(f32, Float, extract_float);
+persistent_data_type!(f64, Double, extract_double);
+persistent_data_type!(Box<[u8]>, ByteArray, extract_byte_array);
+
+impl PersistentDataType for bool {
+    fn to_tag(self) -> NbtTag {
+        NbtTag::Byte(self.into())
+    }
+
+    fn from_tag(tag: &NbtTag) -> Option<Self> {
+        tag.extract_bool()
+    }
+}
+
+impl PersistentDataType for String {
+    fn to_tag(self) -> NbtTag {
+        NbtTag::String(self)
+    }
+
+    fn from_tag(tag: &NbtTag) -> Option<Self> {
+        tag.extract_string().map(ToString::to_string)
+    }
+}
+
+impl PersistentDataType for Vec<i32> {
+    fn to_tag(self) -> NbtTag {
+        NbtTag::IntArray(self)
+    }
+
+    fn from_tag(tag: &NbtTag) -> Option<Self> {
+        tag.extract_int_array().map(<[i32]>::to_vec)
+    }
+}
+
+impl PersistentDataType for Vec<i64> {
+    fn to_tag(self) -> NbtTag {
+        NbtTag::LongArray(self)
+    }
+
+    fn from_tag(tag: &NbtTag) -> Option<Self> {
+        tag.extract_long_array().map(<[i64]>::to_vec)
+    }
+}
+
+/// Stored like vanilla stores UUIDs, as four ints
+impl PersistentDataType for uuid::Uuid {
+    fn to_tag(self) -> NbtTag {
+        let uuid = self.as_u128();
+        NbtTag::IntArray(vec![
+            (uuid >> 96) as i32,
+            (uuid >> 64) as i32,
+            (uuid >> 32) as i32,
+            uuid as i32,
+        ])
+    }
+
+    fn from_tag(tag: &NbtTag) -> Option<Self> {
+        let [a, b, c, d] = tag.extract_int_array()? else {
+            return None;
+        };
+        Some(Self::from_u128(
+            (u128::from(*a as u32) << 96)
+                | (u128::from(*b as u32) << 64)
+                | (u128::from(*c as u32) << 32)
+                | u128::from(*d as u32),
+        ))
+    }
+}
+
+impl PersistentDataType for NbtCompound {
+    fn to_tag(self) -> NbtTag {
+        NbtTag::Compound(self)
+    }
+
+    fn from_tag(tag: &NbtTag) -> Option<Self> {
+        tag.extract_compound().cloned()
+    }
+}
+
+/// Lets plugins nest containers, e.g. to keep all their data under one key
+impl PersistentDataType for PersistentDataContainer {
+    fn to_tag(self) -> NbtTag {
+        NbtTag::Compound(self.data)
+    }
+
+    fn from_tag(tag: &NbtTag) -> Option<Self> {
+        tag.extract_compound().cloned().map(Self::from_nbt)
+    }
+}
+
+/// Data plugins attach to entities, items, chunks and worlds, which is saved along with them.
+///
+/// Every value is stored under a namespaced key, the namespace being the plugin's name, so plugins
+/// don't overwrite each other's data.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PersistentDataContainer {
+    data: NbtCompound,
+}
+
+impl PersistentDataContainer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn from_nbt(data: NbtCompound) -> Self {
+        Self { data }
+    }
+
+    #[must_use]
+    pub fn as_nbt(&self) -> &NbtCompound {
+        &self.data
+    }
+
+    #[must_use]
+    pub fn into_nbt(self) -> NbtCompound {
+        self.data
+    }
+
+    /// Reads the container stored under [`PERSISTENT_DATA_KEY`] in `nbt`, an empty one if there
+    /// is none
+    #[must_use]
+    pub fn read(nbt: &NbtCompound) -> Self {
+        nbt.get_compound(PERSISTENT_DATA_KEY)
+            .cloned()
+            .map(Self::from_nbt)
+            .unwrap_or_default()
+    }
+
+    /// Stores the container under [`PERSISTENT_DATA_KEY`] in `nbt`, unless it is empty
+    pub fn write(&self, nbt: &mut NbtCompound) {
+        if !self.is_empty() {
+            nbt.put_component(PERSISTENT_DATA_KEY, self.data.clone());
+        }
+    }
+
+    /// Returns `None` if there is no value for `key` or it has another type
+    #[must_use]
+    pub fn get<T: PersistentDataType>(&self, key: &ResourceLocation) -> Option<T> {
+        T::from_tag(self.data.get(&key.to_string())?)
+    }
+
+    /// Stores `value` under `key`, replacing the previous value
+    pub fn set<T: PersistentDataType>(&mut self, key: &ResourceLocation, value: T) {
+        let key = key.to_string();
+        let tag = value.to_tag();
+        match self
+            .data
+            .child_tags
+            .iter_mut()
+            .find(|(name, _)| *name == key)
+        {
+            Some((_, previous)) => *previous = tag,
+            None => self.data.child_tags.push((key, tag)),
+        }
+    }
+
+    /// Returns whether there was a value for `key`
+    pub fn remove(&mut self, key: &ResourceLocation) -> bool {
+        let key = key.to_string();
+        let len = self.data.child_tags.len();
+        self.data.child_tags.retain(|(name, _)| *name != key);
+        self.data.child_tags.len() != len
+    }
+
+    #[must_use]
+    pub fn contains(&self, key: &ResourceLocation) -> bool {
+        self.data.get(&key.to_string()).is_some()
+    }
+
+    /// The keys that have a value, skipping entries not written by this API
+    pub fn keys(&self) -> impl Iterator<Item = ResourceLocation> + '_ {
+        self.data
+            .child_tags
+            .iter()
+            .filter_map(|(name, _)| name.parse().ok())
+    }
+
+    /// Removes every value of a plugin
+    pub fn clear_namespace(&mut self, namespace: &str) {
+        self.data.child_tags.retain(|(name, _)| {
+            name.split_once(':')
+                .is_none_or(|(name_namespace, _)| name_namespace != namespace)
+        });
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum PersistentDataError {
+    #[error("Io error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Deserialization error: {0}")]
+    DeserializationError(String),
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+}
+
+/// The `data/pumpkin_data.dat` file, holding the data plugins attached to a dimension
+#[derive(Serialize, Deserialize)]
+struct PersistentDataDat {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    data: PersistentDataContainer,
+}
+
+fn persistent_data_path(root_folder: &Path) -> PathBuf {
+    root_folder.join("data").join(PERSISTENT_DATA_FILE_NAME)
+}
+
+/// Reads the data plugins attached to the dimension stored in `root_folder`, a missing file means
+/// none
+pub fn read_world_persistent_data(
+    root_folder: &Path,
+) -> Result<PersistentDataContainer, PersistentDataError> {
+    let file = match OpenOptions::new()
+        .read(true)
+        .open(persistent_data_path(root_folder))
+    {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(PersistentDataContainer::new());
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut buf = Vec::new();
+    GzDecoder::new(file).read_to_end(&mut buf)?;
+    let dat: PersistentDataDat = pumpkin_nbt::from_bytes(Cursor::new(buf))
+        .map_err(|err| PersistentDataError::DeserializationError(err.to_string()))?;
+    Ok(dat.data)
+}
+
+/// Writes the data plugins attached to the dimension stored in `root_folder`
+pub fn write_world_persistent_data(
+    root_folder: &Path,
+    data: &PersistentDataContainer,
+) -> Result<(), PersistentDataError> {
+    let path = persistent_data_path(root_folder);
+    if data.is_empty() && !path.exists() {
+        return Ok(());
+    }
+    let dat = PersistentDataDat {
+        data_version: WORLD_DATA_VERSION,
+        data: data.clone(),
+    };
+
+    fs::create_dir_all(path.parent().expect("pumpkin_data.dat always has a parent"))?;
+    // Write next to the old file first so a crash never leaves a truncated file behind
+    let temp_path = path.with_extension("dat_tmp");
+    let file = OpenOptions::new()
+        .truncate(true)
+        .create(true)
+        .write(true)
+        .open(&temp_path)?;
+    let mut writer = GzEncoder::new(file, Compression::default());
+    pumpkin_nbt::to_bytes(&dat, &mut writer)
+        .map_err(|err| PersistentDataError::SerializationError(err.to_string()))?;
+    writer.finish()?;
+    fs::rename(temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use pumpkin_nbt::compound::NbtCompound;
+    use pumpkin_util::resource_location::ResourceLocation;
+    use temp_dir::TempDir;
+
+    use super::{PersistentDataContainer, read_world_persistent_data, write_world_persistent_data};
+
+    #[test]
+    fn typed_values() {
+        let coins = ResourceLocation::from("economy:coins");
+        let owner = ResourceLocation::from("claims:owner");
+        let mut container = PersistentDataContainer::new();
+
+        container.set(&coins, 10i64);
+        container.set(&coins, 25i64);
+        container.set(
+            &owner,
+            uuid::Uuid::from_u128(0x0123_4567_89ab_cdef_fedc_ba98_7654_3210),
+        );
+
+        assert_eq!(container.get::<i64>(&coins), Some(25));
+        assert_eq!(container.get::<i32>(&coins), None);
+        assert_eq!(
+            container.get::<uuid::Uuid>(&owner),
+            Some(uuid::Uuid::from_u128(
+                0x0123_4567_89ab_cdef_fedc_ba98_7654_3210
+            ))
+        );
+        assert_eq!(container.keys().count(), 2);
+
+        assert!(container.remove(&coins));
+        assert!(!container.contains(&coins));
+        container.clear_namespace("claims");
+        assert!(container.is_empty());
+    }
+
+    #[test]
+    fn nbt_round_trip() {
+        let mut container = PersistentDataContainer::new();
+        container.set(&ResourceLocation::from("quests:done"), true);
+        let mut nbt = NbtCompound::new();
+        container.write(&mut nbt);
+
+        assert_eq!(PersistentDataContainer::read(&nbt), container);
+        assert!(PersistentDataContainer::read(&NbtCompound::new()).is_empty());
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        assert!(
+            read_world_persistent_data(temp_dir.path())
+                .unwrap()
+                .is_empty()
+        );
+
+        let mut container = PersistentDataContainer::new();
+        container.set(
+            &ResourceLocation::from("arena:name"),
+            "Colosseum".to_string(),
+        );
+        write_world_persistent_data(temp_dir.path(), &container).unwrap();
+
+        assert_eq!(
+            read_world_persistent_data(temp_dir.path()).unwrap(),
+            container
+        );
+    }
+}
@@ -0,0 +1,457 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Cursor,
+    path::PathBuf,
+    pin::Pin,
+    sync::LazyLock,
+};
+
+use bytes::Bytes;
+use pumpkin_data::{
+    Block,
+    block_properties::{BedPart, BlockProperties},
+    tag,
+};
+use pumpkin_nbt::nbt_int_array;
+use pumpkin_util::math::{position::BlockPos, vector2::Vector2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BlockStateId,
+    chunk::{
+        ChunkParsingError, ChunkReadingError, ChunkSerializingError,
+        format::anvil::{SingleChunkDataSerializer, WORLD_DATA_VERSION},
+        io::{Dirtiable, file_manager::PathFromLevelFolder},
+    },
+    generation::section_coords,
+    level::LevelFolder,
+};
+
+mod storage;
+
+pub use storage::{PoiStorage, SyncPoiChunk};
+
+type BedProperties = pumpkin_data::block_properties::WhiteBedLikeProperties;
+
+/// The point of interest of every block that can be one, so most block changes are a single lookup
+static POI_BLOCKS: LazyLock<HashMap<u16, &'static PoiType>> = LazyLock::new(|| {
+    let beds = tag::Block::MINECRAFT_BEDS
+        .1
+        .iter()
+        .map(|block_id| (*block_id, &PoiType::HOME));
+    PoiType::ALL
+        .into_iter()
+        .flat_map(|poi_type| {
+            poi_type
+                .blocks
+                .iter()
+                .map(move |block_id| (*block_id, poi_type))
+        })
+        .chain(beds)
+        .collect()
+});
+
+/// A kind of point of interest, like vanilla's `PointOfInterestType`
+#[derive(Debug, PartialEq, Eq)]
+pub struct PoiType {
+    pub name: &'static str,
+    /// How many entities can claim a single point at once
+    pub ticket_count: i32,
+    /// How close (in blocks) an entity must get to use the point
+    pub search_distance: i32,
+    blocks: &'static [u16],
+}
+
+impl PoiType {
+    pub const ARMORER: Self = Self::new("armorer", 1, 1, &[Block::BLAST_FURNACE.id]);
+    pub const BUTCHER: Self = Self::new("butcher", 1, 1, &[Block::SMOKER.id]);
+    pub const CARTOGRAPHER: Self = Self::new("cartographer", 1, 1, &[Block::CARTOGRAPHY_TABLE.id]);
+    pub const CLERIC: Self = Self::new("cleric", 1, 1, &[Block::BREWING_STAND.id]);
+    pub const FARMER: Self = Self::new("farmer", 1, 1, &[Block::COMPOSTER.id]);
+    pub const FISHERMAN: Self = Self::new("fisherman", 1, 1, &[Block::BARREL.id]);
+    pub const FLETCHER: Self = Self::new("fletcher", 1, 1, &[Block::FLETCHING_TABLE.id]);
+    pub const LEATHERWORKER: Self = Self::new(
+        "leatherworker",
+        1,
+        1,
+        &[
+            Block::CAULDRON.id,
+            Block::WATER_CAULDRON.id,
+            Block::LAVA_CAULDRON.id,
+            Block::POWDER_SNOW_CAULDRON.id,
+        ],
+    );
+    pub const LIBRARIAN: Self = Self::new("librarian", 1, 1, &[Block::LECTERN.id]);
+    pub const MASON: Self = Self::new("mason", 1, 1, &[Block::STONECUTTER.id]);
+    pub const SHEPHERD: Self = Self::new("shepherd", 1, 1, &[Block::LOOM.id]);
+    pub const TOOLSMITH: Self = Self::new("toolsmith", 1, 1, &[Block::SMITHING_TABLE.id]);
+    pub const WEAPONSMITH: Self = Self::new("weaponsmith", 1, 1, &[Block::GRINDSTONE.id]);
+    /// The head part of every bed, see [`PoiType::from_state`]
+    pub const HOME: Self = Self::new("home", 1, 1, &[]);
+    pub const MEETING: Self = Self::new("meeting", 32, 6, &[Block::BELL.id]);
+    pub const BEEHIVE: Self = Self::new("beehive", 0, 1, &[Block::BEEHIVE.id]);
+    pub const BEE_NEST: Self = Self::new("bee_nest", 0, 1, &[Block::BEE_NEST.id]);
+    pub const NETHER_PORTAL: Self = Self::new("nether_portal", 0, 1, &[Block::NETHER_PORTAL.id]);
+    pub const LODESTONE: Self = Self::new("lodestone", 0, 1, &[Block::LODESTONE.id]);
+    pub const LIGHTNING_ROD: Self = Self::new(
+        "lightning_rod",
+        0,
+        1,
+        &[
+            Block::LIGHTNING_ROD.id,
+            Block::EXPOSED_LIGHTNING_ROD.id,
+            Block::WEATHERED_LIGHTNING_ROD.id,
+            Block::OXIDIZED_LIGHTNING_ROD.id,
+            Block::WAXED_LIGHTNING_ROD.id,
+            Block::WAXED_EXPOSED_LIGHTNING_ROD.id,
+            Block::WAXED_WEATHERED_LIGHTNING_ROD.id,
+            Block::WAXED_OXIDIZED_LIGHTNING_ROD.id,
+        ],
+    );
+
+    pub const ALL: [&'static Self; 20] = [
+        &Self::ARMORER,
+        &Self::BUTCHER,
+        &Self::CARTOGRAPHER,
+        &Self::CLERIC,
+        &Self::FARMER,
+        &Self::FISHERMAN,
+        &Self::FLETCHER,
+        &Self::LEATHERWORKER,
+        &Self::LIBRARIAN,
+        &Self::MASON,
+        &Self::SHEPHERD,
+        &Self::TOOLSMITH,
+        &Self::WEAPONSMITH,
+        &Self::HOME,
+        &Self::MEETING,
+        &Self::BEEHIVE,
+        &Self::BEE_NEST,
+        &Self::NETHER_PORTAL,
+        &Self::LODESTONE,
+        &Self::LIGHTNING_ROD,
+    ];
+
+    const fn new(
+        name: &'static str,
+        ticket_count: i32,
+        search_distance: i32,
+        blocks: &'static [u16],
+    ) -> Self {
+        Self {
+            name,
+            ticket_count,
+            search_distance,
+            blocks,
+        }
+    }
+
+    /// The point of interest a block state is, if any
+    #[must_use]
+    pub fn from_state(state_id: BlockStateId) -> Option<&'static Self> {
+        let block = Block::from_state_id(state_id);
+        let poi_type = *POI_BLOCKS.get(&block.id)?;
+        if *poi_type == Self::HOME {
+            // Only the head of a bed counts, otherwise every bed would be two homes
+            return (BedProperties::from_state_id(state_id, block).part == BedPart::Head)
+                .then_some(&Self::HOME);
+        }
+        Some(poi_type)
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<&'static Self> {
+        let name = name.strip_prefix("minecraft:").unwrap_or(name);
+        Self::ALL.into_iter().find(|poi_type| poi_type.name == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoiRecord {
+    pub pos: BlockPos,
+    pub poi_type: &'static PoiType,
+    pub free_tickets: i32,
+}
+
+impl PoiRecord {
+    #[must_use]
+    pub const fn new(pos: BlockPos, poi_type: &'static PoiType) -> Self {
+        Self {
+            pos,
+            poi_type,
+            free_tickets: poi_type.ticket_count,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PoiSection {
+    /// Vanilla rescans sections that aren't valid, we trust every section we wrote ourselves
+    pub valid: bool,
+    pub records: Vec<PoiRecord>,
+}
+
+/// The points of interest of one chunk column, stored in `poi/r.x.z.mca`
+pub struct PoiChunkData {
+    pub x: i32,
+    pub z: i32,
+    /// Sections by their section y coordinate
+    pub sections: BTreeMap<i32, PoiSection>,
+    pub dirty: bool,
+}
+
+impl PoiChunkData {
+    #[must_use]
+    pub fn new(pos: Vector2<i32>) -> Self {
+        Self {
+            x: pos.x,
+            z: pos.y,
+            sections: BTreeMap::new(),
+            dirty: false,
+        }
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &PoiRecord> {
+        self.sections
+            .values()
+            .flat_map(|section| section.records.iter())
+    }
+
+    #[must_use]
+    pub fn get(&self, pos: &BlockPos) -> Option<&PoiRecord> {
+        self.sections
+            .get(&section_coords::block_to_section(pos.0.y))?
+            .records
+            .iter()
+            .find(|record| record.pos == *pos)
+    }
+
+    /// Adds or replaces the point at the record position
+    pub fn add(&mut self, record: PoiRecord) {
+        let section = self
+            .sections
+            .entry(section_coords::block_to_section(record.pos.0.y))
+            .or_insert_with(|| PoiSection {
+                valid: true,
+                records: Vec::new(),
+            });
+        section.records.retain(|old| old.pos != record.pos);
+        section.records.push(record);
+        self.dirty = true;
+    }
+
+    /// Removes the point at `pos`, returning it if there was one
+    pub fn remove(&mut self, pos: &BlockPos) -> Option<PoiRecord> {
+        let section_y = section_coords::block_to_section(pos.0.y);
+        let section = self.sections.get_mut(&section_y)?;
+        let index = section
+            .records
+            .iter()
+            .position(|record| record.pos == *pos)?;
+        let record = section.records.swap_remove(index);
+        self.dirty = true;
+        Some(record)
+    }
+
+    fn internal_from_bytes(bytes: &[u8], pos: Vector2<i32>) -> Result<Self, ChunkParsingError> {
+        let nbt = pumpkin_nbt::from_bytes::<PoiChunkNbt>(Cursor::new(bytes))
+            .map_err(|err| ChunkParsingError::ErrorDeserializingChunk(err.to_string()))?;
+
+        let mut chunk = Self::new(pos);
+        for (section_y, section) in nbt.sections {
+            let Ok(section_y) = section_y.parse::<i32>() else {
+                log::warn!("Invalid poi section {section_y} in chunk {pos:?}");
+                continue;
+            };
+            let records = section
+                .records
+                .into_iter()
+                .filter_map(|record| {
+                    let Some(poi_type) = PoiType::from_name(&record.poi_type) else {
+                        log::debug!("Unknown poi type {} in chunk {pos:?}", record.poi_type);
+                        return None;
+                    };
+                    Some(PoiRecord {
+                        pos: BlockPos::new(record.pos[0], record.pos[1], record.pos[2]),
+                        poi_type,
+                        free_tickets: record.free_tickets,
+                    })
+                })
+                .collect();
+            chunk.sections.insert(
+                section_y,
+                PoiSection {
+                    valid: section.valid,
+                    records,
+                },
+            );
+        }
+        Ok(chunk)
+    }
+
+    fn internal_to_bytes(&self) -> Result<Bytes, ChunkSerializingError> {
+        let nbt = PoiChunkNbt {
+            data_version: WORLD_DATA_VERSION,
+            sections: self
+                .sections
+                .iter()
+                .map(|(section_y, section)| {
+                    let records = section
+                        .records
+                        .iter()
+                        .map(|record| PoiRecordNbt {
+                            pos: [record.pos.0.x, record.pos.0.y, record.pos.0.z],
+                            poi_type: format!("minecraft:{}", record.poi_type.name),
+                            free_tickets: record.free_tickets,
+                        })
+                        .collect();
+                    (
+                        section_y.to_string(),
+                        PoiSectionNbt {
+                            valid: section.valid,
+                            records,
+                        },
+                    )
+                })
+                .collect(),
+        };
+
+        let mut result = Vec::new();
+        pumpkin_nbt::to_bytes(&nbt, &mut result)
+            .map_err(ChunkSerializingError::ErrorSerializingChunk)?;
+        Ok(result.into())
+    }
+}
+
+impl PathFromLevelFolder for PoiChunkData {
+    #[inline]
+    fn file_path(folder: &LevelFolder, file_name: &str) -> PathBuf {
+        folder.poi_folder.join(file_name)
+    }
+}
+
+impl Dirtiable for PoiChunkData {
+    #[inline]
+    fn mark_dirty(&mut self, flag: bool) {
+        self.dirty = flag;
+    }
+
+    #[inline]
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+}
+
+impl SingleChunkDataSerializer for PoiChunkData {
+    #[inline]
+    fn from_bytes(bytes: &Bytes, pos: Vector2<i32>) -> Result<Self, ChunkReadingError> {
+        Self::internal_from_bytes(bytes, pos).map_err(ChunkReadingError::ParsingError)
+    }
+
+    #[inline]
+    fn to_bytes(
+        &self,
+    ) -> Pin<Box<dyn Future<Output = Result<Bytes, ChunkSerializingError>> + Send + '_>> {
+        Box::pin(async move { self.internal_to_bytes() })
+    }
+
+    #[inline]
+    fn position(&self) -> (i32, i32) {
+        (self.x, self.z)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PoiChunkNbt {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    #[serde(rename = "Sections", default)]
+    sections: BTreeMap<String, PoiSectionNbt>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PoiSectionNbt {
+    #[serde(rename = "Valid")]
+    valid: bool,
+    #[serde(rename = "Records", default)]
+    records: Vec<PoiRecordNbt>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PoiRecordNbt {
+    #[serde(serialize_with = "nbt_int_array")]
+    pos: [i32; 3],
+    #[serde(rename = "type")]
+    poi_type: String,
+    free_tickets: i32,
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+    use pumpkin_data::Block;
+    use pumpkin_util::math::{position::BlockPos, vector2::Vector2};
+
+    use crate::chunk::format::anvil::SingleChunkDataSerializer;
+
+    use super::{PoiChunkData, PoiRecord, PoiType};
+
+    #[test]
+    fn names_round_trip() {
+        for poi_type in PoiType::ALL {
+            assert_eq!(
+                PoiType::from_name(&format!("minecraft:{}", poi_type.name)),
+                Some(poi_type)
+            );
+        }
+    }
+
+    #[test]
+    fn types_of_states() {
+        assert_eq!(
+            PoiType::from_state(Block::BELL.default_state.id),
+            Some(&PoiType::MEETING)
+        );
+        assert_eq!(
+            PoiType::from_state(Block::WATER_CAULDRON.default_state.id),
+            Some(&PoiType::LEATHERWORKER)
+        );
+        assert_eq!(PoiType::from_state(Block::STONE.default_state.id), None);
+
+        let heads = Block::RED_BED
+            .states
+            .iter()
+            .filter(|state| PoiType::from_state(state.id) == Some(&PoiType::HOME))
+            .count();
+        assert_eq!(heads * 2, Block::RED_BED.states.len());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn chunk_round_trip() {
+        let mut chunk = PoiChunkData::new(Vector2::new(-2, 5));
+        chunk.add(PoiRecord::new(
+            BlockPos::new(-30, -60, 85),
+            &PoiType::NETHER_PORTAL,
+        ));
+        chunk.add(PoiRecord::new(
+            BlockPos::new(-25, 70, 90),
+            &PoiType::MEETING,
+        ));
+        chunk.add(PoiRecord::new(BlockPos::new(-25, 71, 90), &PoiType::HOME));
+        assert!(chunk.remove(&BlockPos::new(-25, 71, 90)).is_some());
+        assert!(chunk.remove(&BlockPos::new(-25, 71, 90)).is_none());
+
+        let bytes: Bytes = chunk.to_bytes().await.unwrap();
+        let read = PoiChunkData::from_bytes(&bytes, Vector2::new(-2, 5)).unwrap();
+        let mut records = read.records().copied().collect::<Vec<_>>();
+        records.sort_by_key(|record| record.pos.0.y);
+        assert_eq!(
+            records,
+            vec![
+                PoiRecord::new(BlockPos::new(-30, -60, 85), &PoiType::NETHER_PORTAL),
+                PoiRecord::new(BlockPos::new(-25, 70, 90), &PoiType::MEETING),
+            ]
+        );
+        assert!(read.sections.values().all(|section| section.valid));
+    }
+}
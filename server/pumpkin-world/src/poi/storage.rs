@@ -0,0 +1,276 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use dashmap::DashMap;
+use pumpkin_config::chunk::AnvilChunkConfig;
+use pumpkin_util::math::{position::BlockPos, vector2::Vector2};
+use tokio::sync::RwLock;
+
+use crate::{
+    chunk::{
+        format::anvil::AnvilChunkFile,
+        io::{FileIO, LoadedData, file_manager::ChunkFileManager},
+    },
+    generation::section_coords,
+    level::{LevelFolder, SyncChunk},
+};
+
+use super::{PoiChunkData, PoiRecord, PoiSection, PoiType};
+
+pub type SyncPoiChunk = Arc<RwLock<PoiChunkData>>;
+
+/// Points of interest of a dimension, loaded lazily per chunk column from `poi/`.
+///
+/// Unlike block chunks, poi chunks aren't tied to the chunk system: they are loaded when a
+/// query or change touches them and dropped again on save once their block chunk is unloaded.
+pub struct PoiStorage {
+    loaded: DashMap<Vector2<i32>, SyncPoiChunk>,
+    saver: Arc<dyn FileIO<Data = SyncPoiChunk>>,
+}
+
+impl PoiStorage {
+    #[must_use]
+    pub fn new(chunk_config: AnvilChunkConfig) -> Self {
+        Self {
+            loaded: DashMap::new(),
+            saver: Arc::new(ChunkFileManager::<AnvilChunkFile<PoiChunkData>>::new(
+                chunk_config,
+            )),
+        }
+    }
+
+    /// Makes sure the poi chunks at `positions` are in memory, reading them from disk in one go
+    async fn load_chunks(&self, folder: &LevelFolder, positions: &[Vector2<i32>]) {
+        let missing = positions
+            .iter()
+            .filter(|pos| !self.loaded.contains_key(pos))
+            .copied()
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return;
+        }
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(missing.len());
+        let fetch = self.saver.fetch_chunks(folder, &missing, tx);
+        let receive = async {
+            while let Some(data) = rx.recv().await {
+                match data {
+                    LoadedData::Loaded(chunk) => {
+                        let read = chunk.read().await;
+                        let pos = Vector2::new(read.x, read.z);
+                        drop(read);
+                        // Someone else may have loaded the same chunk in the meantime, theirs wins
+                        self.loaded.entry(pos).or_insert(chunk);
+                    }
+                    LoadedData::Missing(_) => {}
+                    LoadedData::Error((pos, err)) => {
+                        log::error!(
+                            "Failed to read the points of interest near chunk {pos:?}: {err}"
+                        );
+                    }
+                }
+            }
+        };
+        tokio::join!(fetch, receive);
+
+        // Chunks without data (or whose region failed to read) start out empty
+        for pos in missing {
+            self.loaded
+                .entry(pos)
+                .or_insert_with(|| Arc::new(RwLock::new(PoiChunkData::new(pos))));
+        }
+    }
+
+    async fn get_chunk(&self, folder: &LevelFolder, pos: Vector2<i32>) -> SyncPoiChunk {
+        // A save may unload the chunk again right after we loaded it, so retry until we get it
+        loop {
+            if let Some(chunk) = self.loaded.get(&pos) {
+                return chunk.clone();
+            }
+            self.load_chunks(folder, &[pos]).await;
+        }
+    }
+
+    /// Adds the points of interest of a loaded block chunk to sections that were never scanned,
+    /// like vanilla's `PointOfInterestStorage::initForPalette`
+    pub async fn init_chunk(&self, folder: &LevelFolder, chunk: &SyncChunk) {
+        let pos = {
+            let chunk = chunk.read().await;
+            Vector2::new(chunk.x, chunk.z)
+        };
+        let poi_chunk = self.get_chunk(folder, pos).await;
+        let scanned = poi_chunk
+            .read()
+            .await
+            .sections
+            .iter()
+            .filter(|(_, section)| section.valid)
+            .map(|(section_y, _)| *section_y)
+            .collect::<BTreeSet<_>>();
+
+        let mut sections = BTreeMap::new();
+        let chunk = chunk.read().await;
+        let min_section = section_coords::block_to_section(chunk.section.min_y);
+        for (i, section) in chunk.section.sections.iter().enumerate() {
+            let section_y = min_section + i as i32;
+            // Checking the palette first skips almost every section
+            if scanned.contains(&section_y)
+                || !section
+                    .block_states
+                    .palette_any(|state| PoiType::from_state(state).is_some())
+            {
+                continue;
+            }
+
+            let mut records = Vec::new();
+            let mut index = 0;
+            section.block_states.for_each(|state| {
+                if let Some(poi_type) = PoiType::from_state(state) {
+                    // Blocks are stored y, z, x
+                    let pos = BlockPos::new(
+                        (chunk.x << 4) + (index & 15),
+                        (section_y << 4) + (index >> 8),
+                        (chunk.z << 4) + ((index >> 4) & 15),
+                    );
+                    records.push(PoiRecord::new(pos, poi_type));
+                }
+                index += 1;
+            });
+            sections.insert(section_y, records);
+        }
+        drop(chunk);
+        if sections.is_empty() {
+            return;
+        }
+
+        let mut poi_chunk = poi_chunk.write().await;
+        for (section_y, records) in sections {
+            let section = poi_chunk.sections.entry(section_y).or_default();
+            // A block change may have registered the section in the meantime
+            if !section.valid {
+                *section = PoiSection {
+                    valid: true,
+                    records,
+                };
+            }
+        }
+        poi_chunk.dirty = true;
+    }
+
+    /// Registers the point of interest at `pos`, replacing whatever was there
+    pub async fn add(&self, folder: &LevelFolder, pos: BlockPos, poi_type: &'static PoiType) {
+        let chunk = self
+            .get_chunk(folder, pos.chunk_and_chunk_relative_position().0)
+            .await;
+        chunk.write().await.add(PoiRecord::new(pos, poi_type));
+    }
+
+    /// Removes the point of interest at `pos`, returning it if there was one
+    pub async fn remove(&self, folder: &LevelFolder, pos: &BlockPos) -> Option<PoiRecord> {
+        let chunk = self
+            .get_chunk(folder, pos.chunk_and_chunk_relative_position().0)
+            .await;
+        chunk.write().await.remove(pos)
+    }
+
+    pub async fn get(&self, folder: &LevelFolder, pos: &BlockPos) -> Option<PoiRecord> {
+        let chunk = self
+            .get_chunk(folder, pos.chunk_and_chunk_relative_position().0)
+            .await;
+        chunk.read().await.get(pos).copied()
+    }
+
+    /// All points of interest matching `predicate` in the square column of `radius` blocks around
+    /// `center`, like vanilla's `getInSquare`
+    pub async fn in_square(
+        &self,
+        folder: &LevelFolder,
+        center: BlockPos,
+        radius: i32,
+        predicate: impl Fn(&PoiType) -> bool,
+    ) -> Vec<PoiRecord> {
+        let (x, z) = (center.0.x, center.0.z);
+        self.collect(folder, center, radius, |record| {
+            predicate(record.poi_type)
+                && (record.pos.0.x - x).abs() <= radius
+                && (record.pos.0.z - z).abs() <= radius
+        })
+        .await
+    }
+
+    /// All points of interest matching `predicate` within `radius` blocks of `center`
+    pub async fn in_circle(
+        &self,
+        folder: &LevelFolder,
+        center: BlockPos,
+        radius: i32,
+        predicate: impl Fn(&PoiType) -> bool,
+    ) -> Vec<PoiRecord> {
+        self.collect(folder, center, radius, |record| {
+            predicate(record.poi_type) && record.pos.squared_distance(center) <= radius * radius
+        })
+        .await
+    }
+
+    async fn collect(
+        &self,
+        folder: &LevelFolder,
+        center: BlockPos,
+        radius: i32,
+        filter: impl Fn(&PoiRecord) -> bool,
+    ) -> Vec<PoiRecord> {
+        let min = BlockPos::new(center.0.x - radius, 0, center.0.z - radius)
+            .chunk_and_chunk_relative_position()
+            .0;
+        let max = BlockPos::new(center.0.x + radius, 0, center.0.z + radius)
+            .chunk_and_chunk_relative_position()
+            .0;
+
+        let positions = (min.x..=max.x)
+            .flat_map(|x| (min.y..=max.y).map(move |z| Vector2::new(x, z)))
+            .collect::<Vec<_>>();
+        self.load_chunks(folder, &positions).await;
+
+        let mut records = Vec::new();
+        for pos in positions {
+            let chunk = self.get_chunk(folder, pos).await;
+            let chunk = chunk.read().await;
+            records.extend(chunk.records().filter(|record| filter(record)).copied());
+        }
+        records
+    }
+
+    /// Writes every changed poi chunk and unloads the ones `keep_loaded` rejects
+    pub async fn save(&self, folder: &LevelFolder, keep_loaded: impl Fn(&Vector2<i32>) -> bool) {
+        let loaded = self
+            .loaded
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect::<Vec<_>>();
+        let mut to_write = Vec::new();
+        for (pos, chunk) in loaded {
+            if chunk.read().await.dirty {
+                to_write.push((pos, chunk));
+            }
+        }
+
+        if !to_write.is_empty()
+            && let Err(err) = self.saver.save_chunks(folder, to_write).await
+        {
+            log::error!("Failed to write points of interest to disk: {err}");
+        }
+
+        self.loaded.retain(|pos, chunk| {
+            keep_loaded(pos)
+                // A chunk changed since we wrote it has to wait for the next save
+                || !chunk.try_read().is_ok_and(|chunk| !chunk.dirty)
+        });
+    }
+
+    pub async fn shutdown(&self, folder: &LevelFolder) {
+        self.save(folder, |_| false).await;
+        self.saver.block_and_await_ongoing_tasks().await;
+    }
+}
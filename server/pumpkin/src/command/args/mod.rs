@@ -49,6 +49,7 @@ pub mod sound_category;
 pub mod summonable_entities;
 pub mod textcomponent;
 pub mod time;
+pub mod users;
 
 /// see [`crate::commands::tree::builder::argument`]
 pub type ConsumeResult<'a> = Pin<Box<dyn Future<Output = Option<Arg<'a>>> + Send + 'a>>;
@@ -93,6 +94,7 @@ pub enum Arg<'a> {
     Entities(Vec<Arc<dyn EntityBase>>),
     Entity(Arc<dyn EntityBase>),
     Players(Vec<Arc<Player>>),
+    Users(Vec<users::User>),
     BlockPos(BlockPos),
     Pos3D(Vector3<f64>),
     Pos2D(Vector2<f64>),
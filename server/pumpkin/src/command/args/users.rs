@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use pumpkin_protocol::java::client::play::{
+    ArgumentType, CommandSuggestion, StringProtoArgBehavior, SuggestionProviders,
+};
+use uuid::Uuid;
+
+use crate::command::CommandSender;
+use crate::command::args::players::PlayersArgumentConsumer;
+use crate::command::args::{
+    ConsumeResult, SplitSingleWhitespaceIncludingEmptyParts, SuggestResult,
+};
+use crate::command::dispatcher::CommandError;
+use crate::command::tree::RawArgs;
+use crate::data::user_cache::USER_CACHE;
+use crate::entity::player::Player;
+use crate::server::Server;
+
+use super::super::args::ArgumentConsumer;
+use super::{Arg, DefaultNameArgConsumer, FindArg, GetClientSideArgParser};
+
+/// A player that is either online or has joined before
+#[derive(Clone)]
+pub struct User {
+    pub id: Uuid,
+    pub name: String,
+    /// Only set while the player is online
+    pub player: Option<Arc<Player>>,
+}
+
+impl From<Arc<Player>> for User {
+    fn from(player: Arc<Player>) -> Self {
+        Self {
+            id: player.gameprofile.id,
+            name: player.gameprofile.name.clone(),
+            player: Some(player),
+        }
+    }
+}
+
+/// Select players like [`PlayersArgumentConsumer`], but also offline players by name or UUID from the user cache
+pub struct UsersArgumentConsumer;
+
+impl GetClientSideArgParser for UsersArgumentConsumer {
+    fn get_client_side_parser(&self) -> ArgumentType<'_> {
+        // The entity parser would reject UUIDs and names of offline players
+        ArgumentType::String(StringProtoArgBehavior::SingleWord)
+    }
+
+    fn get_client_side_suggestion_type_override(&self) -> Option<SuggestionProviders> {
+        Some(SuggestionProviders::AskServer)
+    }
+}
+
+impl ArgumentConsumer for UsersArgumentConsumer {
+    fn consume<'a, 'b>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        args: &'b mut RawArgs<'a>,
+    ) -> ConsumeResult<'a> {
+        let Some(&s) = args.last() else {
+            return Box::pin(async move { None });
+        };
+        let players = PlayersArgumentConsumer.consume(sender, server, args);
+
+        Box::pin(async move {
+            if let Some(Arg::Players(players)) = players.await {
+                return Some(Arg::Users(players.into_iter().map(User::from).collect()));
+            }
+            if s.starts_with('@') {
+                return None;
+            }
+
+            let cache = USER_CACHE.read().await;
+            let user = match cache.find(s) {
+                Some(entry) => User {
+                    id: entry.uuid,
+                    name: entry.name.clone(),
+                    player: server.get_player_by_uuid(entry.uuid).await,
+                },
+                // Still allow UUIDs of players that never joined
+                None => {
+                    let id = Uuid::parse_str(s).ok()?;
+                    User {
+                        id,
+                        name: id.to_string(),
+                        player: server.get_player_by_uuid(id).await,
+                    }
+                }
+            };
+            Some(Arg::Users(vec![user]))
+        })
+    }
+
+    fn suggest<'a>(
+        &'a self,
+        _sender: &CommandSender,
+        _server: &'a Server,
+        input: &'a str,
+    ) -> SuggestResult<'a> {
+        Box::pin(async move {
+            let Some(input) = input.split_single_whitespace_including_empty_parts().last() else {
+                return Ok(None);
+            };
+
+            // Everyone online is in the cache as well, they are remembered when joining
+            let cache = USER_CACHE.read().await;
+            let suggestions = cache
+                .users
+                .iter()
+                .filter(|user| user.name.to_lowercase().starts_with(&input.to_lowercase()))
+                .map(|user| CommandSuggestion::new(user.name.clone(), None))
+                .collect();
+            Ok(Some(suggestions))
+        })
+    }
+}
+
+impl DefaultNameArgConsumer for UsersArgumentConsumer {
+    fn default_name(&self) -> &'static str {
+        "target"
+    }
+}
+
+impl<'a> FindArg<'a> for UsersArgumentConsumer {
+    type Data = &'a [User];
+
+    fn find_arg(args: &'a super::ConsumedArgs, name: &str) -> Result<Self::Data, CommandError> {
+        match args.get(name) {
+            Some(Arg::Users(data)) => Ok(data),
+            _ => Err(CommandError::InvalidConsumption(Some(name.to_string()))),
+        }
+    }
+}
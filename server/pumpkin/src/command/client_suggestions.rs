@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use pumpkin_protocol::java::client::play::{CCommands, ProtoNode, ProtoNodeType};
 
@@ -26,8 +26,18 @@ pub async fn send_c_commands_packet(player: &Arc<Player>, dispatcher: &CommandDi
             continue;
         }
 
+        // Permissions are checked asynchronously, so they are resolved before building the nodes
+        let mut granted = HashSet::new();
+        for node in &tree.nodes {
+            if let NodeType::RequirePermission { node: permission } = &node.node_type
+                && cmd_src.has_permission(permission).await
+            {
+                granted.insert(permission.as_str());
+            }
+        }
+
         let (is_executable, child_nodes) =
-            nodes_to_proto_node_builders(&cmd_src, &tree.nodes, &tree.children);
+            nodes_to_proto_node_builders(&cmd_src, &granted, &tree.nodes, &tree.children);
 
         let proto_node = ProtoNodeBuilder {
             child_nodes,
@@ -76,6 +86,7 @@ impl<'a> ProtoNodeBuilder<'a> {
 
 fn nodes_to_proto_node_builders<'a>(
     cmd_src: &super::CommandSender,
+    granted: &HashSet<&str>,
     nodes: &'a [Node],
     children: &[usize],
 ) -> (bool, Vec<ProtoNodeBuilder<'a>>) {
@@ -87,7 +98,7 @@ fn nodes_to_proto_node_builders<'a>(
         match &node.node_type {
             NodeType::Argument { name, consumer } => {
                 let (node_is_executable, node_children) =
-                    nodes_to_proto_node_builders(cmd_src, nodes, &node.children);
+                    nodes_to_proto_node_builders(cmd_src, granted, nodes, &node.children);
                 child_nodes.push(ProtoNodeBuilder {
                     child_nodes: node_children,
                     node_type: ProtoNodeType::Argument {
@@ -102,7 +113,7 @@ fn nodes_to_proto_node_builders<'a>(
 
             NodeType::Literal { string, .. } => {
                 let (node_is_executable, node_children) =
-                    nodes_to_proto_node_builders(cmd_src, nodes, &node.children);
+                    nodes_to_proto_node_builders(cmd_src, granted, nodes, &node.children);
                 child_nodes.push(ProtoNodeBuilder {
                     child_nodes: node_children,
                     node_type: ProtoNodeType::Literal {
@@ -114,15 +125,17 @@ fn nodes_to_proto_node_builders<'a>(
 
             NodeType::ExecuteLeaf { .. } => is_executable = true,
 
-            NodeType::Require { predicate } => {
-                if predicate(cmd_src) {
-                    let (node_is_executable, node_children) =
-                        nodes_to_proto_node_builders(cmd_src, nodes, &node.children);
-                    if node_is_executable {
-                        is_executable = true;
-                    }
-                    child_nodes.extend(node_children);
+            NodeType::Require { predicate } if !predicate(cmd_src) => {}
+            NodeType::RequirePermission { node: permission }
+                if !granted.contains(permission.as_str()) => {}
+
+            NodeType::Require { .. } | NodeType::RequirePermission { .. } => {
+                let (node_is_executable, node_children) =
+                    nodes_to_proto_node_builders(cmd_src, granted, nodes, &node.children);
+                if node_is_executable {
+                    is_executable = true;
                 }
+                child_nodes.extend(node_children);
             }
         }
     }
@@ -0,0 +1,271 @@
+use std::sync::Arc;
+
+use pumpkin_registry::VanillaDimensionType;
+use pumpkin_util::math::vector2::Vector2;
+use pumpkin_util::text::TextComponent;
+
+use crate::command::args::position_2d::Position2DArgumentConsumer;
+use crate::command::args::{ConsumedArgs, FindArg};
+use crate::command::dispatcher::CommandError::{self, CommandFailed};
+use crate::command::tree::CommandTree;
+use crate::command::tree::builder::{argument, literal};
+use crate::command::{CommandExecutor, CommandResult, CommandSender};
+use crate::server::Server;
+use crate::world::World;
+
+const NAMES: [&str; 1] = ["forceload"];
+
+const DESCRIPTION: &str = "Forces chunks to stay loaded.";
+
+const ARG_FROM: &str = "from";
+const ARG_TO: &str = "to";
+const ARG_POS: &str = "pos";
+
+/// Vanilla refuses to change more chunks than this at once
+const MAX_CHUNKS: i64 = 256;
+
+/// The world of the sender, the console works on the overworld
+async fn world_of(sender: &CommandSender, server: &Server) -> Arc<World> {
+    match sender.world() {
+        Some(world) => world,
+        None => {
+            server
+                .get_world_from_dimension(VanillaDimensionType::Overworld)
+                .await
+        }
+    }
+}
+
+fn chunk_at(args: &ConsumedArgs, name: &str) -> Result<Vector2<i32>, CommandError> {
+    let pos = Position2DArgumentConsumer::find_arg(args, name)?;
+    Ok(Vector2::new(
+        (pos.x.floor() as i32) >> 4,
+        (pos.y.floor() as i32) >> 4,
+    ))
+}
+
+fn format_chunk(chunk: Vector2<i32>) -> String {
+    format!("[{}, {}]", chunk.x, chunk.y)
+}
+
+fn chunk_text(chunk: Vector2<i32>) -> TextComponent {
+    TextComponent::text(format_chunk(chunk))
+}
+
+fn dimension_text(world: &World) -> TextComponent {
+    TextComponent::text(world.dimension_type.resource_location().to_string())
+}
+
+/// Adds or removes the chunks between `from` and an optional `to`
+struct ChangeExecutor {
+    forced: bool,
+    ranged: bool,
+}
+
+impl CommandExecutor for ChangeExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let from = chunk_at(args, ARG_FROM)?;
+            let to = if self.ranged {
+                chunk_at(args, ARG_TO)?
+            } else {
+                from
+            };
+            let (min_x, max_x) = (from.x.min(to.x), from.x.max(to.x));
+            let (min_z, max_z) = (from.y.min(to.y), from.y.max(to.y));
+
+            let count = (i64::from(max_x) - i64::from(min_x) + 1)
+                * (i64::from(max_z) - i64::from(min_z) + 1);
+            if count > MAX_CHUNKS {
+                return Err(CommandFailed(TextComponent::translate(
+                    "commands.forceload.toobig",
+                    [
+                        TextComponent::text(MAX_CHUNKS.to_string()),
+                        TextComponent::text(count.to_string()),
+                    ],
+                )));
+            }
+
+            let chunks = (min_x..=max_x)
+                .flat_map(|x| (min_z..=max_z).map(move |z| Vector2::new(x, z)))
+                .collect::<Vec<_>>();
+
+            let world = world_of(sender, server).await;
+            let changed = world.level.set_chunks_forced(&chunks, self.forced);
+
+            let action = if self.forced { "added" } else { "removed" };
+            let message = match changed.as_slice() {
+                [] => {
+                    return Err(CommandFailed(TextComponent::translate(
+                        format!("commands.forceload.{action}.failure"),
+                        [],
+                    )));
+                }
+                [chunk] => TextComponent::translate(
+                    format!("commands.forceload.{action}.single"),
+                    [chunk_text(*chunk), dimension_text(&world)],
+                ),
+                changed => TextComponent::translate(
+                    format!("commands.forceload.{action}.multiple"),
+                    [
+                        TextComponent::text(changed.len().to_string()),
+                        dimension_text(&world),
+                        chunk_text(Vector2::new(min_x, min_z)),
+                        chunk_text(Vector2::new(max_x, max_z)),
+                    ],
+                ),
+            };
+            sender.send_message(message).await;
+            Ok(())
+        })
+    }
+}
+
+struct RemoveAllExecutor;
+
+impl CommandExecutor for RemoveAllExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let world = world_of(sender, server).await;
+            let forced = world.level.forced_chunks();
+            world.level.set_chunks_forced(&forced, false);
+
+            sender
+                .send_message(TextComponent::translate(
+                    "commands.forceload.removed.all",
+                    [dimension_text(&world)],
+                ))
+                .await;
+            Ok(())
+        })
+    }
+}
+
+/// Lists every forced chunk of the dimension
+struct ListExecutor;
+
+impl CommandExecutor for ListExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let world = world_of(sender, server).await;
+            let forced = world.level.forced_chunks();
+            let list = || {
+                TextComponent::text(
+                    forced
+                        .iter()
+                        .map(|chunk| format_chunk(*chunk))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                )
+            };
+
+            let message = match forced.len() {
+                0 => TextComponent::translate(
+                    "commands.forceload.added.none",
+                    [dimension_text(&world)],
+                ),
+                1 => TextComponent::translate(
+                    "commands.forceload.list.single",
+                    [dimension_text(&world), list()],
+                ),
+                count => TextComponent::translate(
+                    "commands.forceload.list.multiple",
+                    [
+                        TextComponent::text(count.to_string()),
+                        dimension_text(&world),
+                        list(),
+                    ],
+                ),
+            };
+            sender.send_message(message).await;
+            Ok(())
+        })
+    }
+}
+
+/// Tells whether the chunk at a position is forced
+struct QueryExecutor;
+
+impl CommandExecutor for QueryExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let chunk = chunk_at(args, ARG_POS)?;
+            let world = world_of(sender, server).await;
+
+            if !world.level.is_chunk_forced(&chunk) {
+                return Err(CommandFailed(TextComponent::translate(
+                    "commands.forceload.query.failure",
+                    [chunk_text(chunk), dimension_text(&world)],
+                )));
+            }
+            sender
+                .send_message(TextComponent::translate(
+                    "commands.forceload.query.success",
+                    [chunk_text(chunk), dimension_text(&world)],
+                ))
+                .await;
+            Ok(())
+        })
+    }
+}
+
+pub fn init_command_tree() -> CommandTree {
+    CommandTree::new(NAMES, DESCRIPTION)
+        .then(
+            literal("add").then(
+                argument(ARG_FROM, Position2DArgumentConsumer)
+                    .execute(ChangeExecutor {
+                        forced: true,
+                        ranged: false,
+                    })
+                    .then(
+                        argument(ARG_TO, Position2DArgumentConsumer).execute(ChangeExecutor {
+                            forced: true,
+                            ranged: true,
+                        }),
+                    ),
+            ),
+        )
+        .then(
+            literal("remove")
+                .then(literal("all").execute(RemoveAllExecutor))
+                .then(
+                    argument(ARG_FROM, Position2DArgumentConsumer)
+                        .execute(ChangeExecutor {
+                            forced: false,
+                            ranged: false,
+                        })
+                        .then(argument(ARG_TO, Position2DArgumentConsumer).execute(
+                            ChangeExecutor {
+                                forced: false,
+                                ranged: true,
+                            },
+                        )),
+                ),
+        )
+        .then(
+            literal("query")
+                .execute(ListExecutor)
+                .then(argument(ARG_POS, Position2DArgumentConsumer).execute(QueryExecutor)),
+        )
+}
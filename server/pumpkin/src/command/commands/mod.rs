@@ -22,6 +22,7 @@ mod effect;
 mod enchant;
 mod experience;
 mod fill;
+mod forceload;
 mod gamemode;
 mod gamerule;
 mod give;
@@ -35,9 +36,11 @@ mod op;
 mod pardon;
 mod pardonip;
 mod particle;
+mod perm;
 mod playsound;
 mod plugin;
 mod plugins;
+mod pregen;
 mod pumpkin;
 mod say;
 mod seed;
@@ -122,6 +125,10 @@ pub async fn default_dispatcher(basic_config: &BasicConfiguration) -> CommandDis
         "minecraft:command.setworldspawn",
     );
     dispatcher.register(data::init_command_tree(), "minecraft:command.data");
+    dispatcher.register(
+        forceload::init_command_tree(),
+        "minecraft:command.forceload",
+    );
     // Three
     dispatcher.register(op::init_command_tree(), "minecraft:command.op");
     dispatcher.register(deop::init_command_tree(), "minecraft:command.deop");
@@ -138,8 +145,10 @@ pub async fn default_dispatcher(basic_config: &BasicConfiguration) -> CommandDis
         "minecraft:command.whitelist",
     );
     dispatcher.register(transfer::init_command_tree(), "minecraft:command.transfer");
+    dispatcher.register(perm::init_command_tree(), "pumpkin:command.perm");
     // Four
     dispatcher.register(stop::init_command_tree(), "minecraft:command.stop");
+    dispatcher.register(pregen::init_command_tree(), "pumpkin:command.pregen");
 
     dispatcher
 }
@@ -384,6 +393,13 @@ fn register_level_2_permissions(registry: &mut PermissionRegistry) {
             PermissionDefault::Op(PermissionLvl::Two),
         ))
         .unwrap();
+    registry
+        .register_permission(Permission::new(
+            "minecraft:command.forceload",
+            "Forces chunks to stay loaded",
+            PermissionDefault::Op(PermissionLvl::Two),
+        ))
+        .unwrap();
     registry
         .register_permission(Permission::new(
             "minecraft:command.enchant",
@@ -493,6 +509,13 @@ fn register_level_3_permissions(registry: &mut PermissionRegistry) {
             PermissionDefault::Op(PermissionLvl::Three),
         ))
         .unwrap();
+    registry
+        .register_permission(Permission::new(
+            "pumpkin:command.perm",
+            "Manages the permissions of players and groups",
+            PermissionDefault::Op(PermissionLvl::Three),
+        ))
+        .unwrap();
 }
 
 fn register_level_4_permissions(registry: &mut PermissionRegistry) {
@@ -504,4 +527,18 @@ fn register_level_4_permissions(registry: &mut PermissionRegistry) {
             PermissionDefault::Op(PermissionLvl::Four),
         ))
         .unwrap();
+    registry
+        .register_permission(Permission::new(
+            "pumpkin:command.pumpkin.debug",
+            "Debugs the packets of players",
+            PermissionDefault::Op(PermissionLvl::Four),
+        ))
+        .unwrap();
+    registry
+        .register_permission(Permission::new(
+            "pumpkin:command.pregen",
+            "Pre-generates chunks in the background",
+            PermissionDefault::Op(PermissionLvl::Four),
+        ))
+        .unwrap();
 }
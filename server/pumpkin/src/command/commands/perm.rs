@@ -0,0 +1,554 @@
+use pumpkin_util::{
+    permission::{PermissionNodes, PermissionStorage},
+    text::{TextComponent, color::NamedColor},
+};
+
+use crate::{
+    PERMISSION_MANAGER,
+    command::{
+        CommandError, CommandExecutor, CommandResult, CommandSender,
+        args::{
+            Arg, ConsumedArgs,
+            bool::BoolArgConsumer,
+            simple::SimpleArgConsumer,
+            users::{User, UsersArgumentConsumer},
+        },
+        client_suggestions,
+        tree::{
+            CommandTree,
+            builder::{NonLeafNodeBuilder, argument, literal},
+        },
+    },
+    data::permission_data::save_permissions,
+    server::Server,
+};
+
+use CommandError::InvalidConsumption;
+
+const NAMES: [&str; 1] = ["perm"];
+const DESCRIPTION: &str = "Manages the permissions of players and groups.";
+
+const ARG_TARGETS: &str = "targets";
+const ARG_GROUP: &str = "group";
+const ARG_PARENT: &str = "parent";
+const ARG_NODE: &str = "node";
+const ARG_VALUE: &str = "value";
+const ARG_WORLD: &str = "world";
+
+/// The users or the group a command modifies
+#[derive(Clone, Copy)]
+enum Target<'a> {
+    Users(&'a [User]),
+    Group(&'a str),
+}
+
+fn target<'a>(args: &'a ConsumedArgs) -> Result<Target<'a>, CommandError> {
+    match (args.get(&ARG_TARGETS), args.get(&ARG_GROUP)) {
+        (Some(Arg::Users(users)), _) => Ok(Target::Users(users)),
+        (_, Some(Arg::Simple(group))) => Ok(Target::Group(group)),
+        _ => Err(InvalidConsumption(Some(ARG_TARGETS.into()))),
+    }
+}
+
+fn simple_arg<'a>(args: &'a ConsumedArgs, name: &str) -> Result<&'a str, CommandError> {
+    match args.get(name) {
+        Some(Arg::Simple(value)) => Ok(value),
+        _ => Err(InvalidConsumption(Some(name.into()))),
+    }
+}
+
+/// The world a node is set in, `None` if it is set globally
+fn world<'a>(args: &'a ConsumedArgs) -> Option<&'a str> {
+    match args.get(&ARG_WORLD) {
+        Some(Arg::Simple(world)) => Some(world),
+        _ => None,
+    }
+}
+
+fn in_world(world: Option<&str>) -> String {
+    world.map_or_else(String::new, |world| format!(" in {world}"))
+}
+
+/// Modifies the nodes of the targeted users or group and saves the storage. Users are removed
+/// from the storage once nothing is set for them, groups are only created if `create` is set
+async fn modify_nodes(
+    sender: &CommandSender,
+    server: &Server,
+    args: &ConsumedArgs<'_>,
+    create: bool,
+    modify: impl Fn(&mut PermissionNodes) -> bool,
+    message: impl Fn(&str, bool) -> String,
+) -> Result<(), CommandError> {
+    let target = target(args)?;
+    let mut messages = Vec::new();
+
+    let mut manager = PERMISSION_MANAGER.write().await;
+    let storage = &mut manager.storage;
+    match target {
+        Target::Users(users) => {
+            for target in users {
+                let user = storage.users.entry(target.id).or_default();
+                let changed = modify(&mut user.nodes);
+                if user.groups.is_empty() && user.nodes.is_empty() {
+                    storage.users.remove(&target.id);
+                }
+                messages.push(message(&target.name, changed));
+            }
+        }
+        Target::Group(group) => {
+            let nodes = if create {
+                Some(&mut storage.groups.entry(group.to_string()).or_default().nodes)
+            } else {
+                storage.groups.get_mut(group).map(|group| &mut group.nodes)
+            };
+            messages.push(nodes.map_or_else(
+                || format!("Group {group} does not exist"),
+                |nodes| message(&format!("group {group}"), modify(nodes)),
+            ));
+        }
+    }
+    drop(manager);
+    save_permissions().await;
+
+    for message in messages {
+        sender.send_message(TextComponent::text(message)).await;
+    }
+    update_commands(server, &target).await;
+    Ok(())
+}
+
+/// Resends the commands to the affected online players, as the commands they may use changed
+async fn update_commands(server: &Server, target: &Target<'_>) {
+    let players = match target {
+        Target::Users(users) => users
+            .iter()
+            .filter_map(|user| user.player.clone())
+            .collect(),
+        // Any player may be in the group or in a group inheriting from it
+        Target::Group(_) => server.get_all_players().await,
+    };
+    let command_dispatcher = server.command_dispatcher.read().await;
+    for player in &players {
+        client_suggestions::send_c_commands_packet(player, &command_dispatcher).await;
+    }
+}
+
+fn describe_nodes(nodes: &PermissionNodes) -> Vec<String> {
+    let mut lines: Vec<String> = nodes
+        .permissions
+        .iter()
+        .map(|(node, value)| format!("{node} = {value}"))
+        .chain(nodes.worlds.iter().flat_map(|(world, permissions)| {
+            permissions
+                .iter()
+                .map(move |(node, value)| format!("{node} = {value} (in {world})"))
+        }))
+        .collect();
+    lines.sort();
+    lines
+}
+
+struct SetExecutor;
+
+impl CommandExecutor for SetExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let node = simple_arg(args, ARG_NODE)?;
+            let Some(Arg::Bool(value)) = args.get(&ARG_VALUE) else {
+                return Err(InvalidConsumption(Some(ARG_VALUE.into())));
+            };
+            let world = world(args);
+
+            modify_nodes(
+                sender,
+                server,
+                args,
+                true,
+                |nodes| {
+                    nodes.set_permission(node, *value, world);
+                    true
+                },
+                |name, _| format!("Set {node} to {value} for {name}{}", in_world(world)),
+            )
+            .await
+        })
+    }
+}
+
+struct UnsetExecutor;
+
+impl CommandExecutor for UnsetExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let node = simple_arg(args, ARG_NODE)?;
+            let world = world(args);
+
+            modify_nodes(
+                sender,
+                server,
+                args,
+                false,
+                |nodes| nodes.unset_permission(node, world),
+                |name, changed| {
+                    if changed {
+                        format!("Unset {node} for {name}{}", in_world(world))
+                    } else {
+                        format!("{node} is not set for {name}{}", in_world(world))
+                    }
+                },
+            )
+            .await
+        })
+    }
+}
+
+struct CheckExecutor;
+
+impl CommandExecutor for CheckExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let node = simple_arg(args, ARG_NODE)?;
+
+            match target(args)? {
+                Target::Users(users) => {
+                    for user in users {
+                        let name = &user.name;
+                        let message = if let Some(player) = &user.player {
+                            // Resolved like any other check, so attachments and defaults apply as well
+                            let value = player.has_permission(node).await;
+                            TextComponent::text(format!("{name} has {node}: "))
+                                .add_child(bool_text(value))
+                        } else {
+                            // Defaults depend on the op level, which is only known for online players
+                            let value = PERMISSION_MANAGER
+                                .read()
+                                .await
+                                .storage
+                                .lookup(&user.id, node, None);
+                            value.map_or_else(
+                                || TextComponent::text(format!("{name} does not set {node}")),
+                                |value| {
+                                    TextComponent::text(format!("{name} has {node}: "))
+                                        .add_child(bool_text(value))
+                                },
+                            )
+                        };
+                        sender.send_message(message).await;
+                    }
+                }
+                Target::Group(group) => {
+                    let value = PERMISSION_MANAGER
+                        .read()
+                        .await
+                        .storage
+                        .lookup_group(group, node, None);
+                    let message = value.map_or_else(
+                        || TextComponent::text(format!("Group {group} does not set {node}")),
+                        |value| {
+                            TextComponent::text(format!("Group {group} has {node}: "))
+                                .add_child(bool_text(value))
+                        },
+                    );
+                    sender.send_message(message).await;
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
+
+fn bool_text(value: bool) -> TextComponent {
+    if value {
+        TextComponent::text("true").color_named(NamedColor::Green)
+    } else {
+        TextComponent::text("false").color_named(NamedColor::Red)
+    }
+}
+
+struct InfoExecutor;
+
+impl CommandExecutor for InfoExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let manager = PERMISSION_MANAGER.read().await;
+            let storage = &manager.storage;
+            let mut messages = Vec::new();
+
+            match target(args)? {
+                Target::Users(users) => {
+                    for target in users {
+                        let id = &target.id;
+                        let mut lines = vec![
+                            format!("Permissions of {}:", target.name),
+                            format!("Groups: {}", storage.user_groups(id).join(", ")),
+                        ];
+                        if let Some(user) = storage.users.get(id) {
+                            lines.extend(describe_nodes(&user.nodes));
+                        }
+                        messages.push(lines.join("\n"));
+                    }
+                }
+                Target::Group(group) => {
+                    messages.push(describe_group(storage, group));
+                }
+            }
+            drop(manager);
+
+            for message in messages {
+                sender.send_message(TextComponent::text(message)).await;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn describe_group(storage: &PermissionStorage, name: &str) -> String {
+    let Some(group) = storage.groups.get(name) else {
+        return format!("Group {name} does not exist");
+    };
+    let mut lines = vec![format!("Permissions of group {name}:")];
+    if !group.inherits.is_empty() {
+        lines.push(format!("Inherits from: {}", group.inherits.join(", ")));
+    }
+    lines.extend(describe_nodes(&group.nodes));
+    lines.join("\n")
+}
+
+/// Adds the targeted users to a group or removes them from it
+struct MembershipExecutor {
+    add: bool,
+}
+
+impl CommandExecutor for MembershipExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let Some(Arg::Users(users)) = args.get(&ARG_TARGETS) else {
+                return Err(InvalidConsumption(Some(ARG_TARGETS.into())));
+            };
+            let group = simple_arg(args, ARG_PARENT)?;
+
+            let mut manager = PERMISSION_MANAGER.write().await;
+            let storage = &mut manager.storage;
+            let mut messages = Vec::new();
+            for target in users {
+                let id = target.id;
+                let name = &target.name;
+                let user = storage.users.entry(id).or_default();
+                let is_member = user.groups.iter().any(|g| g == group);
+                let message = match (self.add, is_member) {
+                    (true, true) => format!("{name} already is in group {group}"),
+                    (true, false) => {
+                        user.groups.push(group.to_string());
+                        format!("Added {name} to group {group}")
+                    }
+                    (false, true) => {
+                        user.groups.retain(|g| g != group);
+                        format!("Removed {name} from group {group}")
+                    }
+                    (false, false) => format!("{name} is not in group {group}"),
+                };
+                if user.groups.is_empty() && user.nodes.is_empty() {
+                    storage.users.remove(&id);
+                }
+                messages.push(message);
+            }
+            if self.add && !storage.groups.contains_key(group) {
+                messages.push(format!("Group {group} has no permissions yet"));
+            }
+            drop(manager);
+            save_permissions().await;
+
+            for message in messages {
+                sender.send_message(TextComponent::text(message)).await;
+            }
+            update_commands(server, &Target::Users(users)).await;
+            Ok(())
+        })
+    }
+}
+
+/// Makes the targeted group inherit from another group or stop doing so
+struct ParentExecutor {
+    add: bool,
+}
+
+impl CommandExecutor for ParentExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let group = simple_arg(args, ARG_GROUP)?;
+            let parent = simple_arg(args, ARG_PARENT)?;
+
+            let mut manager = PERMISSION_MANAGER.write().await;
+            let storage = &mut manager.storage;
+            let message = if self.add {
+                if storage.inherits_from(parent, group) {
+                    // Inheriting from each other would make neither group's permissions clear
+                    format!("Group {parent} already inherits from {group}")
+                } else {
+                    let inherits = &mut storage
+                        .groups
+                        .entry(group.to_string())
+                        .or_default()
+                        .inherits;
+                    if inherits.iter().any(|g| g == parent) {
+                        format!("Group {group} already inherits from {parent}")
+                    } else {
+                        inherits.push(parent.to_string());
+                        format!("Group {group} now inherits from {parent}")
+                    }
+                }
+            } else {
+                match storage.groups.get_mut(group) {
+                    Some(entry) if entry.inherits.iter().any(|g| g == parent) => {
+                        entry.inherits.retain(|g| g != parent);
+                        format!("Group {group} no longer inherits from {parent}")
+                    }
+                    _ => format!("Group {group} does not inherit from {parent}"),
+                }
+            };
+            drop(manager);
+            save_permissions().await;
+
+            sender.send_message(TextComponent::text(message)).await;
+            update_commands(server, &Target::Group(group)).await;
+            Ok(())
+        })
+    }
+}
+
+struct DeleteExecutor;
+
+impl CommandExecutor for DeleteExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let group = simple_arg(args, ARG_GROUP)?;
+
+            let mut manager = PERMISSION_MANAGER.write().await;
+            let storage = &mut manager.storage;
+            let message = if storage.groups.remove(group).is_some() {
+                // Don't leave references behind, which would apply to a new group of that name
+                for other in storage.groups.values_mut() {
+                    other.inherits.retain(|g| g != group);
+                }
+                for user in storage.users.values_mut() {
+                    user.groups.retain(|g| g != group);
+                }
+                storage
+                    .users
+                    .retain(|_, user| !user.groups.is_empty() || !user.nodes.is_empty());
+                format!("Deleted group {group}")
+            } else {
+                format!("Group {group} does not exist")
+            };
+            drop(manager);
+            save_permissions().await;
+
+            sender.send_message(TextComponent::text(message)).await;
+            update_commands(server, &Target::Group(group)).await;
+            Ok(())
+        })
+    }
+}
+
+/// The subcommands shared by users and groups
+fn node_commands(subject: NonLeafNodeBuilder) -> NonLeafNodeBuilder {
+    subject
+        .then(literal("info").execute(InfoExecutor))
+        .then(literal("check").then(argument(ARG_NODE, SimpleArgConsumer).execute(CheckExecutor)))
+        .then(
+            literal("set").then(
+                argument(ARG_NODE, SimpleArgConsumer).then(
+                    argument(ARG_VALUE, BoolArgConsumer)
+                        .execute(SetExecutor)
+                        .then(argument(ARG_WORLD, SimpleArgConsumer).execute(SetExecutor)),
+                ),
+            ),
+        )
+        .then(
+            literal("unset").then(
+                argument(ARG_NODE, SimpleArgConsumer)
+                    .execute(UnsetExecutor)
+                    .then(argument(ARG_WORLD, SimpleArgConsumer).execute(UnsetExecutor)),
+            ),
+        )
+}
+
+pub fn init_command_tree() -> CommandTree {
+    CommandTree::new(NAMES, DESCRIPTION)
+        .then(
+            literal("user").then(
+                node_commands(argument(ARG_TARGETS, UsersArgumentConsumer)).then(
+                    literal("group")
+                        .then(
+                            literal("add").then(
+                                argument(ARG_PARENT, SimpleArgConsumer)
+                                    .execute(MembershipExecutor { add: true }),
+                            ),
+                        )
+                        .then(
+                            literal("remove").then(
+                                argument(ARG_PARENT, SimpleArgConsumer)
+                                    .execute(MembershipExecutor { add: false }),
+                            ),
+                        ),
+                ),
+            ),
+        )
+        .then(
+            literal("group").then(
+                node_commands(argument(ARG_GROUP, SimpleArgConsumer))
+                    .then(
+                        literal("parent")
+                            .then(
+                                literal("add").then(
+                                    argument(ARG_PARENT, SimpleArgConsumer)
+                                        .execute(ParentExecutor { add: true }),
+                                ),
+                            )
+                            .then(
+                                literal("remove").then(
+                                    argument(ARG_PARENT, SimpleArgConsumer)
+                                        .execute(ParentExecutor { add: false }),
+                                ),
+                            ),
+                    )
+                    .then(literal("delete").execute(DeleteExecutor)),
+            ),
+        )
+}
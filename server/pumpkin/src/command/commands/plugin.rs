@@ -1,9 +1,6 @@
 use std::path::Path;
 
-use pumpkin_util::{
-    PermissionLvl,
-    text::{TextComponent, color::NamedColor, hover::HoverEvent},
-};
+use pumpkin_util::text::{TextComponent, color::NamedColor, hover::HoverEvent};
 
 use crate::{
     PLUGIN_MANAGER,
@@ -12,7 +9,7 @@ use crate::{
         args::{Arg, ConsumedArgs, simple::SimpleArgConsumer},
         tree::{
             CommandTree,
-            builder::{argument, literal, require},
+            builder::{argument, literal},
         },
     },
 };
@@ -178,17 +175,71 @@ impl CommandExecutor for UnloadExecutor {
     }
 }
 
+struct ReloadExecutor;
+
+impl CommandExecutor for ReloadExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a crate::server::Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let Some(Arg::Simple(plugin_name)) = args.get(PLUGIN_NAME) else {
+                return Err(InvalidConsumption(Some(PLUGIN_NAME.into())));
+            };
+
+            if !PLUGIN_MANAGER.is_plugin_active(plugin_name).await {
+                sender
+                    .send_message(
+                        TextComponent::text(format!("Plugin {plugin_name} is not loaded"))
+                            .color_named(NamedColor::Red),
+                    )
+                    .await;
+                return Ok(());
+            }
+
+            let result = PLUGIN_MANAGER.reload_plugin(plugin_name).await;
+
+            match result {
+                Ok(()) => {
+                    sender
+                        .send_message(
+                            TextComponent::text(format!(
+                                "Plugin {plugin_name} reloaded successfully",
+                            ))
+                            .color_named(NamedColor::Green),
+                        )
+                        .await;
+                }
+                Err(e) => {
+                    sender
+                        .send_message(
+                            TextComponent::text(format!(
+                                "Failed to reload plugin {plugin_name}: {e}"
+                            ))
+                            .color_named(NamedColor::Red),
+                        )
+                        .await;
+                }
+            }
+
+            Ok(())
+        })
+    }
+}
+
 pub fn init_command_tree() -> CommandTree {
-    CommandTree::new(NAMES, DESCRIPTION).then(
-        require(|sender| sender.has_permission_lvl(PermissionLvl::Three))
-            .then(
-                literal("load")
-                    .then(argument(PLUGIN_NAME, SimpleArgConsumer).execute(LoadExecutor)),
-            )
-            .then(
-                literal("unload")
-                    .then(argument(PLUGIN_NAME, SimpleArgConsumer).execute(UnloadExecutor)),
-            )
-            .then(literal("list").execute(ListExecutor)),
-    )
+    // Reaching the command at all already needs `pumpkin:command.plugin`
+    CommandTree::new(NAMES, DESCRIPTION)
+        .then(literal("load").then(argument(PLUGIN_NAME, SimpleArgConsumer).execute(LoadExecutor)))
+        .then(
+            literal("unload")
+                .then(argument(PLUGIN_NAME, SimpleArgConsumer).execute(UnloadExecutor)),
+        )
+        .then(
+            literal("reload")
+                .then(argument(PLUGIN_NAME, SimpleArgConsumer).execute(ReloadExecutor)),
+        )
+        .then(literal("list").execute(ListExecutor))
 }
@@ -0,0 +1,252 @@
+use pumpkin_registry::VanillaDimensionType;
+use pumpkin_util::text::TextComponent;
+use pumpkin_util::text::color::NamedColor;
+
+use crate::command::args::bounded_num::BoundedNumArgumentConsumer;
+use crate::command::args::{ConsumedArgs, FindArg};
+use crate::command::dispatcher::CommandError::CommandFailed;
+use crate::command::tree::CommandTree;
+use crate::command::tree::builder::{NonLeafNodeBuilder, argument, literal};
+use crate::command::{CommandExecutor, CommandResult, CommandSender};
+use crate::server::Server;
+use crate::server::pregen::PregenTask;
+
+const NAMES: [&str; 1] = ["pregen"];
+
+const DESCRIPTION: &str = "Pre-generates chunks in the background.";
+
+const ARG_RADIUS: &str = "radius";
+
+const DIMENSIONS: [(&str, VanillaDimensionType); 3] = [
+    ("overworld", VanillaDimensionType::Overworld),
+    ("the_nether", VanillaDimensionType::TheNether),
+    ("the_end", VanillaDimensionType::TheEnd),
+];
+
+fn radius_consumer() -> BoundedNumArgumentConsumer<i32> {
+    BoundedNumArgumentConsumer::new()
+        .name(ARG_RADIUS)
+        .min(1)
+        .max(29_999_984)
+}
+
+/// The dimension the command targets, defaulting to the one of the sender
+fn dimension_of(
+    sender: &CommandSender,
+    dimension: Option<VanillaDimensionType>,
+) -> VanillaDimensionType {
+    dimension
+        .or_else(|| sender.world().map(|world| world.dimension_type))
+        .unwrap_or(VanillaDimensionType::Overworld)
+}
+
+#[derive(Clone, Copy)]
+enum Area {
+    /// A radius in blocks around the world spawn
+    Radius,
+    /// Everything inside the world border
+    WorldBorder,
+}
+
+struct StartExecutor(Area, Option<VanillaDimensionType>);
+
+impl CommandExecutor for StartExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let dimension = dimension_of(sender, self.1);
+            let world = server.get_world_from_dimension(dimension).await;
+
+            let (border_x, border_z, border_radius) = {
+                let border = world.worldborder.lock().await;
+                (border.center_x, border.center_z, border.new_diameter / 2.0)
+            };
+
+            let (center_x, center_z, radius) = match self.0 {
+                Area::Radius => {
+                    let radius = BoundedNumArgumentConsumer::<i32>::find_arg(args, ARG_RADIUS)??;
+                    let level_info = world.level_info.read().await;
+                    let (spawn_x, spawn_z) = (level_info.spawn_x, level_info.spawn_z);
+                    // Chunks outside the world border can't be reached anyway
+                    let offset = (f64::from(spawn_x) - border_x)
+                        .abs()
+                        .max((f64::from(spawn_z) - border_z).abs());
+                    let radius = f64::from(radius).min((border_radius - offset).max(0.0));
+                    (spawn_x, spawn_z, radius)
+                }
+                Area::WorldBorder => (
+                    border_x.floor() as i32,
+                    border_z.floor() as i32,
+                    border_radius,
+                ),
+            };
+            let max_radius = server.advanced_config.world.pregen.max_radius;
+            if radius > f64::from(max_radius) {
+                sender
+                    .send_message(
+                        TextComponent::text(format!(
+                            "The radius of {radius:.0} blocks was clamped to the allowed {max_radius} blocks (world.pregen.max_radius)"
+                        ))
+                        .color_named(NamedColor::Yellow),
+                    )
+                    .await;
+            }
+            let radius = radius.min(f64::from(max_radius));
+            let task = PregenTask::new(center_x >> 4, center_z >> 4, (radius / 16.0).ceil() as i32);
+            let total = task.total();
+
+            server
+                .pregen
+                .start(server, dimension, task)
+                .await
+                .map_err(|err| CommandFailed(TextComponent::text(err.to_string())))?;
+
+            sender
+                .send_message(
+                    TextComponent::text(format!(
+                        "Started pre-generating {total} chunks in {}",
+                        dimension.resource_location()
+                    ))
+                    .color_named(NamedColor::Green),
+                )
+                .await;
+            Ok(())
+        })
+    }
+}
+
+struct PauseExecutor(bool, Option<VanillaDimensionType>);
+
+impl CommandExecutor for PauseExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let changed = server.pregen.set_paused(server, self.1, self.0).await;
+            if changed == 0 {
+                return Err(CommandFailed(TextComponent::text(if self.0 {
+                    "There is no running pre-generation to pause"
+                } else {
+                    "There is no paused pre-generation to resume"
+                })));
+            }
+
+            let action = if self.0 { "Paused" } else { "Resumed" };
+            sender
+                .send_message(TextComponent::text(format!(
+                    "{action} {changed} pre-generation(s)"
+                )))
+                .await;
+            Ok(())
+        })
+    }
+}
+
+struct CancelExecutor(Option<VanillaDimensionType>);
+
+impl CommandExecutor for CancelExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let dimension = dimension_of(sender, self.0);
+            server
+                .pregen
+                .cancel(server, dimension)
+                .await
+                .map_err(|err| CommandFailed(TextComponent::text(err.to_string())))?;
+
+            sender
+                .send_message(TextComponent::text(format!(
+                    "Cancelled the pre-generation of {}, generated chunks are kept",
+                    dimension.resource_location()
+                )))
+                .await;
+            Ok(())
+        })
+    }
+}
+
+struct StatusExecutor;
+
+impl CommandExecutor for StatusExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let status = server.pregen.status();
+            if status.is_empty() {
+                sender
+                    .send_message(TextComponent::text("No pre-generation is running"))
+                    .await;
+                return Ok(());
+            }
+
+            for pregen in status {
+                let state = if pregen.paused {
+                    "paused".to_string()
+                } else {
+                    let remaining = pregen.remaining().map_or_else(
+                        || "unknown".to_string(),
+                        |remaining| format!("{}m", remaining.as_secs().div_ceil(60)),
+                    );
+                    format!("{:.1} chunks/s, {remaining} left", pregen.chunks_per_second)
+                };
+                sender
+                    .send_message(TextComponent::text(format!(
+                        "{}: {}/{} chunks ({:.1}%), {state}",
+                        pregen.dimension, pregen.done, pregen.total, pregen.percent
+                    )))
+                    .await;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Adds a literal for every dimension after `node`, the node itself executes without a dimension
+fn with_dimensions<E: CommandExecutor + 'static>(
+    node: NonLeafNodeBuilder,
+    executor: impl Fn(Option<VanillaDimensionType>) -> E,
+) -> NonLeafNodeBuilder {
+    DIMENSIONS
+        .into_iter()
+        .fold(node.execute(executor(None)), |node, (name, dimension)| {
+            node.then(literal(name).execute(executor(Some(dimension))))
+        })
+}
+
+pub fn init_command_tree() -> CommandTree {
+    CommandTree::new(NAMES, DESCRIPTION)
+        .then(
+            literal("start")
+                .then(with_dimensions(literal("worldborder"), |dimension| {
+                    StartExecutor(Area::WorldBorder, dimension)
+                }))
+                .then(with_dimensions(
+                    argument(ARG_RADIUS, radius_consumer()),
+                    |dimension| StartExecutor(Area::Radius, dimension),
+                )),
+        )
+        .then(with_dimensions(literal("pause"), |dimension| {
+            PauseExecutor(true, dimension)
+        }))
+        .then(with_dimensions(literal("resume"), |dimension| {
+            PauseExecutor(false, dimension)
+        }))
+        .then(with_dimensions(literal("cancel"), CancelExecutor))
+        .then(literal("status").execute(StatusExecutor))
+}
@@ -1,13 +1,27 @@
+use pumpkin_config::chunk::{AnvilChunkConfig, ChunkConfig, Compression, LinearChunkConfig};
 use pumpkin_data::packet::CURRENT_MC_PROTOCOL;
+use pumpkin_protocol::recording::Platform;
 use pumpkin_util::text::click::ClickEvent;
 use pumpkin_util::text::hover::HoverEvent;
 use pumpkin_util::text::{TextComponent, color::NamedColor};
 use pumpkin_util::translation::get_translation_text;
-use pumpkin_world::CURRENT_MC_VERSION;
+use pumpkin_world::chunk::convert::{ConversionJob, ConvertOptions, describe_target};
+use pumpkin_world::{CURRENT_BEDROCK_MC_PROTOCOL, CURRENT_MC_VERSION};
 use std::borrow::Cow;
+use std::path::Path;
 
-use crate::command::CommandResult;
+use crate::command::args::bounded_num::BoundedNumArgumentConsumer;
+use crate::command::args::players::PlayersArgumentConsumer;
+use crate::command::args::{Arg, FindArg};
+use crate::command::tree::builder::{
+    NonLeafNodeBuilder, argument, literal, require, require_permission,
+};
 use crate::command::{CommandExecutor, CommandSender, args::ConsumedArgs, tree::CommandTree};
+use crate::command::{
+    CommandResult,
+    dispatcher::CommandError::{CommandFailed, InvalidConsumption},
+};
+use crate::net::{ClientPlatform, packet_recorder::RECORDING_FOLDER};
 
 const NAMES: [&str; 2] = ["pumpkin", "version"];
 
@@ -153,6 +167,260 @@ impl CommandExecutor for Executor {
     }
 }
 
+const ARG_MIN_INHABITED_TIME: &str = "min_inhabited_ticks";
+
+fn min_inhabited_time_consumer() -> BoundedNumArgumentConsumer<i64> {
+    BoundedNumArgumentConsumer::new()
+        .name(ARG_MIN_INHABITED_TIME)
+        .min(0)
+}
+
+#[derive(Clone, Copy)]
+enum ConvertTarget {
+    Anvil(Compression),
+    Linear,
+}
+
+impl ConvertTarget {
+    fn chunk_config(self) -> ChunkConfig {
+        match self {
+            Self::Anvil(algorithm) => {
+                let mut config = AnvilChunkConfig::default();
+                config.compression.algorithm = algorithm;
+                ChunkConfig::Anvil(config)
+            }
+            Self::Linear => ChunkConfig::Linear(LinearChunkConfig::default()),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum PruneMode {
+    Keep,
+    DropEmpty,
+    DropUninhabited,
+}
+
+struct ConvertExecutor(ConvertTarget, PruneMode);
+
+impl CommandExecutor for ConvertExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a crate::server::Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let options = match self.1 {
+                PruneMode::Keep => ConvertOptions::default(),
+                PruneMode::DropEmpty => ConvertOptions {
+                    drop_empty: true,
+                    min_inhabited_time: None,
+                },
+                PruneMode::DropUninhabited => ConvertOptions {
+                    drop_empty: true,
+                    min_inhabited_time: Some(BoundedNumArgumentConsumer::<i64>::find_arg(
+                        args,
+                        ARG_MIN_INHABITED_TIME,
+                    )??),
+                },
+            };
+
+            let target = self.0.chunk_config();
+            let world_path = server.config_dir.join(server.basic_config.get_world_path());
+            ConversionJob::new(target.clone(), options)
+                .write(&world_path)
+                .map_err(|err| CommandFailed(TextComponent::text(err.to_string())))?;
+
+            sender
+                .send_message(
+                    TextComponent::text(format!(
+                        "Scheduled a conversion of the world to {}. It runs on the next start, before the world is loaded.",
+                        describe_target(&target)
+                    ))
+                    .color_named(NamedColor::Green),
+                )
+                .await;
+            sender
+                .send_message(
+                    TextComponent::text(
+                        "Set `world.chunk` in features.toml to the same format and compression before restarting.",
+                    )
+                    .color_named(NamedColor::Gold),
+                )
+                .await;
+            Ok(())
+        })
+    }
+}
+
+struct ConvertStatusExecutor;
+
+impl CommandExecutor for ConvertStatusExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a crate::server::Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let world_path = server.config_dir.join(server.basic_config.get_world_path());
+            let job = ConversionJob::read(&world_path)
+                .map_err(|err| CommandFailed(TextComponent::text(err.to_string())))?;
+
+            let message = match job {
+                None => TextComponent::text("No world conversion is pending."),
+                Some(job) if job.progress.regions_total == 0 => TextComponent::text(format!(
+                    "A conversion to {} is scheduled for the next start.",
+                    describe_target(&job.target)
+                )),
+                Some(job) => TextComponent::text(format!(
+                    "An interrupted conversion to {} resumes on the next start: {}/{} regions done, {} KiB saved so far.",
+                    describe_target(&job.target),
+                    job.progress.regions_done,
+                    job.progress.regions_total,
+                    job.progress.bytes_saved() / 1024
+                )),
+            };
+            sender.send_message(message).await;
+            Ok(())
+        })
+    }
+}
+
+struct ConvertCancelExecutor;
+
+impl CommandExecutor for ConvertCancelExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        server: &'a crate::server::Server,
+        _args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let world_path = server.config_dir.join(server.basic_config.get_world_path());
+            let job = ConversionJob::read(&world_path)
+                .map_err(|err| CommandFailed(TextComponent::text(err.to_string())))?;
+
+            match job {
+                None => Err(CommandFailed(TextComponent::text(
+                    "No world conversion is pending.",
+                ))),
+                // Region files are already partly in the new format, cancelling would lose chunks
+                Some(job) if !job.completed.is_empty() => Err(CommandFailed(TextComponent::text(
+                    "The conversion is already in progress and can't be cancelled.",
+                ))),
+                Some(_) => {
+                    ConversionJob::remove(&world_path)
+                        .map_err(|err| CommandFailed(TextComponent::text(err.to_string())))?;
+                    sender
+                        .send_message(TextComponent::text("Cancelled the world conversion."))
+                        .await;
+                    Ok(())
+                }
+            }
+        })
+    }
+}
+
+const ARG_TARGETS: &str = "targets";
+
+/// Starts or stops recording the packets of players
+struct DebugPacketsExecutor;
+
+impl CommandExecutor for DebugPacketsExecutor {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a crate::server::Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let Some(Arg::Players(targets)) = args.get(ARG_TARGETS) else {
+                return Err(InvalidConsumption(Some(ARG_TARGETS.into())));
+            };
+
+            for target in targets {
+                let name = &target.gameprofile.name;
+                let recorder = target.client.packet_recorder();
+                if let Some(path) = recorder.stop() {
+                    sender
+                        .send_message(TextComponent::text(format!(
+                            "Stopped recording the packets of {name}, saved to {}",
+                            path.display()
+                        )))
+                        .await;
+                    continue;
+                }
+
+                let (platform, protocol) = match &target.client {
+                    ClientPlatform::Java(_) => (Platform::Java, CURRENT_MC_PROTOCOL),
+                    ClientPlatform::Bedrock(_) => (Platform::Bedrock, CURRENT_BEDROCK_MC_PROTOCOL),
+                };
+                let path = recorder
+                    .start(Path::new(RECORDING_FOLDER), name, platform, protocol)
+                    .map_err(|err| {
+                        CommandFailed(TextComponent::text(format!(
+                            "Failed to record the packets of {name}: {err}"
+                        )))
+                    })?;
+                sender
+                    .send_message(TextComponent::text(format!(
+                        "Recording the packets of {name} to {}",
+                        path.display()
+                    )))
+                    .await;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn convert_target(name: &str, target: ConvertTarget) -> NonLeafNodeBuilder {
+    literal(name)
+        .execute(ConvertExecutor(target, PruneMode::Keep))
+        .then(
+            literal("prune")
+                .execute(ConvertExecutor(target, PruneMode::DropEmpty))
+                .then(
+                    argument(ARG_MIN_INHABITED_TIME, min_inhabited_time_consumer())
+                        .execute(ConvertExecutor(target, PruneMode::DropUninhabited)),
+                ),
+        )
+}
+
 pub fn init_command_tree() -> CommandTree {
-    CommandTree::new(NAMES, DESCRIPTION).execute(Executor)
+    CommandTree::new(NAMES, DESCRIPTION)
+        .then(
+            // Converting touches every region file, so only the console may do it
+            require(CommandSender::is_console).then(
+                literal("world").then(
+                    literal("convert")
+                        .then(literal("status").execute(ConvertStatusExecutor))
+                        .then(literal("cancel").execute(ConvertCancelExecutor))
+                        .then(
+                            convert_target("anvil", ConvertTarget::Anvil(Compression::LZ4))
+                                .then(convert_target(
+                                    "gzip",
+                                    ConvertTarget::Anvil(Compression::GZip),
+                                ))
+                                .then(convert_target(
+                                    "zlib",
+                                    ConvertTarget::Anvil(Compression::ZLib),
+                                ))
+                                .then(convert_target(
+                                    "lz4",
+                                    ConvertTarget::Anvil(Compression::LZ4),
+                                )),
+                        )
+                        .then(convert_target("linear", ConvertTarget::Linear)),
+                ),
+            ),
+        )
+        .then(require_permission("pumpkin:command.pumpkin.debug").then(
+            literal("debug").then(literal("packets").then(
+                argument(ARG_TARGETS, PlayersArgumentConsumer).execute(DebugPacketsExecutor),
+            )),
+        ))
+        .execute(Executor)
 }
@@ -1,5 +1,3 @@
-use pumpkin_protocol::codec::var_int::VarInt;
-use pumpkin_protocol::java::client::play::CTransfer;
 use pumpkin_util::text::TextComponent;
 use pumpkin_util::text::color::{Color, NamedColor};
 
@@ -43,7 +41,7 @@ impl CommandExecutor for TargetSelfExecutor {
 
             let port = match port_consumer().find_arg_default_name(args) {
                 Err(_) => 25565,
-                Ok(Ok(count)) => count,
+                Ok(Ok(port)) => port as u16,
                 Ok(Err(_)) => {
                     sender
                         .send_message(
@@ -58,10 +56,7 @@ impl CommandExecutor for TargetSelfExecutor {
             if let CommandSender::Player(player) = sender {
                 let name = &player.gameprofile.name;
                 log::info!("[{name}: Transferring {name} to {hostname}:{port}]");
-                player
-                    .client
-                    .enqueue_packet(&CTransfer::new(hostname, VarInt(port)))
-                    .await;
+                player.transfer(hostname, port).await;
                 Ok(())
             } else {
                 Err(InvalidRequirement)
@@ -86,7 +81,7 @@ impl CommandExecutor for TargetPlayerExecutor {
 
             let port = match port_consumer().find_arg_default_name(args) {
                 Err(_) => 25565,
-                Ok(Ok(count)) => count,
+                Ok(Ok(port)) => port as u16,
                 Ok(Err(_)) => {
                     sender
                         .send_message(
@@ -103,9 +98,7 @@ impl CommandExecutor for TargetPlayerExecutor {
             };
 
             for p in players {
-                p.client
-                    .enqueue_packet(&CTransfer::new(hostname, VarInt(port)))
-                    .await;
+                p.transfer(hostname, port).await;
                 log::info!(
                     "[{sender}: Transferring {} to {hostname}:{port}]",
                     p.gameprofile.name
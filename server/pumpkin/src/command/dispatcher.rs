@@ -337,6 +337,14 @@ impl CommandDispatcher {
                         return Ok(false);
                     }
                 }
+                NodeType::RequirePermission { node } => {
+                    if !src.has_permission(node).await {
+                        log::debug!(
+                            "Error while parsing command: {raw_args:?} is missing permission {node}"
+                        );
+                        return Ok(false);
+                    }
+                }
             }
         }
 
@@ -386,6 +394,11 @@ impl CommandDispatcher {
                         return Ok(None);
                     }
                 }
+                NodeType::RequirePermission { node } => {
+                    if !src.has_permission(node).await {
+                        return Ok(None);
+                    }
+                }
             }
         }
 
@@ -188,3 +188,13 @@ pub fn require(
         leaf_nodes: Vec::new(),
     }
 }
+
+/// Following [Node]s are only reachable by senders with the permission ```node```, which should be
+/// registered.
+pub fn require_permission(node: impl Into<String>) -> NonLeafNodeBuilder {
+    NonLeafNodeBuilder {
+        node_type: NodeType::RequirePermission { node: node.into() },
+        child_nodes: Vec::new(),
+        leaf_nodes: Vec::new(),
+    }
+}
@@ -40,7 +40,7 @@ fn flatten_require_nodes(nodes: &[Node], children: &[usize]) -> Vec<usize> {
     for &i in children {
         let node = &nodes[i];
         match &node.node_type {
-            NodeType::Require { .. } => {
+            NodeType::Require { .. } | NodeType::RequirePermission { .. } => {
                 new_children.extend(flatten_require_nodes(nodes, node.children.as_slice()));
             }
             _ => new_children.push(i),
@@ -29,6 +29,10 @@ pub enum NodeType {
     Require {
         predicate: Arc<dyn Fn(&CommandSender) -> bool + Send + Sync>,
     },
+    /// Like [`NodeType::Require`], but the sender needs the permission `node`
+    RequirePermission {
+        node: String,
+    },
 }
 
 impl Debug for NodeType {
@@ -45,6 +49,10 @@ impl Debug for NodeType {
                 .field("consumer", &"..")
                 .finish(),
             Self::Require { .. } => f.debug_struct("Require").field("predicate", &"..").finish(),
+            Self::RequirePermission { node } => f
+                .debug_struct("RequirePermission")
+                .field("node", node)
+                .finish(),
         }
     }
 }
@@ -9,11 +9,13 @@ use serde::{Deserialize, Serialize};
 const DATA_FOLDER: &str = "data/";
 
 pub mod op_data;
+pub mod permission_data;
 
 pub mod banlist_serializer;
 pub mod banned_ip_data;
 pub mod banned_player_data;
 pub mod player_server_data;
+pub mod user_cache;
 pub mod whitelist_data;
 
 pub static OVERRIDE_DATA_ROOT: OnceLock<PathBuf> = OnceLock::new();
@@ -0,0 +1,41 @@
+use std::path::Path;
+
+use pumpkin_util::permission::PermissionStorage;
+use tokio::sync::Mutex;
+
+use crate::PERMISSION_MANAGER;
+
+use super::{LoadJSONConfiguration, SaveJSONConfiguration};
+
+impl LoadJSONConfiguration for PermissionStorage {
+    fn get_path() -> &'static Path {
+        Path::new("permissions.json")
+    }
+    fn validate(&self) {
+        let groups = self
+            .groups
+            .values()
+            .flat_map(|group| &group.inherits)
+            .chain(self.users.values().flat_map(|user| &user.groups));
+        for group in groups {
+            if !self.groups.contains_key(group) {
+                log::warn!("Permission group {group} is used but not defined");
+            }
+        }
+    }
+}
+
+impl SaveJSONConfiguration for PermissionStorage {}
+
+/// Only one save runs at a time, see [`save_permissions`]
+static SAVING: Mutex<()> = Mutex::const_new(());
+
+/// Saves the permission storage on a blocking thread, without holding the [`PERMISSION_MANAGER`] lock meanwhile
+pub async fn save_permissions() {
+    // The snapshot is taken once it's this save's turn, so the last save writes the latest state
+    let _saving = SAVING.lock().await;
+    let snapshot = PERMISSION_MANAGER.read().await.storage.clone();
+    if let Err(err) = tokio::task::spawn_blocking(move || snapshot.save()).await {
+        log::error!("Failed to save the permissions: {err}");
+    }
+}
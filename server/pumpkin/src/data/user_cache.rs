@@ -0,0 +1,123 @@
+use std::{path::Path, sync::LazyLock};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+
+use crate::net::GameProfile;
+
+use super::{LoadJSONConfiguration, SaveJSONConfiguration};
+
+/// Vanilla keeps this many players
+const MAX_ENTRIES: usize = 1000;
+
+pub static USER_CACHE: LazyLock<RwLock<UserCache>> =
+    LazyLock::new(|| RwLock::new(UserCache::load()));
+
+/// Only one save runs at a time, see [`save_user_cache`]
+static SAVING: Mutex<()> = Mutex::const_new(());
+
+#[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Debug)]
+pub struct UserCacheEntry {
+    pub name: String,
+    pub uuid: Uuid,
+}
+
+/// The players that joined before, so commands can refer to them while they are offline
+#[derive(Deserialize, Serialize, Default, Clone)]
+#[serde(transparent)]
+pub struct UserCache {
+    /// Most recently joined first
+    pub users: Vec<UserCacheEntry>,
+}
+
+impl UserCache {
+    /// Moves a player that joined to the front, returns whether the cache changed
+    pub fn remember(&mut self, profile: &GameProfile) -> bool {
+        let entry = UserCacheEntry {
+            name: profile.name.clone(),
+            uuid: profile.id,
+        };
+        if self.users.first() == Some(&entry) {
+            return false;
+        }
+        self.users
+            .retain(|user| user.uuid != entry.uuid && user.name != entry.name);
+        self.users.insert(0, entry);
+        self.users.truncate(MAX_ENTRIES);
+        true
+    }
+
+    /// Finds a player by UUID or by name, ignoring case like vanilla does
+    #[must_use]
+    pub fn find(&self, name_or_uuid: &str) -> Option<&UserCacheEntry> {
+        match Uuid::parse_str(name_or_uuid) {
+            Ok(uuid) => self.users.iter().find(|user| user.uuid == uuid),
+            Err(_) => self
+                .users
+                .iter()
+                .find(|user| user.name.eq_ignore_ascii_case(name_or_uuid)),
+        }
+    }
+}
+
+/// Remembers a player that joined, the cache is saved in the background so joining doesn't wait
+/// on the disk
+pub async fn remember_user(profile: &GameProfile) {
+    if USER_CACHE.write().await.remember(profile) {
+        tokio::spawn(save_user_cache());
+    }
+}
+
+async fn save_user_cache() {
+    // The snapshot is taken once it's this save's turn, so the last save writes the latest state
+    let _saving = SAVING.lock().await;
+    let snapshot = USER_CACHE.read().await.clone();
+    if let Err(err) = tokio::task::spawn_blocking(move || snapshot.save()).await {
+        log::error!("Failed to save the user cache: {err}");
+    }
+}
+
+impl LoadJSONConfiguration for UserCache {
+    fn get_path() -> &'static Path {
+        Path::new("usercache.json")
+    }
+    fn validate(&self) {}
+}
+
+impl SaveJSONConfiguration for UserCache {}
+
+#[cfg(test)]
+mod test {
+    use uuid::Uuid;
+
+    use super::UserCache;
+    use crate::net::GameProfile;
+
+    fn profile(name: &str, id: Uuid) -> GameProfile {
+        GameProfile {
+            id,
+            name: name.to_string(),
+            properties: Vec::new(),
+            profile_actions: None,
+        }
+    }
+
+    #[test]
+    fn remembers_most_recent_first() {
+        let mut cache = UserCache::default();
+        let alex = Uuid::new_v4();
+        let steve = Uuid::new_v4();
+        assert!(cache.remember(&profile("Alex", alex)));
+        assert!(cache.remember(&profile("Steve", steve)));
+        assert!(!cache.remember(&profile("Steve", steve)));
+        // A renamed player replaces their old entry
+        assert!(cache.remember(&profile("Alexandra", alex)));
+
+        let names: Vec<_> = cache.users.iter().map(|user| user.name.as_str()).collect();
+        assert_eq!(names, ["Alexandra", "Steve"]);
+        assert_eq!(cache.find("steve").unwrap().uuid, steve);
+        assert_eq!(cache.find(&alex.to_string()).unwrap().name, "Alexandra");
+        assert!(cache.find("Alex").is_none());
+    }
+}
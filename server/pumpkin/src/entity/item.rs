@@ -17,7 +17,10 @@ use pumpkin_util::math::vector3::Vector3;
 use pumpkin_world::item::ItemStack;
 use tokio::sync::Mutex;
 
-use crate::{entity::EntityBaseFuture, server::Server};
+use crate::{
+    PLUGIN_MANAGER, entity::EntityBaseFuture,
+    plugin::player::player_pickup_item::PlayerPickupItemEvent, server::Server,
+};
 
 use super::{Entity, EntityBase, NBTStorage, living::LivingEntity, player::Player};
 
@@ -392,13 +395,23 @@ impl EntityBase for ItemEntity {
                 *delay == 0
             };
 
-            if can_pickup
-                && player.living_entity.health.load() > 0.0
-                && (player
-                    .inventory
-                    .insert_stack_anywhere(&mut *self.item_stack.lock().await)
-                    .await
-                    || player.is_creative())
+            if !can_pickup || player.living_entity.health.load() <= 0.0 {
+                return;
+            }
+
+            let item_stack = self.item_stack.lock().await.clone();
+            let event = PLUGIN_MANAGER
+                .fire(PlayerPickupItemEvent::new(player.clone(), item_stack))
+                .await;
+            if event.cancelled {
+                return;
+            }
+
+            if player
+                .inventory
+                .insert_stack_anywhere(&mut *self.item_stack.lock().await)
+                .await
+                || player.is_creative()
             {
                 player
                     .client
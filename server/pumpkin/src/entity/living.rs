@@ -13,8 +13,12 @@ use std::{collections::HashMap, sync::atomic::AtomicI32};
 
 use super::{Entity, NBTStorage};
 use super::{EntityBase, NBTStorageInit};
+use crate::PLUGIN_MANAGER;
 use crate::entity::{EntityBaseFuture, NbtFuture};
+use crate::plugin::entity::{entity_damage::EntityDamageEvent, entity_death::EntityDeathEvent};
+use crate::plugin::player::player_item_consume::PlayerItemConsumeEvent;
 use crate::server::Server;
+use crate::world::World;
 use crate::world::loot::{LootContextParameters, LootTableExt};
 use crossbeam::atomic::AtomicCell;
 use pumpkin_data::Block;
@@ -790,12 +794,21 @@ impl LivingEntity {
                     EntityStatus::PlayDeathSoundOrAddProjectileHitParticles,
                 )
                 .await;
-            let params = LootContextParameters {
-                killed_by_player: cause.map(|c| c.get_entity().entity_type == &EntityType::PLAYER),
-                ..Default::default()
-            };
-
-            self.drop_loot(params).await;
+            let event = EntityDeathEvent::new(
+                dyn_self.clone(),
+                damage_type,
+                shared_entity(world, cause).await,
+            );
+            let event = PLUGIN_MANAGER.fire(event).await;
+
+            if event.drop_loot {
+                let params = LootContextParameters {
+                    killed_by_player: cause
+                        .map(|c| c.get_entity().entity_type == &EntityType::PLAYER),
+                    ..Default::default()
+                };
+                self.drop_loot(params).await;
+            }
             self.entity.pose.store(EntityPose::Dying);
 
             let level_info = world.level_info.read().await;
@@ -959,6 +972,14 @@ impl NBTStorage for LivingEntity {
     }
 }
 
+/// Looks up the shared handle of an entity that was passed by reference, plugin events need one
+async fn shared_entity(
+    world: &World,
+    entity: Option<&dyn EntityBase>,
+) -> Option<Arc<dyn EntityBase>> {
+    world.get_entity_by_id(entity?.get_entity().entity_id).await
+}
+
 impl EntityBase for LivingEntity {
     fn damage_with_context<'a>(
         &'a self,
@@ -991,6 +1012,20 @@ impl EntityBase for LivingEntity {
 
             let world = &self.entity.world;
 
+            let event = EntityDamageEvent::new(
+                caller.clone(),
+                amount,
+                damage_type,
+                shared_entity(world, source).await,
+                shared_entity(world, cause).await,
+            );
+            let event = PLUGIN_MANAGER.fire(event).await;
+            if event.cancelled {
+                return false;
+            }
+            // A plugin can't heal the entity, unlike comparing `max` also turns NaN into 0
+            let amount = event.amount.max(0.0);
+
             let last_damage = self.last_damage_taken.load();
             let play_sound;
             let mut damage_amount = if self.hurt_cooldown.load(Relaxed) > 10 {
@@ -1079,8 +1114,23 @@ impl EntityBase for LivingEntity {
                 if let Some(item) = item_in_use.as_ref()
                     && self.item_use_time.fetch_sub(1, Ordering::Relaxed) <= 0
                 {
+                    let consumer = match caller.get_player() {
+                        Some(player) => {
+                            self.entity.world.get_player_by_id(player.entity_id()).await
+                        }
+                        None => None,
+                    };
+                    let cancelled = match consumer {
+                        Some(player) => {
+                            let event = PlayerItemConsumeEvent::new(player, item.clone());
+                            PLUGIN_MANAGER.fire(event).await.cancelled
+                        }
+                        None => false,
+                    };
+
                     // Consume item
-                    if let Some(food) = item.get_data_component::<FoodImpl>()
+                    if !cancelled
+                        && let Some(food) = item.get_data_component::<FoodImpl>()
                         && let Some(player) = caller.get_player()
                     {
                         player
@@ -1088,7 +1138,7 @@ impl EntityBase for LivingEntity {
                             .eat(player, food.nutrition as u8, food.saturation)
                             .await;
                     }
-                    if let Some(player) = caller.get_player() {
+                    if !cancelled && let Some(player) = caller.get_player() {
                         player
                             .inventory
                             .held_item()
@@ -1,12 +1,17 @@
 use crate::entity::item::ItemEntity;
 use crate::world::World;
-use crate::{server::Server, world::portal::PortalManager};
+use crate::{
+    server::Server,
+    world::portal::{PortalManager, nether::NetherPortal},
+};
 use bytes::BufMut;
 use crossbeam::atomic::AtomicCell;
 use living::LivingEntity;
 use player::Player;
 use pumpkin_data::BlockState;
-use pumpkin_data::block_properties::{EnumVariants, Integer0To15};
+use pumpkin_data::block_properties::{
+    BlockProperties, EnumVariants, HorizontalAxis, Integer0To15, NetherPortalLikeProperties,
+};
 use pumpkin_data::fluid::Fluid;
 use pumpkin_data::{Block, BlockDirection};
 use pumpkin_data::{
@@ -38,6 +43,7 @@ use pumpkin_util::math::{
 use pumpkin_util::text::TextComponent;
 use pumpkin_util::text::hover::HoverEvent;
 use pumpkin_world::entity::entity_data_flags::DATA_POSE;
+use pumpkin_world::persistent_data::PersistentDataContainer;
 use serde::Serialize;
 use std::collections::BTreeMap;
 use std::f32::consts::PI;
@@ -60,6 +66,7 @@ pub mod hunger;
 pub mod item;
 pub mod living;
 pub mod mob;
+pub mod movement;
 pub mod player;
 pub mod projectile;
 pub mod projectile_deflection;
@@ -358,6 +365,8 @@ pub struct Entity {
     pub velocity_dirty: AtomicBool,
     /// Set when an Entity is to be removed but could still be referenced
     pub removed: AtomicBool,
+    /// Data plugins attached to the entity, saved with it
+    pub persistent_data: Mutex<PersistentDataContainer>,
 }
 
 impl Entity {
@@ -432,6 +441,7 @@ impl Entity {
             movement_multiplier: AtomicCell::new(Vector3::default()),
             velocity_dirty: AtomicBool::new(true),
             removed: AtomicBool::new(false),
+            persistent_data: Mutex::new(PersistentDataContainer::new()),
         }
     }
 
@@ -1305,32 +1315,32 @@ impl Entity {
                 self.portal_cooldown
                     .store(self.default_portal_cooldown(), Ordering::Relaxed);
                 let pos = self.pos.load();
-                // TODO: this is bad
-                let scale_factor_new = if portal_manager.portal_world.dimension_type
-                    == VanillaDimensionType::TheNether
-                {
-                    8.0
-                } else {
-                    1.0
-                };
-                // TODO: this is bad
-                let scale_factor_current =
-                    if self.world.dimension_type == VanillaDimensionType::TheNether {
-                        8.0
+                let portal_world = portal_manager.portal_world.clone();
+                let nether_travel = portal_world.dimension_type == VanillaDimensionType::TheNether
+                    || self.world.dimension_type == VanillaDimensionType::TheNether;
+                let destination = if nether_travel {
+                    let scale_factor =
+                        if self.world.dimension_type == VanillaDimensionType::TheNether {
+                            8.0
+                        } else {
+                            1.0 / 8.0
+                        };
+                    let target =
+                        BlockPos::floored(pos.x * scale_factor, pos.y, pos.z * scale_factor);
+                    let (block, state) =
+                        self.world.get_block_and_state_id(&portal_manager.pos).await;
+                    let axis = if block == &Block::NETHER_PORTAL {
+                        NetherPortalLikeProperties::from_state_id(state, block).axis
                     } else {
-                        1.0
+                        HorizontalAxis::X
                     };
-                let scale_factor = scale_factor_current / scale_factor_new;
-                // TODO
-                let pos = BlockPos::floored(pos.x * scale_factor, pos.y, pos.z * scale_factor);
+                    NetherPortal::find_or_create_destination(&portal_world, target, axis).await
+                } else {
+                    BlockPos::floored(pos.x, pos.y, pos.z).0.to_f64()
+                };
                 caller
                     .clone()
-                    .teleport(
-                        pos.0.to_f64(),
-                        None,
-                        None,
-                        portal_manager.portal_world.clone(),
-                    )
+                    .teleport(destination, None, None, portal_world)
                     .await;
                 drop(portal_manager);
             } else if portal_manager.ticks_in_portal == 0 {
@@ -1823,6 +1833,7 @@ impl NBTStorage for Entity {
             if self.has_visual_fire.load(Relaxed) {
                 nbt.put_bool("HasVisualFire", true);
             }
+            self.persistent_data.lock().await.write(nbt);
 
             // todo more...
         })
@@ -1857,6 +1868,7 @@ impl NBTStorage for Entity {
                 .store(nbt.get_int("PortalCooldown").unwrap_or(0) as u32, Relaxed);
             self.has_visual_fire
                 .store(nbt.get_bool("HasVisualFire").unwrap_or(false), Relaxed);
+            *self.persistent_data.lock().await = PersistentDataContainer::read(nbt);
             // todo more...
         })
     }
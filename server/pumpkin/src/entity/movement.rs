@@ -0,0 +1,359 @@
+use std::sync::{Arc, atomic::Ordering};
+
+use pumpkin_config::MovementConfig;
+use pumpkin_data::{effect::StatusEffect, entity::EntityPose};
+use pumpkin_util::{
+    GameMode,
+    math::{boundingbox::BoundingBox, position::BlockPos, vector3::Axis, vector3::Vector3},
+};
+use tokio::sync::Mutex;
+
+use super::player::Player;
+use crate::world::World;
+
+/// How high players step up blocks without jumping
+const STEP_HEIGHT: f64 = 0.6;
+/// Vanilla ignores any more movement packets per tick, a lagging client sends them in bursts
+const MAX_MOVE_PACKETS_PER_TICK: u32 = 5;
+
+/// The result of validating a movement packet
+#[derive(Debug, PartialEq, Eq)]
+pub enum MovementCheck {
+    Accepted,
+    /// The player moved too quickly
+    TooQuickly,
+    /// The player moved through or into blocks
+    Wrongly,
+}
+
+struct MovementState {
+    /// Where the player was at the start of the tick
+    first_good_pos: Vector3<f64>,
+    received_move_packets: u32,
+    known_move_packets: u32,
+    /// Whether the last movement left the player in the air without a reason to be
+    floating: bool,
+    floating_ticks: u32,
+}
+
+/// Validates the positions clients send, like vanilla's "moved too quickly" and
+/// "moved wrongly" checks
+pub struct MovementValidator {
+    state: Mutex<MovementState>,
+}
+
+impl MovementValidator {
+    #[must_use]
+    pub fn new(position: Vector3<f64>) -> Self {
+        Self {
+            state: Mutex::new(MovementState {
+                first_good_pos: position,
+                received_move_packets: 0,
+                known_move_packets: 0,
+                floating: false,
+                floating_ticks: 0,
+            }),
+        }
+    }
+
+    /// Forgets the movement of this tick, used after teleporting the player
+    pub async fn reset(&self, position: Vector3<f64>) {
+        let mut state = self.state.lock().await;
+        state.first_good_pos = position;
+        state.known_move_packets = state.received_move_packets;
+        state.floating = false;
+        state.floating_ticks = 0;
+    }
+
+    /// Starts a new tick, returns whether the player has been floating for too long
+    pub async fn tick(&self, player: &Player, config: &MovementConfig) -> bool {
+        let mut state = self.state.lock().await;
+        state.first_good_pos = player.living_entity.entity.pos.load();
+        state.known_move_packets = state.received_move_packets;
+
+        if state.floating
+            && config.enabled
+            && config.kick_for_flying
+            && player.sleeping_since.load().is_none()
+            && !player.living_entity.entity.has_vehicle().await
+        {
+            state.floating_ticks += 1;
+            return state.floating_ticks > config.max_floating_ticks;
+        }
+        state.floating_ticks = 0;
+        false
+    }
+
+    /// Checks whether the player may move to `to`. The player's position has not been updated yet
+    pub async fn check_move(
+        &self,
+        player: &Player,
+        config: &MovementConfig,
+        to: Vector3<f64>,
+        on_ground: bool,
+    ) -> MovementCheck {
+        let entity = &player.living_entity.entity;
+        let gamemode = player.gamemode.load();
+        let mut state = self.state.lock().await;
+        state.received_move_packets = state.received_move_packets.wrapping_add(1);
+        if !config.enabled
+            || gamemode == GameMode::Spectator
+            || player.sleeping_since.load().is_some()
+            || entity.has_vehicle().await
+        {
+            state.floating = false;
+            return MovementCheck::Accepted;
+        }
+
+        let mut packets = state
+            .received_move_packets
+            .wrapping_sub(state.known_move_packets);
+        if packets > MAX_MOVE_PACKETS_PER_TICK {
+            packets = 1;
+        }
+
+        let gliding = entity.fall_flying.load(Ordering::Relaxed)
+            || entity.pose.load() == EntityPose::SpinAttack;
+        let max_speed = if gliding {
+            config.max_elytra_speed_squared
+        } else {
+            config.max_speed_squared
+        } * speed_multiplier(player).await;
+
+        let moved = to.sub(&state.first_good_pos).length_squared();
+        let expected = entity.velocity.load().length_squared();
+        if moved - expected > max_speed * f64::from(packets) {
+            log::warn!(
+                "{} moved too quickly! {:.3},{:.3},{:.3}",
+                player.gameprofile.name,
+                to.x - state.first_good_pos.x,
+                to.y - state.first_good_pos.y,
+                to.z - state.first_good_pos.z
+            );
+            return MovementCheck::TooQuickly;
+        }
+
+        let from = entity.pos.load();
+        let delta = to.sub(&from);
+        let world = player.world();
+        let old_box = entity.bounding_box.load();
+        let new_box = old_box.shift(delta);
+
+        if config.prevent_no_clip {
+            let moved_wrongly = gamemode != GameMode::Creative && {
+                let area = old_box
+                    .stretch(delta)
+                    .stretch(Vector3::new(0.0, STEP_HEIGHT, 0.0));
+                let (collisions, _) = world.get_block_collisions(area).await;
+                let allowed = collide_with_step(
+                    &old_box,
+                    delta,
+                    &collisions,
+                    entity.on_ground.load(Ordering::Relaxed),
+                );
+                // Like vanilla, only horizontal errors count, falling is up to the client
+                delta.sub(&allowed).horizontal_length_squared() > config.max_error_squared
+            };
+
+            if moved_wrongly {
+                log::warn!("{} moved wrongly!", player.gameprofile.name);
+            }
+            if (moved_wrongly && !collides(world, &old_box).await)
+                || collides_with_anything_new(world, &old_box, &new_box).await
+            {
+                return MovementCheck::Wrongly;
+            }
+        }
+
+        state.floating = delta.y >= -0.03125
+            && !on_ground
+            && !gliding
+            && !player.abilities.lock().await.allow_flying
+            && !player
+                .living_entity
+                .has_effect(&StatusEffect::LEVITATION)
+                .await
+            && no_blocks_around(world, &new_box).await;
+        MovementCheck::Accepted
+    }
+}
+
+/// How much further than usual the effects of a player let them move
+async fn speed_multiplier(player: &Player) -> f64 {
+    let mut multiplier = 1.0;
+    for effect in [&StatusEffect::SPEED, &StatusEffect::DOLPHINS_GRACE] {
+        if let Some(effect) = player.living_entity.get_effect(effect).await {
+            let boost = 0.2 * (f64::from(effect.amplifier) + 1.0);
+            multiplier *= (1.0 + boost).powi(2);
+        }
+    }
+    multiplier
+}
+
+/// Shrinks boxes a bit so touching blocks does not count as colliding
+fn deflate(bounding_box: &BoundingBox) -> BoundingBox {
+    bounding_box.expand(-1.0E-5, -1.0E-5, -1.0E-5)
+}
+
+async fn collides(world: &Arc<World>, bounding_box: &BoundingBox) -> bool {
+    let bounding_box = deflate(bounding_box);
+    let (collisions, _) = world.get_block_collisions(bounding_box).await;
+    collisions
+        .iter()
+        .any(|collision| collision.intersects(&bounding_box))
+}
+
+/// Whether the new box is inside a block the old box was not already stuck in
+async fn collides_with_anything_new(
+    world: &Arc<World>,
+    old_box: &BoundingBox,
+    new_box: &BoundingBox,
+) -> bool {
+    let old_box = deflate(old_box);
+    let new_box = deflate(new_box);
+    let (collisions, _) = world.get_block_collisions(new_box).await;
+    collisions
+        .iter()
+        .any(|collision| collision.intersects(&new_box) && !collision.intersects(&old_box))
+}
+
+/// Whether there is nothing but air around and just below the box
+async fn no_blocks_around(world: &Arc<World>, bounding_box: &BoundingBox) -> bool {
+    let area = bounding_box
+        .expand(0.0625, 0.0625, 0.0625)
+        .stretch(Vector3::new(0.0, -0.55, 0.0));
+    let min = BlockPos::floored_v(area.min);
+    let max = BlockPos::floored_v(area.max);
+    for x in min.0.x..=max.0.x {
+        for y in min.0.y..=max.0.y {
+            for z in min.0.z..=max.0.z {
+                if !world
+                    .get_block_state(&BlockPos::new(x, y, z))
+                    .await
+                    .is_air()
+                {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Moves the box as far as the collisions allow, first along Y and then X and Z
+#[expect(clippy::float_cmp)]
+fn collide(
+    bounding_box: &BoundingBox,
+    movement: Vector3<f64>,
+    collisions: &[BoundingBox],
+) -> Vector3<f64> {
+    let mut adjusted = movement;
+    let mut moved_box = *bounding_box;
+    for axis in [Axis::Y, Axis::X, Axis::Z] {
+        let axis_movement = adjusted.get_axis(axis);
+        if axis_movement == 0.0 {
+            continue;
+        }
+        let mut on_axis = Vector3::new(0.0, 0.0, 0.0);
+        on_axis.set_axis(axis, axis_movement);
+
+        let time = collisions
+            .iter()
+            .filter_map(|collision| {
+                moved_box.calculate_collision_time(collision, on_axis, axis, 1.0)
+            })
+            .fold(1.0, f64::min);
+        adjusted.set_axis(axis, axis_movement * time);
+        on_axis.set_axis(axis, axis_movement * time);
+        moved_box = moved_box.shift(on_axis);
+    }
+    adjusted
+}
+
+/// Like `collide`, but players on the ground may also step up blocks on the way
+#[expect(clippy::float_cmp)]
+fn collide_with_step(
+    bounding_box: &BoundingBox,
+    movement: Vector3<f64>,
+    collisions: &[BoundingBox],
+    on_ground: bool,
+) -> Vector3<f64> {
+    let direct = collide(bounding_box, movement, collisions);
+    let blocked = direct.x != movement.x || direct.z != movement.z;
+    if !blocked || !(on_ground || (movement.y < 0.0 && direct.y != movement.y)) {
+        return direct;
+    }
+
+    let up = collide(
+        bounding_box,
+        Vector3::new(0.0, STEP_HEIGHT, 0.0),
+        collisions,
+    );
+    let raised = bounding_box.shift(up);
+    let horizontal = collide(
+        &raised,
+        Vector3::new(movement.x, 0.0, movement.z),
+        collisions,
+    );
+    let down = collide(
+        &raised.shift(horizontal),
+        Vector3::new(0.0, movement.y - up.y, 0.0),
+        collisions,
+    );
+    let stepped = Vector3::new(horizontal.x, up.y + down.y, horizontal.z);
+    if stepped.horizontal_length_squared() > direct.horizontal_length_squared() {
+        stepped
+    } else {
+        direct
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pumpkin_util::math::{boundingbox::BoundingBox, vector3::Vector3};
+
+    use super::{collide, collide_with_step};
+
+    fn player_box(x: f64, y: f64, z: f64) -> BoundingBox {
+        BoundingBox::new(
+            Vector3::new(x - 0.3, y, z - 0.3),
+            Vector3::new(x + 0.3, y + 1.8, z + 0.3),
+        )
+    }
+
+    fn block(x: f64, y: f64, z: f64, height: f64) -> BoundingBox {
+        BoundingBox::new(
+            Vector3::new(x, y, z),
+            Vector3::new(x + 1.0, y + height, z + 1.0),
+        )
+    }
+
+    #[test]
+    fn wall_stops_movement() {
+        let wall = [block(1.0, 0.0, 0.0, 1.0), block(1.0, 1.0, 0.0, 1.0)];
+        let moved = collide(
+            &player_box(0.5, 0.0, 0.5),
+            Vector3::new(2.0, 0.0, 0.0),
+            &wall,
+        );
+        assert!((moved.x - 0.2).abs() < 1.0E-9);
+    }
+
+    #[test]
+    fn steps_up_slabs_but_not_blocks() {
+        let start = player_box(0.5, 0.0, 0.5);
+        let movement = Vector3::new(0.8, 0.0, 0.0);
+
+        let slab = [block(1.0, 0.0, 0.0, 0.5)];
+        let moved = collide_with_step(&start, movement, &slab, true);
+        assert!((moved.x - 0.8).abs() < 1.0E-9);
+        assert!((moved.y - 0.5).abs() < 1.0E-9);
+
+        let full = [block(1.0, 0.0, 0.0, 1.0)];
+        let moved = collide_with_step(&start, movement, &full, true);
+        assert!((moved.x - 0.2).abs() < 1.0E-9);
+        // Can't step in the air
+        let moved = collide_with_step(&start, movement, &slab, false);
+        assert!((moved.x - 0.2).abs() < 1.0E-9);
+    }
+}
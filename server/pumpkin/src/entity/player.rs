@@ -11,8 +11,8 @@ use crossbeam::atomic::AtomicCell;
 use crossbeam::channel::Receiver;
 use log::warn;
 use pumpkin_inventory::player::ender_chest_inventory::EnderChestInventory;
-use pumpkin_protocol::bedrock::client::level_chunk::CLevelChunk;
 use pumpkin_protocol::bedrock::client::set_time::CSetTime;
+use pumpkin_protocol::bedrock::client::transfer::CTransfer;
 use pumpkin_protocol::bedrock::client::update_abilities::{
     Ability, AbilityLayer, CUpdateAbilities,
 };
@@ -24,6 +24,7 @@ use tokio::sync::{Mutex, RwLock};
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+use pumpkin_config::MovementConfig;
 use pumpkin_data::damage::DamageType;
 use pumpkin_data::data_component_impl::{AttributeModifiersImpl, Operation};
 use pumpkin_data::data_component_impl::{EquipmentSlot, EquippableImpl};
@@ -33,6 +34,7 @@ use pumpkin_data::particle::Particle;
 use pumpkin_data::sound::{Sound, SoundCategory};
 use pumpkin_data::tag::Taggable;
 use pumpkin_data::{Block, BlockState, tag};
+use pumpkin_inventory::crafting::crafting_screen_handler::CraftingTableScreenHandler;
 use pumpkin_inventory::player::{
     player_inventory::PlayerInventory, player_screen_handler::PlayerScreenHandler,
 };
@@ -46,14 +48,15 @@ use pumpkin_nbt::tag::NbtTag;
 use pumpkin_protocol::IdOr;
 use pumpkin_protocol::codec::var_int::VarInt;
 use pumpkin_protocol::java::client::play::{
-    Animation, CAcknowledgeBlockChange, CActionBar, CChangeDifficulty, CChunkBatchEnd,
-    CChunkBatchStart, CChunkData, CCloseContainer, CCombatDeath, CDisguisedChatMessage,
+    Animation, CAcknowledgeBlockChange, CActionBar, CAddResourcePack, CChangeDifficulty,
+    CChunkBatchEnd, CChunkBatchStart, CCloseContainer, CCombatDeath, CDisguisedChatMessage,
     CEntityAnimation, CEntityPositionSync, CGameEvent, CKeepAlive, COpenScreen, CParticle,
-    CPlayerAbilities, CPlayerInfoUpdate, CPlayerPosition, CPlayerSpawnPosition, CRespawn,
-    CSetContainerContent, CSetContainerProperty, CSetContainerSlot, CSetCursorItem, CSetEquipment,
-    CSetExperience, CSetHealth, CSetPlayerInventory, CSetSelectedSlot, CSoundEffect, CStopSound,
-    CSubtitle, CSystemChatMessage, CTitleAnimation, CTitleText, CUnloadChunk, CUpdateMobEffect,
-    CUpdateTime, GameEvent, MetaDataType, Metadata, PlayerAction, PlayerInfoFlags, PreviousMessage,
+    CPlayerAbilities, CPlayerInfoUpdate, CPlayerPosition, CPlayerSpawnPosition,
+    CRemoveResourcePack, CRespawn, CSetContainerContent, CSetContainerProperty, CSetContainerSlot,
+    CSetCursorItem, CSetEquipment, CSetExperience, CSetHealth, CSetPlayerInventory,
+    CSetSelectedSlot, CSoundEffect, CStopSound, CSubtitle, CSystemChatMessage, CTitleAnimation,
+    CTitleText, CUnloadChunk, CUpdateMobEffect, CUpdateTime, GameEvent, MetaDataType, Metadata,
+    PlayerAction, PlayerInfoFlags, PreviousMessage,
 };
 use pumpkin_protocol::java::server::play::SClickSlot;
 use pumpkin_registry::VanillaDimensionType;
@@ -79,23 +82,32 @@ use crate::data::op_data::OPERATOR_CONFIG;
 use crate::entity::{EntityBaseFuture, NbtFuture, TeleportFuture};
 use crate::net::{ClientPlatform, GameProfile};
 use crate::net::{DisconnectReason, PlayerConfig};
+use crate::plugin::inventory::{
+    craft_item::CraftItemEvent, inventory_click::InventoryClickEvent,
+    inventory_close::InventoryCloseEvent, inventory_open::InventoryOpenEvent,
+};
 use crate::plugin::player::player_change_world::PlayerChangeWorldEvent;
+use crate::plugin::player::player_drop_item::PlayerDropItemEvent;
 use crate::plugin::player::player_gamemode_change::PlayerGamemodeChangeEvent;
 use crate::plugin::player::player_teleport::PlayerTeleportEvent;
 use crate::server::Server;
+use crate::server::resource_pack::ResourcePack;
 use crate::world::World;
-use crate::{PERMISSION_MANAGER, block};
+use crate::{PERMISSION_MANAGER, PLUGIN_MANAGER, block};
 
 use super::combat::{self, AttackType, player_attack_sound};
 use super::hunger::HungerManager;
 use super::item::ItemEntity;
 use super::living::LivingEntity;
+use super::movement::MovementValidator;
 use super::{Entity, EntityBase, NBTStorage, NBTStorageInit};
 use pumpkin_data::potion::Effect;
 use pumpkin_world::chunk_system::ChunkLoading;
 
 const MAX_CACHED_SIGNATURES: u8 = 128; // Vanilla: 128
 const MAX_PREVIOUS_MESSAGES: u8 = 20; // Vanilla: 20
+const DEFAULT_BLOCK_REACH_BUFFER: f64 = 1.0; // Vanilla: 1.0
+const DEFAULT_ENTITY_REACH_BUFFER: f64 = 3.0; // Vanilla: 3.0
 
 pub const DATA_VERSION: i32 = 4671; // 1.21.11
 
@@ -356,6 +368,8 @@ pub struct Player {
     pub teleport_id_count: AtomicI32,
     /// The pending teleport information, including the teleport ID and target location.
     pub awaiting_teleport: Mutex<Option<(VarInt, Vector3<f64>)>>,
+    /// Checks the positions the client sends
+    pub movement: MovementValidator,
     /// The coordinates of the chunk section the player is currently watching.
     pub watched_section: AtomicCell<Cylindrical>,
     /// Whether we are waiting for a response after sending a keep alive packet.
@@ -430,12 +444,16 @@ impl Player {
             PlayerScreenHandler::new(&inventory, None, 0).await,
         ));
 
+        // The spawn position is only known once the player joins the world, which resets this
+        let movement = MovementValidator::new(living_entity.entity.pos.load());
+
         Self {
             living_entity,
             config: RwLock::new(config),
             gameprofile,
             client,
             awaiting_teleport: Mutex::new(None),
+            movement,
             // TODO: Load this from previous instance
             hunger_manager: HungerManager::default(),
             current_block_destroy_stage: AtomicI32::new(-1),
@@ -859,6 +877,35 @@ impl Player {
             .await;
     }
 
+    /// Sends a resource pack, which the client applies on top of the ones it already has.
+    /// Use [`crate::server::resource_pack::ResourcePackManager::host`] to serve a pack from the
+    /// built-in resource pack host.
+    ///
+    /// Bedrock clients only download packs while joining, so this does nothing for them.
+    pub async fn push_resource_pack(
+        &self,
+        pack: &ResourcePack,
+        force: bool,
+        prompt_message: Option<TextComponent>,
+    ) {
+        self.client
+            .enqueue_packet(&CAddResourcePack::new(
+                &pack.uuid,
+                &pack.url,
+                &pack.sha1,
+                force,
+                prompt_message,
+            ))
+            .await;
+    }
+
+    /// Removes a resource pack sent before, or every pack if `uuid` is [`None`]
+    pub async fn pop_resource_pack(&self, uuid: Option<uuid::Uuid>) {
+        self.client
+            .enqueue_packet(&CRemoveResourcePack::new(uuid))
+            .await;
+    }
+
     // TODO Abstract the chunk sending
     #[expect(clippy::too_many_lines)]
     pub async fn tick(self: &Arc<Self>, server: &Server) {
@@ -912,7 +959,7 @@ impl Player {
                         // log::debug!("send chunk {:?}", chunk.position);
                         // TODO: Can we check if we still need to send the chunk? Like if it's a fast moving
                         // player or something.
-                        java_client.send_packet_now(&CChunkData(&chunk)).await;
+                        java_client.send_chunk(&chunk).await;
                     }
                     java_client
                         .send_packet_now(&CChunkBatchEnd::new(chunk_count as u16))
@@ -922,13 +969,7 @@ impl Player {
                     for chunk in chunk_of_chunks {
                         let chunk = chunk.read().await;
 
-                        bedrock_client
-                            .send_game_packet(&CLevelChunk {
-                                dimension: 0,
-                                cache_enabled: false,
-                                chunk: &chunk,
-                            })
-                            .await;
+                        bedrock_client.send_chunk(&chunk).await;
                     }
                 }
             }
@@ -966,6 +1007,23 @@ impl Player {
 
         self.last_attacked_ticks.fetch_add(1, Ordering::Relaxed);
 
+        if self
+            .movement
+            .tick(self, &server.advanced_config.movement)
+            .await
+        {
+            log::warn!(
+                "{} was kicked for floating too long!",
+                self.gameprofile.name
+            );
+            self.kick(
+                DisconnectReason::Kicked,
+                TextComponent::translate("multiplayer.disconnect.flying", []),
+            )
+            .await;
+            return;
+        }
+
         self.living_entity.tick(self.clone(), server).await;
         self.hunger_manager.tick(self).await;
 
@@ -1376,6 +1434,42 @@ impl Player {
         }
     }
 
+    pub fn entity_interaction_range(&self) -> f64 {
+        if self.gamemode.load() == GameMode::Creative {
+            5.0
+        } else {
+            3.0
+        }
+    }
+
+    /// Whether the player can reach the block, allowing for the configured leniency or vanilla's
+    /// if movement validation is disabled
+    pub fn can_reach_block(&self, config: &MovementConfig, position: &BlockPos) -> bool {
+        let buffer = if config.enabled {
+            config.block_reach_buffer
+        } else {
+            DEFAULT_BLOCK_REACH_BUFFER
+        };
+        self.can_interact_with_block_at(position, buffer)
+    }
+
+    /// Whether the player can reach the entity, allowing for the configured leniency or vanilla's
+    /// if movement validation is disabled
+    pub fn can_reach_entity(&self, config: &MovementConfig, entity: &Entity) -> bool {
+        let buffer = if config.enabled {
+            config.entity_reach_buffer
+        } else {
+            DEFAULT_ENTITY_REACH_BUFFER
+        };
+        let range = self.entity_interaction_range() + buffer;
+        let eye_pos = self.living_entity.entity.pos.load().add_raw(
+            0.0,
+            f64::from(self.living_entity.entity.standing_eye_height),
+            0.0,
+        );
+        entity.bounding_box.load().squared_magnitude(eye_pos) < range * range
+    }
+
     pub fn can_interact_with_block_at(&self, position: &BlockPos, additional_range: f64) -> bool {
         let d = self.block_interaction_range() + additional_range;
         let box_pos = BoundingBox::from_block(position);
@@ -1392,6 +1486,67 @@ impl Player {
         self.client.kick(reason, message).await;
     }
 
+    /// Sends the player to another server. Java clients keep their cookies on the way
+    pub async fn transfer(&self, host: &str, port: u16) {
+        log::debug!("Transferring {} to {host}:{port}", self.gameprofile.name);
+        match &self.client {
+            ClientPlatform::Java(java) => java.transfer(host, port).await,
+            ClientPlatform::Bedrock(bedrock) => {
+                bedrock
+                    .send_game_packet(&CTransfer::new(host.to_string(), port))
+                    .await;
+            }
+        }
+    }
+
+    /// Whether the player was transferred here from another server
+    pub fn was_transferred(&self) -> bool {
+        match &self.client {
+            ClientPlatform::Java(java) => java.transferred.load(Ordering::Relaxed),
+            ClientPlatform::Bedrock(_) => false,
+        }
+    }
+
+    /// Stores a cookie of at most 5 kiB on the player's client, which keeps it across transfers.
+    /// Only Java clients support cookies
+    pub async fn store_cookie(&self, key: &ResourceLocation, payload: &[u8]) {
+        if let ClientPlatform::Java(java) = &self.client {
+            java.store_cookie(key, payload).await;
+        }
+    }
+
+    /// Asks the player's client for a cookie, see [`crate::net::java::JavaClient::request_cookie`]
+    pub async fn request_cookie(&self, key: ResourceLocation) -> Option<Box<[u8]>> {
+        match &self.client {
+            ClientPlatform::Java(java) => java.request_cookie(key).await,
+            ClientPlatform::Bedrock(_) => None,
+        }
+    }
+
+    /// A cookie the client sent while logging in. Only the keys in `Server::login_cookies`
+    /// are requested
+    pub async fn login_cookie(&self, key: &ResourceLocation) -> Option<Box<[u8]>> {
+        match &self.client {
+            ClientPlatform::Java(java) => java.login_cookie(key).await,
+            ClientPlatform::Bedrock(_) => None,
+        }
+    }
+
+    /// Sends a custom payload to the player's client, Bedrock clients have no plugin channels
+    pub async fn send_custom_payload(&self, channel: &ResourceLocation, data: &[u8]) {
+        if let ClientPlatform::Java(java) = &self.client {
+            java.send_custom_payload(channel, data).await;
+        }
+    }
+
+    /// Whether the player's client registered the plugin channel
+    pub async fn listens_on_channel(&self, channel: &ResourceLocation) -> bool {
+        match &self.client {
+            ClientPlatform::Java(java) => java.listens_on(channel).await,
+            ClientPlatform::Bedrock(_) => false,
+        }
+    }
+
     pub fn can_food_heal(&self) -> bool {
         let health = self.living_entity.health.load();
         let max_health = 20.0; // TODO
@@ -1639,10 +1794,31 @@ impl Player {
         self.world().spawn_entity(item_entity).await;
     }
 
-    pub async fn drop_held_item(&self, drop_stack: bool) {
-        // should be locked first otherwise cause deadlock in tick() (this thread lock stack, that thread lock screen_handler)
-
+    pub async fn drop_held_item(self: &Arc<Self>, drop_stack: bool) {
         let binding = self.inventory.held_item();
+        let held = binding.lock().await.clone();
+        if held.is_empty() {
+            return;
+        }
+        let drop_amount = if drop_stack { held.item_count } else { 1 };
+
+        send_cancellable! {{
+            PlayerDropItemEvent::new(self.clone(), held.copy_with_count(drop_amount));
+
+            'cancelled: {
+                // The client already took the item out of the hand, give it back
+                self.current_screen_handler
+                    .lock()
+                    .await
+                    .lock()
+                    .await
+                    .update_to_client()
+                    .await;
+                return;
+            }
+        }}
+
+        // should be locked first otherwise cause deadlock in tick() (this thread lock stack, that thread lock screen_handler)
         let mut item_stack = binding.lock().await;
 
         if !item_stack.is_empty() {
@@ -1901,6 +2077,10 @@ impl Player {
     }
 
     pub async fn on_handled_screen_closed(&self) {
+        if let Some(player) = self.world().get_player_by_id(self.entity_id()).await {
+            PLUGIN_MANAGER.fire(InventoryCloseEvent::new(player)).await;
+        }
+
         self.current_screen_handler
             .lock()
             .await
@@ -1941,6 +2121,13 @@ impl Player {
         &self,
         screen_handler_factory: &dyn ScreenHandlerFactory,
     ) -> Option<u8> {
+        if let Some(player) = self.world().get_player_by_id(self.entity_id()).await {
+            let event = InventoryOpenEvent::new(player, screen_handler_factory.get_display_name());
+            if PLUGIN_MANAGER.fire(event).await.cancelled {
+                return None;
+            }
+        }
+
         if !self
             .current_screen_handler
             .lock()
@@ -1985,7 +2172,52 @@ impl Player {
         }
     }
 
-    pub async fn on_slot_click(&self, packet: SClickSlot) {
+    pub async fn on_slot_click(self: &Arc<Self>, packet: SClickSlot) {
+        // Plugins may use the screen themselves, so it isn't locked while their events fire
+        let crafted = {
+            let screen_handler = self.current_screen_handler.lock().await.clone();
+            let screen_handler = screen_handler.lock().await;
+            if i32::from(screen_handler.get_behaviour().sync_id) != packet.sync_id.0 {
+                return;
+            }
+            let handler = screen_handler.as_any();
+            let is_crafting =
+                handler.is::<CraftingTableScreenHandler>() || handler.is::<PlayerScreenHandler>();
+            match screen_handler.get_behaviour().slots.first() {
+                // Both handlers put the crafting result into the first slot
+                Some(result) if is_crafting && packet.slot == 0 => {
+                    Some(result.get_cloned_stack().await)
+                }
+                _ => None,
+            }
+        };
+
+        let event = InventoryClickEvent::new(
+            self.clone(),
+            packet.slot,
+            packet.button,
+            packet.mode.clone(),
+        );
+        let mut cancelled = PLUGIN_MANAGER.fire(event).await.cancelled;
+        if !cancelled
+            && let Some(result) = crafted
+            && !result.is_empty()
+        {
+            let event = CraftItemEvent::new(self.clone(), result);
+            cancelled = PLUGIN_MANAGER.fire(event).await.cancelled;
+        }
+        if cancelled {
+            // The client already applied the click, show it the real contents again
+            self.current_screen_handler
+                .lock()
+                .await
+                .lock()
+                .await
+                .update_to_client()
+                .await;
+            return;
+        }
+
         let screen_handler = self.current_screen_handler.lock().await;
         let mut screen_handler = screen_handler.lock().await;
         let behaviour = screen_handler.get_behaviour();
@@ -2000,7 +2232,7 @@ impl Player {
             return;
         }
 
-        if !screen_handler.can_use(self) {
+        if !screen_handler.can_use(self.as_ref()) {
             warn!(
                 "Player {} interacted with invalid menu {:?}",
                 self.gameprofile.name,
@@ -2029,7 +2261,7 @@ impl Player {
                 i32::from(slot),
                 i32::from(packet.button),
                 packet.mode.clone(),
-                self,
+                self.as_ref(),
             )
             .await;
 
@@ -2050,9 +2282,15 @@ impl Player {
 
     /// Check if the player has a specific permission
     pub async fn has_permission(&self, node: &str) -> bool {
+        let world = self.world().dimension_type.resource_location().to_string();
         let perm_manager = PERMISSION_MANAGER.read().await;
         perm_manager
-            .has_permission(&self.gameprofile.id, node, self.permission_lvl.load())
+            .has_permission(
+                &self.gameprofile.id,
+                node,
+                self.permission_lvl.load(),
+                Some(&world),
+            )
             .await
     }
 
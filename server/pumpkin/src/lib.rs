@@ -5,8 +5,13 @@ use crate::logging::ReadlineLogWrapper;
 use crate::net::DisconnectReason;
 use crate::net::bedrock::BedrockClient;
 use crate::net::java::JavaClient;
-use crate::net::{lan_broadcast::LANBroadcast, query, rcon::RCONServer};
-use crate::server::{Server, ticker::Ticker};
+use crate::net::java::legacy_ping::handle_legacy_ping;
+use crate::net::{
+    lan_broadcast::LANBroadcast, metrics_host::start_metrics_host, query, rcon::RCONServer,
+    resource_pack_host::start_resource_pack_host,
+};
+use crate::server::{Server, pregen, ticker::Ticker};
+use data::LoadJSONConfiguration;
 use log::{Level, LevelFilter};
 use net::authentication::fetch_mojang_public_keys;
 use plugin::PluginManager;
@@ -14,7 +19,7 @@ use plugin::server::server_command::ServerCommandEvent;
 use pumpkin_config::{AdvancedConfiguration, BasicConfiguration};
 use pumpkin_macros::send_cancellable;
 use pumpkin_protocol::ConnectionState::Play;
-use pumpkin_util::permission::{PermissionManager, PermissionRegistry};
+use pumpkin_util::permission::{PermissionManager, PermissionRegistry, PermissionStorage};
 use pumpkin_util::text::TextComponent;
 #[cfg(feature = "console")]
 use rustyline_async::{Readline, ReadlineEvent};
@@ -52,9 +57,9 @@ pub static PERMISSION_REGISTRY: LazyLock<Arc<RwLock<PermissionRegistry>>> =
     LazyLock::new(|| Arc::new(RwLock::new(PermissionRegistry::new())));
 
 pub static PERMISSION_MANAGER: LazyLock<Arc<RwLock<PermissionManager>>> = LazyLock::new(|| {
-    Arc::new(RwLock::new(PermissionManager::new(
-        PERMISSION_REGISTRY.clone(),
-    )))
+    let mut manager = PermissionManager::new(PERMISSION_REGISTRY.clone());
+    manager.storage = PermissionStorage::load();
+    Arc::new(RwLock::new(manager))
 });
 
 pub type LoggerOption = Option<(ReadlineLogWrapper, LevelFilter)>;
@@ -240,6 +245,21 @@ impl PumpkinServer {
             });
         }
 
+        if let Some(host) = server.resource_packs.host_config() {
+            if host.public_url.is_empty() && host.address.ip().is_loopback() {
+                log::warn!(
+                    "Resource packs are hosted at {}, which only clients on this machine can download from. Set public_url to the address other clients reach the server at",
+                    host.address
+                );
+            }
+            server.spawn_task(start_resource_pack_host(server.clone(), host.address));
+        }
+
+        let metrics = &server.advanced_config.networking.metrics;
+        if metrics.enabled {
+            server.spawn_task(start_metrics_host(server.clone(), metrics.address));
+        }
+
         let mut tcp_listener = None;
 
         if server.basic_config.java_edition {
@@ -288,6 +308,14 @@ impl PumpkinServer {
             });
         };
 
+        // Chunk pre-generation
+        {
+            let pregen_server = server.clone();
+            server.spawn_task(async move {
+                pregen::run(&pregen_server).await;
+            });
+        };
+
         let mut udp_socket = None;
 
         if server.basic_config.bedrock_edition {
@@ -308,6 +336,16 @@ impl PumpkinServer {
     pub async fn init_plugins(&self) {
         PLUGIN_MANAGER.set_self_ref(PLUGIN_MANAGER.clone()).await;
         PLUGIN_MANAGER.set_server(self.server.clone()).await;
+        #[cfg(feature = "wasm-plugins")]
+        {
+            let wasm = &self.server.advanced_config.plugins.wasm;
+            if wasm.enabled {
+                match plugin::loader::wasm::WasmPluginLoader::new(wasm) {
+                    Ok(loader) => PLUGIN_MANAGER.add_loader(Arc::new(loader)).await,
+                    Err(err) => log::error!("Failed to set up WebAssembly plugins: {err:#}"),
+                }
+            }
+        }
         if let Err(err) = PLUGIN_MANAGER.load_plugins().await {
             log::error!("{err}");
         };
@@ -387,7 +425,14 @@ impl PumpkinServer {
             // Branch for TCP connections (Java Edition)
             tcp_result = resolve_some(self.tcp_listener.as_ref(), |listener| listener.accept()) => {
                 match tcp_result {
-                    Ok((connection, client_addr)) => {
+                    Ok((mut connection, client_addr)) => {
+                        let connection_guard = match self.server.connection_throttle.try_connect(client_addr.ip()) {
+                            Ok(guard) => guard,
+                            Err(reason) => {
+                                log::debug!("Refused Java Edition connection from {client_addr}: {reason:?}");
+                                return true;
+                            }
+                        };
                         if let Err(e) = connection.set_nodelay(true) {
                             log::warn!("Failed to set TCP_NODELAY: {e}");
                         }
@@ -402,16 +447,22 @@ impl PumpkinServer {
                         };
                         log::debug!("Accepted connection from Java Edition: {formatted_address} (id {client_id})");
 
-                        let mut java_client = JavaClient::new(connection, client_addr, client_id);
-                        java_client.start_outgoing_packet_task();
-                        let java_client = Arc::new(java_client);
-
+                        let traffic = self.server.connection_throttle.traffic_limiter();
                         let server_clone = self.server.clone();
 
                         tasks.spawn(async move {
+                            if handle_legacy_ping(&server_clone, &mut connection).await {
+                                return;
+                            }
+
+                            let mut java_client = JavaClient::new(connection, client_addr, client_id, traffic);
+                            java_client.start_outgoing_packet_task();
+                            let java_client = Arc::new(java_client);
+
                             java_client.process_packets(&server_clone).await;
                             java_client.close();
                             java_client.await_tasks().await;
+                            drop(connection_guard);
 
                             let player = java_client.player.lock().await;
                             if let Some(player) = player.as_ref() {
@@ -461,9 +512,17 @@ impl PumpkinServer {
                                         client.process_packet(&server, reader).await;
                                     });
                                 } else if let Ok(packet) = BedrockClient::is_connection_request(&mut Cursor::new(&udp_buf[4..len])) {
+                                    let connection_guard = match self.server.connection_throttle.try_connect(client_addr.ip()) {
+                                        Ok(guard) => guard,
+                                        Err(reason) => {
+                                            log::debug!("Refused Bedrock Edition connection from {client_addr}: {reason:?}");
+                                            return true;
+                                        }
+                                    };
                                     *master_client_id_counter += 1;
 
-                                    let mut platform = BedrockClient::new(self.udp_socket.clone().unwrap(), client_addr, be_clients);
+                                    let traffic = self.server.connection_throttle.traffic_limiter();
+                                    let mut platform = BedrockClient::new(self.udp_socket.clone().unwrap(), client_addr, be_clients, traffic, connection_guard);
                                     platform.handle_connection_request(packet).await;
                                     platform.start_outgoing_packet_task();
 
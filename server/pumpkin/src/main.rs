@@ -55,9 +55,10 @@ use tokio::sync::RwLock;
 
 use pumpkin::{LoggerOption, PumpkinServer, SHOULD_STOP, STOP_INTERRUPT, stop_server};
 
+use data::LoadJSONConfiguration;
 use pumpkin_config::{AdvancedConfiguration, BasicConfiguration, LoadConfiguration};
 use pumpkin_util::{
-    permission::{PermissionManager, PermissionRegistry},
+    permission::{PermissionManager, PermissionRegistry, PermissionStorage},
     text::{TextComponent, color::NamedColor},
 };
 use std::time::Instant;
@@ -83,9 +84,9 @@ pub static PERMISSION_REGISTRY: LazyLock<Arc<RwLock<PermissionRegistry>>> =
     LazyLock::new(|| Arc::new(RwLock::new(PermissionRegistry::new())));
 
 pub static PERMISSION_MANAGER: LazyLock<Arc<RwLock<PermissionManager>>> = LazyLock::new(|| {
-    Arc::new(RwLock::new(PermissionManager::new(
-        PERMISSION_REGISTRY.clone(),
-    )))
+    let mut manager = PermissionManager::new(PERMISSION_REGISTRY.clone());
+    manager.storage = PermissionStorage::load();
+    Arc::new(RwLock::new(manager))
 });
 
 pub static LOGGER_IMPL: LazyLock<Arc<OnceLock<LoggerOption>>> =
@@ -1,18 +1,26 @@
 use crate::net::authentication::MOJANG_BEDROCK_PUBLIC_KEY_BASE64;
 use crate::{
     net::{ClientPlatform, DisconnectReason, GameProfile, bedrock::BedrockClient},
-    server::Server,
+    server::{Server, resource_pack::BedrockResourcePack},
 };
 use pumpkin_config::networking::compression::CompressionInfo;
 use pumpkin_protocol::{
     bedrock::{
         client::{
-            network_settings::CNetworkSettings, play_status::CPlayStatus,
-            resource_pack_stack::CResourcePackStackPacket, resource_packs_info::CResourcePacksInfo,
+            network_settings::CNetworkSettings,
+            play_status::CPlayStatus,
+            resource_pack_chunk_data::CResourcePackChunkData,
+            resource_pack_data_info::{CResourcePackDataInfo, PACK_TYPE_RESOURCES},
+            resource_pack_stack::{CResourcePackStackPacket, StackEntry},
+            resource_packs_info::{CResourcePacksInfo, ResourcePack},
             start_game::Experiments,
         },
         frame_set::FrameSet,
-        server::{login::SLogin, request_network_settings::SRequestNetworkSettings},
+        server::{
+            login::SLogin, request_network_settings::SRequestNetworkSettings,
+            resource_pack_chunk_request::SResourcePackChunkRequest,
+            resource_pack_response::SResourcePackResponse,
+        },
     },
     codec::var_uint::VarUInt,
 };
@@ -54,6 +62,18 @@ impl BedrockClient {
     }
 
     pub async fn handle_login(self: &Arc<Self>, packet: SLogin, server: &Server) -> Option<()> {
+        if server
+            .connection_throttle
+            .try_login(self.address.ip())
+            .is_err()
+        {
+            self.kick(
+                DisconnectReason::Kicked,
+                "Too many login attempts, please wait before reconnecting".to_string(),
+            )
+            .await;
+            return None;
+        }
         match self.try_handle_login(packet, server).await {
             Ok(()) => Some(()),
             Err(error) => {
@@ -95,41 +115,69 @@ impl BedrockClient {
         //    String::from_utf8_unchecked(general_purpose::URL_SAFE_NO_PAD.decode(raw_token[1]).unwrap())
         //};
 
+        let packs = server.resource_packs.bedrock_packs();
         let mut frame_set = FrameSet::default();
 
         self.write_game_packet_to_set(&CPlayStatus::LoginSuccess, &mut frame_set)
             .await;
         self.write_game_packet_to_set(
             &CResourcePacksInfo::new(
-                false,
+                !packs.is_empty() && server.advanced_config.resource_pack.force,
                 false,
                 false,
                 false,
                 uuid::Uuid::default(),
                 String::new(),
-                Vec::new(),
-            ),
-            &mut frame_set,
-        )
-        .await;
-        self.write_game_packet_to_set(
-            &CResourcePackStackPacket::new(
-                false,
-                VarUInt(0),
-                VarUInt(0),
-                CURRENT_BEDROCK_MC_VERSION.to_string(),
-                Experiments {
-                    names_size: 0,
-                    experiments_ever_toggled: false,
-                },
-                false,
+                packs
+                    .iter()
+                    .map(|pack| {
+                        ResourcePack::new(pack.uuid, pack.version.clone(), pack.data.len() as u64)
+                    })
+                    .collect(),
             ),
             &mut frame_set,
         )
         .await;
 
+        if !packs.is_empty() {
+            // The client answers with the packs it needs first, the login continues once it is
+            // done loading them
+            self.send_frame_set(frame_set, 0x84).await;
+            *self.pending_login.lock().await = Some(profile);
+            return Ok(());
+        }
+
+        self.write_game_packet_to_set(&Self::resource_pack_stack(server, false), &mut frame_set)
+            .await;
         self.send_frame_set(frame_set, 0x84).await;
+        self.finish_login(server, profile).await;
+        Ok(())
+    }
 
+    fn resource_pack_stack(server: &Server, with_packs: bool) -> CResourcePackStackPacket {
+        let packs = if with_packs {
+            server.resource_packs.bedrock_packs()
+        } else {
+            &[]
+        };
+        CResourcePackStackPacket::new(
+            !packs.is_empty() && server.advanced_config.resource_pack.force,
+            VarUInt(0),
+            packs
+                .iter()
+                .map(|pack| StackEntry::new(pack.uuid.to_string(), pack.version.clone()))
+                .collect(),
+            CURRENT_BEDROCK_MC_VERSION.to_string(),
+            Experiments {
+                names_size: 0,
+                experiments_ever_toggled: false,
+            },
+            false,
+        )
+    }
+
+    async fn finish_login(self: &Arc<Self>, server: &Server, profile: GameProfile) {
+        let _ = self.player_uuid.set(profile.id);
         if let Some((player, world)) = server
             .add_player(ClientPlatform::Bedrock(self.clone()), profile, None)
             .await
@@ -139,7 +187,83 @@ impl BedrockClient {
                 .await;
             *self.player.lock().await = Some(player);
         }
+    }
 
-        Ok(())
+    pub async fn handle_resource_pack_response(
+        self: &Arc<Self>,
+        server: &Server,
+        packet: SResourcePackResponse,
+    ) {
+        match packet.response {
+            SResourcePackResponse::REFUSED => {
+                if server.advanced_config.resource_pack.force {
+                    self.kick(
+                        DisconnectReason::ResourcePackProblem,
+                        "You need to accept the resource packs to join.".to_string(),
+                    )
+                    .await;
+                } else {
+                    self.send_game_packet(&Self::resource_pack_stack(server, false))
+                        .await;
+                }
+            }
+            SResourcePackResponse::SEND_PACKS => {
+                for pack_id in &packet.pack_ids {
+                    let Some(pack) = server.resource_packs.bedrock_pack(pack_id) else {
+                        log::warn!("Bedrock client requested unknown resource pack {pack_id}");
+                        continue;
+                    };
+                    self.send_game_packet(&CResourcePackDataInfo {
+                        pack_id: pack.id(),
+                        max_chunk_size: BedrockResourcePack::CHUNK_SIZE,
+                        chunk_count: pack.chunk_count(),
+                        size: pack.data.len() as u64,
+                        hash: &pack.sha256,
+                        is_premium: false,
+                        pack_type: PACK_TYPE_RESOURCES,
+                    })
+                    .await;
+                }
+            }
+            SResourcePackResponse::HAVE_ALL_PACKS => {
+                self.send_game_packet(&Self::resource_pack_stack(server, true))
+                    .await;
+            }
+            SResourcePackResponse::COMPLETED => {
+                let profile = self.pending_login.lock().await.take();
+                if let Some(profile) = profile {
+                    self.finish_login(server, profile).await;
+                }
+            }
+            response => {
+                log::debug!("Bedrock client sent unknown resource pack response {response}");
+            }
+        }
+    }
+
+    pub async fn handle_resource_pack_chunk_request(
+        &self,
+        server: &Server,
+        packet: SResourcePackChunkRequest,
+    ) {
+        let chunk = server
+            .resource_packs
+            .bedrock_pack(&packet.pack_id)
+            .and_then(|pack| Some((pack, pack.chunk(packet.chunk_index)?)));
+        let Some((pack, (progress, data))) = chunk else {
+            log::warn!(
+                "Bedrock client requested unknown resource pack chunk {} of {}",
+                packet.chunk_index,
+                packet.pack_id
+            );
+            return;
+        };
+        self.send_game_packet(&CResourcePackChunkData {
+            pack_id: pack.id(),
+            chunk_index: packet.chunk_index,
+            progress,
+            data,
+        })
+        .await;
     }
 }
@@ -3,7 +3,7 @@ use std::{
     collections::HashMap,
     io::{Cursor, Error, Write},
     sync::{
-        Arc,
+        Arc, OnceLock,
         atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering},
     },
 };
@@ -11,15 +11,21 @@ use std::{
 use bytes::Bytes;
 use pumpkin_config::networking::compression::CompressionInfo;
 use pumpkin_protocol::{
-    BClientPacket, PacketDecodeError, RawPacket,
+    BClientPacket, ConnectionState, PacketDecodeError, RawPacket,
     bedrock::{
         MTU, RAKNET_ACK, RAKNET_GAME_PACKET, RAKNET_NACK, RakReliability, SubClient,
         ack::Ack,
-        client::disconnect_player::CDisconnectPlayer,
+        blob_cache::PendingBlobs,
+        client::{
+            client_cache_miss_response::CClientCacheMissResponse,
+            disconnect_player::CDisconnectPlayer,
+            level_chunk::{CLevelChunk, ChunkBlobs, LevelChunkData},
+        },
         frame_set::{Frame, FrameSet},
         packet_decoder::UDPNetworkDecoder,
         packet_encoder::UDPNetworkEncoder,
         server::{
+            client_cache_blob_status::SClientCacheBlobStatus,
             client_cache_status::SClientCacheStatus,
             command_request::SCommandRequest,
             container_close::SContainerClose,
@@ -36,14 +42,17 @@ use pumpkin_protocol::{
             },
             request_chunk_radius::SRequestChunkRadius,
             request_network_settings::SRequestNetworkSettings,
+            resource_pack_chunk_request::SResourcePackChunkRequest,
             resource_pack_response::SResourcePackResponse,
             text::SText,
         },
     },
     codec::u24,
     packet::Packet,
+    recording::Direction,
     serial::PacketRead,
 };
+use pumpkin_world::chunk::{ChunkData, packet_cache::ChunkPacketKind};
 use std::net::SocketAddr;
 use tokio::{
     net::UdpSocket,
@@ -57,13 +66,33 @@ pub mod connection;
 pub mod login;
 pub mod open_connection;
 pub mod unconnected;
-use crate::{entity::player::Player, net::DisconnectReason, server::Server};
+use crate::{
+    PLUGIN_MANAGER,
+    entity::player::Player,
+    net::{
+        DisconnectReason, GameProfile,
+        packet_recorder::PacketRecorder,
+        protection::{ConnectionGuard, TrafficLimiter},
+    },
+    server::{
+        Server,
+        metrics::{Edition, METRICS},
+    },
+};
+
+/// How many blobs we keep around for a client before falling back to sending chunks without the
+/// blob cache, so a client that never answers can't grow this forever
+const MAX_PENDING_BLOBS: usize = 4096;
+/// How many split packets a client may be sending us at the same time
+const MAX_OPEN_COMPOUNDS: usize = 16;
 
 pub struct BedrockClient {
     socket: Arc<UdpSocket>,
     /// The client's IP address.
     pub address: SocketAddr,
     pub player: Mutex<Option<Arc<Player>>>,
+    /// Set once the client starts playing, its packets are handed to packet listeners from then on
+    pub player_uuid: OnceLock<uuid::Uuid>,
     /// All Bedrock clients
     /// This list is used to remove the client if the connection gets closed
     pub be_clients: Arc<Mutex<HashMap<SocketAddr, Arc<Self>>>>,
@@ -93,6 +122,19 @@ pub struct BedrockClient {
 
     /// Store Fragments until the packet is complete
     compounds: Arc<Mutex<HashMap<u16, Vec<Option<Frame>>>>>,
+
+    /// Whether the client told us it supports the blob cache
+    blob_cache_enabled: AtomicBool,
+    /// Blobs we sent the ids of, but the client didn't tell us yet whether it has them
+    pending_blobs: Mutex<PendingBlobs>,
+    /// The profile of a client that is still loading resource packs before joining
+    pending_login: Mutex<Option<GameProfile>>,
+    /// How many packets and bytes the client may still send before being kicked
+    traffic: TrafficLimiter,
+    /// Counts the client towards its IP's connections until it is closed
+    connection_guard: Mutex<Option<ConnectionGuard>>,
+    /// Records the packets exchanged with the client while debugging it
+    pub packet_recorder: PacketRecorder,
     //input_sequence_number: AtomicU32,
 }
 
@@ -102,11 +144,14 @@ impl BedrockClient {
         socket: Arc<UdpSocket>,
         address: SocketAddr,
         be_clients: Arc<Mutex<HashMap<SocketAddr, Arc<Self>>>>,
+        traffic: TrafficLimiter,
+        connection_guard: ConnectionGuard,
     ) -> Self {
         let (send, recv) = tokio::sync::mpsc::channel(128);
         Self {
             socket,
             player: Mutex::new(None),
+            player_uuid: OnceLock::new(),
             address,
             be_clients,
             network_writer: Arc::new(Mutex::new(UDPNetworkEncoder::new())),
@@ -123,6 +168,12 @@ impl BedrockClient {
             compounds: Arc::new(Mutex::new(HashMap::new())),
             closed: Arc::new(AtomicBool::new(false)),
             close_interrupt: Arc::new(Notify::new()),
+            blob_cache_enabled: AtomicBool::new(false),
+            pending_blobs: Mutex::new(PendingBlobs::default()),
+            pending_login: Mutex::new(None),
+            traffic,
+            connection_guard: Mutex::new(Some(connection_guard)),
+            packet_recorder: PacketRecorder::default(),
             //input_sequence_number: AtomicU32::new(0),
         }
     }
@@ -149,6 +200,7 @@ impl BedrockClient {
                     break;
                 };
 
+                METRICS.add_sent(Edition::Bedrock, packet_data.len());
                 if let Err(err) = writer
                     .lock()
                     .await
@@ -170,6 +222,19 @@ impl BedrockClient {
     }
 
     pub async fn process_packet(self: &Arc<Self>, server: &Arc<Server>, packet: Cursor<Vec<u8>>) {
+        METRICS.add_received(Edition::Bedrock, packet.get_ref().len());
+        if let Err(violation) = self.traffic.record_packet(packet.get_ref().len()) {
+            log::warn!(
+                "Bedrock client {} exceeded its packet budget ({violation:?}), kicking",
+                self.address
+            );
+            self.kick(
+                DisconnectReason::Kicked,
+                "Kicked for exceeding packet rate limit".to_string(),
+            )
+            .await;
+            return;
+        }
         let packet = self.get_packet_payload(packet).await;
         if let Some(packet) = packet
             && let Err(error) = self.handle_packet_payload(server, packet).await
@@ -214,6 +279,21 @@ impl BedrockClient {
         }
     }
 
+    /// Hands a packet exchanged while playing to the packet listeners of plugins, `None` if one of
+    /// them cancelled it
+    async fn intercept(&self, direction: Direction, id: i32, payload: Bytes) -> Option<Bytes> {
+        let Some(player) = self.player_uuid.get() else {
+            return Some(payload);
+        };
+        let listeners = PLUGIN_MANAGER.packet_listeners();
+        if !listeners.is_intercepted(Edition::Bedrock, direction, id) {
+            return Some(payload);
+        }
+        listeners
+            .intercept(*player, Edition::Bedrock, direction, id, payload)
+            .await
+    }
+
     pub fn write_raw_packet<P: BClientPacket>(
         packet: &P,
         mut write: impl Write,
@@ -229,6 +309,19 @@ impl BedrockClient {
     ) -> Result<(), Error> {
         let mut packet_payload = Vec::new();
         packet.write_packet(&mut packet_payload)?;
+        // Nothing is written if a plugin cancelled the packet
+        let Some(packet_payload) = self
+            .intercept(Direction::Clientbound, P::PACKET_ID, packet_payload.into())
+            .await
+        else {
+            return Ok(());
+        };
+        self.packet_recorder.record(
+            Direction::Clientbound,
+            ConnectionState::Play,
+            P::PACKET_ID,
+            &packet_payload,
+        );
 
         // TODO
         self.network_writer
@@ -238,7 +331,7 @@ impl BedrockClient {
                 P::PACKET_ID as u16,
                 SubClient::Main,
                 SubClient::Main,
-                packet_payload.into(),
+                packet_payload,
                 write,
             )
             .await
@@ -261,10 +354,128 @@ impl BedrockClient {
         self.write_game_packet(packet, &mut packet_buf)
             .await
             .unwrap();
+        if packet_buf.is_empty() {
+            return;
+        }
         self.send_framed_packet_data(packet_buf, RakReliability::Unreliable)
             .await;
     }
 
+    /// Sends a chunk. With the blob cache only the blob ids are sent and the client asks for the
+    /// ones it doesn't have, otherwise the payload encoded for other players is reused if the
+    /// chunk hasn't changed since
+    pub async fn send_chunk(&self, chunk: &ChunkData) {
+        if self.blob_cache_enabled.load(Ordering::Relaxed) && self.send_cached_chunk(chunk).await {
+            return;
+        }
+
+        let payload =
+            chunk
+                .packet_cache
+                .get_or_encode(ChunkPacketKind::Bedrock, chunk.generation, || {
+                    let mut payload = Vec::new();
+                    CLevelChunk {
+                        x: chunk.x,
+                        z: chunk.z,
+                        dimension: 0,
+                        data: LevelChunkData::Blobs(&ChunkBlobs::new(chunk)?),
+                    }
+                    .write_packet(&mut payload)?;
+                    Ok::<_, Error>(Bytes::from(payload))
+                });
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(err) => {
+                log::error!("Failed to encode chunk {} {}: {err}", chunk.x, chunk.z);
+                return;
+            }
+        };
+        let Some(payload) = self
+            .intercept(Direction::Clientbound, CLevelChunk::PACKET_ID, payload)
+            .await
+        else {
+            return;
+        };
+
+        self.packet_recorder.record(
+            Direction::Clientbound,
+            ConnectionState::Play,
+            CLevelChunk::PACKET_ID,
+            &payload,
+        );
+        let mut packet_buf = Vec::new();
+        self.network_writer
+            .lock()
+            .await
+            .write_game_packet(
+                CLevelChunk::PACKET_ID as u16,
+                SubClient::Main,
+                SubClient::Main,
+                payload,
+                &mut packet_buf,
+            )
+            .await
+            .unwrap();
+        self.send_framed_packet_data(packet_buf, RakReliability::Unreliable)
+            .await;
+    }
+
+    /// Sends the blob ids of a chunk and remembers the blobs until the client asks for them.
+    /// Returns false if the chunk has to be sent without the blob cache instead
+    async fn send_cached_chunk(&self, chunk: &ChunkData) -> bool {
+        let blobs = chunk
+            .packet_cache
+            .get_or_encode_blobs(chunk.generation, || {
+                let blobs = ChunkBlobs::new(chunk)?;
+                Ok::<_, Error>(
+                    blobs
+                        .blobs()
+                        .map(|(hash, blob)| (hash, Bytes::copy_from_slice(blob)))
+                        .collect(),
+                )
+            });
+        let blobs = match blobs {
+            Ok(blobs) => blobs,
+            Err(err) => {
+                log::error!("Failed to encode chunk {} {}: {err}", chunk.x, chunk.z);
+                return false;
+            }
+        };
+
+        {
+            let mut pending_blobs = self.pending_blobs.lock().await;
+            if pending_blobs.len() + blobs.len() > MAX_PENDING_BLOBS {
+                return false;
+            }
+            for (hash, blob) in blobs.iter() {
+                pending_blobs.add(*hash, blob);
+            }
+        }
+
+        let hashes = blobs.iter().map(|(hash, _)| *hash).collect::<Vec<_>>();
+        self.send_game_packet(&CLevelChunk {
+            x: chunk.x,
+            z: chunk.z,
+            dimension: 0,
+            data: LevelChunkData::BlobIds(&hashes),
+        })
+        .await;
+        true
+    }
+
+    /// The client tells us which blobs it already had and which ones we have to send
+    async fn handle_client_cache_blob_status(&self, status: SClientCacheBlobStatus) {
+        let missing = self
+            .pending_blobs
+            .lock()
+            .await
+            .resolve(&status.hits, &status.missing);
+        if !missing.is_empty() {
+            self.send_game_packet(&CClientCacheMissResponse { blobs: missing })
+                .await;
+        }
+    }
+
     pub async fn write_game_packet_to_set<P: BClientPacket>(
         &self,
         packet: &P,
@@ -272,6 +483,9 @@ impl BedrockClient {
     ) {
         let mut payload = Vec::new();
         self.write_game_packet(packet, &mut payload).await.unwrap();
+        if payload.is_empty() {
+            return;
+        }
 
         frame_set.frames.push(Frame::new_unreliable(payload));
     }
@@ -352,6 +566,7 @@ impl BedrockClient {
         frame_set.sequence = u24(self.output_sequence_number.fetch_add(1, Ordering::Relaxed));
         let mut frame_set_buf = Vec::new();
         frame_set.write_packet_data(&mut frame_set_buf, id).unwrap();
+        METRICS.add_sent(Edition::Bedrock, frame_set_buf.len());
 
         // I dont know if thats the right place to make encryption & decoding
         if let Err(err) = self
@@ -377,6 +592,8 @@ impl BedrockClient {
         self.tasks.close();
         self.tasks.wait().await;
         self.be_clients.lock().await.remove(&self.address);
+        self.connection_guard.lock().await.take();
+        self.packet_recorder.stop();
 
         if let Some(player) = self.player.lock().await.as_ref() {
             player.remove().await;
@@ -386,6 +603,7 @@ impl BedrockClient {
     pub async fn send_ack(&self, ack: &Ack) {
         let mut packet_buf = Vec::new();
         ack.write(&mut packet_buf).unwrap();
+        METRICS.add_sent(Edition::Bedrock, packet_buf.len());
 
         if let Err(err) = self
             .network_writer
@@ -442,7 +660,21 @@ impl BedrockClient {
         if frame.split_size > 0 {
             let fragment_index = frame.split_index as usize;
             let compound_id = frame.split_id;
+            if fragment_index >= frame.split_size as usize {
+                return Err(Error::other("Fragment index is out of bounds"));
+            }
+            // Every fragment carries at least a byte, so this bounds what we buffer
+            if self
+                .traffic
+                .max_packet_size()
+                .is_some_and(|max| frame.split_size as usize > max)
+            {
+                return Err(Error::other("Compound packet is too large"));
+            }
             let mut compounds = self.compounds.lock().await;
+            if compounds.len() >= MAX_OPEN_COMPOUNDS && !compounds.contains_key(&compound_id) {
+                return Err(Error::other("Too many incomplete compound packets"));
+            }
 
             let entry = compounds.entry(compound_id).or_insert_with(|| {
                 let mut vec = Vec::with_capacity(frame.split_size as usize);
@@ -497,10 +729,37 @@ impl BedrockClient {
             SLogin::PACKET_ID => {
                 self.handle_login(SLogin::read(payload)?, server).await;
             }
-            SClientCacheStatus::PACKET_ID | SResourcePackResponse::PACKET_ID => {
-                // TODO
+            SClientCacheStatus::PACKET_ID => {
+                let status = SClientCacheStatus::read(payload)?;
+                self.blob_cache_enabled
+                    .store(status.cache_supported, Ordering::Relaxed);
+            }
+            SClientCacheBlobStatus::PACKET_ID => {
+                self.handle_client_cache_blob_status(SClientCacheBlobStatus::read(payload)?)
+                    .await;
+            }
+            SResourcePackResponse::PACKET_ID => {
+                self.handle_resource_pack_response(server, SResourcePackResponse::read(payload)?)
+                    .await;
+            }
+            SResourcePackChunkRequest::PACKET_ID => {
+                self.handle_resource_pack_chunk_request(
+                    server,
+                    SResourcePackChunkRequest::read(payload)?,
+                )
+                .await;
             }
             _ => {
+                let Some(payload) = self
+                    .intercept(Direction::Serverbound, packet.id, packet.payload)
+                    .await
+                else {
+                    return Ok(());
+                };
+                let packet = RawPacket {
+                    id: packet.id,
+                    payload,
+                };
                 self.handle_play_packet(self.player.lock().await.as_ref().unwrap(), server, packet)
                     .await;
             }
@@ -585,6 +844,12 @@ impl BedrockClient {
                     .await
                     .map_err(|e| Error::other(e.to_string()))?;
 
+                self.packet_recorder.record(
+                    Direction::Serverbound,
+                    ConnectionState::Play,
+                    game_packet.id,
+                    &game_packet.payload,
+                );
                 self.handle_game_packet(server, game_packet).await?;
             }
             _ => {
@@ -603,6 +868,9 @@ impl BedrockClient {
     ) -> Result<(), Error> {
         match i32::from(packet_id) {
             SUnconnectedPing::PACKET_ID => {
+                if !server.connection_throttle.allow_ping(addr.ip()) {
+                    return Ok(());
+                }
                 Self::handle_unconnected_ping(
                     server,
                     SUnconnectedPing::read(payload)?,
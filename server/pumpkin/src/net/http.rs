@@ -0,0 +1,57 @@
+//! The bits of HTTP/1.1 our small HTTP listeners share
+
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+/// Requests are a single line plus a few headers, anything longer is not a client we serve
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+pub(super) const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reads the request line and headers, we never expect a body
+pub(super) async fn read_request(connection: &mut TcpStream) -> std::io::Result<String> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.ends_with(b"\r\n\r\n") {
+        let read = connection.read(&mut buf).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_SIZE {
+            return Err(std::io::Error::other("Incomplete HTTP request"));
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+    Ok(String::from_utf8_lossy(&request).into_owned())
+}
+
+/// The method and path of a request
+pub(super) fn request_target(request: &str) -> (&str, Option<&str>) {
+    let mut parts = request.lines().next().unwrap_or_default().split(' ');
+    (parts.next().unwrap_or_default(), parts.next())
+}
+
+/// Responds without a body
+pub(super) async fn respond(connection: &mut TcpStream, status: &str) -> std::io::Result<()> {
+    let header = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    connection.write_all(header.as_bytes()).await?;
+    connection.shutdown().await
+}
+
+/// Responds with `body`, which is left out for `HEAD` requests
+pub(super) async fn respond_ok(
+    connection: &mut TcpStream,
+    method: &str,
+    content_type: &str,
+    body: &[u8],
+) -> std::io::Result<()> {
+    let header = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    connection.write_all(header.as_bytes()).await?;
+    if method == "GET" {
+        connection.write_all(body).await?;
+    }
+    connection.shutdown().await
+}
@@ -3,9 +3,9 @@ use std::{num::NonZeroU8, sync::Arc};
 use crate::{
     entity::player::ChatMode,
     net::{ClientPlatform, PlayerConfig, can_not_join, java::JavaClient},
+    plugin::channels::MessageSender,
     server::Server,
 };
-use core::str;
 use pumpkin_protocol::{
     ConnectionState,
     java::{
@@ -53,110 +53,130 @@ impl JavaClient {
         }
     }
 
-    pub async fn handle_plugin_message(&self, plugin_message: SPluginMessage) {
-        log::debug!("Handling plugin message");
-        if plugin_message
-            .channel
-            .to_string()
-            .starts_with("minecraft:brand")
-        {
-            log::debug!("Got a client brand");
-            match str::from_utf8(&plugin_message.data) {
-                Ok(brand) => *self.brand.lock().await = Some(brand.to_string()),
-                Err(e) => self.kick(TextComponent::text(e.to_string())).await,
-            }
-        }
+    pub async fn handle_plugin_message(&self, server: &Server, plugin_message: SPluginMessage) {
+        let Ok(channel) = plugin_message.channel.to_string().parse() else {
+            log::debug!(
+                "Client {} sent a plugin message on invalid channel {}",
+                self.id,
+                plugin_message.channel
+            );
+            return;
+        };
+        self.receive_custom_payload(
+            server,
+            MessageSender::Configuring(self),
+            channel,
+            &plugin_message.data,
+        )
+        .await;
     }
 
-    pub async fn handle_resource_pack_response(
-        &self,
-        server: &Server,
-        packet: SConfigResourcePack,
-    ) {
-        let resource_config = &server.advanced_config.resource_pack;
-        if resource_config.enabled {
-            let expected_uuid =
-                uuid::Uuid::new_v3(&uuid::Uuid::NAMESPACE_DNS, resource_config.url.as_bytes());
+    pub async fn handle_resource_pack_response(&self, packet: SConfigResourcePack) {
+        let mut pending_resource_packs = self.pending_resource_packs.lock().await;
+        if !pending_resource_packs.contains(&packet.uuid) {
+            log::warn!(
+                "Client {} returned a response for a resource pack we did not set!",
+                self.id
+            );
+            return;
+        }
 
-            if packet.uuid == expected_uuid {
-                match packet.response_result() {
-                    ResourcePackResponseResult::DownloadSuccess => {
-                        log::trace!(
-                            "Client {} successfully downloaded the resource pack",
-                            self.id
-                        );
-                    }
-                    ResourcePackResponseResult::DownloadFail => {
-                        log::warn!(
-                            "Client {} failed to downloaded the resource pack. Is it available on the internet?",
-                            self.id
-                        );
-                    }
-                    ResourcePackResponseResult::Downloaded => {
-                        log::trace!("Client {} already has the resource pack", self.id);
-                    }
-                    ResourcePackResponseResult::Accepted => {
-                        log::trace!("Client {} accepted the resource pack", self.id);
+        if !self.log_resource_pack_response(packet.response_result()) {
+            // Wait for the next response update
+            return;
+        }
+        pending_resource_packs.remove(&packet.uuid);
+        if pending_resource_packs.is_empty() {
+            drop(pending_resource_packs);
+            self.send_known_packs().await;
+        }
+    }
 
-                        // Return here to wait for the next response update
-                        return;
-                    }
-                    ResourcePackResponseResult::Declined => {
-                        log::trace!("Client {} declined the resource pack", self.id);
-                    }
-                    ResourcePackResponseResult::InvalidUrl => {
-                        log::warn!(
-                            "Client {} reported that the resource pack URL is invalid!",
-                            self.id
-                        );
-                    }
-                    ResourcePackResponseResult::ReloadFailed => {
-                        log::trace!("Client {} failed to reload the resource pack", self.id);
-                    }
-                    ResourcePackResponseResult::Discarded => {
-                        log::trace!("Client {} discarded the resource pack", self.id);
-                    }
-                    ResourcePackResponseResult::Unknown(result) => {
-                        log::warn!(
-                            "Client {} responded with a bad result: {}!",
-                            self.id,
-                            result
-                        );
-                    }
-                }
-            } else {
+    /// Logs a resource pack response, returns whether the client is done with the pack
+    pub fn log_resource_pack_response(&self, result: ResourcePackResponseResult) -> bool {
+        match result {
+            ResourcePackResponseResult::DownloadSuccess => {
+                log::trace!(
+                    "Client {} successfully downloaded the resource pack",
+                    self.id
+                );
+            }
+            ResourcePackResponseResult::DownloadFail => {
                 log::warn!(
-                    "Client {} returned a response for a resource pack we did not set!",
+                    "Client {} failed to downloaded the resource pack. Is it available on the internet?",
                     self.id
                 );
             }
-        } else {
-            log::warn!(
-                "Client {} returned a response for a resource pack that was not enabled!",
-                self.id
-            );
+            ResourcePackResponseResult::Downloaded => {
+                log::trace!("Client {} downloaded the resource pack", self.id);
+                return false;
+            }
+            ResourcePackResponseResult::Accepted => {
+                log::trace!("Client {} accepted the resource pack", self.id);
+                return false;
+            }
+            ResourcePackResponseResult::Declined => {
+                log::trace!("Client {} declined the resource pack", self.id);
+            }
+            ResourcePackResponseResult::InvalidUrl => {
+                log::warn!(
+                    "Client {} reported that the resource pack URL is invalid!",
+                    self.id
+                );
+            }
+            ResourcePackResponseResult::ReloadFailed => {
+                log::trace!("Client {} failed to reload the resource pack", self.id);
+            }
+            ResourcePackResponseResult::Discarded => {
+                log::trace!("Client {} discarded the resource pack", self.id);
+            }
+            ResourcePackResponseResult::Unknown(result) => {
+                log::warn!(
+                    "Client {} responded with a bad result: {}!",
+                    self.id,
+                    result
+                );
+            }
         }
-        self.send_known_packs().await;
+        true
     }
 
-    pub fn handle_config_cookie_response(&self, packet: &SConfigCookieResponse) {
-        // TODO: allow plugins to access this
-        log::debug!(
-            "Received cookie_response[config]: key: \"{}\", has_payload: \"{}\", payload_length: \"{:?}\"",
-            packet.key,
-            packet.has_payload,
-            packet.payload.as_ref().map(|p| p.len()),
-        );
+    pub async fn handle_config_cookie_response(
+        &self,
+        server: &Server,
+        packet: SConfigCookieResponse,
+    ) {
+        self.receive_cookie(server, packet.key, packet.payload)
+            .await;
     }
 
     pub async fn handle_known_packs(&self, server: &Server, _config_acknowledged: SKnownPacks) {
         log::debug!("Handling known packs");
+        let translation = self.translation.get();
         for registry in &server.cached_registry {
-            self.send_packet_now(&CRegistryData::new(
-                &registry.registry_id,
-                &registry.registry_entries,
-            ))
-            .await;
+            // Older clients get only the entries they know, in their order
+            let known = translation
+                .and_then(|mappings| mappings.registry_entries(&registry.registry_id.to_string()));
+            if let Some(known) = known {
+                let entries = known
+                    .iter()
+                    .filter_map(|name| {
+                        registry
+                            .registry_entries
+                            .iter()
+                            .find(|entry| entry.entry_id.to_string() == *name)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                self.send_packet_now(&CRegistryData::new(&registry.registry_id, &entries))
+                    .await;
+            } else {
+                self.send_packet_now(&CRegistryData::new(
+                    &registry.registry_id,
+                    &registry.registry_entries,
+                ))
+                .await;
+            }
         }
         self.send_packet_now(&CUpdateTags::new(&[
             pumpkin_data::tag::RegistryKey::Block,
@@ -190,6 +210,7 @@ impl JavaClient {
 
         let config = self.config.lock().await;
 
+        let _ = self.player_uuid.set(profile.id);
         if let Some((player, world)) = server
             .add_player(ClientPlatform::Java(self.clone()), profile, config.clone())
             .await
@@ -0,0 +1,117 @@
+//! Cookies are small payloads the client keeps for the server, even across transfers to
+//! other servers. Together with transfers they let servers hand players over without a proxy
+
+use std::time::Duration;
+
+use pumpkin_protocol::{
+    ConnectionState,
+    codec::var_int::VarInt,
+    java::client::{
+        login::CLoginCookieRequest,
+        play::{CPlayCookieRequest, CStoreCookie, CTransfer},
+    },
+};
+use pumpkin_util::resource_location::ResourceLocation;
+use tokio::sync::oneshot;
+
+use crate::{net::java::JavaClient, server::Server};
+
+/// The vanilla client does not store or send larger cookies
+pub const MAX_COOKIE_SIZE: usize = 5120;
+/// How long to wait for the client to answer a cookie request
+const COOKIE_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl JavaClient {
+    /// Asks the client for a cookie, returns `None` if it has none stored under `key` or did
+    /// not answer in time.
+    ///
+    /// The answer is read with the client's other packets, so awaiting this while handling one
+    /// of the client's packets (e.g. in a handler of an event fired by it) only times out.
+    /// Spawn a task instead
+    pub async fn request_cookie(&self, key: ResourceLocation) -> Option<Box<[u8]>> {
+        let (send, recv) = oneshot::channel();
+        self.pending_cookies
+            .lock()
+            .await
+            .entry(key.clone())
+            .or_default()
+            .push(send);
+
+        match self.connection_state.load() {
+            ConnectionState::Login => self.send_packet_now(&CLoginCookieRequest::new(&key)).await,
+            ConnectionState::Play => self.enqueue_packet(&CPlayCookieRequest::new(&key)).await,
+            _ => {
+                self.pending_cookies.lock().await.remove(&key);
+                return None;
+            }
+        }
+
+        tokio::time::timeout(COOKIE_TIMEOUT, recv)
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .flatten()
+    }
+
+    /// Stores a cookie on the client, which it keeps until it quits the game
+    pub async fn store_cookie(&self, key: &ResourceLocation, payload: &[u8]) {
+        if payload.len() > MAX_COOKIE_SIZE {
+            log::warn!(
+                "Not storing cookie {key} of {} bytes on client {}, the maximum is {MAX_COOKIE_SIZE}",
+                payload.len(),
+                self.id
+            );
+            return;
+        }
+        self.enqueue_packet(&CStoreCookie::new(key, payload)).await;
+    }
+
+    /// Sends the client to another server, it reconnects there with the transfer intent
+    pub async fn transfer(&self, host: &str, port: u16) {
+        self.enqueue_packet(&CTransfer::new(host, VarInt(port.into())))
+            .await;
+    }
+
+    /// A cookie the client sent while logging in, see [`Server::login_cookies`]
+    pub async fn login_cookie(&self, key: &ResourceLocation) -> Option<Box<[u8]>> {
+        self.login_cookies.lock().await.get(key).cloned()
+    }
+
+    /// Requests the cookies plugins want during login. The client answers before acknowledging
+    /// the login, so all of them are known by the time the player joins
+    pub(super) async fn request_login_cookies(&self, server: &Server) {
+        for key in server.login_cookies.read().await.iter() {
+            self.send_packet_now(&CLoginCookieRequest::new(key)).await;
+        }
+    }
+
+    /// Passes a cookie the client sent to whoever requested it
+    pub(super) async fn receive_cookie(
+        &self,
+        server: &Server,
+        key: ResourceLocation,
+        payload: Option<Box<[u8]>>,
+    ) {
+        log::debug!(
+            "Received cookie {key} from client {}: payload_length: {:?}",
+            self.id,
+            payload.as_ref().map(|payload| payload.len())
+        );
+        let requests = self.pending_cookies.lock().await.remove(&key);
+        match requests {
+            Some(requests) => {
+                for request in requests {
+                    let _ = request.send(payload.clone());
+                }
+            }
+            None if self.connection_state.load() == ConnectionState::Login
+                && server.login_cookies.read().await.contains(&key) =>
+            {
+                if let Some(payload) = payload {
+                    self.login_cookies.lock().await.insert(key, payload);
+                }
+            }
+            None => log::debug!("Client {} sent cookie {key} nobody asked for", self.id),
+        }
+    }
+}
@@ -1,30 +1,83 @@
-use pumpkin_data::packet::CURRENT_MC_PROTOCOL;
-use pumpkin_protocol::{ConnectionState, java::server::handshake::SHandShake};
+use std::{fs, path::Path, sync::atomic::Ordering};
+
+use pumpkin_config::networking::protocol::ProtocolConfig;
+use pumpkin_protocol::{
+    ConnectionState,
+    java::{
+        server::handshake::SHandShake,
+        translation::{SupportedVersions, UnsupportedVersion, VersionMappings},
+    },
+};
 use pumpkin_util::text::TextComponent;
 
 use pumpkin_world::CURRENT_MC_VERSION;
 
-use crate::net::java::JavaClient;
+use crate::{net::java::JavaClient, server::Server};
+
+/// Reads the mappings of every other protocol version from the configured mappings folder
+pub fn load_supported_versions(config: &ProtocolConfig, config_dir: &Path) -> SupportedVersions {
+    let mut versions = SupportedVersions::new(config.min_version, config.max_version);
+
+    let folder = config_dir.join(&config.mappings_folder);
+    let Ok(entries) = fs::read_dir(&folder) else {
+        return versions;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let mappings = fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|json| VersionMappings::from_json(&json).map_err(|err| err.to_string()));
+        match mappings {
+            Ok(mappings) => {
+                log::debug!(
+                    "Loaded protocol mappings for {} ({})",
+                    mappings.name,
+                    mappings.protocol
+                );
+                versions.add(mappings);
+            }
+            Err(err) => log::warn!("Failed to load protocol mappings {}: {err}", path.display()),
+        }
+    }
+
+    let names = versions.names();
+    if !names.is_empty() {
+        log::info!("Also accepting clients on {}", names.join(", "));
+    }
+    versions
+}
 
 impl JavaClient {
-    pub async fn handle_handshake(&self, handshake: SHandShake) {
+    pub async fn handle_handshake(&self, server: &Server, handshake: SHandShake) {
         let version = handshake.protocol_version.0;
         *self.server_address.lock().await = handshake.server_address;
 
         log::debug!("Handshake: next state is {:?}", &handshake.next_state);
-        self.connection_state.store(handshake.next_state);
+        if handshake.next_state == ConnectionState::Transfer {
+            // A transfer is a login, we just remember it for the plugins
+            self.transferred.store(true, Ordering::Relaxed);
+            self.connection_state.store(ConnectionState::Login);
+        } else {
+            self.connection_state.store(handshake.next_state);
+        }
         if self.connection_state.load() != ConnectionState::Status {
-            let protocol = version;
-            match protocol.cmp(&(CURRENT_MC_PROTOCOL as i32)) {
-                std::cmp::Ordering::Less => {
+            match server.supported_versions.get(version) {
+                Ok(None) => {}
+                Ok(Some(mappings)) => {
+                    log::debug!("Client {} joins on {}", self.id, mappings.name);
+                    let _ = self.translation.set(mappings);
+                }
+                Err(UnsupportedVersion::Outdated) => {
                     self.kick(TextComponent::translate(
                         "multiplayer.disconnect.outdated_client",
                         [TextComponent::text(CURRENT_MC_VERSION.to_string())],
                     ))
                     .await;
                 }
-                std::cmp::Ordering::Equal => {}
-                std::cmp::Ordering::Greater => {
+                Err(UnsupportedVersion::Incompatible) => {
                     self.kick(TextComponent::translate(
                         "multiplayer.disconnect.incompatible",
                         [TextComponent::text(CURRENT_MC_VERSION.to_string())],
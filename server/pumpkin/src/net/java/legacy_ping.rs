@@ -0,0 +1,124 @@
+//! The server list ping of clients before 1.7, which starts with `0xFE` instead of a framed
+//! handshake. Some monitoring tools and launchers still use it
+
+use std::time::Duration;
+
+use pumpkin_world::CURRENT_MC_VERSION;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::server::Server;
+
+const LEGACY_PING: u8 = 0xFE;
+const LEGACY_KICK: u8 = 0xFF;
+/// Clients since 1.4 follow the ping with a `0x01` payload
+const LEGACY_PING_PAYLOAD: u8 = 0x01;
+/// Tells old clients they can't join, like vanilla does
+const LEGACY_PROTOCOL: u32 = 127;
+/// 1.6 clients follow the payload with a `MC|PingHost` plugin message
+const PLUGIN_MESSAGE: u8 = 0xFA;
+/// Beta 1.8 to 1.3 only send `0xFE`, so we can't wait for more forever
+const PAYLOAD_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Answers the connection if it starts with a legacy ping, returns whether it did
+pub async fn handle_legacy_ping(server: &Server, connection: &mut TcpStream) -> bool {
+    let mut first = [0; 1];
+    if connection.peek(&mut first).await.ok() != Some(1) || first[0] != LEGACY_PING {
+        return false;
+    }
+
+    if connection.read_exact(&mut first).await.is_err() {
+        return true;
+    }
+    let mut payload = [0; 1];
+    let with_payload = tokio::time::timeout(PAYLOAD_TIMEOUT, connection.read_exact(&mut payload))
+        .await
+        .is_ok_and(|read| read.is_ok())
+        && payload[0] == LEGACY_PING_PAYLOAD;
+    // Closing the connection with unread data resets it, which can drop the response
+    if with_payload {
+        let _ = tokio::time::timeout(PAYLOAD_TIMEOUT, discard_plugin_message(connection)).await;
+    }
+
+    let response = legacy_response(
+        with_payload,
+        &server.basic_config.motd,
+        server.get_player_count().await,
+        server.basic_config.max_players as usize,
+    );
+    if let Err(err) = connection.write_all(&response).await {
+        log::debug!("Failed to answer legacy ping: {err}");
+    }
+    let _ = connection.shutdown().await;
+    true
+}
+
+/// Reads the `MC|PingHost` plugin message of 1.6 clients, if there is one
+async fn discard_plugin_message(connection: &mut TcpStream) -> std::io::Result<()> {
+    let mut id = [0; 1];
+    if connection.peek(&mut id).await? != 1 || id[0] != PLUGIN_MESSAGE {
+        return Ok(());
+    }
+    connection.read_exact(&mut id).await?;
+    // The channel name in UTF-16, then the data
+    let channel_len = connection.read_u16().await?;
+    let mut channel = vec![0; usize::from(channel_len) * 2];
+    connection.read_exact(&mut channel).await?;
+    let data_len = connection.read_u16().await?;
+    let mut data = vec![0; usize::from(data_len)];
+    connection.read_exact(&mut data).await?;
+    Ok(())
+}
+
+/// Encodes the kick packet old clients read the server list entry from
+fn legacy_response(with_payload: bool, motd: &str, online: usize, max: usize) -> Vec<u8> {
+    let message = if with_payload {
+        format!("§1\0{LEGACY_PROTOCOL}\0{CURRENT_MC_VERSION}\0{motd}\0{online}\0{max}")
+    } else {
+        // The oldest format splits on `§`, so the MOTD can't contain any
+        format!("{}§{online}§{max}", motd.replace('§', ""))
+    };
+
+    let chars = message.encode_utf16().collect::<Vec<_>>();
+    let mut response = Vec::with_capacity(3 + chars.len() * 2);
+    response.push(LEGACY_KICK);
+    response.extend_from_slice(&(chars.len() as u16).to_be_bytes());
+    for char in chars {
+        response.extend_from_slice(&char.to_be_bytes());
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::legacy_response;
+
+    fn decode(response: &[u8]) -> String {
+        assert_eq!(response[0], 0xFF);
+        let len = u16::from_be_bytes([response[1], response[2]]) as usize;
+        let chars = response[3..]
+            .chunks_exact(2)
+            .map(|char| u16::from_be_bytes([char[0], char[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(chars.len(), len);
+        String::from_utf16(&chars).unwrap()
+    }
+
+    #[test]
+    fn oldest_format() {
+        let response = legacy_response(false, "§aA Pumpkin server", 3, 20);
+        assert_eq!(decode(&response), "aA Pumpkin server§3§20");
+    }
+
+    #[test]
+    fn payload_format() {
+        let response = legacy_response(true, "A Pumpkin server", 3, 20);
+        let message = decode(&response);
+        let fields = message.split('\0').collect::<Vec<_>>();
+        assert_eq!(fields[0], "§1");
+        assert_eq!(fields[1], "127");
+        assert_eq!(fields[3..], ["A Pumpkin server", "3", "20"]);
+    }
+}
@@ -9,7 +9,6 @@ use pumpkin_protocol::{
     },
 };
 use pumpkin_util::text::TextComponent;
-use uuid::Uuid;
 
 use crate::{
     net::{
@@ -27,6 +26,15 @@ impl JavaClient {
     pub async fn handle_login_start(&self, server: &Server, login_start: SLoginStart) {
         log::debug!("login start");
 
+        let ip = self.address.lock().await.ip();
+        if server.connection_throttle.try_login(ip).is_err() {
+            self.kick(TextComponent::text(
+                "Too many login attempts, please wait before reconnecting",
+            ))
+            .await;
+            return;
+        }
+
         // Don't allow new logons when the server is full.
         // If `max_players` is set to zero, then there is no max player count enforced.
         // TODO: If client is an operator or has otherwise suitable elevated permissions, allow the client to bypass this requirement.
@@ -62,7 +70,7 @@ impl JavaClient {
                 {
                     Ok((_ip, profile)) => {
                         // self.address.lock() = ip;
-                        self.finish_login(&profile).await;
+                        self.finish_login(server, &profile).await;
                         *gameprofile = Some(profile);
                     }
                     Err(error) => self.kick(TextComponent::text(error.to_string())).await,
@@ -94,7 +102,7 @@ impl JavaClient {
                 )
                 .await;
             } else {
-                self.finish_login(&profile).await;
+                self.finish_login(server, &profile).await;
             }
 
             *gameprofile = Some(profile);
@@ -178,7 +186,7 @@ impl JavaClient {
             return;
         }
 
-        self.finish_login(profile).await;
+        self.finish_login(server, profile).await;
     }
 
     async fn enable_compression(&self, server: &Server) {
@@ -196,7 +204,8 @@ impl JavaClient {
         self.set_compression(compression).await;
     }
 
-    async fn finish_login(&self, profile: &GameProfile) {
+    async fn finish_login(&self, server: &Server, profile: &GameProfile) {
+        self.request_login_cookies(server).await;
         let packet = CLoginSuccess::new(&profile.id, &profile.name, &profile.properties);
         self.send_packet_now(&packet).await;
     }
@@ -254,14 +263,15 @@ impl JavaClient {
         Ok(profile)
     }
 
-    pub fn handle_login_cookie_response(&self, packet: &SLoginCookieResponse) {
-        // TODO: allow plugins to access this
-        log::debug!(
-            "Received cookie_response[login]: key: \"{}\", payload_length: \"{:?}\"",
-            packet.key,
-            packet.payload.as_ref().map(|p| p.len())
-        );
+    pub async fn handle_login_cookie_response(
+        &self,
+        server: &Server,
+        packet: SLoginCookieResponse,
+    ) {
+        self.receive_cookie(server, packet.key, packet.payload)
+            .await;
     }
+
     pub async fn handle_plugin_response(
         &self,
         server: &Server,
@@ -277,7 +287,7 @@ impl JavaClient {
                 plugin_response,
             ) {
                 Ok((profile, new_address)) => {
-                    self.finish_login(&profile).await;
+                    self.finish_login(server, &profile).await;
                     *self.gameprofile.lock().await = Some(profile);
                     *address = new_address;
                     drop(address);
@@ -291,6 +301,8 @@ impl JavaClient {
         log::debug!("Handling login acknowledgement");
         self.connection_state.store(ConnectionState::Config);
         self.send_packet_now(&server.get_branding()).await;
+        self.register_channels(&server.plugin_channels.channels().await)
+            .await;
 
         if server.advanced_config.server_links.enabled {
             let mut links: Vec<Link> = Vec::new();
@@ -354,24 +366,30 @@ impl JavaClient {
         }
 
         let resource_config = &server.advanced_config.resource_pack;
-        if resource_config.enabled {
-            let uuid = Uuid::new_v3(&uuid::Uuid::NAMESPACE_DNS, resource_config.url.as_bytes());
-            let resource_pack = CConfigAddResourcePack::new(
-                &uuid,
-                &resource_config.url,
-                &resource_config.sha1,
-                resource_config.force,
-                if resource_config.prompt_message.is_empty() {
-                    None
-                } else {
-                    Some(TextComponent::text(resource_config.prompt_message.clone()))
-                },
-            );
-
-            self.send_packet_now(&resource_pack).await;
-        } else {
-            // This will be invoked by our resource pack handler in the case of the above branch.
+        let resource_packs = server.resource_packs.java_packs();
+        if resource_packs.is_empty() {
+            // This will be invoked by our resource pack handler once every pack was answered.
             self.send_known_packs().await;
+        } else {
+            let prompt_message = if resource_config.prompt_message.is_empty() {
+                None
+            } else {
+                Some(TextComponent::text(resource_config.prompt_message.clone()))
+            };
+            self.pending_resource_packs
+                .lock()
+                .await
+                .extend(resource_packs.iter().map(|pack| pack.uuid));
+            for pack in resource_packs {
+                let resource_pack = CConfigAddResourcePack::new(
+                    &pack.uuid,
+                    &pack.url,
+                    &pack.sha1,
+                    resource_config.force,
+                    prompt_message.clone(),
+                );
+                self.send_packet_now(&resource_pack).await;
+            }
         }
         log::debug!("login acknowledged");
     }
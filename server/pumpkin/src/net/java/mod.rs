@@ -1,23 +1,32 @@
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::sync::OnceLock;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::{io::Write, sync::Arc};
 
 use bytes::Bytes;
 use crossbeam::atomic::AtomicCell;
+use flate2::{Compression, write::ZlibEncoder};
 use pumpkin_config::networking::compression::CompressionInfo;
 use pumpkin_protocol::java::server::play::{
     SChangeGameMode, SChatCommand, SChatMessage, SChunkBatch, SClickSlot, SClientCommand,
     SClientInformationPlay, SClientTickEnd, SCloseContainer, SCommandSuggestion, SConfirmTeleport,
     SCookieResponse as SPCookieResponse, SCustomPayload, SInteract, SKeepAlive, SPickItemFromBlock,
     SPlayPingRequest, SPlayerAbilities, SPlayerAction, SPlayerCommand, SPlayerInput, SPlayerLoaded,
-    SPlayerPosition, SPlayerPositionRotation, SPlayerRotation, SPlayerSession, SSetCommandBlock,
-    SSetCreativeSlot, SSetHeldItem, SSetPlayerGround, SSwingArm, SUpdateSign, SUseItem, SUseItemOn,
+    SPlayerPosition, SPlayerPositionRotation, SPlayerRotation, SPlayerSession,
+    SResourcePackResponse, SSetCommandBlock, SSetCreativeSlot, SSetHeldItem, SSetPlayerGround,
+    SSwingArm, SUpdateSign, SUseItem, SUseItemOn,
 };
 use pumpkin_protocol::{
-    ClientPacket, ConnectionState, PacketDecodeError, RawPacket, ServerPacket,
+    ClientPacket, CompressionLevel, ConnectionState, PacketDecodeError, PacketEncodeError,
+    RawPacket, ServerPacket,
     codec::var_int::VarInt,
     java::{
-        client::{config::CConfigDisconnect, login::CLoginDisconnect, play::CPlayDisconnect},
+        client::{
+            config::CConfigDisconnect,
+            login::CLoginDisconnect,
+            play::{CChunkData, CPlayDisconnect},
+        },
         packet_decoder::TCPNetworkDecoder,
         packet_encoder::TCPNetworkEncoder,
         server::{
@@ -32,11 +41,14 @@ use pumpkin_protocol::{
             },
             status::{SStatusPingRequest, SStatusRequest},
         },
+        translation::{TranslationError, VersionMappings},
     },
     packet::Packet,
+    recording::Direction,
     ser::{NetworkWriteExt, ReadingError, WritingError},
 };
-use pumpkin_util::text::TextComponent;
+use pumpkin_util::{resource_location::ResourceLocation, text::TextComponent};
+use pumpkin_world::chunk::{ChunkData, packet_cache::ChunkPacketKind};
 use tokio::sync::Notify;
 use tokio::{
     io::{BufReader, BufWriter},
@@ -44,7 +56,7 @@ use tokio::{
         TcpStream,
         tcp::{OwnedReadHalf, OwnedWriteHalf},
     },
-    sync::Mutex,
+    sync::{Mutex, oneshot},
 };
 use tokio::{
     sync::mpsc::{Receiver, Sender},
@@ -53,14 +65,28 @@ use tokio::{
 use tokio_util::task::TaskTracker;
 
 pub mod config;
+pub mod cookie;
 pub mod handshake;
+pub mod legacy_ping;
 pub mod login;
 pub mod play;
+pub mod plugin_channel;
 pub mod status;
 
 use crate::entity::player::Player;
-use crate::net::{GameProfile, PlayerConfig};
-use crate::{error::PumpkinError, net::EncryptionError, server::Server};
+use crate::net::{
+    GameProfile, PlayerConfig, packet_recorder::PacketRecorder, protection::TrafficLimiter,
+};
+use crate::{
+    PLUGIN_MANAGER,
+    error::PumpkinError,
+    net::EncryptionError,
+    plugin::channels::MessageSender,
+    server::{
+        Server,
+        metrics::{Edition, METRICS},
+    },
+};
 
 pub struct JavaClient {
     pub id: u64,
@@ -79,6 +105,24 @@ pub struct JavaClient {
     /// The client's brand or modpack information, Optional.
     pub brand: Mutex<Option<String>>,
     pub player: Mutex<Option<Arc<Player>>>,
+    /// Set once the client starts playing, its packets are handed to packet listeners from then on
+    pub player_uuid: OnceLock<uuid::Uuid>,
+    /// Set after the handshake when the client is on another protocol version than ours
+    pub translation: OnceLock<Arc<VersionMappings>>,
+    /// Resource packs the client has not finished loading yet while configuring
+    pending_resource_packs: Mutex<HashSet<uuid::Uuid>>,
+    /// Whether the client was transferred here from another server
+    pub transferred: AtomicBool,
+    /// Cookie requests waiting for the client's answer
+    pending_cookies: Mutex<HashMap<ResourceLocation, Vec<oneshot::Sender<Option<Box<[u8]>>>>>>,
+    /// The cookies the client sent while logging in
+    login_cookies: Mutex<HashMap<ResourceLocation, Box<[u8]>>>,
+    /// The plugin channels the client registered
+    channels: Mutex<HashSet<ResourceLocation>>,
+    /// How many packets and bytes the client may still send before being kicked
+    traffic: TrafficLimiter,
+    /// Records the packets exchanged with the client while debugging it
+    pub packet_recorder: PacketRecorder,
     /// A collection of tasks associated with this client. The tasks await completion when removing the client.
     tasks: TaskTracker,
     /// An notifier that is triggered when this client is closed.
@@ -95,9 +139,18 @@ pub struct JavaClient {
 
 impl JavaClient {
     #[must_use]
-    pub fn new(tcp_stream: TcpStream, address: SocketAddr, id: u64) -> Self {
+    pub fn new(
+        tcp_stream: TcpStream,
+        address: SocketAddr,
+        id: u64,
+        traffic: TrafficLimiter,
+    ) -> Self {
         let (read, write) = tcp_stream.into_split();
         let (send, recv) = tokio::sync::mpsc::channel(128);
+        let mut network_reader = TCPNetworkDecoder::new(BufReader::new(read));
+        if let Some(max_packet_size) = traffic.max_packet_size() {
+            network_reader.set_max_packet_data_size(max_packet_size);
+        }
         Self {
             id,
             gameprofile: Mutex::new(None),
@@ -112,9 +165,18 @@ impl JavaClient {
             outgoing_packet_queue_recv: Some(recv),
 
             network_writer: Arc::new(Mutex::new(TCPNetworkEncoder::new(BufWriter::new(write)))),
-            network_reader: Mutex::new(TCPNetworkDecoder::new(BufReader::new(read))),
+            network_reader: Mutex::new(network_reader),
             brand: Mutex::new(None),
             player: Mutex::new(None),
+            player_uuid: OnceLock::new(),
+            translation: OnceLock::new(),
+            pending_resource_packs: Mutex::new(HashSet::new()),
+            transferred: AtomicBool::new(false),
+            pending_cookies: Mutex::new(HashMap::new()),
+            login_cookies: Mutex::new(HashMap::new()),
+            channels: Mutex::new(HashSet::new()),
+            traffic,
+            packet_recorder: PacketRecorder::default(),
         }
     }
     pub async fn set_encryption(
@@ -160,6 +222,26 @@ impl JavaClient {
     /// * `server`: A reference to the `Server` instance.
     pub async fn process_packets(self: &Arc<Self>, server: &Arc<Server>) {
         while let Some(packet) = self.get_packet().await {
+            self.packet_recorder.record(
+                Direction::Serverbound,
+                self.connection_state.load(),
+                packet.id,
+                &packet.payload,
+            );
+            let size = VarInt(packet.id).written_size() + packet.payload.len();
+            METRICS.add_received(Edition::Java, size);
+            if let Err(violation) = self.traffic.record_packet(size) {
+                log::warn!(
+                    "Client {} exceeded its packet budget ({violation:?}), kicking",
+                    self.id
+                );
+                self.kick(TextComponent::translate(
+                    "disconnect.exceeded_packet_rate",
+                    [],
+                ))
+                .await;
+                break;
+            }
             if let Err(error) = self.handle_packet(server, &packet).await {
                 let text = format!("Error while reading incoming packet {error}");
                 log::error!(
@@ -208,6 +290,17 @@ impl JavaClient {
     ///
     /// * `packet`: A reference to a packet object implementing the `ClientPacket` trait.
     pub async fn enqueue_packet_data(&self, packet_data: Bytes) {
+        let Some(packet_data) = self.intercept_clientbound(packet_data).await else {
+            return;
+        };
+        self.packet_recorder.record_serialized(
+            Direction::Clientbound,
+            self.connection_state.load(),
+            &packet_data,
+        );
+        let Some(packet_data) = self.translate_clientbound(packet_data) else {
+            return;
+        };
         if let Err(err) = self.outgoing_packet_queue_send.send(packet_data).await {
             // This is expected to fail if we are closed
             if !self.closed.load(Ordering::Relaxed) {
@@ -232,8 +325,13 @@ impl JavaClient {
                 None
             },
             packet_result = network_reader.get_raw_packet() => {
-                match packet_result {
-                    Ok(packet) => Some(packet),
+                match packet_result.map(|packet| self.translate_serverbound(packet)) {
+                    Ok(Ok(packet)) => Some(packet),
+                    Ok(Err(err)) => {
+                        log::warn!("Failed to translate packet from client {}: {}", self.id, err);
+                        self.kick(TextComponent::text(format!("Error while reading incoming packet {err}"))).await;
+                        None
+                    }
                     Err(err) => {
                         if !matches!(err, PacketDecodeError::ConnectionClosed) {
                             log::warn!("Failed to decode packet from client {}: {}", self.id, err);
@@ -271,17 +369,85 @@ impl JavaClient {
         let mut packet_buf = Vec::new();
         let writer = &mut packet_buf;
         Self::write_packet(packet, writer).unwrap();
-        self.send_packet_now_data(packet_buf).await;
+        self.send_packet_now_data(packet_buf.into()).await;
     }
 
-    pub async fn send_packet_now_data(&self, packet: Vec<u8>) {
-        if let Err(err) = self
-            .network_writer
-            .lock()
-            .await
-            .write_packet(packet.into())
-            .await
+    pub async fn send_packet_now_data(&self, packet: Bytes) {
+        let Some(packet) = self.intercept_clientbound(packet).await else {
+            return;
+        };
+        self.packet_recorder.record_serialized(
+            Direction::Clientbound,
+            self.connection_state.load(),
+            &packet,
+        );
+        let Some(packet) = self.translate_clientbound(packet) else {
+            return;
+        };
+        METRICS.add_sent(Edition::Java, packet.len());
+        let result = self.network_writer.lock().await.write_packet(packet).await;
+        self.handle_send_result(result);
+    }
+
+    /// Sends a chunk right away, reusing the packet encoded for other players if the chunk hasn't
+    /// changed since
+    pub async fn send_chunk(&self, chunk: &ChunkData) {
+        let packet =
+            chunk
+                .packet_cache
+                .get_or_encode(ChunkPacketKind::Java, chunk.generation, || {
+                    let mut packet_buf = Vec::new();
+                    Self::write_packet(&CChunkData(chunk), &mut packet_buf)?;
+                    Ok::<_, WritingError>(Bytes::from(packet_buf))
+                });
+        let packet = match packet {
+            Ok(packet) => packet,
+            Err(err) => {
+                log::error!("Failed to encode chunk {} {}: {err}", chunk.x, chunk.z);
+                return;
+            }
+        };
+
+        // Translated packets are specific to one version, so only share compressed ones when the
+        // client is on ours. Plugins may also rewrite the packet for this client
+        let compression = self.network_writer.lock().await.compression();
+        if self.translation.get().is_none()
+            && !self.is_intercepted(Direction::Clientbound, CChunkData::PACKET_ID)
+            && let Some((threshold, level)) = compression
+            && packet.len() >= threshold
         {
+            let compressed = chunk.packet_cache.get_or_encode(
+                ChunkPacketKind::JavaCompressed,
+                chunk.generation,
+                || compress_packet(&packet, level),
+            );
+            match compressed {
+                Ok(compressed) => {
+                    self.packet_recorder.record_serialized(
+                        Direction::Clientbound,
+                        self.connection_state.load(),
+                        &packet,
+                    );
+                    METRICS.add_sent(Edition::Java, packet.len());
+                    let result = self
+                        .network_writer
+                        .lock()
+                        .await
+                        .write_precompressed_packet(&compressed)
+                        .await;
+                    self.handle_send_result(result);
+                    return;
+                }
+                Err(err) => {
+                    log::warn!("Failed to compress chunk {} {}: {err}", chunk.x, chunk.z);
+                }
+            }
+        }
+        self.send_packet_now_data(packet).await;
+    }
+
+    fn handle_send_result(&self, result: Result<(), PacketEncodeError>) {
+        if let Err(err) = result {
             // It is expected that the packet will fail if we are closed
             if !self.closed.load(Ordering::Relaxed) {
                 log::warn!("Failed to send packet to client {}: {}", self.id, err);
@@ -292,6 +458,74 @@ impl JavaClient {
         }
     }
 
+    /// Whether plugins listen to a packet exchanged with the client, which they only do while it
+    /// is playing
+    fn is_intercepted(&self, direction: Direction, id: i32) -> bool {
+        self.player_uuid.get().is_some()
+            && self.connection_state.load() == ConnectionState::Play
+            && PLUGIN_MANAGER
+                .packet_listeners()
+                .is_intercepted(Edition::Java, direction, id)
+    }
+
+    /// Hands a packet exchanged while playing to the packet listeners of plugins, `None` if one of
+    /// them cancelled it
+    async fn intercept(&self, direction: Direction, id: i32, payload: Bytes) -> Option<Bytes> {
+        let Some(player) = self.player_uuid.get() else {
+            return Some(payload);
+        };
+        if !self.is_intercepted(direction, id) {
+            return Some(payload);
+        }
+        PLUGIN_MANAGER
+            .packet_listeners()
+            .intercept(*player, Edition::Java, direction, id, payload)
+            .await
+    }
+
+    /// Like [`Self::intercept`] for a serialized clientbound packet, which starts with its id
+    async fn intercept_clientbound(&self, packet: Bytes) -> Option<Bytes> {
+        let mut payload = &packet[..];
+        let Ok(id) = VarInt::decode(&mut payload) else {
+            return Some(packet);
+        };
+        if !self.is_intercepted(Direction::Clientbound, id.0) {
+            return Some(packet);
+        }
+        let payload = packet.slice(packet.len() - payload.len()..);
+        let payload = self
+            .intercept(Direction::Clientbound, id.0, payload)
+            .await?;
+        let mut packet = Vec::with_capacity(id.written_size() + payload.len());
+        id.encode(&mut packet).ok()?;
+        packet.extend_from_slice(&payload);
+        Some(packet.into())
+    }
+
+    /// Brings a serialized packet into the format of the client's version, `None` if the
+    /// client's version doesn't have it
+    fn translate_clientbound(&self, packet: Bytes) -> Option<Bytes> {
+        let Some(translation) = self.translation.get() else {
+            return Some(packet);
+        };
+        match translation.translate_clientbound(self.connection_state.load(), &packet) {
+            Ok(packet) => packet,
+            Err(err) => {
+                log::warn!("Failed to translate packet for client {}: {}", self.id, err);
+                None
+            }
+        }
+    }
+
+    fn translate_serverbound(&self, packet: RawPacket) -> Result<RawPacket, TranslationError> {
+        match self.translation.get() {
+            Some(translation) => {
+                translation.translate_serverbound(self.connection_state.load(), packet)
+            }
+            None => Ok(packet),
+        }
+    }
+
     pub fn write_packet<P: ClientPacket>(
         packet: &P,
         write: impl Write,
@@ -331,7 +565,7 @@ impl JavaClient {
         packet: &RawPacket,
     ) -> Result<(), ReadingError> {
         match self.connection_state.load() {
-            ConnectionState::HandShake => self.handle_handshake_packet(packet).await,
+            ConnectionState::HandShake => self.handle_handshake_packet(server, packet).await,
             ConnectionState::Status => self.handle_status_packet(server, packet).await,
             // TODO: Check config if transfer is enabled
             ConnectionState::Login | ConnectionState::Transfer => {
@@ -339,6 +573,16 @@ impl JavaClient {
             }
             ConnectionState::Config => self.handle_config_packet(server, packet).await,
             ConnectionState::Play => {
+                let Some(payload) = self
+                    .intercept(Direction::Serverbound, packet.id, packet.payload.clone())
+                    .await
+                else {
+                    return Ok(());
+                };
+                let packet = &RawPacket {
+                    id: packet.id,
+                    payload,
+                };
                 if let Some(player) = self.player.lock().await.as_ref() {
                     match self.handle_play_packet(player, server, packet).await {
                         Ok(()) => {}
@@ -362,12 +606,17 @@ impl JavaClient {
         }
     }
 
-    async fn handle_handshake_packet(&self, packet: &RawPacket) -> Result<(), ReadingError> {
+    async fn handle_handshake_packet(
+        &self,
+        server: &Server,
+        packet: &RawPacket,
+    ) -> Result<(), ReadingError> {
         log::debug!("Handling handshake group");
         let payload = &packet.payload[..];
         match packet.id {
             0 => {
-                self.handle_handshake(SHandShake::read(payload)?).await;
+                self.handle_handshake(server, SHandShake::read(payload)?)
+                    .await;
                 Ok(())
             }
             _ => Err(ReadingError::Message(format!(
@@ -425,6 +674,7 @@ impl JavaClient {
                     break;
                 };
 
+                METRICS.add_sent(Edition::Java, packet_data.len());
                 if let Err(err) = writer.lock().await.write_packet(packet_data).await {
                     // It is expected that the packet will fail if we are closed
                     if !closed.load(Ordering::Relaxed) {
@@ -452,6 +702,7 @@ impl JavaClient {
     pub fn close(&self) {
         self.close_interrupt.notify_waiters();
         self.closed.store(true, Ordering::Relaxed);
+        self.packet_recorder.stop();
     }
 
     async fn handle_login_packet(
@@ -478,7 +729,8 @@ impl JavaClient {
                 self.handle_login_acknowledged(server).await;
             }
             SLoginCookieResponse::PACKET_ID => {
-                self.handle_login_cookie_response(&SLoginCookieResponse::read(payload)?);
+                self.handle_login_cookie_response(server, SLoginCookieResponse::read(payload)?)
+                    .await;
             }
             _ => {
                 log::error!(
@@ -503,7 +755,7 @@ impl JavaClient {
                     .await;
             }
             SPluginMessage::PACKET_ID => {
-                self.handle_plugin_message(SPluginMessage::read(payload)?)
+                self.handle_plugin_message(server, SPluginMessage::read(payload)?)
                     .await;
             }
             SAcknowledgeFinishConfig::PACKET_ID => {
@@ -514,10 +766,11 @@ impl JavaClient {
                     .await;
             }
             SConfigCookieResponse::PACKET_ID => {
-                self.handle_config_cookie_response(&SConfigCookieResponse::read(payload)?);
+                self.handle_config_cookie_response(server, SConfigCookieResponse::read(payload)?)
+                    .await;
             }
             SConfigResourcePack::PACKET_ID => {
-                self.handle_resource_pack_response(server, SConfigResourcePack::read(payload)?)
+                self.handle_resource_pack_response(SConfigResourcePack::read(payload)?)
                     .await;
             }
             _ => {
@@ -579,12 +832,16 @@ impl JavaClient {
                 // TODO
             }
             SPlayerPosition::PACKET_ID => {
-                self.handle_position(player, SPlayerPosition::read(payload)?)
+                self.handle_position(player, server, SPlayerPosition::read(payload)?)
                     .await;
             }
             SPlayerPositionRotation::PACKET_ID => {
-                self.handle_position_rotation(player, SPlayerPositionRotation::read(payload)?)
-                    .await;
+                self.handle_position_rotation(
+                    player,
+                    server,
+                    SPlayerPositionRotation::read(payload)?,
+                )
+                .await;
             }
             SPlayerRotation::PACKET_ID => {
                 self.handle_rotation(player, SPlayerRotation::read(payload)?)
@@ -650,7 +907,11 @@ impl JavaClient {
                     .await;
             }
             SPCookieResponse::PACKET_ID => {
-                self.handle_cookie_response(&SPCookieResponse::read(payload)?);
+                self.handle_cookie_response(server, SPCookieResponse::read(payload)?)
+                    .await;
+            }
+            SResourcePackResponse::PACKET_ID => {
+                self.handle_play_resource_pack_response(&SResourcePackResponse::read(payload)?);
             }
             SCloseContainer::PACKET_ID => {
                 self.handle_close_container(player, server, SCloseContainer::read(payload)?)
@@ -665,7 +926,14 @@ impl JavaClient {
                     .await;
             }
             SCustomPayload::PACKET_ID => {
-                // TODO: this fixes Failed to handle player packet id for now
+                let packet = SCustomPayload::read(payload)?;
+                self.receive_custom_payload(
+                    server,
+                    MessageSender::Player(player),
+                    packet.channel,
+                    &packet.data,
+                )
+                .await;
             }
             _ => {
                 log::warn!("Failed to handle player packet id {}", packet.id);
@@ -674,3 +942,13 @@ impl JavaClient {
         Ok(())
     }
 }
+
+/// Compresses a serialized packet into the body of a compressed packet: its uncompressed length
+/// followed by the zlib-compressed packet
+fn compress_packet(packet: &[u8], level: CompressionLevel) -> Result<Bytes, WritingError> {
+    let mut compressed = Vec::new();
+    VarInt(packet.len() as i32).encode(&mut compressed)?;
+    let mut encoder = ZlibEncoder::new(compressed, Compression::new(level));
+    encoder.write_all(packet).map_err(WritingError::IoError)?;
+    Ok(encoder.finish().map_err(WritingError::IoError)?.into())
+}
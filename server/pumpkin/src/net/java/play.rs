@@ -14,6 +14,7 @@ use crate::block::registry::BlockActionResult;
 use crate::block::{self, BlockIsReplacing};
 use crate::command::CommandSender;
 use crate::entity::EntityBase;
+use crate::entity::movement::MovementCheck;
 use crate::entity::player::{ChatMode, ChatSession, Player};
 use crate::error::PumpkinError;
 use crate::net::PlayerConfig;
@@ -45,8 +46,8 @@ use pumpkin_protocol::java::server::play::{
     SCommandSuggestion, SConfirmTeleport, SCookieResponse as SPCookieResponse, SInteract,
     SKeepAlive, SPickItemFromBlock, SPlayPingRequest, SPlayerAbilities, SPlayerAction,
     SPlayerCommand, SPlayerInput, SPlayerPosition, SPlayerPositionRotation, SPlayerRotation,
-    SPlayerSession, SSetCommandBlock, SSetCreativeSlot, SSetHeldItem, SSetPlayerGround, SSwingArm,
-    SUpdateSign, SUseItem, SUseItemOn, Status,
+    SPlayerSession, SResourcePackResponse, SSetCommandBlock, SSetCreativeSlot, SSetHeldItem,
+    SSetPlayerGround, SSwingArm, SUpdateSign, SUseItem, SUseItemOn, Status,
 };
 use pumpkin_util::math::vector3::Vector3;
 use pumpkin_util::math::{polynomial_rolling_hash, position::BlockPos, wrap_degrees};
@@ -177,6 +178,7 @@ impl JavaClient {
                 // We should set the position now to what we requested in the teleport packet.
                 // This may fix issues when the client sends the position while being teleported.
                 player.living_entity.entity.set_pos(*position);
+                player.movement.reset(*position).await;
 
                 *awaiting_teleport = None;
                 drop(awaiting_teleport);
@@ -259,8 +261,38 @@ impl JavaClient {
         true
     }
 
-    pub async fn handle_position(&self, player: &Arc<Player>, packet: SPlayerPosition) {
-        if !player.has_client_loaded() {
+    /// Returns whether the player may move to the position, teleports them back otherwise
+    async fn validate_movement(
+        &self,
+        player: &Arc<Player>,
+        server: &Server,
+        position: Vector3<f64>,
+        on_ground: bool,
+    ) -> bool {
+        let check = player
+            .movement
+            .check_move(
+                player,
+                &server.advanced_config.movement,
+                position,
+                on_ground,
+            )
+            .await;
+        if check == MovementCheck::Accepted {
+            return true;
+        }
+        self.force_tp(player, player.living_entity.entity.pos.load())
+            .await;
+        false
+    }
+
+    pub async fn handle_position(
+        &self,
+        player: &Arc<Player>,
+        server: &Server,
+        packet: SPlayerPosition,
+    ) {
+        if !player.has_client_loaded() || player.awaiting_teleport.lock().await.is_some() {
             return;
         }
         // y = feet Y
@@ -278,6 +310,17 @@ impl JavaClient {
             Self::clamp_vertical(position.y),
             Self::clamp_horizontal(position.z),
         );
+        if !self
+            .validate_movement(
+                player,
+                server,
+                position,
+                packet.collision & FLAG_ON_GROUND != 0,
+            )
+            .await
+        {
+            return;
+        }
 
         send_cancellable! {{
             PlayerMoveEvent {
@@ -301,7 +344,6 @@ impl JavaClient {
                 entity.on_ground.store(packet.collision & FLAG_ON_GROUND != 0, Ordering::Relaxed);
                 let world = &player.world();
 
-                // TODO: Warn when player moves to quickly
                 if !self.sync_position(player, world, pos, last_pos, entity.yaw.load(), entity.pitch.load(), packet.collision & FLAG_ON_GROUND != 0).await {
                     // Send the new position to all other players.
                     world
@@ -355,9 +397,10 @@ impl JavaClient {
     pub async fn handle_position_rotation(
         &self,
         player: &Arc<Player>,
+        server: &Server,
         packet: SPlayerPositionRotation,
     ) {
-        if !player.has_client_loaded() {
+        if !player.has_client_loaded() || player.awaiting_teleport.lock().await.is_some() {
             return;
         }
         // y = feet Y
@@ -381,6 +424,17 @@ impl JavaClient {
             Self::clamp_vertical(position.y),
             Self::clamp_horizontal(position.z),
         );
+        if !self
+            .validate_movement(
+                player,
+                server,
+                position,
+                packet.collision & FLAG_ON_GROUND != 0,
+            )
+            .await
+        {
+            return;
+        }
 
         send_cancellable! {{
             PlayerMoveEvent::new(
@@ -415,7 +469,6 @@ impl JavaClient {
                 // let head_yaw = (entity.head_yaw * 256.0 / 360.0).floor();
                 let world = &entity.world;
 
-                // TODO: Warn when player moves to quickly
                 if !self
                     .sync_position(player, world, pos, last_pos, yaw, pitch, (packet.collision & FLAG_ON_GROUND) != 0)
                     .await
@@ -1113,6 +1166,12 @@ impl JavaClient {
                     return;
                 }
                 if let Some(player_victim) = player_victim {
+                    if !player.can_reach_entity(
+                        &server.advanced_config.movement,
+                        &player_victim.living_entity.entity,
+                    ) {
+                        return;
+                    }
                     if player_victim.living_entity.health.load() <= 0.0 {
                         // You can trigger this from a non-modded / innocent client,
                         // so we shouldn't kick the player.
@@ -1132,6 +1191,12 @@ impl JavaClient {
                     }
                     player.attack(player_victim).await;
                 } else if let Some(entity_victim) = world.get_entity_by_id(entity_id.0).await {
+                    if !player.can_reach_entity(
+                        &server.advanced_config.movement,
+                        entity_victim.get_entity(),
+                    ) {
+                        return;
+                    }
                     player.attack(entity_victim).await;
                 } else {
                     log::error!(
@@ -1149,7 +1214,12 @@ impl JavaClient {
             ActionType::Interact | ActionType::InteractAt => {
                 // TODO: split this up
                 let entity = player.world().get_player_by_id(entity_id.0).await;
-                if let Some(entity) = entity {
+                if let Some(entity) = entity
+                    && player.can_reach_entity(
+                        &server.advanced_config.movement,
+                        &entity.living_entity.entity,
+                    )
+                {
                     let held = player.inventory.held_item();
                     let mut stack = held.lock().await;
                     server
@@ -1174,7 +1244,9 @@ impl JavaClient {
         match Status::try_from(player_action.status.0) {
             Ok(status) => match status {
                 Status::StartedDigging => {
-                    if !player.can_interact_with_block_at(&player_action.position, 1.0) {
+                    if !player
+                        .can_reach_block(&server.advanced_config.movement, &player_action.position)
+                    {
                         log::warn!(
                             "Player {0} tried to interact with block out of reach at {1}",
                             player.gameprofile.name,
@@ -1253,7 +1325,9 @@ impl JavaClient {
                     self.update_sequence(player, player_action.sequence.0);
                 }
                 Status::CancelledDigging => {
-                    if !player.can_interact_with_block_at(&player_action.position, 1.0) {
+                    if !player
+                        .can_reach_block(&server.advanced_config.movement, &player_action.position)
+                    {
                         log::warn!(
                             "Player {0} tried to interact with block out of reach at {1}",
                             player.gameprofile.name,
@@ -1272,7 +1346,7 @@ impl JavaClient {
                 Status::FinishedDigging => {
                     // TODO: do validation
                     let location = player_action.position;
-                    if !player.can_interact_with_block_at(&location, 1.0) {
+                    if !player.can_reach_block(&server.advanced_config.movement, &location) {
                         log::warn!(
                             "Player {0} tried to interact with block out of reach at {1}",
                             player.gameprofile.name,
@@ -1387,7 +1461,7 @@ impl JavaClient {
 
         let mut should_try_decrement = false;
 
-        if !player.can_interact_with_block_at(&position, 1.0) {
+        if !player.can_reach_block(&server.advanced_config.movement, &position) {
             // TODO: maybe log?
             return Err(BlockPlacingError::BlockOutOfReach);
         }
@@ -1787,13 +1861,14 @@ impl JavaClient {
         self.enqueue_packet(&response).await;
     }
 
-    pub fn handle_cookie_response(&self, packet: &SPCookieResponse) {
+    pub async fn handle_cookie_response(&self, server: &Server, packet: SPCookieResponse) {
+        self.receive_cookie(server, packet.key, packet.payload)
+            .await;
+    }
+
+    pub fn handle_play_resource_pack_response(&self, packet: &SResourcePackResponse) {
         // TODO: allow plugins to access this
-        log::debug!(
-            "Received cookie_response[play]: key: \"{}\", payload_length: \"{:?}\"",
-            packet.key,
-            packet.payload.as_ref().map(|p| p.len())
-        );
+        self.log_resource_pack_response(packet.response_result());
     }
 
     const WORLD_LOWEST_Y: i8 = -64;
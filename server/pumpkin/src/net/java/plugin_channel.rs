@@ -0,0 +1,128 @@
+//! Plugin channels carry custom payloads between the server and client mods or proxies. Both
+//! sides tell each other which channels they listen on through `minecraft:register` and
+//! `minecraft:unregister`
+
+use pumpkin_protocol::{
+    ConnectionState,
+    java::client::{config::CPluginMessage, play::CCustomPayload},
+};
+use pumpkin_util::{resource_location::ResourceLocation, text::TextComponent};
+
+use crate::{
+    net::java::JavaClient,
+    plugin::channels::{MessageSender, decode_channel_list, encode_channel_list},
+    server::Server,
+};
+
+/// The Notchian client does not accept larger payloads
+pub const MAX_PAYLOAD_SIZE: usize = 1 << 20;
+/// How many channels a client may register, so it can't make us hold on to any number of them
+pub const MAX_CHANNELS: usize = 128;
+
+pub const BRAND_CHANNEL: &str = "minecraft:brand";
+pub const REGISTER_CHANNEL: &str = "minecraft:register";
+pub const UNREGISTER_CHANNEL: &str = "minecraft:unregister";
+
+impl JavaClient {
+    /// Sends a custom payload to the client, which has to be configuring or playing
+    pub async fn send_custom_payload(&self, channel: &ResourceLocation, data: &[u8]) {
+        self.send_payload(&channel.to_string(), data).await;
+    }
+
+    async fn send_payload(&self, channel: &str, data: &[u8]) {
+        if data.len() > MAX_PAYLOAD_SIZE {
+            log::warn!(
+                "Not sending payload of {} bytes on channel {channel} to client {}, the maximum is {MAX_PAYLOAD_SIZE}",
+                data.len(),
+                self.id
+            );
+            return;
+        }
+        match self.connection_state.load() {
+            ConnectionState::Config => {
+                self.send_packet_now(&CPluginMessage::new(channel, data))
+                    .await;
+            }
+            ConnectionState::Play => {
+                self.enqueue_packet(&CCustomPayload::new(channel, data))
+                    .await
+            }
+            state => log::debug!(
+                "Not sending payload on channel {channel} to client {} in state {state:?}",
+                self.id
+            ),
+        }
+    }
+
+    /// Whether the client registered the channel, i.e. a mod or proxy listens on it
+    pub async fn listens_on(&self, channel: &ResourceLocation) -> bool {
+        self.channels.lock().await.contains(channel)
+    }
+
+    /// Tells the client which channels the server listens on from now on
+    pub async fn register_channels(&self, channels: &[ResourceLocation]) {
+        if !channels.is_empty() {
+            self.send_payload(REGISTER_CHANNEL, &encode_channel_list(channels))
+                .await;
+        }
+    }
+
+    /// Tells the client which channels the server no longer listens on
+    pub async fn unregister_channels(&self, channels: &[ResourceLocation]) {
+        if !channels.is_empty() {
+            self.send_payload(UNREGISTER_CHANNEL, &encode_channel_list(channels))
+                .await;
+        }
+    }
+
+    /// Handles a custom payload the client sent while configuring or playing, the player is
+    /// only known once it joined
+    pub(super) async fn receive_custom_payload(
+        &self,
+        server: &Server,
+        sender: MessageSender<'_>,
+        channel: ResourceLocation,
+        data: &[u8],
+    ) {
+        log::debug!(
+            "Received payload on channel {channel} from client {}: length: {}",
+            self.id,
+            data.len()
+        );
+        match channel.to_string().as_str() {
+            BRAND_CHANNEL => match str::from_utf8(data) {
+                Ok(brand) => *self.brand.lock().await = Some(brand.to_string()),
+                Err(e) => self.kick(TextComponent::text(e.to_string())).await,
+            },
+            REGISTER_CHANNEL => {
+                let channels = decode_channel_list(data);
+                log::debug!("Client {} registered channels {channels:?}", self.id);
+                let mut registered = self.channels.lock().await;
+                registered.extend(channels);
+                if registered.len() > MAX_CHANNELS {
+                    drop(registered);
+                    self.kick(TextComponent::text("Too many plugin channels"))
+                        .await;
+                }
+            }
+            UNREGISTER_CHANNEL => {
+                let mut registered = self.channels.lock().await;
+                for channel in decode_channel_list(data) {
+                    registered.remove(&channel);
+                }
+            }
+            _ => {
+                if !server
+                    .plugin_channels
+                    .dispatch(sender, &channel, data)
+                    .await
+                {
+                    log::debug!(
+                        "Client {} sent payload on channel {channel} nobody listens on",
+                        self.id
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Serves the server metrics in the OpenMetrics format under `/metrics` for Prometheus to scrape
+
+use std::{net::SocketAddr, sync::Arc, sync::atomic::Ordering};
+
+use tokio::{
+    net::{TcpListener, TcpStream},
+    select,
+};
+
+use super::http::{REQUEST_TIMEOUT, read_request, request_target, respond, respond_ok};
+use crate::{
+    SHOULD_STOP, STOP_INTERRUPT,
+    server::{
+        Server,
+        metrics::{CONTENT_TYPE, METRICS, ServerSnapshot},
+    },
+};
+
+pub async fn start_metrics_host(server: Arc<Server>, address: SocketAddr) {
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            log::error!("Failed to start the metrics endpoint on {address}: {err}");
+            return;
+        }
+    };
+    log::info!("Serving metrics on http://{address}/metrics");
+
+    while !SHOULD_STOP.load(Ordering::Relaxed) {
+        let accept_result = select! {
+            result = listener.accept() => Some(result),
+            () = STOP_INTERRUPT.notified() => None,
+        };
+        let Some(accept_result) = accept_result else {
+            break;
+        };
+        let Ok((connection, _)) = accept_result else {
+            continue;
+        };
+
+        let server = server.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle_connection(&server, connection).await {
+                log::debug!("Metrics scrape failed: {err}");
+            }
+        });
+    }
+}
+
+async fn handle_connection(server: &Server, mut connection: TcpStream) -> std::io::Result<()> {
+    let Ok(request) = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut connection)).await
+    else {
+        return Ok(());
+    };
+    let request = request?;
+
+    let (method, path) = request_target(&request);
+    if method != "GET" && method != "HEAD" {
+        return respond(&mut connection, "405 Method Not Allowed").await;
+    }
+    // Prometheus may add query parameters to the path
+    if path.and_then(|path| path.split('?').next()) != Some("/metrics") {
+        return respond(&mut connection, "404 Not Found").await;
+    }
+
+    let snapshot = ServerSnapshot::collect(server).await;
+    let body = METRICS.render(&snapshot);
+    respond_ok(&mut connection, method, CONTENT_TYPE, body.as_bytes()).await
+}
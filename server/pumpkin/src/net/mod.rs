@@ -10,7 +10,7 @@ use crate::{
         op_data::OPERATOR_CONFIG, whitelist_data::WHITELIST_CONFIG,
     },
     entity::player::ChatMode,
-    net::{bedrock::BedrockClient, java::JavaClient},
+    net::{bedrock::BedrockClient, java::JavaClient, packet_recorder::PacketRecorder},
     server::Server,
 };
 
@@ -25,11 +25,16 @@ use thiserror::Error;
 use uuid::Uuid;
 pub mod authentication;
 pub mod bedrock;
+mod http;
 pub mod java;
 pub mod lan_broadcast;
+pub mod metrics_host;
+pub mod packet_recorder;
+pub mod protection;
 mod proxy;
 pub mod query;
 pub mod rcon;
+pub mod resource_pack_host;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct GameProfile {
@@ -172,6 +177,14 @@ impl ClientPlatform {
             Self::Bedrock(bedrock) => bedrock.kick(reason, message.get_text()).await,
         }
     }
+
+    #[must_use]
+    pub fn packet_recorder(&self) -> &PacketRecorder {
+        match self {
+            Self::Java(java) => &java.packet_recorder,
+            Self::Bedrock(bedrock) => &bedrock.packet_recorder,
+        }
+    }
 }
 
 pub async fn can_not_join(
@@ -0,0 +1,149 @@
+//! Records the packets of single connections to debug misbehaving clients, see
+//! `pumpkin_protocol::recording` for the format and the decoder
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use pumpkin_protocol::{
+    ConnectionState,
+    codec::var_int::VarInt,
+    recording::{Direction, Platform, RecordedPacket, RecordingHeader, RecordingWriter},
+};
+
+/// Where recordings are written, relative to the working directory
+pub const RECORDING_FOLDER: &str = "debug/packets";
+
+struct Recording {
+    /// Packets are written to the file on a thread of their own, so the connection never waits
+    /// on the disk
+    packets: Sender<RecordedPacket>,
+    start: Instant,
+    path: PathBuf,
+}
+
+/// Writes the packets of a recording until it is stopped, which drops the sender
+fn write_recording(
+    mut writer: RecordingWriter<BufWriter<File>>,
+    path: &Path,
+    packets: &Receiver<RecordedPacket>,
+) {
+    for packet in packets {
+        if let Err(err) = writer.write_packet(
+            packet.time,
+            packet.direction,
+            packet.state,
+            packet.id,
+            &packet.payload,
+        ) {
+            log::warn!(
+                "Failed to write packet recording {}, stopping it: {err}",
+                path.display()
+            );
+            return;
+        }
+    }
+    if let Err(err) = writer.flush() {
+        log::warn!("Failed to write packet recording {}: {err}", path.display());
+    }
+}
+
+/// The packet recording of a connection, if one is running
+#[derive(Default)]
+pub struct PacketRecorder {
+    /// Lets the packet paths skip the lock while nothing is recorded
+    recording: AtomicBool,
+    inner: Mutex<Option<Recording>>,
+}
+
+impl PacketRecorder {
+    #[must_use]
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::Relaxed)
+    }
+
+    /// Starts recording into a new file in `folder` named after `name`, returns its path
+    pub fn start(
+        &self,
+        folder: &Path,
+        name: &str,
+        platform: Platform,
+        protocol: u32,
+    ) -> io::Result<PathBuf> {
+        fs::create_dir_all(folder)?;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = folder.join(format!("{name}-{timestamp}.pkpr"));
+        let writer = RecordingWriter::new(
+            BufWriter::new(File::create(&path)?),
+            RecordingHeader { platform, protocol },
+        )
+        .map_err(io::Error::other)?;
+        let (sender, receiver) = mpsc::channel();
+        let thread_path = path.clone();
+        thread::Builder::new()
+            .name(format!("Packet recording {name}"))
+            .spawn(move || write_recording(writer, &thread_path, &receiver))?;
+
+        // Replacing an older recording stops it
+        *self.inner.lock().unwrap() = Some(Recording {
+            packets: sender,
+            start: Instant::now(),
+            path: path.clone(),
+        });
+        self.recording.store(true, Ordering::Relaxed);
+        Ok(path)
+    }
+
+    /// Stops recording, returns the file the packets are written to. Packets still queued are
+    /// written out in the background
+    pub fn stop(&self) -> Option<PathBuf> {
+        self.recording.store(false, Ordering::Relaxed);
+        let recording = self.inner.lock().unwrap().take()?;
+        Some(recording.path)
+    }
+
+    pub fn record(&self, direction: Direction, state: ConnectionState, id: i32, payload: &[u8]) {
+        if !self.is_recording() {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let Some(recording) = inner.as_mut() else {
+            return;
+        };
+        let packet = RecordedPacket {
+            time: recording.start.elapsed(),
+            direction,
+            state,
+            id,
+            payload: payload.into(),
+        };
+        // The writer only hangs up after failing to write, which it already logged
+        if recording.packets.send(packet).is_err() {
+            *inner = None;
+            self.recording.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Records a serialized Java packet, which starts with its id
+    pub fn record_serialized(&self, direction: Direction, state: ConnectionState, packet: &[u8]) {
+        if !self.is_recording() {
+            return;
+        }
+        let mut payload = packet;
+        if let Ok(id) = VarInt::decode(&mut payload) {
+            self.record(direction, state, id.0, payload);
+        }
+    }
+}
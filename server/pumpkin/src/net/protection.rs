@@ -0,0 +1,364 @@
+//! Per-IP connection throttling and per-connection packet budgets
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use pumpkin_config::networking::protection::ProtectionConfig;
+
+/// How often IPs without open connections or recent activity are forgotten
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A token bucket: refills at `rate` per second and may go `burst` below zero before the limit
+/// is hit
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    available: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    #[must_use]
+    pub fn new(rate: u64, burst_seconds: u32) -> Self {
+        let rate = rate as f64;
+        Self {
+            rate,
+            burst: rate * f64::from(burst_seconds),
+            available: rate,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Takes `amount` from the budget, returns false once the budget and burst are used up.
+    /// A rate of zero never limits
+    pub fn try_acquire(&mut self, amount: u64, now: Instant) -> bool {
+        if self.rate == 0.0 {
+            return true;
+        }
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.last_refill = now;
+        self.available = (self.available + elapsed * self.rate).min(self.rate);
+        self.available -= amount as f64;
+        self.available >= -self.burst
+    }
+}
+
+/// Why a client went over its budget
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrafficViolation {
+    PacketRate,
+    ByteRate,
+    PacketSize,
+}
+
+/// The packet budget of a single connection
+pub struct TrafficLimiter {
+    enabled: bool,
+    max_packet_size: usize,
+    packets: Mutex<RateLimiter>,
+    bytes: Mutex<RateLimiter>,
+}
+
+impl TrafficLimiter {
+    #[must_use]
+    pub fn new(config: &ProtectionConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            max_packet_size: config.max_packet_size,
+            packets: Mutex::new(RateLimiter::new(
+                config.packets_per_second.into(),
+                config.kick_threshold,
+            )),
+            bytes: Mutex::new(RateLimiter::new(
+                config.bytes_per_second,
+                config.kick_threshold,
+            )),
+        }
+    }
+
+    /// The largest packet the client may send, if limited
+    #[must_use]
+    pub fn max_packet_size(&self) -> Option<usize> {
+        (self.enabled && self.max_packet_size > 0).then_some(self.max_packet_size)
+    }
+
+    /// Records a received packet of `size` bytes
+    pub fn record_packet(&self, size: usize) -> Result<(), TrafficViolation> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.max_packet_size().is_some_and(|max| size > max) {
+            return Err(TrafficViolation::PacketSize);
+        }
+        let now = Instant::now();
+        if !self.packets.lock().unwrap().try_acquire(1, now) {
+            return Err(TrafficViolation::PacketRate);
+        }
+        if !self.bytes.lock().unwrap().try_acquire(size as u64, now) {
+            return Err(TrafficViolation::ByteRate);
+        }
+        Ok(())
+    }
+}
+
+/// Why a connection was refused
+#[derive(Debug, PartialEq, Eq)]
+pub enum ThrottleReason {
+    ConnectionRate,
+    TooManyConnections,
+    LoginRate,
+}
+
+#[derive(Default)]
+struct IpState {
+    connections: VecDeque<Instant>,
+    logins: VecDeque<Instant>,
+    open: u32,
+    pings: Option<RateLimiter>,
+}
+
+impl IpState {
+    fn is_idle(&self) -> bool {
+        self.open == 0 && self.connections.is_empty() && self.logins.is_empty()
+    }
+}
+
+struct ThrottleState {
+    ips: HashMap<IpAddr, IpState>,
+    last_sweep: Instant,
+}
+
+/// Tracks connections and logins by IP, shared by the Java and Bedrock listeners
+pub struct ConnectionThrottle {
+    config: ProtectionConfig,
+    /// Behind a proxy every connection comes from the proxy, so only packets are limited
+    behind_proxy: bool,
+    state: Mutex<ThrottleState>,
+}
+
+/// Keeps a connection counted towards its IP's concurrent connections until dropped
+pub struct ConnectionGuard {
+    throttle: Arc<ConnectionThrottle>,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut state = self.throttle.state.lock().unwrap();
+        if let Some(ip) = state.ips.get_mut(&self.ip) {
+            ip.open = ip.open.saturating_sub(1);
+        }
+    }
+}
+
+/// Forgets timestamps that left the window
+fn expire(timestamps: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while timestamps
+        .front()
+        .is_some_and(|time| now.saturating_duration_since(*time) > window)
+    {
+        timestamps.pop_front();
+    }
+}
+
+impl ConnectionThrottle {
+    #[must_use]
+    pub fn new(config: ProtectionConfig, behind_proxy: bool) -> Self {
+        Self {
+            config,
+            behind_proxy,
+            state: Mutex::new(ThrottleState {
+                ips: HashMap::new(),
+                last_sweep: Instant::now(),
+            }),
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.connection_window)
+    }
+
+    fn with_ip<T>(&self, ip: IpAddr, f: impl FnOnce(&mut IpState, Instant) -> T) -> T {
+        let now = Instant::now();
+        let window = self.window();
+        let mut state = self.state.lock().unwrap();
+        if now.saturating_duration_since(state.last_sweep) > SWEEP_INTERVAL {
+            state.last_sweep = now;
+            state.ips.retain(|_, ip| {
+                expire(&mut ip.connections, now, window);
+                expire(&mut ip.logins, now, window);
+                !ip.is_idle()
+            });
+        }
+        let ip = state.ips.entry(ip).or_default();
+        expire(&mut ip.connections, now, window);
+        expire(&mut ip.logins, now, window);
+        f(ip, now)
+    }
+
+    /// Registers a new connection, the returned guard has to be kept until it closes
+    pub fn try_connect(self: &Arc<Self>, ip: IpAddr) -> Result<ConnectionGuard, ThrottleReason> {
+        let limited = self.config.enabled && !self.behind_proxy;
+        self.with_ip(ip, |state, now| {
+            if limited {
+                let max_open = self.config.max_connections_per_ip;
+                if max_open > 0 && state.open >= max_open {
+                    return Err(ThrottleReason::TooManyConnections);
+                }
+                let max_new = self.config.connections_per_ip as usize;
+                if max_new > 0 && state.connections.len() >= max_new {
+                    return Err(ThrottleReason::ConnectionRate);
+                }
+                state.connections.push_back(now);
+            }
+            state.open += 1;
+            Ok(())
+        })?;
+        Ok(ConnectionGuard {
+            throttle: self.clone(),
+            ip,
+        })
+    }
+
+    /// Registers a login attempt
+    pub fn try_login(&self, ip: IpAddr) -> Result<(), ThrottleReason> {
+        if !self.config.enabled || self.behind_proxy {
+            return Ok(());
+        }
+        self.with_ip(ip, |state, now| {
+            let max_logins = self.config.login_attempts_per_ip as usize;
+            if max_logins > 0 && state.logins.len() >= max_logins {
+                return Err(ThrottleReason::LoginRate);
+            }
+            state.logins.push_back(now);
+            Ok(())
+        })
+    }
+
+    /// Whether an unconnected ping should be answered
+    pub fn allow_ping(&self, ip: IpAddr) -> bool {
+        if !self.config.enabled || self.config.pings_per_second == 0 {
+            return true;
+        }
+        self.with_ip(ip, |state, now| {
+            state
+                .pings
+                .get_or_insert_with(|| RateLimiter::new(self.config.pings_per_second.into(), 0))
+                .try_acquire(1, now)
+        })
+    }
+
+    /// Creates the packet budget for a new connection
+    #[must_use]
+    pub fn traffic_limiter(&self) -> TrafficLimiter {
+        TrafficLimiter::new(&self.config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use pumpkin_config::networking::protection::ProtectionConfig;
+
+    use super::{
+        ConnectionThrottle, RateLimiter, ThrottleReason, TrafficLimiter, TrafficViolation,
+    };
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 2));
+
+    #[test]
+    fn rate_limiter_refills() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(10, 1);
+        // One second of budget plus one second of burst
+        for _ in 0..20 {
+            assert!(limiter.try_acquire(1, start));
+        }
+        assert!(!limiter.try_acquire(1, start));
+        // Going over doesn't stop the refill
+        assert!(limiter.try_acquire(1, start + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn concurrent_connections() {
+        let throttle = Arc::new(ConnectionThrottle::new(
+            ProtectionConfig {
+                max_connections_per_ip: 2,
+                ..Default::default()
+            },
+            false,
+        ));
+        let first = throttle.try_connect(IP).unwrap();
+        let _second = throttle.try_connect(IP).unwrap();
+        assert_eq!(
+            throttle.try_connect(IP).err(),
+            Some(ThrottleReason::TooManyConnections)
+        );
+        drop(first);
+        assert!(throttle.try_connect(IP).is_ok());
+    }
+
+    #[test]
+    fn connection_and_login_rate() {
+        let throttle = Arc::new(ConnectionThrottle::new(
+            ProtectionConfig {
+                connections_per_ip: 2,
+                login_attempts_per_ip: 1,
+                ..Default::default()
+            },
+            false,
+        ));
+        drop(throttle.try_connect(IP).unwrap());
+        drop(throttle.try_connect(IP).unwrap());
+        assert_eq!(
+            throttle.try_connect(IP).err(),
+            Some(ThrottleReason::ConnectionRate)
+        );
+
+        assert!(throttle.try_login(IP).is_ok());
+        assert_eq!(throttle.try_login(IP), Err(ThrottleReason::LoginRate));
+
+        // Proxies connect every player from the same IP
+        let proxied = Arc::new(ConnectionThrottle::new(
+            ProtectionConfig {
+                connections_per_ip: 1,
+                ..Default::default()
+            },
+            true,
+        ));
+        for _ in 0..5 {
+            drop(proxied.try_connect(IP).unwrap());
+        }
+    }
+
+    #[test]
+    fn traffic_budget() {
+        let limiter = TrafficLimiter::new(&ProtectionConfig {
+            packets_per_second: 5,
+            kick_threshold: 1,
+            max_packet_size: 100,
+            ..Default::default()
+        });
+        assert_eq!(
+            limiter.record_packet(101),
+            Err(TrafficViolation::PacketSize)
+        );
+        let results = (0..11)
+            .map(|_| limiter.record_packet(10))
+            .collect::<Vec<_>>();
+        assert!(results[..10].iter().all(Result::is_ok));
+        assert_eq!(results[10], Err(TrafficViolation::PacketRate));
+    }
+}
@@ -1,7 +1,7 @@
 use std::{
     collections::HashMap,
     ffi::{CString, NulError},
-    net::SocketAddr,
+    net::{IpAddr, SocketAddr},
     sync::{Arc, atomic::Ordering},
     time::Duration,
 };
@@ -35,18 +35,23 @@ pub async fn start_query_handler(server: Arc<Server>, query_addr: SocketAddr) {
         }
     });
 
-    log::info!(
-        "Server query running on port {}",
-        socket
-            .local_addr()
-            .expect("Unable to find running address!")
-            .port()
+    let local_addr = socket
+        .local_addr()
+        .expect("Unable to find running address!");
+    log::info!("Server query running on port {}", local_addr.port());
+
+    let host_ip = host_ip(
+        server.advanced_config.networking.query.host_ip,
+        server.basic_config.java_edition_address,
+        local_addr,
     );
+    let host_ip = CString::new(host_ip.to_string()).expect("IP addresses never contain 0 bytes");
 
     while !SHOULD_STOP.load(Ordering::Relaxed) {
         let socket = socket.clone();
         let valid_challenge_tokens = valid_challenge_tokens.clone();
         let server = server.clone();
+        let host_ip = host_ip.clone();
         let mut buf = vec![0; 1024];
 
         let recv_result = tokio::select! {
@@ -59,15 +64,8 @@ pub async fn start_query_handler(server: Arc<Server>, query_addr: SocketAddr) {
         };
 
         tokio::spawn(async move {
-            if let Err(err) = handle_packet(
-                buf,
-                valid_challenge_tokens,
-                server,
-                socket,
-                addr,
-                query_addr,
-            )
-            .await
+            if let Err(err) =
+                handle_packet(buf, valid_challenge_tokens, server, socket, addr, host_ip).await
             {
                 log::error!("Interior 0 bytes found! Cannot encode query response! {err}");
             }
@@ -85,7 +83,7 @@ async fn handle_packet(
     server: Arc<Server>,
     socket: Arc<UdpSocket>,
     addr: SocketAddr,
-    bound_addr: SocketAddr,
+    host_ip: CString,
 ) -> Result<(), NulError> {
     if let Ok(mut raw_packet) = RawQueryPacket::decode(buf).await {
         match raw_packet.packet_type {
@@ -114,47 +112,26 @@ async fn handle_packet(
                         .get(&packet.challenge_token)
                         .is_some_and(|token_bound_ip: &SocketAddr| token_bound_ip == &addr)
                 {
+                    let map = CString::new(server.level_info.read().await.level_name.as_str())?;
+                    let host_port = server.basic_config.java_edition_address.port();
                     if packet.is_full_request {
-                        // Get 4 players
                         let mut players: Vec<CString> = Vec::new();
                         for world in server.worlds.read().await.iter() {
-                            let mut world_players = world
-                                .players
-                                .read()
-                                .await
-                                // Although there is no documented limit, we will limit to 4 players
-                                .values()
-                                .take(4 - players.len())
-                                .map(|player| {
-                                    CString::new(player.gameprofile.name.as_str()).unwrap()
-                                })
-                                .collect::<Vec<_>>();
-
-                            players.append(&mut world_players); // Append players from this world
-
-                            if players.len() >= 4 {
-                                break; // Stop if we've collected 4 players
+                            for player in world.players.read().await.values() {
+                                players.push(CString::new(player.gameprofile.name.as_str())?);
                             }
                         }
 
-                        let plugins = PLUGIN_MANAGER
-                            .active_plugins()
-                            .await
-                            .into_iter()
-                            .map(|meta| meta.name.to_string())
-                            .reduce(|acc, name| format!("{acc}, {name}"))
-                            .unwrap_or_default();
-
                         let response = CFullStatus {
                             session_id: packet.session_id,
                             hostname: CString::new(server.basic_config.motd.as_str())?,
                             version: CString::new(CURRENT_MC_VERSION)?,
-                            plugins: CString::new(plugins)?,
-                            map: CString::new("world")?, // TODO: Get actual world name
+                            plugins: CString::new(plugin_list().await)?,
+                            map,
                             num_players: server.get_player_count().await,
                             max_players: server.basic_config.max_players as usize,
-                            host_port: bound_addr.port(),
-                            host_ip: CString::new(bound_addr.ip().to_string())?,
+                            host_port,
+                            host_ip,
                             players,
                         };
 
@@ -165,11 +142,11 @@ async fn handle_packet(
                         let response = CBasicStatus {
                             session_id: packet.session_id,
                             motd: CString::new(server.basic_config.motd.as_str())?,
-                            map: CString::new("world")?,
+                            map,
                             num_players: server.get_player_count().await,
                             max_players: server.basic_config.max_players as usize,
-                            host_port: bound_addr.port(),
-                            host_ip: CString::new(bound_addr.ip().to_string())?,
+                            host_port,
+                            host_ip,
                         };
 
                         let _ = socket
@@ -182,3 +159,37 @@ async fn handle_packet(
     }
     Ok(())
 }
+
+/// The plugin list in the format Bukkit uses, `<server>: <plugin> <version>; ...`, which is
+/// what monitors parse
+async fn plugin_list() -> String {
+    let plugins = PLUGIN_MANAGER
+        .active_plugins()
+        .await
+        .into_iter()
+        .map(|meta| format!("{} {}", meta.name, meta.version))
+        .collect::<Vec<_>>();
+    let server = format!("Pumpkin {CURRENT_MC_VERSION}");
+    if plugins.is_empty() {
+        server
+    } else {
+        format!("{server}: {}", plugins.join("; "))
+    }
+}
+
+/// The IP players connect to: the configured one, or else the address the Java listener or the
+/// query socket is bound to. Bound to all interfaces, that is the unspecified address like vanilla
+/// reports it, as the address clients reach the server on can't be told from here
+fn host_ip(
+    configured: Option<IpAddr>,
+    java_address: SocketAddr,
+    query_address: SocketAddr,
+) -> IpAddr {
+    configured.unwrap_or_else(|| {
+        if java_address.ip().is_unspecified() && !query_address.ip().is_unspecified() {
+            query_address.ip()
+        } else {
+            java_address.ip()
+        }
+    })
+}
@@ -0,0 +1,66 @@
+//! A minimal HTTP server Java clients download hosted resource packs from
+
+use std::{net::SocketAddr, sync::Arc, sync::atomic::Ordering};
+
+use tokio::{
+    net::{TcpListener, TcpStream},
+    select,
+};
+
+use super::http::{REQUEST_TIMEOUT, read_request, request_target, respond, respond_ok};
+use crate::{SHOULD_STOP, STOP_INTERRUPT, server::Server};
+
+pub async fn start_resource_pack_host(server: Arc<Server>, address: SocketAddr) {
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            log::error!("Failed to start the resource pack host on {address}: {err}");
+            return;
+        }
+    };
+    log::info!("Hosting resource packs on {address}");
+
+    while !SHOULD_STOP.load(Ordering::Relaxed) {
+        let accept_result = select! {
+            result = listener.accept() => Some(result),
+            () = STOP_INTERRUPT.notified() => None,
+        };
+        let Some(accept_result) = accept_result else {
+            break;
+        };
+        let Ok((connection, _)) = accept_result else {
+            continue;
+        };
+
+        let server = server.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle_connection(&server, connection).await {
+                log::debug!("Resource pack download failed: {err}");
+            }
+        });
+    }
+}
+
+async fn handle_connection(server: &Server, mut connection: TcpStream) -> std::io::Result<()> {
+    let Ok(request) = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut connection)).await
+    else {
+        return Ok(());
+    };
+    let request = request?;
+
+    let (method, path) = request_target(&request);
+    if method != "GET" && method != "HEAD" {
+        return respond(&mut connection, "405 Method Not Allowed").await;
+    }
+
+    let file_name = path.and_then(|path| path.strip_prefix('/'));
+    let file = match file_name {
+        Some(file_name) => server.resource_packs.hosted_file(file_name).await,
+        None => None,
+    };
+    let Some(file) = file else {
+        return respond(&mut connection, "404 Not Found").await;
+    };
+
+    respond_ok(&mut connection, method, "application/zip", &file).await
+}
@@ -0,0 +1,197 @@
+use std::{collections::HashMap, sync::Arc};
+
+use pumpkin_util::resource_location::ResourceLocation;
+use tokio::sync::RwLock;
+
+use crate::{
+    entity::player::Player,
+    net::{ClientPlatform, java::JavaClient},
+    plugin::BoxFuture,
+    server::Server,
+};
+
+/// Where a plugin message came from.
+#[derive(Clone, Copy)]
+pub enum MessageSender<'a> {
+    /// A client that is still being configured, before its player joined.
+    Configuring(&'a JavaClient),
+    /// A player in the game.
+    Player(&'a Arc<Player>),
+}
+
+impl MessageSender<'_> {
+    /// Sends a message back to the sender.
+    ///
+    /// # Arguments
+    /// - `channel`: The channel to send the message on.
+    /// - `data`: The message, at most 1 MiB.
+    pub async fn send_custom_payload(&self, channel: &ResourceLocation, data: &[u8]) {
+        match self {
+            Self::Configuring(client) => client.send_custom_payload(channel, data).await,
+            Self::Player(player) => player.send_custom_payload(channel, data).await,
+        }
+    }
+}
+
+/// A trait for receiving the messages clients send on a plugin channel.
+pub trait PluginMessageHandler: Send + Sync {
+    /// Asynchronously handles a message.
+    ///
+    /// # Arguments
+    /// - `sender`: The client or player that sent the message.
+    /// - `channel`: The channel the message was sent on.
+    /// - `data`: The message.
+    fn handle<'a>(
+        &'a self,
+        sender: MessageSender<'a>,
+        channel: &'a ResourceLocation,
+        data: &'a [u8],
+    ) -> BoxFuture<'a, ()>;
+}
+
+struct RegisteredHandler {
+    plugin: String,
+    handler: Arc<dyn PluginMessageHandler>,
+}
+
+/// The plugin channels the server listens on, along with the handlers plugins registered for them.
+#[derive(Default)]
+pub struct PluginChannels {
+    channels: RwLock<HashMap<ResourceLocation, Vec<RegisteredHandler>>>,
+}
+
+impl PluginChannels {
+    /// Adds a handler of `plugin` for `channel`, returns whether the server did not listen on the
+    /// channel before.
+    pub async fn register(
+        &self,
+        plugin: &str,
+        channel: ResourceLocation,
+        handler: Arc<dyn PluginMessageHandler>,
+    ) -> bool {
+        let mut channels = self.channels.write().await;
+        let handlers = channels.entry(channel).or_default();
+        handlers.push(RegisteredHandler {
+            plugin: plugin.to_string(),
+            handler,
+        });
+        handlers.len() == 1
+    }
+
+    /// Removes the handlers of `plugin` for `channel`, returns whether the server stopped
+    /// listening on the channel.
+    pub async fn unregister(&self, plugin: &str, channel: &ResourceLocation) -> bool {
+        let mut channels = self.channels.write().await;
+        let Some(handlers) = channels.get_mut(channel) else {
+            return false;
+        };
+        handlers.retain(|handler| handler.plugin != plugin);
+        if handlers.is_empty() {
+            channels.remove(channel);
+            return true;
+        }
+        false
+    }
+
+    /// Removes every handler of `plugin`, returns the channels the server stopped listening on.
+    pub async fn unregister_plugin(&self, plugin: &str) -> Vec<ResourceLocation> {
+        let mut channels = self.channels.write().await;
+        let mut closed = Vec::new();
+        channels.retain(|channel, handlers| {
+            handlers.retain(|handler| handler.plugin != plugin);
+            if handlers.is_empty() {
+                closed.push(channel.clone());
+            }
+            !handlers.is_empty()
+        });
+        closed
+    }
+
+    /// The channels the server listens on.
+    pub async fn channels(&self) -> Vec<ResourceLocation> {
+        self.channels.read().await.keys().cloned().collect()
+    }
+
+    /// Passes a message to the handlers of its channel, returns false if there are none.
+    pub async fn dispatch(
+        &self,
+        sender: MessageSender<'_>,
+        channel: &ResourceLocation,
+        data: &[u8],
+    ) -> bool {
+        // Handlers may register channels themselves, so they run without the lock held
+        let handlers: Vec<_> = match self.channels.read().await.get(channel) {
+            Some(handlers) => handlers
+                .iter()
+                .map(|handler| handler.handler.clone())
+                .collect(),
+            None => return false,
+        };
+        for handler in handlers {
+            handler.handle(sender, channel, data).await;
+        }
+        true
+    }
+}
+
+/// Tells the online Java players that the server started or stopped listening on `channels`
+pub async fn announce_channels(server: &Server, channels: &[ResourceLocation], open: bool) {
+    if channels.is_empty() {
+        return;
+    }
+    for player in server.get_all_players().await {
+        if let ClientPlatform::Java(java) = &player.client {
+            if open {
+                java.register_channels(channels).await;
+            } else {
+                java.unregister_channels(channels).await;
+            }
+        }
+    }
+}
+
+/// Joins channel names the way `minecraft:register` and `minecraft:unregister` expect them
+#[must_use]
+pub fn encode_channel_list(channels: &[ResourceLocation]) -> Vec<u8> {
+    channels
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\0")
+        .into_bytes()
+}
+
+/// Reads the channel names of a `minecraft:register` or `minecraft:unregister` payload, skipping
+/// invalid ones
+#[must_use]
+pub fn decode_channel_list(data: &[u8]) -> Vec<ResourceLocation> {
+    String::from_utf8_lossy(data)
+        .split('\0')
+        .filter_map(|channel| channel.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn channel_lists_round_trip() {
+        let channels = [
+            ResourceLocation::from("velocity:player_info"),
+            ResourceLocation::from("myserver:menu"),
+        ];
+        let encoded = encode_channel_list(&channels);
+
+        assert_eq!(encoded, b"velocity:player_info\0myserver:menu");
+        assert_eq!(decode_channel_list(&encoded), channels);
+    }
+
+    #[test]
+    fn invalid_channels_are_skipped() {
+        assert_eq!(
+            decode_channel_list(b"no_namespace\0fabric:registry/sync\0"),
+            [ResourceLocation::from("fabric:registry/sync")]
+        );
+    }
+}
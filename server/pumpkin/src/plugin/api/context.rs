@@ -8,6 +8,8 @@ use crate::{LoggerOption, command::client_suggestions};
 use pumpkin_util::{
     PermissionLvl,
     permission::{Permission, PermissionManager},
+    resource_location::ResourceLocation,
+    text::TextComponent,
 };
 use tokio::sync::RwLock;
 
@@ -17,7 +19,13 @@ use crate::{
     server::Server,
 };
 
-use super::{EventPriority, Payload, PluginMetadata};
+use super::{
+    EventPriority, Payload, PluginMetadata,
+    channels::{PluginMessageHandler, announce_channels},
+    menu::{Menu, MenuType},
+    packets::{PacketFilter, PacketListener},
+    scheduler::TaskHandle,
+};
 
 /// The `Context` struct represents the context of a plugin, containing metadata,
 /// a server reference, and event handlers.
@@ -76,6 +84,23 @@ impl Context {
         path
     }
 
+    /// Creates a key in the plugin's namespace, for the persistent data of entities, items,
+    /// chunks and worlds.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// let coins = context.namespaced_key("coins");
+    /// player.living_entity.entity.persistent_data.lock().await.set(&coins, 10i64);
+    /// ```
+    #[must_use]
+    pub fn namespaced_key(&self, key: &str) -> ResourceLocation {
+        ResourceLocation {
+            namespace: self.metadata.name.to_lowercase(),
+            path: key.to_string(),
+        }
+    }
+
     /// Asynchronously retrieves a player by their name.
     ///
     /// # Arguments
@@ -87,6 +112,58 @@ impl Context {
         self.server.get_player_by_name(&player_name).await
     }
 
+    /// Requests a cookie from every Java client while it logs in.
+    ///
+    /// The cookies are available through `Player::login_cookie` once the player joins, e.g. in
+    /// the `PlayerTransferJoinEvent` or `PlayerLoginEvent`.
+    ///
+    /// # Arguments
+    /// - `key`: The key the cookie is stored under.
+    pub async fn register_login_cookie(&self, key: ResourceLocation) {
+        self.server.login_cookies.write().await.insert(key);
+    }
+
+    /// Listens on a plugin channel, passing the messages clients send on it to `handler`.
+    ///
+    /// Clients are told about the channel through `minecraft:register`, so mods and proxies know
+    /// the server listens on it. Messages are sent with `Player::send_custom_payload`.
+    ///
+    /// # Arguments
+    /// - `channel`: The channel to listen on, which can't be in the `minecraft` namespace.
+    /// - `handler`: The handler for the messages.
+    pub async fn register_channel(
+        &self,
+        channel: ResourceLocation,
+        handler: Arc<dyn PluginMessageHandler>,
+    ) -> Result<(), String> {
+        if channel.namespace == "minecraft" {
+            return Err(format!("Channel {channel} is reserved by the game"));
+        }
+        let plugin_channels = &self.server.plugin_channels;
+        if plugin_channels
+            .register(self.metadata.name, channel.clone(), handler)
+            .await
+        {
+            announce_channels(&self.server, &[channel], true).await;
+        }
+        Ok(())
+    }
+
+    /// Stops listening on a plugin channel. Other plugins listening on it keep receiving its
+    /// messages.
+    ///
+    /// # Arguments
+    /// - `channel`: The channel to stop listening on.
+    pub async fn unregister_channel(&self, channel: &ResourceLocation) {
+        let plugin_channels = &self.server.plugin_channels;
+        if plugin_channels
+            .unregister(self.metadata.name, channel)
+            .await
+        {
+            announce_channels(&self.server, std::slice::from_ref(channel), false).await;
+        }
+    }
+
     /// Registers a service with the plugin context.
     ///
     /// This method allows you to associate a service instance with a given name,
@@ -109,7 +186,7 @@ impl Context {
         service: Arc<T>,
     ) {
         let mut services = self.plugin_manager.services.write().await;
-        services.insert(name.into(), service);
+        services.insert(name.into(), (self.metadata.name.to_string(), service));
     }
 
     /// Retrieves a registered service by name and type.
@@ -138,7 +215,7 @@ impl Context {
     /// ```
     pub async fn get_service<T: Payload + 'static>(&self, name: &str) -> Option<Arc<T>> {
         let services = self.plugin_manager.services.read().await;
-        let service = services.get(name)?.clone();
+        let (_, service) = services.get(name)?.clone();
         <dyn Payload>::downcast_arc::<T>(service)
     }
 
@@ -161,10 +238,19 @@ impl Context {
             format!("{plugin_name}:{permission}")
         };
 
+        // Commands are unregistered along with the plugin
+        let name = tree.names[0].clone();
         {
             let mut dispatcher_lock = self.server.command_dispatcher.write().await;
             dispatcher_lock.register(tree, full_permission_node);
         };
+        {
+            let mut commands = self.plugin_manager.commands.write().await;
+            let commands = commands.entry(plugin_name.to_string()).or_default();
+            if !commands.contains(&name) {
+                commands.push(name);
+            }
+        }
 
         for world in self.server.worlds.read().await.iter() {
             for player in world.players.read().await.values() {
@@ -183,6 +269,12 @@ impl Context {
             let mut dispatcher_lock = self.server.command_dispatcher.write().await;
             dispatcher_lock.unregister(name);
         };
+        {
+            let mut commands = self.plugin_manager.commands.write().await;
+            if let Some(commands) = commands.get_mut(self.metadata.name) {
+                commands.retain(|command| command != name);
+            }
+        }
 
         for world in self.server.worlds.read().await.iter() {
             for player in world.players.read().await.values() {
@@ -213,11 +305,31 @@ impl Context {
         let permission_manager = self.permission_manager.read().await;
 
         // If the player isn't online, we need to find their op level
-        let player_op_level = (self.server.get_player_by_uuid(*player_uuid).await)
-            .map_or(PermissionLvl::Zero, |player| player.permission_lvl.load());
+        let player = self.server.get_player_by_uuid(*player_uuid).await;
+        let player_op_level =
+            (player.as_ref()).map_or(PermissionLvl::Zero, |player| player.permission_lvl.load());
+        // Per world overrides only apply while the player is in that world
+        let world = (player.as_ref()).map(|player| {
+            player
+                .world()
+                .dimension_type
+                .resource_location()
+                .to_string()
+        });
 
         permission_manager
-            .has_permission(player_uuid, permission, player_op_level)
+            .has_permission(player_uuid, permission, player_op_level, world.as_deref())
+            .await
+    }
+
+    /// Takes the state the previous instance of the plugin saved in `Plugin::save_state` before it
+    /// was reloaded. Call this in `on_load`, the state is dropped once the plugin is loaded.
+    ///
+    /// # Returns
+    /// The saved state, or `None` if the plugin isn't being reloaded or saved nothing.
+    pub async fn take_saved_state(&self) -> Option<Vec<u8>> {
+        self.plugin_manager
+            .take_saved_state(self.metadata.name)
             .await
     }
 
@@ -252,11 +364,135 @@ impl Context {
             handler,
             priority,
             blocking,
+            plugin: Some(self.metadata.name.to_string()),
             _phantom: std::marker::PhantomData,
         };
         handlers_vec.push(Box::new(typed_handler));
     }
 
+    /// Runs a task once after the given number of ticks.
+    ///
+    /// The task runs during the server tick, so it is delayed by `/tick freeze` and sped up by
+    /// `/tick sprint`. It is cancelled when the plugin is unloaded.
+    ///
+    /// # Arguments
+    /// - `delay`: The ticks to wait, 0 runs the task on the next tick.
+    /// - `task`: The task to run.
+    ///
+    /// # Returns
+    /// A handle to cancel the task.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// context
+    ///     .run_later(20, |server| async move {
+    ///         log::info!("One second later, {} players are online", server.get_player_count().await);
+    ///     })
+    ///     .await;
+    /// ```
+    pub async fn run_later<F, Fut>(&self, delay: u64, task: F) -> TaskHandle
+    where
+        F: FnOnce(Arc<Server>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.plugin_manager
+            .scheduler
+            .run_later(self.metadata.name, delay, task)
+            .await
+    }
+
+    /// Runs a task after `delay` ticks and then every `period` ticks, until it is cancelled or
+    /// the plugin is unloaded.
+    ///
+    /// # Arguments
+    /// - `delay`: The ticks to wait before the first run.
+    /// - `period`: The ticks between two runs, at least 1.
+    /// - `task`: The task to run.
+    ///
+    /// # Returns
+    /// A handle to cancel the task.
+    pub async fn run_repeating<F, Fut>(&self, delay: u64, period: u64, task: F) -> TaskHandle
+    where
+        F: FnMut(Arc<Server>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.plugin_manager
+            .scheduler
+            .run_repeating(self.metadata.name, delay, period, task)
+            .await
+    }
+
+    /// Runs a task in the background, independent of the ticks. Use this for slow work such as
+    /// file or network access, which would otherwise hold up the server tick.
+    ///
+    /// # Arguments
+    /// - `task`: The future to run.
+    ///
+    /// # Returns
+    /// A handle to cancel the task.
+    pub async fn run_async<Fut>(&self, task: Fut) -> TaskHandle
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.plugin_manager
+            .scheduler
+            .run_async(self.metadata.name, task)
+            .await
+    }
+
+    /// Creates a menu, a chest-like screen whose items the plugin controls. It is closed for all
+    /// viewers when the plugin is unloaded.
+    ///
+    /// # Arguments
+    /// - `menu_type`: The container type, which decides the number of slots.
+    /// - `title`: The title shown at the top of the screen.
+    ///
+    /// # Returns
+    /// The menu, which can be filled and opened for players.
+    pub async fn create_menu(&self, menu_type: MenuType, title: TextComponent) -> Arc<Menu> {
+        let menu = Menu::new(menu_type, title);
+        self.plugin_manager
+            .menus
+            .track(self.metadata.name, &menu)
+            .await;
+        menu
+    }
+
+    /// Registers a listener for the packets exchanged with players, to see, cancel or rewrite
+    /// packets no event covers. It is unregistered when the plugin is unloaded.
+    ///
+    /// # Performance
+    /// The listener runs for every matching packet on the task that handles or sends it. For
+    /// serverbound packets that is the player's connection, for clientbound ones often the tick,
+    /// which waits for the listener to be done. Listen to as few packet ids as possible, keep the
+    /// listener fast and move slow work into tasks. How long it takes is exported as `pumpkin_plugin_packet_listener_seconds` in
+    /// the server metrics.
+    ///
+    /// # Arguments
+    /// - `filter`: The edition, direction and ids of the packets to listen to.
+    /// - `listener`: The listener to call for these packets.
+    pub async fn register_packet_listener<L: PacketListener + 'static>(
+        &self,
+        filter: PacketFilter,
+        listener: Arc<L>,
+    ) {
+        if filter.ids.is_empty() {
+            log::warn!(
+                "Plugin {} listens to every {:?} {:?} packet, which slows down all connections",
+                self.metadata.name,
+                filter.edition,
+                filter.direction
+            );
+        }
+        self.plugin_manager.packet_listeners.register(
+            self.metadata.name,
+            std::any::type_name::<L>(),
+            filter,
+            listener,
+        );
+    }
+
     /// Registers a custom plugin loader that can load additional plugin types.
     ///
     /// This method allows plugins to extend the server with support for loading
@@ -0,0 +1,65 @@
+use pumpkin_data::damage::DamageType;
+use pumpkin_macros::{Event, cancellable};
+use std::sync::Arc;
+
+use crate::entity::EntityBase;
+
+use super::EntityEvent;
+
+/// An event that occurs when a living entity is about to take damage.
+///
+/// If the event is cancelled, the entity takes no damage. The amount can be changed by handlers.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct EntityDamageEvent {
+    /// The entity taking damage.
+    pub entity: Arc<dyn EntityBase>,
+
+    /// The amount of damage, before the hurt cooldown is applied.
+    pub amount: f32,
+
+    /// The type of damage, e.g. fall or fire damage.
+    pub damage_type: DamageType,
+
+    /// The entity that directly dealt the damage, like an arrow, if any.
+    pub source: Option<Arc<dyn EntityBase>>,
+
+    /// The entity responsible for the damage, like the shooter of an arrow, if any.
+    pub cause: Option<Arc<dyn EntityBase>>,
+}
+
+impl EntityDamageEvent {
+    /// Creates a new instance of `EntityDamageEvent`.
+    ///
+    /// # Arguments
+    /// - `entity`: The entity taking damage.
+    /// - `amount`: The amount of damage.
+    /// - `damage_type`: The type of damage.
+    /// - `source`: The entity that directly dealt the damage, if any.
+    /// - `cause`: The entity responsible for the damage, if any.
+    ///
+    /// # Returns
+    /// A new instance of `EntityDamageEvent`.
+    pub fn new(
+        entity: Arc<dyn EntityBase>,
+        amount: f32,
+        damage_type: DamageType,
+        source: Option<Arc<dyn EntityBase>>,
+        cause: Option<Arc<dyn EntityBase>>,
+    ) -> Self {
+        Self {
+            entity,
+            amount,
+            damage_type,
+            source,
+            cause,
+            cancelled: false,
+        }
+    }
+}
+
+impl EntityEvent for EntityDamageEvent {
+    fn get_entity(&self) -> &Arc<dyn EntityBase> {
+        &self.entity
+    }
+}
@@ -0,0 +1,55 @@
+use pumpkin_data::damage::DamageType;
+use pumpkin_macros::Event;
+use std::sync::Arc;
+
+use crate::entity::EntityBase;
+
+use super::EntityEvent;
+
+/// An event that occurs when a living entity dies.
+///
+/// Death can't be prevented, but handlers can keep the entity from dropping its loot.
+#[derive(Event, Clone)]
+pub struct EntityDeathEvent {
+    /// The entity that died.
+    pub entity: Arc<dyn EntityBase>,
+
+    /// The type of damage that killed the entity.
+    pub damage_type: DamageType,
+
+    /// The entity responsible for the death, if any.
+    pub cause: Option<Arc<dyn EntityBase>>,
+
+    /// A boolean indicating whether the entity should drop its loot.
+    pub drop_loot: bool,
+}
+
+impl EntityDeathEvent {
+    /// Creates a new instance of `EntityDeathEvent`.
+    ///
+    /// # Arguments
+    /// - `entity`: The entity that died.
+    /// - `damage_type`: The type of damage that killed the entity.
+    /// - `cause`: The entity responsible for the death, if any.
+    ///
+    /// # Returns
+    /// A new instance of `EntityDeathEvent`.
+    pub fn new(
+        entity: Arc<dyn EntityBase>,
+        damage_type: DamageType,
+        cause: Option<Arc<dyn EntityBase>>,
+    ) -> Self {
+        Self {
+            entity,
+            damage_type,
+            cause,
+            drop_loot: true,
+        }
+    }
+}
+
+impl EntityEvent for EntityDeathEvent {
+    fn get_entity(&self) -> &Arc<dyn EntityBase> {
+        &self.entity
+    }
+}
@@ -0,0 +1,38 @@
+use pumpkin_macros::{Event, cancellable};
+use std::sync::Arc;
+
+use crate::entity::EntityBase;
+
+use super::EntityEvent;
+
+/// An event that occurs when an entity is added to a world.
+///
+/// If the event is cancelled, the entity will not be spawned.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct EntitySpawnEvent {
+    /// The entity being spawned.
+    pub entity: Arc<dyn EntityBase>,
+}
+
+impl EntitySpawnEvent {
+    /// Creates a new instance of `EntitySpawnEvent`.
+    ///
+    /// # Arguments
+    /// - `entity`: The entity being spawned.
+    ///
+    /// # Returns
+    /// A new instance of `EntitySpawnEvent`.
+    pub fn new(entity: Arc<dyn EntityBase>) -> Self {
+        Self {
+            entity,
+            cancelled: false,
+        }
+    }
+}
+
+impl EntityEvent for EntitySpawnEvent {
+    fn get_entity(&self) -> &Arc<dyn EntityBase> {
+        &self.entity
+    }
+}
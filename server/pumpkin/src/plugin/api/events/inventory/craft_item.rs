@@ -0,0 +1,43 @@
+use pumpkin_macros::{Event, cancellable};
+use pumpkin_world::item::ItemStack;
+use std::sync::Arc;
+
+use crate::entity::player::Player;
+use crate::plugin::player::PlayerEvent;
+
+/// An event that occurs when a player takes the result out of a crafting grid.
+///
+/// If the event is cancelled, nothing is crafted and the ingredients stay in the grid.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct CraftItemEvent {
+    /// The player who is crafting.
+    pub player: Arc<Player>,
+
+    /// A copy of the item stack being crafted.
+    pub result: ItemStack,
+}
+
+impl CraftItemEvent {
+    /// Creates a new instance of `CraftItemEvent`.
+    ///
+    /// # Arguments
+    /// - `player`: A reference to the player who is crafting.
+    /// - `result`: A copy of the item stack being crafted.
+    ///
+    /// # Returns
+    /// A new instance of `CraftItemEvent`.
+    pub fn new(player: Arc<Player>, result: ItemStack) -> Self {
+        Self {
+            player,
+            result,
+            cancelled: false,
+        }
+    }
+}
+
+impl PlayerEvent for CraftItemEvent {
+    fn get_player(&self) -> &Arc<Player> {
+        &self.player
+    }
+}
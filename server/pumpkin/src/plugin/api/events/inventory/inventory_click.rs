@@ -0,0 +1,53 @@
+use pumpkin_macros::{Event, cancellable};
+use pumpkin_protocol::java::server::play::SlotActionType;
+use std::sync::Arc;
+
+use crate::entity::player::Player;
+use crate::plugin::player::PlayerEvent;
+
+/// An event that occurs when a player clicks a slot of the screen they have open.
+///
+/// If the event is cancelled, the click has no effect and the screen is synced back to the player.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct InventoryClickEvent {
+    /// The player who clicked.
+    pub player: Arc<Player>,
+
+    /// The index of the clicked slot in the screen, -999 for clicks outside of it.
+    pub slot: i16,
+
+    /// The mouse button or hotbar key used, its meaning depends on the action.
+    pub button: i8,
+
+    /// What the click does, e.g. picking up, quick moving or throwing items.
+    pub action: SlotActionType,
+}
+
+impl InventoryClickEvent {
+    /// Creates a new instance of `InventoryClickEvent`.
+    ///
+    /// # Arguments
+    /// - `player`: A reference to the player who clicked.
+    /// - `slot`: The index of the clicked slot.
+    /// - `button`: The mouse button or hotbar key used.
+    /// - `action`: What the click does.
+    ///
+    /// # Returns
+    /// A new instance of `InventoryClickEvent`.
+    pub fn new(player: Arc<Player>, slot: i16, button: i8, action: SlotActionType) -> Self {
+        Self {
+            player,
+            slot,
+            button,
+            action,
+            cancelled: false,
+        }
+    }
+}
+
+impl PlayerEvent for InventoryClickEvent {
+    fn get_player(&self) -> &Arc<Player> {
+        &self.player
+    }
+}
@@ -0,0 +1,33 @@
+use pumpkin_macros::Event;
+use std::sync::Arc;
+
+use crate::entity::player::Player;
+use crate::plugin::player::PlayerEvent;
+
+/// An event that occurs when the screen a player has open is closed.
+///
+/// The client closes screens on its own, so this event can't be cancelled.
+#[derive(Event, Clone)]
+pub struct InventoryCloseEvent {
+    /// The player whose screen is closed.
+    pub player: Arc<Player>,
+}
+
+impl InventoryCloseEvent {
+    /// Creates a new instance of `InventoryCloseEvent`.
+    ///
+    /// # Arguments
+    /// - `player`: A reference to the player whose screen is closed.
+    ///
+    /// # Returns
+    /// A new instance of `InventoryCloseEvent`.
+    pub fn new(player: Arc<Player>) -> Self {
+        Self { player }
+    }
+}
+
+impl PlayerEvent for InventoryCloseEvent {
+    fn get_player(&self) -> &Arc<Player> {
+        &self.player
+    }
+}
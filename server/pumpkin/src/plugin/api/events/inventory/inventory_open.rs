@@ -0,0 +1,43 @@
+use pumpkin_macros::{Event, cancellable};
+use pumpkin_util::text::TextComponent;
+use std::sync::Arc;
+
+use crate::entity::player::Player;
+use crate::plugin::player::PlayerEvent;
+
+/// An event that occurs when a screen like a chest or a furnace is opened for a player.
+///
+/// If the event is cancelled, the screen is not opened.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct InventoryOpenEvent {
+    /// The player the screen is opened for.
+    pub player: Arc<Player>,
+
+    /// The title of the screen.
+    pub title: TextComponent,
+}
+
+impl InventoryOpenEvent {
+    /// Creates a new instance of `InventoryOpenEvent`.
+    ///
+    /// # Arguments
+    /// - `player`: A reference to the player the screen is opened for.
+    /// - `title`: The title of the screen.
+    ///
+    /// # Returns
+    /// A new instance of `InventoryOpenEvent`.
+    pub fn new(player: Arc<Player>, title: TextComponent) -> Self {
+        Self {
+            player,
+            title,
+            cancelled: false,
+        }
+    }
+}
+
+impl PlayerEvent for InventoryOpenEvent {
+    fn get_player(&self) -> &Arc<Player> {
+        &self.player
+    }
+}
@@ -0,0 +1,4 @@
+pub mod craft_item;
+pub mod inventory_click;
+pub mod inventory_close;
+pub mod inventory_open;
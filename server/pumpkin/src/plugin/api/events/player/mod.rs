@@ -1,13 +1,18 @@
 pub mod player_change_world;
 pub mod player_chat;
 pub mod player_command_send;
+pub mod player_drop_item;
 pub mod player_gamemode_change;
 pub mod player_interact_event;
+pub mod player_item_consume;
 pub mod player_join;
 pub mod player_leave;
 pub mod player_login;
 pub mod player_move;
+pub mod player_pickup_item;
+pub mod player_respawn;
 pub mod player_teleport;
+pub mod player_transfer_join;
 
 use std::sync::Arc;
 
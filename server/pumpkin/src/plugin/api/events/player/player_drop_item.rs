@@ -0,0 +1,44 @@
+use pumpkin_macros::{Event, cancellable};
+use pumpkin_world::item::ItemStack;
+use std::sync::Arc;
+
+use crate::entity::player::Player;
+
+use super::PlayerEvent;
+
+/// An event that occurs when a player drops an item from their hand.
+///
+/// If the event is cancelled, the item stays in the hand of the player.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct PlayerDropItemEvent {
+    /// The player dropping the item.
+    pub player: Arc<Player>,
+
+    /// The item stack being dropped.
+    pub item_stack: ItemStack,
+}
+
+impl PlayerDropItemEvent {
+    /// Creates a new instance of `PlayerDropItemEvent`.
+    ///
+    /// # Arguments
+    /// - `player`: A reference to the player dropping the item.
+    /// - `item_stack`: A copy of the item stack being dropped.
+    ///
+    /// # Returns
+    /// A new instance of `PlayerDropItemEvent`.
+    pub fn new(player: Arc<Player>, item_stack: ItemStack) -> Self {
+        Self {
+            player,
+            item_stack,
+            cancelled: false,
+        }
+    }
+}
+
+impl PlayerEvent for PlayerDropItemEvent {
+    fn get_player(&self) -> &Arc<Player> {
+        &self.player
+    }
+}
@@ -0,0 +1,44 @@
+use pumpkin_macros::{Event, cancellable};
+use pumpkin_world::item::ItemStack;
+use std::sync::Arc;
+
+use crate::entity::player::Player;
+
+use super::PlayerEvent;
+
+/// An event that occurs when a player finishes eating or drinking an item.
+///
+/// If the event is cancelled, the item is neither consumed nor used up.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct PlayerItemConsumeEvent {
+    /// The player consuming the item.
+    pub player: Arc<Player>,
+
+    /// The item stack being consumed.
+    pub item_stack: ItemStack,
+}
+
+impl PlayerItemConsumeEvent {
+    /// Creates a new instance of `PlayerItemConsumeEvent`.
+    ///
+    /// # Arguments
+    /// - `player`: A reference to the player consuming the item.
+    /// - `item_stack`: A copy of the item stack being consumed.
+    ///
+    /// # Returns
+    /// A new instance of `PlayerItemConsumeEvent`.
+    pub fn new(player: Arc<Player>, item_stack: ItemStack) -> Self {
+        Self {
+            player,
+            item_stack,
+            cancelled: false,
+        }
+    }
+}
+
+impl PlayerEvent for PlayerItemConsumeEvent {
+    fn get_player(&self) -> &Arc<Player> {
+        &self.player
+    }
+}
@@ -0,0 +1,44 @@
+use pumpkin_macros::{Event, cancellable};
+use pumpkin_world::item::ItemStack;
+use std::sync::Arc;
+
+use crate::entity::player::Player;
+
+use super::PlayerEvent;
+
+/// An event that occurs when a player picks up an item lying on the ground.
+///
+/// If the event is cancelled, the item stays on the ground.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct PlayerPickupItemEvent {
+    /// The player picking up the item.
+    pub player: Arc<Player>,
+
+    /// The item stack being picked up.
+    pub item_stack: ItemStack,
+}
+
+impl PlayerPickupItemEvent {
+    /// Creates a new instance of `PlayerPickupItemEvent`.
+    ///
+    /// # Arguments
+    /// - `player`: A reference to the player picking up the item.
+    /// - `item_stack`: A copy of the item stack being picked up.
+    ///
+    /// # Returns
+    /// A new instance of `PlayerPickupItemEvent`.
+    pub fn new(player: Arc<Player>, item_stack: ItemStack) -> Self {
+        Self {
+            player,
+            item_stack,
+            cancelled: false,
+        }
+    }
+}
+
+impl PlayerEvent for PlayerPickupItemEvent {
+    fn get_player(&self) -> &Arc<Player> {
+        &self.player
+    }
+}
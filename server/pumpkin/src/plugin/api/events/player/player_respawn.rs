@@ -0,0 +1,47 @@
+use pumpkin_macros::Event;
+use pumpkin_util::math::vector3::Vector3;
+use std::sync::Arc;
+
+use crate::entity::player::Player;
+
+use super::PlayerEvent;
+
+/// An event that occurs when a player respawns after dying.
+///
+/// Respawning can't be prevented, but handlers can move the respawn location.
+#[derive(Event, Clone)]
+pub struct PlayerRespawnEvent {
+    /// The player who is respawning.
+    pub player: Arc<Player>,
+
+    /// The position the player respawns at.
+    pub position: Vector3<f64>,
+
+    /// The yaw the player faces after respawning.
+    pub yaw: f32,
+}
+
+impl PlayerRespawnEvent {
+    /// Creates a new instance of `PlayerRespawnEvent`.
+    ///
+    /// # Arguments
+    /// - `player`: A reference to the player who is respawning.
+    /// - `position`: The position the player respawns at.
+    /// - `yaw`: The yaw the player faces after respawning.
+    ///
+    /// # Returns
+    /// A new instance of `PlayerRespawnEvent`.
+    pub fn new(player: Arc<Player>, position: Vector3<f64>, yaw: f32) -> Self {
+        Self {
+            player,
+            position,
+            yaw,
+        }
+    }
+}
+
+impl PlayerEvent for PlayerRespawnEvent {
+    fn get_player(&self) -> &Arc<Player> {
+        &self.player
+    }
+}
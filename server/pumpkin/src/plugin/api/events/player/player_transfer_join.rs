@@ -0,0 +1,45 @@
+use pumpkin_macros::{Event, cancellable};
+use pumpkin_util::text::TextComponent;
+use std::sync::Arc;
+
+use crate::entity::player::Player;
+
+use super::PlayerEvent;
+
+/// An event that occurs when a player joins after being transferred here from another server.
+///
+/// It is fired before the `PlayerLoginEvent`. The cookies requested during login are already
+/// available, see `Player::login_cookie`. If the event is cancelled, the player will be kicked.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct PlayerTransferJoinEvent {
+    /// The player who was transferred.
+    pub player: Arc<Player>,
+
+    /// The kick message to display if the event is cancelled.
+    pub kick_message: TextComponent,
+}
+
+impl PlayerTransferJoinEvent {
+    /// Creates a new instance of `PlayerTransferJoinEvent`.
+    ///
+    /// # Arguments
+    /// - `player`: A reference to the transferred player.
+    /// - `kick_message`: The message to display if the transfer is refused.
+    ///
+    /// # Returns
+    /// A new instance of `PlayerTransferJoinEvent`.
+    pub fn new(player: Arc<Player>, kick_message: TextComponent) -> Self {
+        Self {
+            player,
+            kick_message,
+            cancelled: false,
+        }
+    }
+}
+
+impl PlayerEvent for PlayerTransferJoinEvent {
+    fn get_player(&self) -> &Arc<Player> {
+        &self.player
+    }
+}
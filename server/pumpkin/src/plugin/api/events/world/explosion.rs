@@ -0,0 +1,51 @@
+use crate::world::World;
+use pumpkin_macros::{Event, cancellable};
+use pumpkin_util::math::{position::BlockPos, vector3::Vector3};
+use std::sync::Arc;
+
+/// An event that occurs when an explosion is about to destroy blocks.
+///
+/// If the event is cancelled, nothing is destroyed. Handlers can remove positions from `blocks`
+/// to protect single blocks.
+#[cancellable]
+#[derive(Event, Clone)]
+pub struct ExplosionEvent {
+    /// The world in which the explosion happens.
+    pub world: Arc<World>,
+
+    /// The center of the explosion.
+    pub position: Vector3<f64>,
+
+    /// The power of the explosion, 4 for TNT.
+    pub power: f32,
+
+    /// The positions of the blocks the explosion destroys.
+    pub blocks: Vec<BlockPos>,
+}
+
+impl ExplosionEvent {
+    /// Creates a new instance of `ExplosionEvent`.
+    ///
+    /// # Arguments
+    /// - `world`: The world in which the explosion happens.
+    /// - `position`: The center of the explosion.
+    /// - `power`: The power of the explosion.
+    /// - `blocks`: The positions of the blocks the explosion destroys.
+    ///
+    /// # Returns
+    /// A new instance of `ExplosionEvent`.
+    pub fn new(
+        world: Arc<World>,
+        position: Vector3<f64>,
+        power: f32,
+        blocks: Vec<BlockPos>,
+    ) -> Self {
+        Self {
+            world,
+            position,
+            power,
+            blocks,
+            cancelled: false,
+        }
+    }
+}
@@ -0,0 +1,745 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    pin::Pin,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use pumpkin_data::screen::WindowType;
+use pumpkin_inventory::{
+    generic_container_screen_handler::GenericContainerScreenHandler,
+    player::player_inventory::PlayerInventory,
+    screen_handler::{
+        BoxFuture as ScreenFuture, InventoryPlayer, ItemStackFuture, ScreenHandler,
+        ScreenHandlerBehaviour, ScreenHandlerFactory, ScreenHandlerFuture, SharedScreenHandler,
+    },
+};
+use pumpkin_protocol::java::server::play::SlotActionType;
+use pumpkin_util::text::TextComponent;
+use pumpkin_world::{
+    inventory::{Clearable, Inventory, InventoryFuture, split_stack},
+    item::ItemStack,
+};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::{entity::player::Player, plugin::BoxFuture};
+
+type ClickFn = Arc<dyn Fn(MenuClick) -> BoxFuture<'static, ()> + Send + Sync>;
+type ViewerFn = Arc<dyn Fn(Arc<Player>, Arc<Menu>) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// The layouts a menu can have, which are all shown with the generic container screens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuType {
+    Generic9x1,
+    Generic9x2,
+    Generic9x3,
+    Generic9x4,
+    Generic9x5,
+    Generic9x6,
+    Generic3x3,
+    Hopper,
+}
+
+impl MenuType {
+    const fn window_type(self) -> WindowType {
+        match self {
+            Self::Generic9x1 => WindowType::Generic9x1,
+            Self::Generic9x2 => WindowType::Generic9x2,
+            Self::Generic9x3 => WindowType::Generic9x3,
+            Self::Generic9x4 => WindowType::Generic9x4,
+            Self::Generic9x5 => WindowType::Generic9x5,
+            Self::Generic9x6 => WindowType::Generic9x6,
+            Self::Generic3x3 => WindowType::Generic3x3,
+            Self::Hopper => WindowType::Hopper,
+        }
+    }
+
+    #[must_use]
+    pub const fn rows(self) -> u8 {
+        match self {
+            Self::Generic9x1 | Self::Hopper => 1,
+            Self::Generic9x2 => 2,
+            Self::Generic9x3 | Self::Generic3x3 => 3,
+            Self::Generic9x4 => 4,
+            Self::Generic9x5 => 5,
+            Self::Generic9x6 => 6,
+        }
+    }
+
+    #[must_use]
+    pub const fn columns(self) -> u8 {
+        match self {
+            Self::Generic3x3 => 3,
+            Self::Hopper => 5,
+            _ => 9,
+        }
+    }
+
+    /// The number of slots of the menu, not counting the player's inventory.
+    #[must_use]
+    pub const fn size(self) -> usize {
+        self.rows() as usize * self.columns() as usize
+    }
+}
+
+/// A click of a player on a slot of a menu.
+pub struct MenuClick {
+    /// The player who clicked.
+    pub player: Arc<Player>,
+    /// The menu that was clicked.
+    pub menu: Arc<Menu>,
+    /// The slot that was clicked, counted from the top left of the menu.
+    pub slot: usize,
+    /// What kind of click it was, e.g. a pickup, a number key swap or a throw.
+    pub action: SlotActionType,
+    /// The mouse button or, for swaps, the hotbar slot.
+    pub button: i8,
+    /// Whether shift was held.
+    pub shift: bool,
+}
+
+/// A virtual inventory that plugins show to players, e.g. for shops, kit selectors or settings.
+///
+/// The items of a menu can't be taken out of it, nor can items be put in, unless that is allowed
+/// with [`Menu::set_allow_extraction`]. Changes to the items are shown to the players viewing the
+/// menu with the next tick, or right away with [`Menu::update`].
+///
+/// Callbacks are run in their own task, so they may open other menus or close this one.
+pub struct Menu {
+    menu_type: MenuType,
+    title: TextComponent,
+    inventory: Arc<MenuInventory>,
+    click_handlers: RwLock<HashMap<usize, ClickFn>>,
+    open_handler: RwLock<Option<ViewerFn>>,
+    close_handler: RwLock<Option<ViewerFn>>,
+    allow_extraction: AtomicBool,
+    viewers: Mutex<Vec<Weak<Player>>>,
+}
+
+impl Menu {
+    /// Creates an empty menu, plugins should use `Context::create_menu`.
+    #[must_use]
+    pub fn new(menu_type: MenuType, title: TextComponent) -> Arc<Self> {
+        Arc::new(Self {
+            menu_type,
+            title,
+            inventory: Arc::new(MenuInventory::new(menu_type.size())),
+            click_handlers: RwLock::new(HashMap::new()),
+            open_handler: RwLock::new(None),
+            close_handler: RwLock::new(None),
+            allow_extraction: AtomicBool::new(false),
+            viewers: Mutex::new(Vec::new()),
+        })
+    }
+
+    #[must_use]
+    pub const fn menu_type(&self) -> MenuType {
+        self.menu_type
+    }
+
+    #[must_use]
+    pub fn title(&self) -> &TextComponent {
+        &self.title
+    }
+
+    /// Puts an item into a slot, slots outside of the menu are ignored.
+    ///
+    /// # Arguments
+    /// - `slot`: The slot, counted from the top left of the menu.
+    /// - `stack`: The item to show.
+    pub async fn set_item(&self, slot: usize, stack: ItemStack) {
+        if slot >= self.inventory.size() {
+            log::warn!(
+                "Slot {slot} is outside of a menu with {} slots",
+                self.inventory.size()
+            );
+            return;
+        }
+        self.inventory.set_stack(slot, stack).await;
+    }
+
+    /// Returns the item in a slot, an empty stack for slots outside of the menu.
+    pub async fn get_item(&self, slot: usize) -> ItemStack {
+        let Some(stack) = self.inventory.items.get(slot) else {
+            return ItemStack::EMPTY.clone();
+        };
+        stack.lock().await.clone()
+    }
+
+    /// Removes every item from the menu.
+    pub async fn clear_items(&self) {
+        self.inventory.clear().await;
+    }
+
+    /// Registers the callback for clicks on a slot, replacing the previous one.
+    ///
+    /// # Arguments
+    /// - `slot`: The slot, counted from the top left of the menu.
+    /// - `handler`: The callback to run for every click on the slot.
+    pub async fn on_click<F, Fut>(&self, slot: usize, handler: F)
+    where
+        F: Fn(MenuClick) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.click_handlers
+            .write()
+            .await
+            .insert(slot, Arc::new(move |click| Box::pin(handler(click))));
+    }
+
+    /// Removes the callback for clicks on a slot.
+    pub async fn remove_click_handler(&self, slot: usize) {
+        self.click_handlers.write().await.remove(&slot);
+    }
+
+    /// Registers the callback run when a player opens the menu, replacing the previous one.
+    pub async fn on_open<F, Fut>(&self, handler: F)
+    where
+        F: Fn(Arc<Player>, Arc<Self>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        *self.open_handler.write().await = Some(Arc::new(move |player, menu| {
+            Box::pin(handler(player, menu))
+        }));
+    }
+
+    /// Registers the callback run when a player closes the menu, replacing the previous one.
+    pub async fn on_close<F, Fut>(&self, handler: F)
+    where
+        F: Fn(Arc<Player>, Arc<Self>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        *self.close_handler.write().await = Some(Arc::new(move |player, menu| {
+            Box::pin(handler(player, menu))
+        }));
+    }
+
+    /// Removes all callbacks, e.g. when the plugin that registered them is unloaded.
+    pub async fn clear_handlers(&self) {
+        self.click_handlers.write().await.clear();
+        *self.open_handler.write().await = None;
+        *self.close_handler.write().await = None;
+    }
+
+    /// Sets whether players may take items out of the menu and put their own in, which is not
+    /// allowed by default.
+    pub fn set_allow_extraction(&self, allow: bool) {
+        self.allow_extraction.store(allow, Ordering::Relaxed);
+    }
+
+    #[must_use]
+    pub fn allows_extraction(&self) -> bool {
+        self.allow_extraction.load(Ordering::Relaxed)
+    }
+
+    /// Opens the menu for a player, closing the screen they have open.
+    ///
+    /// # Returns
+    /// Whether the menu was opened, which it isn't if a plugin cancelled the
+    /// `InventoryOpenEvent`.
+    pub async fn open(self: &Arc<Self>, player: &Arc<Player>) -> bool {
+        let factory = MenuScreenFactory {
+            menu: self.clone(),
+            player: Arc::downgrade(player),
+        };
+        if player.open_handled_screen(&factory).await.is_none() {
+            return false;
+        }
+
+        self.viewers.lock().await.push(Arc::downgrade(player));
+        let handler = self.open_handler.read().await.clone();
+        if let Some(handler) = handler {
+            tokio::spawn(handler(player.clone(), self.clone()));
+        }
+        true
+    }
+
+    /// The players that have the menu open.
+    pub async fn viewers(&self) -> Vec<Arc<Player>> {
+        self.viewers
+            .lock()
+            .await
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    /// Sends the current items to all players viewing the menu.
+    pub async fn update(self: &Arc<Self>) {
+        // The viewers aren't locked while the screens are, as closing a screen removes its viewer
+        for player in self.viewers().await {
+            let screen_handler = player.current_screen_handler.lock().await.clone();
+            let mut screen_handler = screen_handler.lock().await;
+            if self.is_shown_by(&*screen_handler) {
+                screen_handler.send_content_updates().await;
+            }
+        }
+    }
+
+    /// Closes the menu for all players viewing it.
+    pub async fn close(self: &Arc<Self>) {
+        for player in self.viewers().await {
+            let screen_handler = player.current_screen_handler.lock().await.clone();
+            if self.is_shown_by(&*screen_handler.lock().await) {
+                player.close_handled_screen().await;
+            }
+        }
+    }
+
+    fn is_shown_by(self: &Arc<Self>, screen_handler: &dyn ScreenHandler) -> bool {
+        screen_handler
+            .as_any()
+            .downcast_ref::<MenuScreenHandler>()
+            .is_some_and(|handler| Arc::ptr_eq(&handler.menu, self))
+    }
+
+    fn click(
+        self: &Arc<Self>,
+        player: &Weak<Player>,
+        slot: usize,
+        button: i32,
+        action: SlotActionType,
+    ) {
+        let Some(player) = player.upgrade() else {
+            return;
+        };
+        let menu = self.clone();
+        tokio::spawn(async move {
+            let handler = menu.click_handlers.read().await.get(&slot).cloned();
+            let Some(handler) = handler else {
+                return;
+            };
+            let click = MenuClick {
+                player,
+                menu: menu.clone(),
+                slot,
+                shift: action == SlotActionType::QuickMove,
+                action,
+                button: button as i8,
+            };
+            handler(click).await;
+        });
+    }
+
+    async fn closed_by(self: &Arc<Self>, player: &Weak<Player>) {
+        self.viewers
+            .lock()
+            .await
+            .retain(|viewer| viewer.strong_count() > 0 && !viewer.ptr_eq(player));
+        let handler = self.close_handler.read().await.clone();
+        if let Some(player) = player.upgrade()
+            && let Some(handler) = handler
+        {
+            tokio::spawn(handler(player, self.clone()));
+        }
+    }
+}
+
+/// Keeps track of the menus of every plugin, so they can be closed when it is unloaded.
+#[derive(Default)]
+pub struct MenuRegistry {
+    menus: Mutex<Vec<(String, Weak<Menu>)>>,
+}
+
+impl MenuRegistry {
+    pub async fn track(&self, plugin: &str, menu: &Arc<Menu>) {
+        let mut menus = self.menus.lock().await;
+        menus.retain(|(_, menu)| menu.strong_count() > 0);
+        menus.push((plugin.to_string(), Arc::downgrade(menu)));
+    }
+
+    /// Closes the menus of a plugin, without running its callbacks as its code may be unloaded.
+    pub async fn close_plugin_menus(&self, plugin: &str) {
+        let menus: Vec<Arc<Menu>> = {
+            let mut menus = self.menus.lock().await;
+            let (closed, kept) = menus.drain(..).partition(|(owner, _)| owner == plugin);
+            *menus = kept;
+            closed
+                .into_iter()
+                .filter_map(|(_, menu)| menu.upgrade())
+                .collect()
+        };
+        for menu in menus {
+            menu.clear_handlers().await;
+            menu.close().await;
+        }
+    }
+}
+
+/// The items shown by a menu.
+struct MenuInventory {
+    items: Box<[Arc<Mutex<ItemStack>>]>,
+}
+
+impl MenuInventory {
+    fn new(size: usize) -> Self {
+        Self {
+            items: (0..size)
+                .map(|_| Arc::new(Mutex::new(ItemStack::EMPTY.clone())))
+                .collect(),
+        }
+    }
+}
+
+impl Inventory for MenuInventory {
+    fn size(&self) -> usize {
+        self.items.len()
+    }
+
+    fn is_empty(&self) -> InventoryFuture<'_, bool> {
+        Box::pin(async move {
+            for slot in &self.items {
+                if !slot.lock().await.is_empty() {
+                    return false;
+                }
+            }
+
+            true
+        })
+    }
+
+    fn get_stack(&self, slot: usize) -> InventoryFuture<'_, Arc<Mutex<ItemStack>>> {
+        Box::pin(async move { self.items[slot].clone() })
+    }
+
+    fn remove_stack(&self, slot: usize) -> InventoryFuture<'_, ItemStack> {
+        Box::pin(async move {
+            let mut removed = ItemStack::EMPTY.clone();
+            let mut guard = self.items[slot].lock().await;
+            std::mem::swap(&mut removed, &mut *guard);
+            removed
+        })
+    }
+
+    fn remove_stack_specific(&self, slot: usize, amount: u8) -> InventoryFuture<'_, ItemStack> {
+        Box::pin(async move { split_stack(&self.items, slot, amount).await })
+    }
+
+    fn set_stack(&self, slot: usize, stack: ItemStack) -> InventoryFuture<'_, ()> {
+        Box::pin(async move {
+            *self.items[slot].lock().await = stack;
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Clearable for MenuInventory {
+    fn clear(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(async move {
+            for item in &self.items {
+                *item.lock().await = ItemStack::EMPTY.clone();
+            }
+        })
+    }
+}
+
+struct MenuScreenFactory {
+    menu: Arc<Menu>,
+    player: Weak<Player>,
+}
+
+impl ScreenHandlerFactory for MenuScreenFactory {
+    fn create_screen_handler<'a>(
+        &'a self,
+        sync_id: u8,
+        player_inventory: &'a Arc<PlayerInventory>,
+        _player: &'a dyn InventoryPlayer,
+    ) -> ScreenFuture<'a, Option<SharedScreenHandler>> {
+        Box::pin(async move {
+            let menu_type = self.menu.menu_type;
+            let inner = GenericContainerScreenHandler::new(
+                menu_type.window_type(),
+                sync_id,
+                player_inventory,
+                self.menu.inventory.clone(),
+                menu_type.rows(),
+                menu_type.columns(),
+            )
+            .await;
+            let handler = MenuScreenHandler {
+                menu: self.menu.clone(),
+                player: self.player.clone(),
+                inner,
+            };
+            Some(Arc::new(Mutex::new(handler)) as SharedScreenHandler)
+        })
+    }
+
+    fn get_display_name(&self) -> TextComponent {
+        self.menu.title.clone()
+    }
+}
+
+/// A generic container screen which hands clicks on the menu to its callbacks instead of moving
+/// the items, unless the menu allows extraction.
+struct MenuScreenHandler {
+    menu: Arc<Menu>,
+    player: Weak<Player>,
+    inner: GenericContainerScreenHandler,
+}
+
+impl ScreenHandler for MenuScreenHandler {
+    fn on_closed<'a>(&'a mut self, player: &'a dyn InventoryPlayer) -> ScreenHandlerFuture<'a, ()> {
+        Box::pin(async move {
+            self.inner.on_closed(player).await;
+            self.menu.closed_by(&self.player).await;
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_behaviour(&self) -> &ScreenHandlerBehaviour {
+        self.inner.get_behaviour()
+    }
+
+    fn get_behaviour_mut(&mut self) -> &mut ScreenHandlerBehaviour {
+        self.inner.get_behaviour_mut()
+    }
+
+    fn quick_move<'a>(
+        &'a mut self,
+        player: &'a dyn InventoryPlayer,
+        slot_index: i32,
+    ) -> ItemStackFuture<'a> {
+        Box::pin(async move {
+            if self.menu.allows_extraction() {
+                self.inner.quick_move(player, slot_index).await
+            } else {
+                ItemStack::EMPTY.clone()
+            }
+        })
+    }
+
+    fn on_slot_click<'a>(
+        &'a mut self,
+        slot_index: i32,
+        button: i32,
+        action_type: SlotActionType,
+        player: &'a dyn InventoryPlayer,
+    ) -> ScreenHandlerFuture<'a, ()> {
+        Box::pin(async move {
+            let menu_slot = usize::try_from(slot_index)
+                .ok()
+                .filter(|&slot| slot < self.menu.inventory.size());
+            // Dragging reports every slot it passes over, which aren't clicks
+            if let Some(slot) = menu_slot
+                && action_type != SlotActionType::QuickCraft
+            {
+                self.menu
+                    .click(&self.player, slot, button, action_type.clone());
+            }
+
+            // Shift clicks and double clicks in the player's inventory could move items from or
+            // into the menu as well. The client is sent the real contents after the click
+            let moves_menu_items = menu_slot.is_some()
+                || matches!(
+                    action_type,
+                    SlotActionType::QuickMove | SlotActionType::PickupAll
+                );
+            if self.menu.allows_extraction() || !moves_menu_items {
+                self.inner
+                    .on_slot_click(slot_index, button, action_type, player)
+                    .await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Weak};
+
+    use pumpkin_data::{data_component_impl::EquipmentSlot, item::Item};
+    use pumpkin_inventory::{
+        build_equipment_slots,
+        entity_equipment::EntityEquipment,
+        generic_container_screen_handler::GenericContainerScreenHandler,
+        player::player_inventory::PlayerInventory,
+        screen_handler::{InventoryPlayer, PlayerFuture, ScreenHandler},
+    };
+    use pumpkin_protocol::java::{
+        client::play::{
+            CSetContainerContent, CSetContainerProperty, CSetContainerSlot, CSetCursorItem,
+            CSetPlayerInventory, CSetSelectedSlot,
+        },
+        server::play::SlotActionType,
+    };
+    use pumpkin_util::text::TextComponent;
+    use pumpkin_world::{inventory::Inventory, item::ItemStack};
+    use tokio::sync::Mutex;
+
+    use super::{Menu, MenuScreenHandler, MenuType};
+
+    /// The first slot of the player's inventory below a 9x1 menu
+    const PLAYER_SLOT: i32 = 9;
+
+    /// A player without a connection, as the clicks only need their inventory
+    struct TestPlayer {
+        inventory: Arc<PlayerInventory>,
+    }
+
+    impl InventoryPlayer for TestPlayer {
+        fn drop_item(&self, _item: ItemStack, _retain_ownership: bool) -> PlayerFuture<'_, ()> {
+            Box::pin(async {})
+        }
+
+        fn get_inventory(&self) -> Arc<PlayerInventory> {
+            self.inventory.clone()
+        }
+
+        fn has_infinite_materials(&self) -> bool {
+            false
+        }
+
+        fn enqueue_inventory_packet<'a>(
+            &'a self,
+            _packet: &'a CSetContainerContent,
+        ) -> PlayerFuture<'a, ()> {
+            Box::pin(async {})
+        }
+
+        fn enqueue_slot_packet<'a>(
+            &'a self,
+            _packet: &'a CSetContainerSlot,
+        ) -> PlayerFuture<'a, ()> {
+            Box::pin(async {})
+        }
+
+        fn enqueue_cursor_packet<'a>(
+            &'a self,
+            _packet: &'a CSetCursorItem,
+        ) -> PlayerFuture<'a, ()> {
+            Box::pin(async {})
+        }
+
+        fn enqueue_property_packet<'a>(
+            &'a self,
+            _packet: &'a CSetContainerProperty,
+        ) -> PlayerFuture<'a, ()> {
+            Box::pin(async {})
+        }
+
+        fn enqueue_slot_set_packet<'a>(
+            &'a self,
+            _packet: &'a CSetPlayerInventory,
+        ) -> PlayerFuture<'a, ()> {
+            Box::pin(async {})
+        }
+
+        fn enqueue_set_held_item_packet<'a>(
+            &'a self,
+            _packet: &'a CSetSelectedSlot,
+        ) -> PlayerFuture<'a, ()> {
+            Box::pin(async {})
+        }
+
+        fn enqueue_equipment_change<'a>(
+            &'a self,
+            _slot: &'a EquipmentSlot,
+            _stack: &'a ItemStack,
+        ) -> PlayerFuture<'a, ()> {
+            Box::pin(async {})
+        }
+    }
+
+    /// A menu with stone in its first slot, shown to a player with dirt in their first slot
+    async fn open_menu() -> (Arc<Menu>, MenuScreenHandler, TestPlayer) {
+        let menu = Menu::new(MenuType::Generic9x1, TextComponent::text("Shop"));
+        menu.set_item(0, ItemStack::new(1, &Item::STONE)).await;
+        let player = TestPlayer {
+            inventory: Arc::new(PlayerInventory::new(
+                Arc::new(Mutex::new(EntityEquipment::new())),
+                Arc::new(build_equipment_slots()),
+            )),
+        };
+        player
+            .inventory
+            .set_stack(PLAYER_SLOT as usize, ItemStack::new(1, &Item::DIRT))
+            .await;
+
+        let menu_type = menu.menu_type;
+        let inner = GenericContainerScreenHandler::new(
+            menu_type.window_type(),
+            1,
+            &player.inventory,
+            menu.inventory.clone(),
+            menu_type.rows(),
+            menu_type.columns(),
+        )
+        .await;
+        let handler = MenuScreenHandler {
+            menu: menu.clone(),
+            // Nobody to run the callbacks for
+            player: Weak::new(),
+            inner,
+        };
+        (menu, handler, player)
+    }
+
+    async fn cursor(handler: &MenuScreenHandler) -> ItemStack {
+        handler.get_behaviour().cursor_stack.lock().await.clone()
+    }
+
+    async fn player_item(player: &TestPlayer) -> ItemStack {
+        let stack = player.inventory.get_stack(PLAYER_SLOT as usize).await;
+        stack.lock().await.clone()
+    }
+
+    #[tokio::test]
+    async fn menu_items_cant_be_taken() {
+        let (menu, mut handler, player) = open_menu().await;
+
+        for action in [
+            SlotActionType::Pickup,
+            SlotActionType::QuickMove,
+            SlotActionType::Throw,
+            SlotActionType::Swap,
+        ] {
+            handler.on_slot_click(0, 0, action, &player).await;
+        }
+        assert_eq!(menu.get_item(0).await.item.id, Item::STONE.id);
+        assert!(cursor(&handler).await.is_empty());
+        assert_eq!(player_item(&player).await.item.id, Item::DIRT.id);
+
+        menu.set_allow_extraction(true);
+        handler
+            .on_slot_click(0, 0, SlotActionType::Pickup, &player)
+            .await;
+        assert!(menu.get_item(0).await.is_empty());
+        assert_eq!(cursor(&handler).await.item.id, Item::STONE.id);
+    }
+
+    #[tokio::test]
+    async fn player_items_cant_be_moved_in() {
+        let (menu, mut handler, player) = open_menu().await;
+
+        // Shift clicking would move the dirt into the menu
+        handler
+            .on_slot_click(PLAYER_SLOT, 0, SlotActionType::QuickMove, &player)
+            .await;
+        assert_eq!(player_item(&player).await.item.id, Item::DIRT.id);
+        assert!(menu.get_item(1).await.is_empty());
+
+        // The player's own items can still be moved around
+        handler
+            .on_slot_click(PLAYER_SLOT, 0, SlotActionType::Pickup, &player)
+            .await;
+        assert!(player_item(&player).await.is_empty());
+        assert_eq!(cursor(&handler).await.item.id, Item::DIRT.id);
+
+        // But not put into the menu
+        handler
+            .on_slot_click(1, 0, SlotActionType::Pickup, &player)
+            .await;
+        assert!(menu.get_item(1).await.is_empty());
+        assert_eq!(cursor(&handler).await.item.id, Item::DIRT.id);
+    }
+}
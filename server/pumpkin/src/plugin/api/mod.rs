@@ -1,5 +1,9 @@
+pub mod channels;
 pub mod context;
 pub mod events;
+pub mod menu;
+pub mod packets;
+pub mod scheduler;
 
 use std::{pin::Pin, sync::Arc};
 
@@ -9,8 +13,9 @@ pub use events::*;
 /// Struct representing metadata for a plugin.
 ///
 /// This struct contains essential information about a plugin, including its name,
-/// version, authors, and a description. It is generic over a lifetime `'s` to allow
-/// for string slices that are valid for the lifetime of the plugin metadata.
+/// version, authors, a description and the plugins it has to be loaded with. It is generic
+/// over a lifetime `'s` to allow for string slices that are valid for the lifetime of the
+/// plugin metadata.
 #[derive(Debug, Clone)]
 pub struct PluginMetadata<'s> {
     /// The name of the plugin.
@@ -21,6 +26,15 @@ pub struct PluginMetadata<'s> {
     pub authors: &'s str,
     /// A description of the plugin.
     pub description: &'s str,
+    /// Plugins that have to be loaded before this one. The plugin isn't loaded without them.
+    pub depends: &'s [&'s str],
+    /// Plugins that are loaded before this one if they are present.
+    pub soft_depends: &'s [&'s str],
+    /// Plugins that are loaded after this one if they are present.
+    pub load_before: &'s [&'s str],
+    /// The server API versions the plugin works with, as a semver requirement like `^0.1`.
+    /// Empty if the plugin works with any version.
+    pub api_version: &'s str,
 }
 
 /// This type represents a future for the plugin.
@@ -56,4 +70,20 @@ pub trait Plugin: Send + Sync + 'static {
     fn on_unload(&mut self, _server: Arc<Context>) -> PluginFuture<'_, Result<(), String>> {
         Box::pin(async move { Ok(()) })
     }
+
+    /// Asynchronous method called before the plugin is unloaded to be reloaded, e.g. by
+    /// `/plugin reload`.
+    ///
+    /// The returned state is handed to the new instance of the plugin, which gets it with
+    /// `Context::take_saved_state` in its `on_load` rather than as an argument, so `on_load` stays
+    /// the same for the first load. `on_unload` is still called afterwards.
+    ///
+    /// # Parameters
+    /// - `_server`: Reference to the server's context.
+    ///
+    /// # Returns
+    /// - The state to hand over, or `None` if there is nothing to keep.
+    fn save_state(&mut self, _server: Arc<Context>) -> PluginFuture<'_, Option<Vec<u8>>> {
+        Box::pin(async move { None })
+    }
 }
@@ -0,0 +1,259 @@
+//! Packet listeners let plugins see, cancel and rewrite the packets exchanged with players, for
+//! what no event covers, e.g. hiding players or showing fake entities.
+//!
+//! Listeners run for every matching packet on the task that handles or sends it. Serverbound
+//! packets are intercepted on the connection's task, so a slow listener slows down the player's
+//! connection. Clientbound packets are intercepted when they are queued, which is often the tick,
+//! so a slow listener of them slows down the whole server. Listen to as few packet ids as possible
+//! and move slow work into tasks. How long each listener takes is exported in the server metrics.
+
+use std::{
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use bytes::Bytes;
+pub use pumpkin_protocol::recording::Direction;
+
+use crate::{
+    plugin::BoxFuture,
+    server::metrics::{Edition, METRICS},
+};
+
+/// A packet received from or about to be sent to a player.
+///
+/// Serverbound packets are intercepted before the server handles them, clientbound ones before
+/// they are translated for the client's version, compressed and encrypted. Both are in the
+/// format of the server's protocol version.
+pub struct InterceptedPacket {
+    /// The UUID of the player the packet is sent to or received from.
+    pub player: uuid::Uuid,
+    pub edition: Edition,
+    pub direction: Direction,
+    id: i32,
+    /// The packet without its id. Changing it changes what is handled or sent.
+    pub payload: Vec<u8>,
+    cancelled: bool,
+}
+
+impl InterceptedPacket {
+    #[must_use]
+    pub fn new(
+        player: uuid::Uuid,
+        edition: Edition,
+        direction: Direction,
+        id: i32,
+        payload: Vec<u8>,
+    ) -> Self {
+        Self {
+            player,
+            edition,
+            direction,
+            id,
+            payload,
+            cancelled: false,
+        }
+    }
+
+    /// The id of the packet in the server's protocol version.
+    #[must_use]
+    pub const fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// Drops the packet, so it is neither handled nor sent.
+    pub const fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    pub const fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+
+    #[must_use]
+    pub const fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+/// Which packets a listener is called for.
+#[derive(Clone, Debug)]
+pub struct PacketFilter {
+    pub edition: Edition,
+    pub direction: Direction,
+    /// The ids of the packets to listen to, every packet if empty.
+    pub ids: Vec<i32>,
+}
+
+impl PacketFilter {
+    #[must_use]
+    pub fn new(edition: Edition, direction: Direction, ids: &[i32]) -> Self {
+        Self {
+            edition,
+            direction,
+            ids: ids.to_vec(),
+        }
+    }
+
+    #[must_use]
+    pub fn matches(&self, edition: Edition, direction: Direction, id: i32) -> bool {
+        self.edition == edition
+            && self.direction == direction
+            && (self.ids.is_empty() || self.ids.contains(&id))
+    }
+}
+
+/// A listener for the packets exchanged with players, see the module documentation.
+pub trait PacketListener: Send + Sync {
+    /// Called for every packet matching the filter the listener was registered with.
+    ///
+    /// # Arguments
+    /// - `packet`: The packet, which can be cancelled or rewritten.
+    fn on_packet<'a>(&'a self, packet: &'a mut InterceptedPacket) -> BoxFuture<'a, ()>;
+}
+
+struct RegisteredListener {
+    plugin: Arc<str>,
+    /// The type name of the listener, used in the metrics
+    name: Arc<str>,
+    filter: PacketFilter,
+    listener: Arc<dyn PacketListener>,
+}
+
+/// The packet listeners of all plugins.
+#[derive(Default)]
+pub struct PacketListeners {
+    /// Whether any listener is registered, so packets aren't slowed down while there is none
+    active: AtomicBool,
+    // Never locked across an await, the listeners are called after it was released
+    listeners: RwLock<Vec<Arc<RegisteredListener>>>,
+}
+
+impl PacketListeners {
+    pub fn register(
+        &self,
+        plugin: &str,
+        name: &str,
+        filter: PacketFilter,
+        listener: Arc<dyn PacketListener>,
+    ) {
+        let mut listeners = self.listeners.write().unwrap();
+        listeners.push(Arc::new(RegisteredListener {
+            plugin: plugin.into(),
+            name: name.into(),
+            filter,
+            listener,
+        }));
+        self.active.store(true, Ordering::Relaxed);
+    }
+
+    /// Removes the listeners of a plugin, e.g. when it is unloaded
+    pub fn unregister_plugin(&self, plugin: &str) {
+        let mut listeners = self.listeners.write().unwrap();
+        listeners.retain(|listener| &*listener.plugin != plugin);
+        self.active.store(!listeners.is_empty(), Ordering::Relaxed);
+    }
+
+    /// Whether any listener would be called for the packet, which is cheap to check
+    pub fn is_intercepted(&self, edition: Edition, direction: Direction, id: i32) -> bool {
+        self.active.load(Ordering::Relaxed)
+            && self
+                .listeners
+                .read()
+                .unwrap()
+                .iter()
+                .any(|listener| listener.filter.matches(edition, direction, id))
+    }
+
+    /// Runs the listeners matching a packet one after another, in the order they were registered.
+    ///
+    /// Returns the payload the packet has after the listeners, `None` if one of them cancelled it.
+    pub async fn intercept(
+        &self,
+        player: uuid::Uuid,
+        edition: Edition,
+        direction: Direction,
+        id: i32,
+        payload: Bytes,
+    ) -> Option<Bytes> {
+        let listeners: Vec<_> = self
+            .listeners
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|listener| listener.filter.matches(edition, direction, id))
+            .cloned()
+            .collect();
+        if listeners.is_empty() {
+            return Some(payload);
+        }
+
+        let mut packet = InterceptedPacket::new(player, edition, direction, id, payload.into());
+        for listener in listeners {
+            let start = Instant::now();
+            listener.listener.on_packet(&mut packet).await;
+            METRICS.observe_packet_listener(&listener.plugin, &listener.name, start.elapsed());
+        }
+        (!packet.is_cancelled()).then(|| packet.payload.into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+
+    use super::{Direction, InterceptedPacket, PacketFilter, PacketListener, PacketListeners};
+    use crate::{plugin::BoxFuture, server::metrics::Edition};
+
+    struct Rewrite;
+
+    impl PacketListener for Rewrite {
+        fn on_packet<'a>(&'a self, packet: &'a mut InterceptedPacket) -> BoxFuture<'a, ()> {
+            Box::pin(async move {
+                if packet.payload.is_empty() {
+                    packet.cancel();
+                } else {
+                    packet.payload.reverse();
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn intercept() {
+        let listeners = PacketListeners::default();
+        let player = uuid::Uuid::new_v4();
+        let intercept = |id, payload: &'static [u8]| {
+            listeners.intercept(
+                player,
+                Edition::Java,
+                Direction::Clientbound,
+                id,
+                Bytes::from_static(payload),
+            )
+        };
+
+        assert!(!listeners.is_intercepted(Edition::Java, Direction::Clientbound, 1));
+        listeners.register(
+            "test",
+            "Rewrite",
+            PacketFilter::new(Edition::Java, Direction::Clientbound, &[1]),
+            Arc::new(Rewrite),
+        );
+        assert!(listeners.is_intercepted(Edition::Java, Direction::Clientbound, 1));
+        assert!(!listeners.is_intercepted(Edition::Java, Direction::Serverbound, 1));
+        assert!(!listeners.is_intercepted(Edition::Bedrock, Direction::Clientbound, 1));
+
+        assert_eq!(intercept(1, b"abc").await.as_deref(), Some(&b"cba"[..]));
+        assert_eq!(intercept(1, b"").await, None);
+        assert_eq!(intercept(2, b"abc").await.as_deref(), Some(&b"abc"[..]));
+
+        listeners.unregister_plugin("test");
+        assert!(!listeners.is_intercepted(Edition::Java, Direction::Clientbound, 1));
+    }
+}
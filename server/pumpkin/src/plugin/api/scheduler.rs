@@ -0,0 +1,289 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use tokio::{sync::Mutex, task::AbortHandle};
+
+use crate::{plugin::BoxFuture, server::Server};
+
+type TaskFn = Box<dyn FnMut(Arc<Server>) -> BoxFuture<'static, ()> + Send>;
+
+/// A handle to a scheduled plugin task, used to cancel it.
+///
+/// Cloning the handle refers to the same task.
+#[derive(Clone)]
+pub struct TaskHandle {
+    cancelled: Arc<AtomicBool>,
+    abort: Option<AbortHandle>,
+}
+
+impl TaskHandle {
+    fn new(abort: Option<AbortHandle>) -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            abort,
+        }
+    }
+
+    /// Cancels the task. A tick task that is currently running finishes its run, but isn't run
+    /// again; an async task is aborted at its next `.await`.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        if let Some(abort) = &self.abort {
+            abort.abort();
+        }
+    }
+
+    /// Whether the task was cancelled or, for async tasks, has finished.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self.abort.as_ref().is_some_and(AbortHandle::is_finished)
+    }
+}
+
+struct ScheduledTask {
+    plugin: String,
+    handle: TaskHandle,
+    /// Ticks left until the next run
+    remaining: u64,
+    period: Option<u64>,
+    run: TaskFn,
+}
+
+/// Runs plugin tasks in step with the server ticks.
+///
+/// Tick tasks are run by `Server::tick` while the game isn't frozen, so they follow the tick
+/// rate, `/tick freeze` and `/tick sprint` like the worlds do. Every task belongs to a plugin and
+/// is cancelled when that plugin is unloaded.
+#[derive(Default)]
+pub struct TaskScheduler {
+    tasks: Mutex<Vec<ScheduledTask>>,
+    async_tasks: Mutex<Vec<(String, TaskHandle)>>,
+    /// The tasks the current tick took out to run, so unloading their plugin still cancels them
+    running: Mutex<Vec<(String, TaskHandle)>>,
+}
+
+impl TaskScheduler {
+    /// Runs `task` once, `delay` ticks from now. A delay of 0 runs it on the next tick.
+    pub async fn run_later<F, Fut>(&self, plugin: &str, delay: u64, task: F) -> TaskHandle
+    where
+        F: FnOnce(Arc<Server>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut task = Some(task);
+        self.schedule(
+            plugin,
+            delay,
+            None,
+            Box::new(move |server| match task.take() {
+                Some(task) => Box::pin(task(server)),
+                None => Box::pin(async {}),
+            }),
+        )
+        .await
+    }
+
+    /// Runs `task` `delay` ticks from now and then every `period` ticks until it is cancelled.
+    pub async fn run_repeating<F, Fut>(
+        &self,
+        plugin: &str,
+        delay: u64,
+        period: u64,
+        mut task: F,
+    ) -> TaskHandle
+    where
+        F: FnMut(Arc<Server>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.schedule(
+            plugin,
+            delay,
+            Some(period.max(1)),
+            Box::new(move |server| Box::pin(task(server))),
+        )
+        .await
+    }
+
+    /// Runs `task` on its own, outside of the ticks.
+    pub async fn run_async<Fut>(&self, plugin: &str, task: Fut) -> TaskHandle
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let handle = TaskHandle::new(Some(tokio::spawn(task).abort_handle()));
+        let mut async_tasks = self.async_tasks.lock().await;
+        async_tasks.retain(|(_, handle)| !handle.is_cancelled());
+        async_tasks.push((plugin.to_string(), handle.clone()));
+        handle
+    }
+
+    async fn schedule(
+        &self,
+        plugin: &str,
+        delay: u64,
+        period: Option<u64>,
+        run: TaskFn,
+    ) -> TaskHandle {
+        let handle = TaskHandle::new(None);
+        self.tasks.lock().await.push(ScheduledTask {
+            plugin: plugin.to_string(),
+            handle: handle.clone(),
+            remaining: delay.max(1),
+            period,
+            run,
+        });
+        handle
+    }
+
+    /// Cancels every task of `plugin`.
+    pub async fn cancel_plugin_tasks(&self, plugin: &str) {
+        self.tasks.lock().await.retain(|task| {
+            let keep = task.plugin != plugin;
+            if !keep {
+                task.handle.cancel();
+            }
+            keep
+        });
+        self.async_tasks.lock().await.retain(|(owner, handle)| {
+            let keep = owner != plugin;
+            if !keep {
+                handle.cancel();
+            }
+            keep
+        });
+        for (owner, handle) in self.running.lock().await.iter() {
+            if owner == plugin {
+                handle.cancel();
+            }
+        }
+    }
+
+    /// Runs the tasks that are due this tick, one after another.
+    pub async fn tick(&self, server: &Arc<Server>) {
+        self.run_due(|task| (task.run)(server.clone())).await;
+    }
+
+    async fn run_due<F>(&self, mut run: F)
+    where
+        F: FnMut(&mut ScheduledTask) -> BoxFuture<'static, ()>,
+    {
+        // Tasks may schedule or cancel tasks themselves, so they run without the lock held
+        let due = take_due(&mut *self.tasks.lock().await);
+        *self.running.lock().await = due
+            .iter()
+            .map(|task| (task.plugin.clone(), task.handle.clone()))
+            .collect();
+        let mut repeating = Vec::new();
+        for mut task in due {
+            // Its plugin may have been unloaded by a task before it
+            if task.handle.is_cancelled() {
+                continue;
+            }
+            run(&mut task).await;
+            if let Some(period) = task.period
+                && !task.handle.is_cancelled()
+            {
+                task.remaining = period;
+                repeating.push(task);
+            }
+        }
+        self.running.lock().await.clear();
+        if !repeating.is_empty() {
+            self.tasks.lock().await.extend(repeating);
+        }
+    }
+}
+
+/// Counts down one tick and removes the tasks that are due, dropping cancelled ones
+fn take_due(tasks: &mut Vec<ScheduledTask>) -> Vec<ScheduledTask> {
+    tasks.retain(|task| !task.handle.is_cancelled());
+    for task in tasks.iter_mut() {
+        task.remaining -= 1;
+    }
+    tasks.extract_if(.., |task| task.remaining == 0).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn task(plugin: &str, delay: u64) -> ScheduledTask {
+        ScheduledTask {
+            plugin: plugin.to_string(),
+            handle: TaskHandle::new(None),
+            remaining: delay.max(1),
+            period: None,
+            run: Box::new(|_| Box::pin(async {})),
+        }
+    }
+
+    #[test]
+    fn tasks_are_due_after_their_delay() {
+        let mut tasks = vec![task("a", 0), task("b", 3)];
+
+        let due = take_due(&mut tasks);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].plugin, "a");
+
+        assert!(take_due(&mut tasks).is_empty());
+        let due = take_due(&mut tasks);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].plugin, "b");
+        assert!(tasks.is_empty());
+    }
+
+    #[test]
+    fn cancelled_tasks_are_dropped() {
+        let cancelled = task("a", 0);
+        cancelled.handle.cancel();
+        let mut tasks = vec![cancelled, task("b", 2)];
+
+        assert!(take_due(&mut tasks).is_empty());
+        assert_eq!(tasks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn unloading_a_plugin_cancels_its_tasks() {
+        let scheduler = TaskScheduler::default();
+        let kept = scheduler.run_later("a", 5, |_| async {}).await;
+        let cancelled = scheduler.run_repeating("b", 0, 1, |_| async {}).await;
+        let spawned = scheduler.run_async("b", std::future::pending::<()>()).await;
+
+        scheduler.cancel_plugin_tasks("b").await;
+
+        assert!(!kept.is_cancelled());
+        assert!(cancelled.is_cancelled());
+        assert!(spawned.is_cancelled());
+        assert_eq!(scheduler.tasks.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn unloading_a_plugin_during_a_tick_cancels_its_due_tasks() {
+        let scheduler = Arc::new(TaskScheduler::default());
+        let unloading = scheduler.run_later("b", 0, |_| async {}).await;
+        let repeating = scheduler.run_repeating("b", 0, 1, |_| async {}).await;
+        scheduler.run_later("a", 0, |_| async {}).await;
+
+        // The first task of "b" unloads its plugin while it runs
+        let ran = Arc::new(std::sync::Mutex::new(Vec::new()));
+        scheduler
+            .run_due(|task| {
+                let scheduler = scheduler.clone();
+                let ran = ran.clone();
+                let plugin = task.plugin.clone();
+                Box::pin(async move {
+                    if plugin == "b" {
+                        scheduler.cancel_plugin_tasks("b").await;
+                    }
+                    ran.lock().unwrap().push(plugin);
+                })
+            })
+            .await;
+
+        assert_eq!(*ran.lock().unwrap(), ["b", "a"]);
+        assert!(unloading.is_cancelled());
+        assert!(repeating.is_cancelled());
+        assert!(scheduler.tasks.lock().await.is_empty());
+    }
+}
@@ -0,0 +1,323 @@
+//! Works out in which order plugins are loaded, based on their metadata
+
+use std::collections::{HashMap, HashSet};
+
+use semver::{Version, VersionReq};
+
+use super::{ManagerError, PluginMetadata};
+
+/// The outcome of resolving the dependencies of a set of plugins
+#[derive(Debug, Default)]
+pub struct LoadOrder {
+    /// Indices of the plugins that can be loaded, each after the plugins it depends on
+    pub order: Vec<usize>,
+    /// Indices of the plugins that can't be loaded and why
+    pub failed: Vec<(usize, ManagerError)>,
+}
+
+/// Orders `plugins` so that every plugin comes after its dependencies and before the plugins it
+/// has to be loaded before.
+///
+/// Dependencies may also be met by the already `loaded` plugins. Plugins that don't support the
+/// `server_version`, miss a hard dependency or are part of a cycle of hard dependencies are left
+/// out, along with everything that hard depends on them. Cycles through a `soft_depends` or
+/// `load_before` edge are broken by ignoring that edge.
+pub fn resolve_load_order(
+    plugins: &[PluginMetadata],
+    loaded: &HashSet<&str>,
+    server_version: &Version,
+) -> LoadOrder {
+    let index: HashMap<&str, usize> = plugins
+        .iter()
+        .enumerate()
+        .map(|(i, plugin)| (plugin.name, i))
+        .collect();
+
+    let mut failed: Vec<Option<ManagerError>> = plugins
+        .iter()
+        .map(|plugin| check_api_version(plugin, server_version).err())
+        .collect();
+
+    // `after[i]` are the plugins that have to be loaded before plugin `i`, `hard[i]` the ones
+    // among them that plugin `i` depends on
+    let mut after = vec![HashSet::new(); plugins.len()];
+    let mut hard = vec![HashSet::new(); plugins.len()];
+    for (i, plugin) in plugins.iter().enumerate() {
+        for &dependency in plugin.depends {
+            if let Some(&dependency) = index.get(dependency) {
+                after[i].insert(dependency);
+                hard[i].insert(dependency);
+            }
+        }
+        for &dependency in plugin.soft_depends {
+            if let Some(&dependency) = index.get(dependency) {
+                after[i].insert(dependency);
+            }
+        }
+        for &dependent in plugin.load_before {
+            if let Some(&dependent) = index.get(dependent) {
+                after[dependent].insert(i);
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(plugins.len());
+    let mut ordered = vec![false; plugins.len()];
+    loop {
+        fail_dependents(plugins, loaded, &index, &mut failed);
+
+        // Keep adding the plugins whose predecessors were all added or left out
+        let ready = |i: usize, ordered: &[bool]| {
+            failed[i].is_none()
+                && !ordered[i]
+                && after[i].iter().all(|&j| ordered[j] || failed[j].is_some())
+        };
+        while let Some(next) = (0..plugins.len()).find(|&i| ready(i, &ordered)) {
+            ordered[next] = true;
+            order.push(next);
+        }
+
+        // Whatever is still waiting has to wait on a cycle
+        let Some(start) = (0..plugins.len()).find(|&i| failed[i].is_none() && !ordered[i]) else {
+            break;
+        };
+        let mut path = vec![start];
+        let cycle = loop {
+            let current = *path.last().unwrap();
+            let previous = after[current]
+                .iter()
+                .copied()
+                .filter(|&j| failed[j].is_none() && !ordered[j])
+                .min()
+                .unwrap();
+            if let Some(position) = path.iter().position(|&i| i == previous) {
+                break path.split_off(position);
+            }
+            path.push(previous);
+        };
+
+        // `cycle[k]` waits on the plugin after it, the last one on the first
+        let soft = (0..cycle.len()).find_map(|k| {
+            let previous = cycle[(k + 1) % cycle.len()];
+            (!hard[cycle[k]].contains(&previous)).then_some((cycle[k], previous))
+        });
+        if let Some((i, previous)) = soft {
+            log::warn!(
+                "Loading {} without waiting for {}, as they depend on each other",
+                plugins[i].name,
+                plugins[previous].name
+            );
+            after[i].remove(&previous);
+            continue;
+        }
+
+        let names: Vec<String> = cycle
+            .iter()
+            .rev()
+            .map(|&i| plugins[i].name.to_string())
+            .collect();
+        for i in cycle {
+            failed[i] = Some(ManagerError::DependencyCycle(names.clone()));
+        }
+    }
+
+    LoadOrder {
+        order,
+        failed: failed
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, error)| Some((i, error?)))
+            .collect(),
+    }
+}
+
+fn check_api_version(
+    plugin: &PluginMetadata,
+    server_version: &Version,
+) -> Result<(), ManagerError> {
+    if plugin.api_version.is_empty() {
+        return Ok(());
+    }
+    let required =
+        VersionReq::parse(plugin.api_version).map_err(|e| ManagerError::InvalidApiVersion {
+            plugin: plugin.name.to_string(),
+            required: plugin.api_version.to_string(),
+            reason: e.to_string(),
+        })?;
+    if required.matches(server_version) {
+        Ok(())
+    } else {
+        Err(ManagerError::IncompatibleApiVersion {
+            plugin: plugin.name.to_string(),
+            required: plugin.api_version.to_string(),
+            server: server_version.to_string(),
+        })
+    }
+}
+
+/// Leaves out every plugin that hard depends on a plugin which is neither loaded nor loadable
+fn fail_dependents(
+    plugins: &[PluginMetadata],
+    loaded: &HashSet<&str>,
+    index: &HashMap<&str, usize>,
+    failed: &mut [Option<ManagerError>],
+) {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, plugin) in plugins.iter().enumerate() {
+            if failed[i].is_some() {
+                continue;
+            }
+            let missing = plugin.depends.iter().find(|&&dependency| {
+                index
+                    .get(dependency)
+                    .map_or_else(|| !loaded.contains(dependency), |&j| failed[j].is_some())
+            });
+            if let Some(dependency) = missing {
+                failed[i] = Some(ManagerError::MissingDependency {
+                    plugin: plugin.name.to_string(),
+                    dependency: (*dependency).to_string(),
+                });
+                changed = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn plugin(
+        name: &'static str,
+        depends: &'static [&'static str],
+        soft_depends: &'static [&'static str],
+        load_before: &'static [&'static str],
+    ) -> PluginMetadata<'static> {
+        PluginMetadata {
+            name,
+            version: "1.0.0",
+            authors: "",
+            description: "",
+            depends,
+            soft_depends,
+            load_before,
+            api_version: "",
+        }
+    }
+
+    fn names(plugins: &[PluginMetadata<'static>], order: &LoadOrder) -> Vec<&'static str> {
+        order.order.iter().map(|&i| plugins[i].name).collect()
+    }
+
+    fn failed(plugins: &[PluginMetadata<'static>], order: &LoadOrder) -> Vec<&'static str> {
+        order.failed.iter().map(|(i, _)| plugins[*i].name).collect()
+    }
+
+    fn resolve(plugins: &[PluginMetadata]) -> LoadOrder {
+        resolve_load_order(plugins, &HashSet::new(), &Version::new(0, 1, 0))
+    }
+
+    #[test]
+    fn dependencies_are_loaded_first() {
+        let plugins = [
+            plugin("economy", &["database"], &["permissions"], &[]),
+            plugin("database", &[], &[], &[]),
+            plugin("permissions", &[], &[], &[]),
+            plugin("migrations", &[], &[], &["database"]),
+        ];
+        let order = resolve(&plugins);
+
+        assert!(order.failed.is_empty());
+        assert_eq!(
+            names(&plugins, &order),
+            ["permissions", "migrations", "database", "economy"]
+        );
+    }
+
+    #[test]
+    fn missing_dependencies_fail_their_dependents() {
+        let plugins = [
+            plugin("shop", &["economy"], &[], &[]),
+            plugin("economy", &["database"], &[], &[]),
+            plugin("chat", &[], &["economy"], &[]),
+        ];
+        let order = resolve(&plugins);
+
+        assert_eq!(names(&plugins, &order), ["chat"]);
+        assert_eq!(failed(&plugins, &order), ["shop", "economy"]);
+        assert!(matches!(
+            &order.failed[1].1,
+            ManagerError::MissingDependency { dependency, .. } if dependency == "database"
+        ));
+    }
+
+    #[test]
+    fn loaded_plugins_count_as_dependencies() {
+        let plugins = [plugin("shop", &["economy"], &[], &[])];
+        let loaded = HashSet::from(["economy"]);
+        let order = resolve_load_order(&plugins, &loaded, &Version::new(0, 1, 0));
+
+        assert_eq!(names(&plugins, &order), ["shop"]);
+    }
+
+    #[test]
+    fn soft_edges_are_dropped_to_break_cycles() {
+        let plugins = [
+            plugin("a", &["b"], &[], &[]),
+            plugin("b", &[], &["c"], &[]),
+            plugin("c", &["a"], &[], &[]),
+            plugin("d", &["c"], &[], &[]),
+            plugin("e", &[], &["a"], &[]),
+        ];
+        let order = resolve(&plugins);
+
+        assert!(order.failed.is_empty());
+        assert_eq!(names(&plugins, &order), ["b", "a", "c", "d", "e"]);
+    }
+
+    #[test]
+    fn hard_cycles_are_detected() {
+        let plugins = [
+            plugin("a", &["b"], &[], &[]),
+            plugin("b", &["a"], &[], &[]),
+            plugin("c", &["a"], &[], &[]),
+            plugin("d", &[], &["a"], &[]),
+        ];
+        let order = resolve(&plugins);
+
+        assert_eq!(names(&plugins, &order), ["d"]);
+        assert_eq!(failed(&plugins, &order), ["a", "b", "c"]);
+        assert!(matches!(
+            &order.failed[0].1,
+            ManagerError::DependencyCycle(cycle) if cycle.len() == 2
+        ));
+        assert!(matches!(
+            &order.failed[2].1,
+            ManagerError::MissingDependency { .. }
+        ));
+    }
+
+    #[test]
+    fn incompatible_api_versions_are_refused() {
+        let mut compatible = plugin("old", &[], &[], &[]);
+        compatible.api_version = "^0.1";
+        let mut incompatible = plugin("new", &[], &[], &[]);
+        incompatible.api_version = ">=0.2";
+        let mut invalid = plugin("broken", &[], &[], &[]);
+        invalid.api_version = "not a version";
+        let plugins = [compatible, incompatible, invalid];
+        let order = resolve(&plugins);
+
+        assert_eq!(names(&plugins, &order), ["old"]);
+        assert!(matches!(
+            order.failed[0].1,
+            ManagerError::IncompatibleApiVersion { .. }
+        ));
+        assert!(matches!(
+            order.failed[1].1,
+            ManagerError::InvalidApiVersion { .. }
+        ));
+    }
+}
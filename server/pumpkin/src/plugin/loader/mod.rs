@@ -3,6 +3,8 @@ use std::{any::Any, path::Path, pin::Pin};
 use thiserror::Error;
 
 pub mod native;
+#[cfg(feature = "wasm-plugins")]
+pub mod wasm;
 
 pub type PluginLoadFuture<'a> = Pin<
     Box<
@@ -0,0 +1,246 @@
+//! Passes server events to WebAssembly plugins and applies their changes
+
+use std::sync::{Arc, Weak};
+
+use pumpkin_data::Block;
+use pumpkin_util::text::TextComponent;
+
+use super::{
+    WasmInstance,
+    bindings::pumpkin::plugin::{
+        events::{self, Event, EventKind, PlayerMessage},
+        types::BlockPos,
+    },
+    host::player,
+};
+use crate::{
+    plugin::{
+        BoxFuture, Cancellable, EventHandler, EventPriority, Payload,
+        api::Context,
+        block::{block_break::BlockBreakEvent, block_place::BlockPlaceEvent},
+        player::{
+            player_chat::PlayerChatEvent, player_command_send::PlayerCommandSendEvent,
+            player_join::PlayerJoinEvent, player_leave::PlayerLeaveEvent,
+        },
+        server::server_command::ServerCommandEvent,
+    },
+    server::Server,
+};
+
+/// An event that has a WIT counterpart
+trait WasmEvent: Payload + Cancellable + 'static {
+    fn to_wit(&self) -> Event;
+
+    /// Takes over what a blocking handler changed, events of another kind are ignored
+    fn apply(&mut self, event: Event);
+}
+
+fn block_name(block: &Block) -> String {
+    format!("minecraft:{}", block.name)
+}
+
+/// Keeps the formatting of a message unless the plugin changed its text
+fn apply_message(message: &mut TextComponent, text: String) {
+    if message.clone().get_text() != text {
+        *message = TextComponent::text(text);
+    }
+}
+
+impl WasmEvent for PlayerJoinEvent {
+    fn to_wit(&self) -> Event {
+        Event::PlayerJoin(PlayerMessage {
+            player: player(&self.player),
+            message: self.join_message.clone().get_text(),
+        })
+    }
+
+    fn apply(&mut self, event: Event) {
+        if let Event::PlayerJoin(event) = event {
+            apply_message(&mut self.join_message, event.message);
+        }
+    }
+}
+
+impl WasmEvent for PlayerLeaveEvent {
+    fn to_wit(&self) -> Event {
+        Event::PlayerLeave(PlayerMessage {
+            player: player(&self.player),
+            message: self.leave_message.clone().get_text(),
+        })
+    }
+
+    fn apply(&mut self, event: Event) {
+        if let Event::PlayerLeave(event) = event {
+            apply_message(&mut self.leave_message, event.message);
+        }
+    }
+}
+
+impl WasmEvent for PlayerChatEvent {
+    fn to_wit(&self) -> Event {
+        Event::PlayerChat(PlayerMessage {
+            player: player(&self.player),
+            message: self.message.clone(),
+        })
+    }
+
+    fn apply(&mut self, event: Event) {
+        if let Event::PlayerChat(event) = event {
+            self.message = event.message;
+        }
+    }
+}
+
+impl WasmEvent for PlayerCommandSendEvent {
+    fn to_wit(&self) -> Event {
+        Event::PlayerCommand(PlayerMessage {
+            player: player(&self.player),
+            message: self.command.clone(),
+        })
+    }
+
+    fn apply(&mut self, event: Event) {
+        if let Event::PlayerCommand(event) = event {
+            self.command = event.message;
+        }
+    }
+}
+
+impl WasmEvent for BlockBreakEvent {
+    fn to_wit(&self) -> Event {
+        let position = self.block_position.0;
+        Event::BlockBreak(events::BlockBreak {
+            player: self.player.as_deref().map(player),
+            block: block_name(self.block),
+            position: BlockPos {
+                x: position.x,
+                y: position.y,
+                z: position.z,
+            },
+            exp: self.exp,
+            drop: self.drop,
+        })
+    }
+
+    fn apply(&mut self, event: Event) {
+        if let Event::BlockBreak(event) = event {
+            self.exp = event.exp;
+            self.drop = event.drop;
+        }
+    }
+}
+
+impl WasmEvent for BlockPlaceEvent {
+    fn to_wit(&self) -> Event {
+        Event::BlockPlace(events::BlockPlace {
+            player: player(&self.player),
+            block: block_name(self.block_placed),
+            placed_against: block_name(self.block_placed_against),
+            can_build: self.can_build,
+        })
+    }
+
+    fn apply(&mut self, event: Event) {
+        if let Event::BlockPlace(event) = event {
+            if let Some(block) = Block::from_name(&event.block) {
+                self.block_placed = block;
+            }
+            self.can_build = event.can_build;
+        }
+    }
+}
+
+impl WasmEvent for ServerCommandEvent {
+    fn to_wit(&self) -> Event {
+        Event::ServerCommand(self.command.clone())
+    }
+
+    fn apply(&mut self, event: Event) {
+        if let Event::ServerCommand(command) = event {
+            self.command = command;
+        }
+    }
+}
+
+/// Handlers stay registered after a plugin is unloaded, so they only hold on to it weakly
+struct WasmEventHandler {
+    instance: Weak<WasmInstance>,
+}
+
+impl<E: WasmEvent> EventHandler<E> for WasmEventHandler {
+    fn handle(&self, _server: &Arc<Server>, event: &E) -> BoxFuture<'_, ()> {
+        let wit = event.to_wit();
+        let cancelled = event.cancelled();
+        Box::pin(async move {
+            if let Some(instance) = self.instance.upgrade() {
+                instance.handle_event(&wit, cancelled).await;
+            }
+        })
+    }
+
+    fn handle_blocking<'a>(
+        &'a self,
+        _server: &'a Arc<Server>,
+        event: &'a mut E,
+    ) -> BoxFuture<'a, ()> {
+        Box::pin(async move {
+            let Some(instance) = self.instance.upgrade() else {
+                return;
+            };
+            if let Some((changed, cancelled)) = instance
+                .handle_event(&event.to_wit(), event.cancelled())
+                .await
+            {
+                event.apply(changed);
+                event.set_cancelled(cancelled);
+            }
+        })
+    }
+}
+
+pub(super) async fn subscribe(
+    context: &Context,
+    instance: Weak<WasmInstance>,
+    kind: EventKind,
+    priority: EventPriority,
+    blocking: bool,
+) {
+    let handler = Arc::new(WasmEventHandler { instance });
+    match kind {
+        EventKind::PlayerJoin => {
+            context
+                .register_event::<PlayerJoinEvent, _>(handler, priority, blocking)
+                .await;
+        }
+        EventKind::PlayerLeave => {
+            context
+                .register_event::<PlayerLeaveEvent, _>(handler, priority, blocking)
+                .await;
+        }
+        EventKind::PlayerChat => {
+            context
+                .register_event::<PlayerChatEvent, _>(handler, priority, blocking)
+                .await;
+        }
+        EventKind::PlayerCommand => {
+            context
+                .register_event::<PlayerCommandSendEvent, _>(handler, priority, blocking)
+                .await;
+        }
+        EventKind::BlockBreak => {
+            context
+                .register_event::<BlockBreakEvent, _>(handler, priority, blocking)
+                .await;
+        }
+        EventKind::BlockPlace => {
+            context
+                .register_event::<BlockPlaceEvent, _>(handler, priority, blocking)
+                .await;
+        }
+        EventKind::ServerCommand => {
+            context
+                .register_event::<ServerCommandEvent, _>(handler, priority, blocking)
+                .await;
+        }
+    }
+}
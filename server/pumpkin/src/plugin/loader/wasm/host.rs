@@ -0,0 +1,375 @@
+//! What WebAssembly plugins can call on the server
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+
+use pumpkin_data::Block;
+use pumpkin_util::{
+    PermissionLvl,
+    math::position::BlockPos,
+    permission::{Permission, PermissionDefault},
+    text::TextComponent,
+};
+use pumpkin_world::world::BlockFlags;
+use wasmtime::StoreLimits;
+
+use super::{
+    WasmInstance,
+    bindings::pumpkin::plugin::{
+        commands, events, logging, players, scheduler, state,
+        types::{self, EventPriority, PermissionLevel},
+        worlds,
+    },
+};
+use crate::{
+    command::{
+        CommandError, CommandExecutor, CommandResult, CommandSender,
+        args::{Arg, ConsumedArgs, message::MsgArgConsumer},
+        tree::{CommandTree, builder::argument},
+    },
+    entity::player::Player,
+    net::DisconnectReason,
+    plugin::api::{Context, scheduler::TaskHandle},
+    server::Server,
+    world::World,
+};
+
+const ARG_ARGS: &str = "args";
+
+pub(super) struct HostState {
+    pub(super) plugin_name: String,
+    pub(super) instance: Weak<WasmInstance>,
+    /// Set once the plugin is loaded, before that it may not call the server
+    pub(super) context: Option<Arc<Context>>,
+    pub(super) limits: StoreLimits,
+    commands: Vec<String>,
+    tasks: HashMap<u32, TaskHandle>,
+}
+
+impl HostState {
+    pub(super) fn new(limits: StoreLimits) -> Self {
+        Self {
+            plugin_name: String::new(),
+            instance: Weak::new(),
+            context: None,
+            limits,
+            commands: Vec::new(),
+            tasks: HashMap::new(),
+        }
+    }
+
+    fn server(&self) -> Option<&Arc<Server>> {
+        let server = self.context.as_ref().map(|context| &context.server);
+        if server.is_none() {
+            log::warn!(
+                "Plugin {} called the server before it was loaded",
+                self.plugin_name
+            );
+        }
+        server
+    }
+
+    async fn player(&self, uuid: &str) -> Option<Arc<Player>> {
+        let uuid = uuid::Uuid::parse_str(uuid).ok()?;
+        self.server()?.get_player_by_uuid(uuid).await
+    }
+
+    async fn world(&self, dimension: &str) -> Option<Arc<World>> {
+        let worlds = self.server()?.worlds.read().await;
+        worlds
+            .iter()
+            .find(|world| world.dimension_type.resource_location().to_string() == dimension)
+            .cloned()
+    }
+
+    /// Stops the tasks and removes the commands of the plugin
+    pub(super) async fn shut_down(self) {
+        for task in self.tasks.into_values() {
+            task.cancel();
+        }
+        if let Some(context) = self.context {
+            for command in &self.commands {
+                context.unregister_command(command).await;
+            }
+        }
+    }
+}
+
+pub(super) fn player(player: &Player) -> types::Player {
+    types::Player {
+        uuid: player.gameprofile.id.to_string(),
+        name: player.gameprofile.name.clone(),
+    }
+}
+
+impl types::Host for HostState {}
+
+impl logging::Host for HostState {
+    async fn log(&mut self, level: logging::Level, message: String) {
+        let level = match level {
+            logging::Level::Error => log::Level::Error,
+            logging::Level::Warn => log::Level::Warn,
+            logging::Level::Info => log::Level::Info,
+            logging::Level::Debug => log::Level::Debug,
+            logging::Level::Trace => log::Level::Trace,
+        };
+        log::log!(level, "[{}] {message}", self.plugin_name);
+    }
+}
+
+impl events::Host for HostState {
+    async fn subscribe(
+        &mut self,
+        kind: events::EventKind,
+        priority: EventPriority,
+        blocking: bool,
+    ) {
+        let Some(context) = &self.context else {
+            log::warn!(
+                "Plugin {} subscribed to events before it was loaded",
+                self.plugin_name
+            );
+            return;
+        };
+        let priority = match priority {
+            EventPriority::Highest => crate::plugin::EventPriority::Highest,
+            EventPriority::High => crate::plugin::EventPriority::High,
+            EventPriority::Normal => crate::plugin::EventPriority::Normal,
+            EventPriority::Low => crate::plugin::EventPriority::Low,
+            EventPriority::Lowest => crate::plugin::EventPriority::Lowest,
+        };
+        super::events::subscribe(context, self.instance.clone(), kind, priority, blocking).await;
+    }
+}
+
+impl commands::Host for HostState {
+    async fn register(&mut self, name: String, description: String, level: PermissionLevel) {
+        let Some(context) = &self.context else {
+            log::warn!(
+                "Plugin {} registered a command before it was loaded",
+                self.plugin_name
+            );
+            return;
+        };
+        let level = match level {
+            PermissionLevel::All => PermissionLvl::Zero,
+            PermissionLevel::Moderator => PermissionLvl::One,
+            PermissionLevel::Gamemaster => PermissionLvl::Two,
+            PermissionLevel::Admin => PermissionLvl::Three,
+            PermissionLevel::Owner => PermissionLvl::Four,
+        };
+        let permission = format!("{}:command.{name}", self.plugin_name);
+        if let Err(err) = context
+            .register_permission(Permission::new(
+                &permission,
+                &description,
+                PermissionDefault::Op(level),
+            ))
+            .await
+        {
+            log::warn!("Plugin {} command {name}: {err}", self.plugin_name);
+        }
+
+        let executor = || WasmCommand {
+            instance: self.instance.clone(),
+            name: name.clone(),
+        };
+        let tree = CommandTree::new([name.clone()], description)
+            .then(argument(ARG_ARGS, MsgArgConsumer).execute(executor()))
+            .execute(executor());
+        context.register_command(tree, permission).await;
+        self.commands.push(name);
+    }
+}
+
+impl players::Host for HostState {
+    async fn online_players(&mut self) -> Vec<types::Player> {
+        let Some(server) = self.server() else {
+            return Vec::new();
+        };
+        server
+            .get_all_players()
+            .await
+            .iter()
+            .map(|online| player(online))
+            .collect()
+    }
+
+    async fn find_player(&mut self, name: String) -> Option<types::Player> {
+        let found = self.server()?.get_player_by_name(&name).await?;
+        Some(player(&found))
+    }
+
+    async fn send_message(&mut self, uuid: String, message: String) {
+        if let Some(player) = self.player(&uuid).await {
+            player
+                .send_system_message(&TextComponent::text(message))
+                .await;
+        }
+    }
+
+    async fn kick(&mut self, uuid: String, reason: String) {
+        if let Some(player) = self.player(&uuid).await {
+            player
+                .kick(DisconnectReason::Kicked, TextComponent::text(reason))
+                .await;
+        }
+    }
+
+    async fn position(&mut self, uuid: String) -> Option<(String, f64, f64, f64)> {
+        let player = self.player(&uuid).await?;
+        let position = player.position();
+        let dimension = player.world().dimension_type.resource_location();
+        Some((dimension.to_string(), position.x, position.y, position.z))
+    }
+}
+
+impl worlds::Host for HostState {
+    async fn worlds(&mut self) -> Vec<String> {
+        let Some(server) = self.server() else {
+            return Vec::new();
+        };
+        server
+            .worlds
+            .read()
+            .await
+            .iter()
+            .map(|world| world.dimension_type.resource_location().to_string())
+            .collect()
+    }
+
+    async fn get_block(&mut self, dimension: String, position: types::BlockPos) -> Option<String> {
+        let world = self.world(&dimension).await?;
+        let position = BlockPos::new(position.x, position.y, position.z);
+        let block = world.get_block(&position).await;
+        Some(format!("minecraft:{}", block.name))
+    }
+
+    async fn set_block(
+        &mut self,
+        dimension: String,
+        position: types::BlockPos,
+        block: String,
+    ) -> Result<(), String> {
+        let Some(world) = self.world(&dimension).await else {
+            return Err(format!("Unknown world {dimension}"));
+        };
+        let Some(block) = Block::from_name(&block) else {
+            return Err(format!("Unknown block {block}"));
+        };
+        let position = BlockPos::new(position.x, position.y, position.z);
+        world
+            .set_block_state(
+                &position,
+                block.default_state.id,
+                BlockFlags::FORCE_STATE | BlockFlags::NOTIFY_NEIGHBORS,
+            )
+            .await;
+        Ok(())
+    }
+
+    async fn broadcast(&mut self, message: String) {
+        let Some(server) = self.server() else {
+            return;
+        };
+        let message = TextComponent::text(message);
+        for player in server.get_all_players().await {
+            player.send_system_message(&message).await;
+        }
+    }
+}
+
+impl scheduler::Host for HostState {
+    async fn schedule(&mut self, id: u32, delay: u32, period: Option<u32>) {
+        let Some(context) = &self.context else {
+            log::warn!(
+                "Plugin {} scheduled a task before it was loaded",
+                self.plugin_name
+            );
+            return;
+        };
+        let instance = self.instance.clone();
+        let task = move |_| {
+            let instance = instance.clone();
+            async move {
+                if let Some(instance) = instance.upgrade() {
+                    instance.run_task(id).await;
+                }
+            }
+        };
+        let handle = match period {
+            Some(period) => {
+                context
+                    .run_repeating(delay.into(), period.into(), task)
+                    .await
+            }
+            None => context.run_later(delay.into(), task).await,
+        };
+
+        self.tasks.retain(|_, task| !task.is_cancelled());
+        if let Some(old) = self.tasks.insert(id, handle) {
+            old.cancel();
+        }
+    }
+
+    async fn cancel(&mut self, id: u32) {
+        if let Some(task) = self.tasks.remove(&id) {
+            task.cancel();
+        }
+    }
+}
+
+impl state::Host for HostState {
+    async fn take_saved_state(&mut self) -> Option<Vec<u8>> {
+        let Some(context) = &self.context else {
+            log::warn!(
+                "Plugin {} took its saved state before it was loaded",
+                self.plugin_name
+            );
+            return None;
+        };
+        context.take_saved_state().await
+    }
+}
+
+/// Runs a command the plugin registered
+struct WasmCommand {
+    instance: Weak<WasmInstance>,
+    name: String,
+}
+
+impl CommandExecutor for WasmCommand {
+    fn execute<'a>(
+        &'a self,
+        sender: &'a CommandSender,
+        _server: &'a Server,
+        args: &'a ConsumedArgs<'a>,
+    ) -> CommandResult<'a> {
+        Box::pin(async move {
+            let args = match args.get(ARG_ARGS) {
+                Some(Arg::Msg(args)) => args.as_str(),
+                _ => "",
+            };
+            let sender = match sender {
+                CommandSender::Console => types::CommandSender::Console,
+                CommandSender::Rcon(_) => types::CommandSender::Rcon,
+                CommandSender::Player(sender) => types::CommandSender::Player(player(sender)),
+            };
+            let result = match self.instance.upgrade() {
+                Some(instance) => instance.execute_command(&self.name, &sender, args).await,
+                None => None,
+            };
+            match result {
+                Some(Ok(())) => Ok(()),
+                Some(Err(message)) => {
+                    Err(CommandError::CommandFailed(TextComponent::text(message)))
+                }
+                None => Err(CommandError::CommandFailed(TextComponent::text(
+                    "The plugin of this command is not running",
+                ))),
+            }
+        })
+    }
+}
@@ -0,0 +1,417 @@
+//! Loads plugins compiled to WebAssembly components against `wit/plugin.wit`.
+//!
+//! Every plugin runs in its own store with limited fuel per call and limited memory, so a broken
+//! plugin can't take the server down with it. Unlike native plugins they can be unloaded on
+//! every platform, unloading drops the whole instance.
+//!
+//! Plugins are compiled to native code, or to bytecode for the Pulley interpreter with the
+//! `wasm-plugins-interpreted` feature, which is slower but works where JIT compiling doesn't.
+
+use std::{
+    any::Any,
+    collections::HashSet,
+    path::Path,
+    sync::{Arc, LazyLock},
+};
+
+use pumpkin_config::plugins::WasmPluginConfig;
+use tokio::sync::Mutex;
+use wasmtime::{
+    Config, Engine, Store, StoreLimitsBuilder,
+    component::{Component, Linker},
+};
+
+use super::{LoaderError, PluginLoadFuture, PluginLoader, PluginUnloadFuture};
+use crate::plugin::api::{Context, Plugin, PluginFuture, PluginMetadata};
+
+mod events;
+mod host;
+
+use host::HostState;
+
+mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit",
+        world: "plugin",
+        async: true,
+    });
+}
+
+use bindings::{CommandSender, Event, Metadata};
+
+/// How much fuel a plugin may burn before it yields to other tasks
+const FUEL_YIELD_INTERVAL: u64 = 10_000;
+
+pub struct WasmPluginLoader {
+    engine: Engine,
+    linker: Arc<Linker<HostState>>,
+    config: WasmPluginConfig,
+}
+
+impl WasmPluginLoader {
+    pub fn new(config: &WasmPluginConfig) -> wasmtime::Result<Self> {
+        let mut engine_config = Config::new();
+        engine_config
+            .async_support(true)
+            .consume_fuel(true)
+            .wasm_component_model(true);
+        #[cfg(feature = "wasm-plugins-interpreted")]
+        engine_config.target(if cfg!(target_pointer_width = "64") {
+            "pulley64"
+        } else {
+            "pulley32"
+        })?;
+        let engine = Engine::new(&engine_config)?;
+
+        let mut linker = Linker::new(&engine);
+        bindings::Plugin::add_to_linker(&mut linker, |state: &mut HostState| state)?;
+
+        Ok(Self {
+            engine,
+            linker: Arc::new(linker),
+            config: config.clone(),
+        })
+    }
+
+    async fn instantiate(&self, path: &Path) -> wasmtime::Result<Arc<WasmInstance>> {
+        let bytes = tokio::fs::read(path).await?;
+        // Compiling takes a while, keep it off the async workers
+        let engine = self.engine.clone();
+        let component =
+            tokio::task::spawn_blocking(move || Component::from_binary(&engine, &bytes)).await??;
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.config.max_memory_mib as usize * 1024 * 1024)
+            .instances(1)
+            .build();
+        let mut store = Store::new(&self.engine, HostState::new(limits));
+        store.limiter(|state| &mut state.limits);
+        store.fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL))?;
+        store.set_fuel(self.config.fuel_per_call)?;
+
+        let plugin =
+            bindings::Plugin::instantiate_async(&mut store, &component, &self.linker).await?;
+        store.set_fuel(self.config.fuel_per_call)?;
+        let metadata = plugin.call_metadata(&mut store).await?;
+        store.data_mut().plugin_name.clone_from(&metadata.name);
+
+        Ok(Arc::new_cyclic(|instance| {
+            store.data_mut().instance = instance.clone();
+            WasmInstance {
+                metadata,
+                fuel: self.config.fuel_per_call,
+                loaded: Mutex::new(Some(Loaded {
+                    store,
+                    bindings: plugin,
+                })),
+            }
+        }))
+    }
+}
+
+impl PluginLoader for WasmPluginLoader {
+    fn load<'a>(&'a self, path: &'a Path) -> PluginLoadFuture<'a> {
+        Box::pin(async move {
+            let instance = self
+                .instantiate(path)
+                .await
+                .map_err(|err| LoaderError::LibraryLoad(format!("{err:#}")))?;
+
+            let metadata = &instance.metadata;
+            let metadata = PluginMetadata {
+                name: intern(&metadata.name),
+                version: intern(&metadata.version),
+                authors: intern(&metadata.authors),
+                description: intern(&metadata.description),
+                depends: intern_all(&metadata.depends),
+                soft_depends: intern_all(&metadata.soft_depends),
+                load_before: intern_all(&metadata.load_before),
+                api_version: intern(&metadata.api_version),
+            };
+
+            Ok((
+                Box::new(WasmPlugin(instance.clone())) as Box<dyn Plugin>,
+                metadata,
+                Box::new(instance) as Box<dyn Any + Send + Sync>,
+            ))
+        })
+    }
+
+    fn can_load(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|s| s.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("wasm"))
+    }
+
+    fn unload(&self, data: Box<dyn Any + Send + Sync>) -> PluginUnloadFuture<'_> {
+        Box::pin(async {
+            let instance = data
+                .downcast::<Arc<WasmInstance>>()
+                .map_err(|_| LoaderError::InvalidLoaderData)?;
+            instance.shut_down().await;
+            Ok(())
+        })
+    }
+
+    fn can_unload(&self) -> bool {
+        true
+    }
+}
+
+/// The strings of plugin metadata, which has to outlive the plugin as other plugins may hold on
+/// to it. They are interned, so reloading a plugin doesn't leak them again
+static INTERNED: LazyLock<std::sync::Mutex<HashSet<&'static str>>> =
+    LazyLock::new(Default::default);
+static INTERNED_LISTS: LazyLock<std::sync::Mutex<HashSet<&'static [&'static str]>>> =
+    LazyLock::new(Default::default);
+
+fn intern(value: &str) -> &'static str {
+    let mut interned = INTERNED.lock().unwrap();
+    if let Some(value) = interned.get(value) {
+        return value;
+    }
+    let value: &'static str = Box::leak(value.into());
+    interned.insert(value);
+    value
+}
+
+fn intern_all(values: &[String]) -> &'static [&'static str] {
+    let values = values.iter().map(|value| intern(value)).collect::<Vec<_>>();
+    let mut interned = INTERNED_LISTS.lock().unwrap();
+    if let Some(values) = interned.get(values.as_slice()) {
+        return values;
+    }
+    let values: &'static [&'static str] = values.leak();
+    interned.insert(values);
+    values
+}
+
+struct Loaded {
+    store: Store<HostState>,
+    bindings: bindings::Plugin,
+}
+
+/// A running plugin, shared with the event handlers, commands and tasks it registered
+pub struct WasmInstance {
+    metadata: Metadata,
+    fuel: u64,
+    /// `None` once the plugin is unloaded or trapped
+    loaded: Mutex<Option<Loaded>>,
+}
+
+tokio::task_local! {
+    /// The instances whose calls the current task is in. A host import that ends up calling the
+    /// same plugin again, like firing an event it listens to, would wait on its own lock forever
+    static CALLING: Vec<usize>;
+}
+
+/// Calls into a plugin with a fresh fuel budget. A plugin that traps, e.g. because it ran out of
+/// fuel or memory, can't be called again and is shut down. Calls into a plugin that is already
+/// running on this task are refused with `None`
+macro_rules! call_plugin {
+    ($instance:expr, |$bindings:ident, $store:ident| $call:expr) => {{
+        let instance: &WasmInstance = $instance;
+        let id = std::ptr::from_ref(instance) as usize;
+        let mut calling = CALLING.try_with(Clone::clone).unwrap_or_default();
+        if calling.contains(&id) {
+            log::error!(
+                "Plugin {} was called while it was already running, which is refused",
+                instance.metadata.name
+            );
+            None
+        } else {
+            calling.push(id);
+            CALLING
+                .scope(calling, async {
+                    let mut loaded = instance.loaded.lock().await;
+                    match loaded.as_mut() {
+                        Some(Loaded { store, bindings }) => {
+                            let result = match store.set_fuel(instance.fuel) {
+                                Ok(()) => {
+                                    let $bindings = &*bindings;
+                                    let $store = &mut *store;
+                                    $call.await
+                                }
+                                Err(err) => Err(err),
+                            };
+                            match result {
+                                Ok(value) => Some(value),
+                                Err(err) => {
+                                    log::error!(
+                                        "Plugin {} failed and is disabled: {err:#}",
+                                        instance.metadata.name
+                                    );
+                                    if let Some(loaded) = loaded.take() {
+                                        loaded.store.into_data().shut_down().await;
+                                    }
+                                    None
+                                }
+                            }
+                        }
+                        None => None,
+                    }
+                })
+                .await
+        }
+    }};
+}
+
+impl WasmInstance {
+    async fn on_load(&self, context: Arc<Context>) -> Result<(), String> {
+        if let Some(Loaded { store, .. }) = self.loaded.lock().await.as_mut() {
+            store.data_mut().context = Some(context);
+        }
+        call_plugin!(self, |bindings, store| bindings.call_on_load(store))
+            .unwrap_or_else(|| Err("The plugin trapped while loading".to_string()))
+    }
+
+    async fn handle_event(&self, event: &Event, cancelled: bool) -> Option<(Event, bool)> {
+        call_plugin!(self, |bindings, store| bindings
+            .call_handle_event(store, event, cancelled))
+    }
+
+    async fn execute_command(
+        &self,
+        name: &str,
+        sender: &CommandSender,
+        args: &str,
+    ) -> Option<Result<(), String>> {
+        call_plugin!(self, |bindings, store| bindings
+            .call_execute_command(store, name, sender, args))
+    }
+
+    async fn save_state(&self) -> Option<Vec<u8>> {
+        call_plugin!(self, |bindings, store| bindings.call_save_state(store)).flatten()
+    }
+
+    async fn run_task(&self, id: u32) {
+        let _ = call_plugin!(self, |bindings, store| bindings.call_run_task(store, id));
+    }
+
+    /// Calls `on-unload` and drops the instance along with everything it registered
+    async fn shut_down(&self) {
+        let _ = call_plugin!(self, |bindings, store| bindings.call_on_unload(store));
+        if let Some(loaded) = self.loaded.lock().await.take() {
+            loaded.store.into_data().shut_down().await;
+        }
+    }
+}
+
+struct WasmPlugin(Arc<WasmInstance>);
+
+impl Plugin for WasmPlugin {
+    fn on_load(&mut self, context: Arc<Context>) -> PluginFuture<'_, Result<(), String>> {
+        Box::pin(self.0.on_load(context))
+    }
+
+    fn on_unload(&mut self, _context: Arc<Context>) -> PluginFuture<'_, Result<(), String>> {
+        Box::pin(async {
+            self.0.shut_down().await;
+            Ok(())
+        })
+    }
+
+    fn save_state(&mut self, _context: Arc<Context>) -> PluginFuture<'_, Option<Vec<u8>>> {
+        Box::pin(self.0.save_state())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{any::Any, path::Path, time::Duration};
+
+    use pumpkin_config::plugins::WasmPluginConfig;
+    use tokio::sync::Mutex;
+
+    use super::{CALLING, Metadata, WasmInstance, WasmPluginLoader, intern, intern_all};
+    use crate::plugin::loader::{LoaderError, PluginLoader};
+
+    /// The header of a component without any imports or exports
+    const EMPTY_COMPONENT: &[u8] = b"\0asm\x0d\0\x01\0";
+
+    fn loader() -> WasmPluginLoader {
+        WasmPluginLoader::new(&WasmPluginConfig::default()).unwrap()
+    }
+
+    async fn load_error(loader: &WasmPluginLoader, bytes: &[u8]) -> LoaderError {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plugin.wasm");
+        std::fs::write(&path, bytes).unwrap();
+        match loader.load(&path).await {
+            Ok(_) => panic!("{} loaded", path.display()),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn loads_wasm_files() {
+        let loader = loader();
+        assert!(loader.can_load(Path::new("plugins/economy.wasm")));
+        assert!(loader.can_load(Path::new("plugins/economy.WASM")));
+        assert!(!loader.can_load(Path::new("plugins/economy.so")));
+        assert!(!loader.can_load(Path::new("plugins/wasm")));
+        assert!(loader.can_unload());
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_plugins() {
+        let loader = loader();
+        // Not WebAssembly at all
+        assert!(matches!(
+            load_error(&loader, b"not a plugin").await,
+            LoaderError::LibraryLoad(_)
+        ));
+        // A valid component that doesn't export the plugin world
+        assert!(matches!(
+            load_error(&loader, EMPTY_COMPONENT).await,
+            LoaderError::LibraryLoad(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn unload_rejects_foreign_data() {
+        let data = Box::new(5u32) as Box<dyn Any + Send + Sync>;
+        assert!(matches!(
+            loader().unload(data).await,
+            Err(LoaderError::InvalidLoaderData)
+        ));
+    }
+
+    #[test]
+    fn reloading_reuses_metadata() {
+        let name = intern(&String::from("economy"));
+        assert!(std::ptr::eq(name, intern("economy")));
+        let depends = intern_all(&["vault".to_string(), "economy".to_string()]);
+        assert!(std::ptr::eq(
+            depends,
+            intern_all(&["vault".to_string(), "economy".to_string()])
+        ));
+        assert_eq!(depends, ["vault", "economy"]);
+        assert!(std::ptr::eq(depends[1], name));
+    }
+
+    #[tokio::test]
+    async fn refuses_calls_from_inside_the_plugin() {
+        let instance = WasmInstance {
+            metadata: Metadata {
+                name: "economy".to_string(),
+                version: String::new(),
+                authors: String::new(),
+                description: String::new(),
+                depends: Vec::new(),
+                soft_depends: Vec::new(),
+                load_before: Vec::new(),
+                api_version: String::new(),
+            },
+            fuel: 0,
+            loaded: Mutex::new(None),
+        };
+        // As if a host import of a running call fired an event the plugin listens to
+        let _running = instance.loaded.lock().await;
+        let id = std::ptr::from_ref(&instance) as usize;
+        let call = CALLING.scope(vec![id], instance.run_task(0));
+        tokio::time::timeout(Duration::from_secs(1), call)
+            .await
+            .expect("the nested call waited on the running one");
+    }
+}
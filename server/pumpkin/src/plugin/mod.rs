@@ -6,18 +6,40 @@ use std::{
     path::{Path, PathBuf},
     pin::Pin,
     sync::Arc,
+    time::Instant,
 };
 use thiserror::Error;
 use tokio::sync::{Notify, RwLock};
 
 pub mod api;
+mod dependencies;
 pub mod loader;
 
-use crate::{LOGGER_IMPL, PERMISSION_MANAGER, server::Server};
+use crate::{
+    LOGGER_IMPL, PERMISSION_MANAGER, PERMISSION_REGISTRY,
+    command::client_suggestions,
+    server::{Server, metrics::METRICS},
+};
 pub use api::*;
+use api::{menu::MenuRegistry, packets::PacketListeners, scheduler::TaskScheduler};
 
 pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
+tokio::task_local! {
+    /// The plugin whose `on_load` is running on this task
+    static LOADING_PLUGIN: String;
+}
+
+/// The plugin API version of the server, which `PluginMetadata::api_version` is checked against.
+///
+/// This is the version of the server without its pre-release and build parts.
+#[must_use]
+pub fn api_version() -> semver::Version {
+    let version = semver::Version::parse(env!("CARGO_PKG_VERSION"))
+        .expect("The crate version should be valid semver");
+    semver::Version::new(version.major, version.minor, version.patch)
+}
+
 /// A trait for handling events dynamically.
 ///
 /// This trait allows for handling events of any type that implements the `Event` trait.
@@ -53,6 +75,12 @@ pub trait DynEventHandler: Send + Sync {
     /// # Returns
     /// The priority of the event handler.
     fn get_priority(&self) -> &EventPriority;
+
+    /// Retrieves the plugin that registered the event handler.
+    ///
+    /// # Returns
+    /// The name of the plugin, or `None` if the server registered the handler.
+    fn get_plugin(&self) -> Option<&str>;
 }
 
 /// A trait for handling specific events.
@@ -91,6 +119,7 @@ where
     handler: Arc<H>,
     priority: EventPriority,
     blocking: bool,
+    plugin: Option<String>,
     _phantom: std::marker::PhantomData<E>,
 }
 
@@ -136,6 +165,11 @@ where
     fn get_priority(&self) -> &EventPriority {
         &self.priority
     }
+
+    /// Retrieves the plugin that registered the handler.
+    fn get_plugin(&self) -> Option<&str> {
+        self.plugin.as_deref()
+    }
 }
 
 /// A type alias for a map of event handlers, where the key is a static string
@@ -145,6 +179,8 @@ type HandlerMap = HashMap<&'static str, Vec<Box<dyn DynEventHandler>>>;
 /// Plugin loading state
 #[derive(Debug, Clone, PartialEq)]
 pub enum PluginState {
+    /// Waiting for the plugins before it in the load order
+    Queued,
     Loading,
     Loaded,
     Failed(String),
@@ -159,11 +195,19 @@ pub struct PluginManager {
     unloaded_files: RwLock<HashSet<PathBuf>>,
     // Self-reference for sharing with contexts
     self_ref: RwLock<Option<Arc<Self>>>,
-    services: Arc<RwLock<HashMap<String, Arc<dyn Payload>>>>,
+    // Services along with the plugin that registered them
+    services: Arc<RwLock<HashMap<String, (String, Arc<dyn Payload>)>>>,
+    // The primary names of the commands each plugin registered
+    commands: RwLock<HashMap<String, Vec<String>>>,
+    // States saved by plugins that are being reloaded, until their new instance takes them
+    saved_states: RwLock<HashMap<String, Vec<u8>>>,
     // Plugin state tracking
     plugin_states: RwLock<HashMap<String, PluginState>>,
     // Notification for plugin state changes
     state_notify: Arc<Notify>,
+    scheduler: TaskScheduler,
+    menus: MenuRegistry,
+    packet_listeners: PacketListeners,
 }
 
 /// Represents a successfully loaded plugin
@@ -171,6 +215,7 @@ pub struct PluginManager {
 /// OS specific issues
 /// - Windows: Plugin cannot be unloaded, it can be only active or not
 struct LoadedPlugin {
+    path: PathBuf,
     metadata: PluginMetadata<'static>,
     instance: Option<Box<dyn Plugin>>,
     loader: Arc<dyn PluginLoader>,
@@ -179,6 +224,15 @@ struct LoadedPlugin {
     context: Arc<Context>,
 }
 
+/// A plugin file that was read by a loader, but isn't initialized yet
+struct PendingPlugin {
+    path: PathBuf,
+    instance: Box<dyn Plugin>,
+    metadata: PluginMetadata<'static>,
+    loader: Arc<dyn PluginLoader>,
+    loader_data: Box<dyn Any + Send + Sync>,
+}
+
 /// Error types for plugin management
 #[derive(Error, Debug)]
 pub enum ManagerError {
@@ -194,8 +248,37 @@ pub enum ManagerError {
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
+    #[error("Plugin {0} can't be reloaded, as its loader can't unload it")]
+    ReloadUnsupported(String),
+
     #[error("Plugin manager not initialized properly")]
     ManagerNotInitialized,
+
+    #[error("Plugin {plugin} depends on {dependency}, which is not available")]
+    MissingDependency { plugin: String, dependency: String },
+
+    #[error(
+        "Plugin {plugin} waits for {dependency} while loading, but {dependency} is loaded after \
+         it. Declare it in depends or soft_depends to have it loaded first"
+    )]
+    LoadedAfterWaiter { plugin: String, dependency: String },
+
+    #[error("Plugins depend on each other: {}", .0.join(" -> "))]
+    DependencyCycle(Vec<String>),
+
+    #[error("Plugin {plugin} requires API version {required}, but the server provides {server}")]
+    IncompatibleApiVersion {
+        plugin: String,
+        required: String,
+        server: String,
+    },
+
+    #[error("Plugin {plugin} has an invalid API version requirement {required}: {reason}")]
+    InvalidApiVersion {
+        plugin: String,
+        required: String,
+        reason: String,
+    },
 }
 
 impl Default for PluginManager {
@@ -208,8 +291,13 @@ impl Default for PluginManager {
             unloaded_files: RwLock::new(HashSet::new()),
             self_ref: RwLock::new(None),
             services: Arc::new(RwLock::new(HashMap::new())),
+            commands: RwLock::new(HashMap::new()),
+            saved_states: RwLock::new(HashMap::new()),
             plugin_states: RwLock::new(HashMap::new()),
             state_notify: Arc::new(Notify::new()),
+            scheduler: TaskScheduler::default(),
+            menus: MenuRegistry::default(),
+            packet_listeners: PacketListeners::default(),
         }
     }
 }
@@ -225,8 +313,10 @@ impl PluginManager {
     pub async fn unload_all_plugins(&self) -> Result<(), ManagerError> {
         let plugin_names: Vec<String> = {
             let plugins = self.plugins.read().await;
+            // Dependents were loaded after their dependencies, so they are unloaded first
             plugins
                 .iter()
+                .rev()
                 .filter(|p| p.is_active)
                 .map(|p| p.metadata.name.to_string())
                 .collect()
@@ -253,16 +343,15 @@ impl PluginManager {
     async fn retry_unloaded_files(&self) {
         let files_to_retry: Vec<PathBuf> =
             { self.unloaded_files.read().await.iter().cloned().collect() };
-        let mut retry_tasks = Vec::new();
+        let mut pending = Vec::new();
 
         for path in files_to_retry {
-            if let Ok(task) = self.start_loading_plugin(&path).await {
-                retry_tasks.push(task);
+            if let Ok(plugin) = self.read_plugin(&path).await {
+                pending.push(plugin);
             }
         }
 
-        // Wait for all retry tasks to complete
-        join_all(retry_tasks).await;
+        self.load_in_order(pending).await;
     }
 
     /// Set server reference for plugin context
@@ -300,7 +389,7 @@ impl PluginManager {
             std::fs::create_dir_all(&plugin_dir)?;
         }
 
-        let mut load_tasks = Vec::new();
+        let mut pending = Vec::new();
 
         for entry in std::fs::read_dir(&plugin_dir)? {
             let entry = entry?;
@@ -310,183 +399,306 @@ impl PluginManager {
                 continue;
             }
 
-            // Start loading plugin concurrently
-            if let Ok(task) = self.start_loading_plugin(&path).await {
-                load_tasks.push(task);
+            match self.read_plugin(&path).await {
+                Ok(plugin) => pending.push(plugin),
+                Err(ManagerError::PluginNotFound(_)) => {}
+                Err(e) => log::error!("Failed to load plugin {}: {e}", path.display()),
             }
         }
 
-        // Wait for all plugins to complete loading (but don't block on individual plugin initialization)
-        join_all(load_tasks).await;
+        self.load_in_order(pending).await;
 
         Ok(())
     }
 
-    /// Start loading a plugin asynchronously
+    /// Read a plugin file with the first loader that can handle it, without initializing it
+    async fn read_plugin(&self, path: &Path) -> Result<PendingPlugin, ManagerError> {
+        for loader in self.loaders.read().await.iter() {
+            if loader.can_load(path) {
+                let (instance, metadata, loader_data) = loader.load(path).await?;
+                return Ok(PendingPlugin {
+                    path: path.to_path_buf(),
+                    instance,
+                    metadata,
+                    loader: loader.clone(),
+                    loader_data,
+                });
+            }
+        }
+
+        // No loader could handle this file, track it for future attempts
+        self.unloaded_files.write().await.insert(path.to_path_buf());
+
+        Err(ManagerError::PluginNotFound(
+            path.to_string_lossy().to_string(),
+        ))
+    }
+
+    /// Initialize plugins one after another, each after the plugins it depends on. A plugin's
+    /// `on_load` has finished before the next one starts, so it can only rely on the plugins it
+    /// declared in `depends` or `soft_depends`
+    ///
+    /// Returns the errors of the plugins that were refused because of their dependencies or API
+    /// version
+    async fn load_in_order(&self, pending: Vec<PendingPlugin>) -> Vec<ManagerError> {
+        let loaded = self.active_plugins().await;
+        let loaded: HashSet<&str> = loaded.iter().map(|plugin| plugin.name).collect();
+        let metadata: Vec<_> = pending
+            .iter()
+            .map(|plugin| plugin.metadata.clone())
+            .collect();
+        let LoadOrder { order, failed } = resolve_load_order(&metadata, &loaded, &api_version());
+
+        {
+            let mut states = self.plugin_states.write().await;
+            for &index in &order {
+                states.insert(metadata[index].name.to_string(), PluginState::Queued);
+            }
+        }
+
+        let mut pending: Vec<_> = pending.into_iter().map(Some).collect();
+        let mut errors = Vec::new();
+        for (index, error) in failed {
+            if let Some(plugin) = pending[index].take() {
+                self.refuse_plugin(plugin, &error).await;
+                errors.push(error);
+            }
+        }
+
+        for index in order {
+            let Some(plugin) = pending[index].take() else {
+                continue;
+            };
+
+            // A dependency may have failed in its `on_load`
+            let mut missing = None;
+            for dependency in plugin.metadata.depends {
+                if !self.is_plugin_active(dependency).await {
+                    missing = Some(*dependency);
+                    break;
+                }
+            }
+            if let Some(dependency) = missing {
+                let error = ManagerError::MissingDependency {
+                    plugin: plugin.metadata.name.to_string(),
+                    dependency: dependency.to_string(),
+                };
+                self.refuse_plugin(plugin, &error).await;
+                errors.push(error);
+                continue;
+            }
+
+            let name = plugin.metadata.name;
+            match self.start_loading_plugin(plugin).await {
+                Ok(task) => {
+                    if let Err(e) = task.await {
+                        log::error!("Failed to initialize plugin {name}: {e}");
+                    }
+                }
+                Err(e) => {
+                    log::error!("Failed to load plugin {name}: {e}");
+                    errors.push(e);
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Give up on a plugin that was read but can't be loaded
+    async fn refuse_plugin(&self, plugin: PendingPlugin, error: &ManagerError) {
+        let name = plugin.metadata.name.to_string();
+        log::error!("Failed to load plugin {name}: {error}");
+
+        // The dependency may become available through another loader later on
+        if matches!(error, ManagerError::MissingDependency { .. }) {
+            self.unloaded_files.write().await.insert(plugin.path);
+        }
+
+        // The instance may be code owned by the loader data, so it has to go first
+        drop(plugin.instance);
+        drop(plugin.loader_data);
+
+        self.plugin_states
+            .write()
+            .await
+            .insert(name, PluginState::Failed(error.to_string()));
+        self.state_notify.notify_waiters();
+    }
+
+    /// Start initializing a plugin asynchronously
     #[expect(clippy::too_many_lines)]
     async fn start_loading_plugin(
         &self,
-        path: &Path,
+        pending: PendingPlugin,
     ) -> Result<tokio::task::JoinHandle<()>, ManagerError> {
-        for loader in self.loaders.read().await.iter() {
-            if loader.can_load(path) {
-                let (mut instance, metadata, loader_data) = loader.load(path).await?;
-
-                // Mark plugin as loading
-                self.plugin_states
-                    .write()
-                    .await
-                    .insert(metadata.name.to_string(), PluginState::Loading);
+        let PendingPlugin {
+            path,
+            mut instance,
+            metadata,
+            loader,
+            loader_data,
+        } = pending;
+
+        // Mark plugin as loading
+        self.plugin_states
+            .write()
+            .await
+            .insert(metadata.name.to_string(), PluginState::Loading);
 
-                let self_ref = self
-                    .self_ref
+        let self_ref = self
+            .self_ref
+            .read()
+            .await
+            .clone()
+            .ok_or(ManagerError::ServerNotInitialized)?;
+
+        let context = Arc::new(Context::new(
+            metadata.clone(),
+            Arc::clone(
+                &self
+                    .server
                     .read()
                     .await
                     .clone()
-                    .ok_or(ManagerError::ServerNotInitialized)?;
-
-                let context = Arc::new(Context::new(
-                    metadata.clone(),
-                    Arc::clone(
-                        &self
-                            .server
-                            .read()
-                            .await
-                            .clone()
-                            .ok_or(ManagerError::ServerNotInitialized)?,
-                    ),
-                    Arc::clone(&self.handlers),
-                    Arc::clone(&self_ref),
-                    Arc::clone(&PERMISSION_MANAGER),
-                    Arc::clone(&LOGGER_IMPL),
-                ));
-
-                // Create the plugin structure first
-                let plugin = LoadedPlugin {
-                    metadata: metadata.clone(),
-                    instance: None, // Will be set after successful initialization
-                    loader: loader.clone(),
-                    loader_data: Some(loader_data),
-                    is_active: false, // Will be set to true after successful initialization
-                    context: context.clone(),
-                };
-
-                let plugin_index = {
-                    let mut plugins = self.plugins.write().await;
-                    plugins.push(plugin);
-                    plugins.len() - 1
-                };
+                    .ok_or(ManagerError::ServerNotInitialized)?,
+            ),
+            Arc::clone(&self.handlers),
+            Arc::clone(&self_ref),
+            Arc::clone(&PERMISSION_MANAGER),
+            Arc::clone(&LOGGER_IMPL),
+        ));
+
+        // Create the plugin structure first
+        let plugin = LoadedPlugin {
+            path: path.clone(),
+            metadata: metadata.clone(),
+            instance: None, // Will be set after successful initialization
+            loader: loader.clone(),
+            loader_data: Some(loader_data),
+            is_active: false, // Will be set to true after successful initialization
+            context: context.clone(),
+        };
 
-                // Remove from unloaded files if it was there
-                self.unloaded_files.write().await.remove(path);
-
-                // Spawn async task for plugin initialization
-                let self_ref_clone = Arc::clone(&self_ref);
-                let state_notify = Arc::clone(&self.state_notify);
-                let plugin_name = metadata.name.to_string();
-                let loader_clone = loader.clone();
-
-                let task = tokio::spawn(async move {
-                    // Initialize the plugin
-                    match instance.on_load(context.clone()).await {
-                        Ok(()) => {
-                            // Update plugin state to loaded
-                            {
-                                let mut plugins = self_ref_clone.plugins.write().await;
-                                if let Some(plugin) = plugins.get_mut(plugin_index) {
-                                    plugin.instance = Some(instance);
-                                    plugin.is_active = true;
-                                }
-                            }
-                            self_ref_clone
-                                .plugin_states
-                                .write()
-                                .await
-                                .insert(plugin_name.clone(), PluginState::Loaded);
-                            state_notify.notify_waiters();
-
-                            log::info!("Loaded {} ({})", metadata.name, metadata.version);
-                        }
-                        Err(e) => {
-                            // Handle initialization failure
-                            let error_msg = format!("Initialization failed: {e}");
-                            let _ = instance.on_unload(context).await;
-
-                            // Get the loader data before removing the plugin
-                            let loader_data: Option<Box<dyn Any + Send + Sync>> = {
-                                let mut plugins = self_ref_clone.plugins.write().await;
-                                if let Some(plugin) = plugins.get_mut(plugin_index) {
-                                    plugin.loader_data.take()
-                                } else {
-                                    None
-                                }
-                            };
-
-                            // Try to unload the plugin data
-                            if let Some(data) = loader_data {
-                                tokio::spawn(async move {
-                                    loader_clone.unload(data).await.ok();
-                                });
-                            }
-
-                            {
-                                let mut plugins = self_ref_clone.plugins.write().await;
-                                if plugin_index < plugins.len() {
-                                    plugins.remove(plugin_index);
-                                }
-                            };
-                            self_ref_clone.plugin_states.write().await.insert(
-                                plugin_name.clone(),
-                                PluginState::Failed(error_msg.clone()),
-                            );
-                            state_notify.notify_waiters();
-
-                            log::error!("Failed to initialize plugin {plugin_name}: {error_msg}",);
+        self.plugins.write().await.push(plugin);
+
+        // Remove from unloaded files if it was there
+        self.unloaded_files.write().await.remove(&path);
+
+        // Spawn async task for plugin initialization
+        let self_ref_clone = Arc::clone(&self_ref);
+        let state_notify = Arc::clone(&self.state_notify);
+        let plugin_name = metadata.name.to_string();
+        let loader_clone = loader.clone();
+
+        let task = tokio::spawn(async move {
+            // Initialize the plugin
+            let loading =
+                LOADING_PLUGIN.scope(plugin_name.clone(), instance.on_load(context.clone()));
+            match loading.await {
+                Ok(()) => {
+                    // Update plugin state to loaded
+                    {
+                        let mut plugins = self_ref_clone.plugins.write().await;
+                        if let Some(plugin) = plugins
+                            .iter_mut()
+                            .rev()
+                            .find(|p| p.metadata.name == plugin_name)
+                        {
+                            plugin.instance = Some(instance);
+                            plugin.is_active = true;
                         }
                     }
-                });
+                    self_ref_clone
+                        .plugin_states
+                        .write()
+                        .await
+                        .insert(plugin_name.clone(), PluginState::Loaded);
+                    state_notify.notify_waiters();
+
+                    log::info!("Loaded {} ({})", metadata.name, metadata.version);
+                }
+                Err(e) => {
+                    // Handle initialization failure
+                    let error_msg = format!("Initialization failed: {e}");
+                    let _ = instance.on_unload(context.clone()).await;
+                    self_ref_clone
+                        .unregister_plugin(&plugin_name, &context.server)
+                        .await;
+
+                    // Take the plugin out again along with its loader data
+                    let plugin = {
+                        let mut plugins = self_ref_clone.plugins.write().await;
+                        plugins
+                            .iter()
+                            .rposition(|p| p.metadata.name == plugin_name)
+                            .map(|index| plugins.remove(index))
+                    };
+
+                    // Try to unload the plugin data
+                    if let Some(data) = plugin.and_then(|plugin| plugin.loader_data) {
+                        tokio::spawn(async move {
+                            loader_clone.unload(data).await.ok();
+                        });
+                    }
 
-                return Ok(task);
-            }
-        }
+                    self_ref_clone
+                        .plugin_states
+                        .write()
+                        .await
+                        .insert(plugin_name.clone(), PluginState::Failed(error_msg.clone()));
+                    state_notify.notify_waiters();
 
-        // No loader could handle this file, track it for future attempts
-        self.unloaded_files.write().await.insert(path.to_path_buf());
+                    log::error!("Failed to initialize plugin {plugin_name}: {error_msg}",);
+                }
+            }
+        });
 
-        Err(ManagerError::PluginNotFound(
-            path.to_string_lossy().to_string(),
-        ))
+        Ok(task)
     }
 
     /// Attempt to load a single plugin file
     pub async fn try_load_plugin(&self, path: &Path) -> Result<(), ManagerError> {
-        self.start_loading_plugin(path).await?.await.map_err(|e| {
-            ManagerError::LoaderError(LoaderError::InitializationFailed(format!(
-                "Task join error: {e}"
-            )))
-        })
+        let plugin = self.read_plugin(path).await?;
+        match self.load_in_order(vec![plugin]).await.pop() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
     }
 
     /// Wait for a plugin to finish loading
+    ///
+    /// Plugins that are queued to load are waited for as well. Plugins load one at a time though,
+    /// so a plugin waiting in its `on_load` for one that is queued after it would never see it
+    /// load; that returns [`ManagerError::LoadedAfterWaiter`] right away instead. Declare the
+    /// plugin in `depends` or `soft_depends` to have it loaded first
     pub async fn wait_for_plugin(&self, plugin_name: &str) -> Result<(), ManagerError> {
         loop {
+            // Listen before reading the state, so a change in between isn't missed
+            let notified = self.state_notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
             let state = self.plugin_states.read().await.get(plugin_name).cloned();
-            if let Some(state) = state {
-                match state {
-                    PluginState::Loaded => return Ok(()),
-                    PluginState::Failed(error) => {
-                        return Err(ManagerError::LoaderError(
-                            LoaderError::InitializationFailed(error),
-                        ));
-                    }
-                    PluginState::Loading => {
-                        // Wait for state change notification
-                        self.state_notify.notified().await;
-                        continue;
+            match state {
+                Some(PluginState::Loaded) => return Ok(()),
+                Some(PluginState::Failed(error)) => {
+                    return Err(ManagerError::LoaderError(
+                        LoaderError::InitializationFailed(error),
+                    ));
+                }
+                Some(PluginState::Queued) => {
+                    if let Ok(plugin) = LOADING_PLUGIN.try_with(Clone::clone) {
+                        return Err(ManagerError::LoadedAfterWaiter {
+                            plugin,
+                            dependency: plugin_name.to_string(),
+                        });
                     }
+                    notified.await;
                 }
+                Some(PluginState::Loading) => notified.await,
+                None => return Err(ManagerError::PluginNotFound(plugin_name.to_string())),
             }
-            return Err(ManagerError::PluginNotFound(plugin_name.to_string()));
         }
     }
 
@@ -529,8 +741,78 @@ impl PluginManager {
         plugins.iter().map(|p| p.metadata.clone()).collect()
     }
 
-    /// Unload a plugin by name
+    /// Unload a plugin by name, along with the plugins that depend on it
     pub async fn unload_plugin(&self, name: &str) -> Result<(), ManagerError> {
+        self.remove_plugin(name, false).await.map(|_| ())
+    }
+
+    /// Reload a plugin from its file, along with the plugins that depend on it
+    ///
+    /// Each plugin may save a state before it is unloaded, which its new instance takes back with
+    /// `Context::take_saved_state` while it loads
+    pub async fn reload_plugin(&self, name: &str) -> Result<(), ManagerError> {
+        let can_unload = {
+            let plugins = self.plugins.read().await;
+            plugins
+                .iter()
+                .find(|p| p.metadata.name == name && p.is_active)
+                .map(|p| p.loader.can_unload())
+                .ok_or_else(|| ManagerError::PluginNotFound(name.to_string()))?
+        };
+        if !can_unload {
+            return Err(ManagerError::ReloadUnsupported(name.to_string()));
+        }
+
+        let unloaded = self.remove_plugin(name, true).await?;
+        let mut pending = Vec::new();
+        let mut error = None;
+        for (plugin, path) in &unloaded {
+            match self.read_plugin(path).await {
+                Ok(plugin) => pending.push(plugin),
+                Err(e) => {
+                    log::error!("Failed to reload plugin {plugin}: {e}");
+                    if plugin == name {
+                        error = Some(e);
+                    }
+                }
+            }
+        }
+        self.load_in_order(pending).await;
+
+        // The states of plugins that didn't come back are dropped
+        self.discard_saved_states(unloaded.iter().map(|(plugin, _)| plugin.as_str()))
+            .await;
+
+        match error {
+            Some(error) => Err(error),
+            None => self.wait_for_plugin(name).await,
+        }
+    }
+
+    /// Unload a plugin along with the plugins that depend on it, letting each of them save its
+    /// state first if they are going to be reloaded
+    ///
+    /// Returns the names and files of the unloaded plugins, dependents first
+    async fn remove_plugin(
+        &self,
+        name: &str,
+        save_state: bool,
+    ) -> Result<Vec<(String, PathBuf)>, ManagerError> {
+        let dependents: Vec<String> = {
+            let plugins = self.plugins.read().await;
+            plugins
+                .iter()
+                .rev()
+                .filter(|p| p.is_active && p.metadata.depends.contains(&name))
+                .map(|p| p.metadata.name.to_string())
+                .collect()
+        };
+        let mut removed = Vec::new();
+        for dependent in dependents {
+            log::info!("Unloading {dependent}, as it depends on {name}");
+            removed.extend(Box::pin(self.remove_plugin(&dependent, save_state)).await?);
+        }
+
         let index = {
             let plugins = self.plugins.read().await;
             plugins
@@ -545,8 +827,13 @@ impl PluginManager {
         };
 
         if let Some(mut instance) = plugin.instance.take() {
+            if save_state && let Some(state) = instance.save_state(plugin.context.clone()).await {
+                self.keep_saved_state(name, state).await;
+            }
             instance.on_unload(plugin.context.clone()).await.ok();
         }
+        self.unregister_plugin(name, &plugin.context.server).await;
+        removed.push((name.to_string(), plugin.path.clone()));
 
         if plugin.loader.can_unload() {
             if let Some(data) = plugin.loader_data {
@@ -560,7 +847,69 @@ impl PluginManager {
         // Remove from plugin states
         self.plugin_states.write().await.remove(name);
 
-        Ok(())
+        Ok(removed)
+    }
+
+    /// Remove everything a plugin registered, so none of its code is called anymore
+    async fn unregister_plugin(&self, name: &str, server: &Arc<Server>) {
+        let closed = server.plugin_channels.unregister_plugin(name).await;
+        channels::announce_channels(server, &closed, false).await;
+
+        if let Some(commands) = self.forget_plugin(name).await {
+            {
+                let mut dispatcher = server.command_dispatcher.write().await;
+                for command in &commands {
+                    dispatcher.unregister(command);
+                }
+            }
+            let dispatcher = server.command_dispatcher.read().await;
+            for player in server.get_all_players().await {
+                client_suggestions::send_c_commands_packet(&player, &dispatcher).await;
+            }
+        }
+    }
+
+    /// Remove what a plugin registered with the manager, returning the commands it registered
+    /// for the caller to remove from the dispatcher
+    async fn forget_plugin(&self, name: &str) -> Option<Vec<String>> {
+        self.scheduler.cancel_plugin_tasks(name).await;
+        self.menus.close_plugin_menus(name).await;
+        self.packet_listeners.unregister_plugin(name);
+
+        {
+            let mut handlers = self.handlers.write().await;
+            for handlers in handlers.values_mut() {
+                handlers.retain(|handler| handler.get_plugin() != Some(name));
+            }
+        }
+        self.services
+            .write()
+            .await
+            .retain(|_, (plugin, _)| plugin != name);
+        PERMISSION_REGISTRY.write().await.unregister_namespace(name);
+
+        self.commands.write().await.remove(name)
+    }
+
+    /// Keep the state a plugin saved before it is reloaded, until its new instance takes it
+    async fn keep_saved_state(&self, plugin: &str, state: Vec<u8>) {
+        self.saved_states
+            .write()
+            .await
+            .insert(plugin.to_string(), state);
+    }
+
+    /// Take the state the previous instance of a plugin saved, see `Context::take_saved_state`
+    async fn take_saved_state(&self, plugin: &str) -> Option<Vec<u8>> {
+        self.saved_states.write().await.remove(plugin)
+    }
+
+    /// Drop the states no new instance took, e.g. as the plugin failed to load again
+    async fn discard_saved_states<'a>(&self, plugins: impl IntoIterator<Item = &'a str>) {
+        let mut saved_states = self.saved_states.write().await;
+        for plugin in plugins {
+            saved_states.remove(plugin);
+        }
     }
 
     /// Get all plugins that are currently loading
@@ -593,7 +942,7 @@ impl PluginManager {
         let plugin_states = self.plugin_states.read().await;
         !plugin_states
             .values()
-            .any(|state| matches!(state, PluginState::Loading))
+            .any(|state| matches!(state, PluginState::Queued | PluginState::Loading))
     }
 
     /// Wait for all plugins to finish loading
@@ -614,6 +963,7 @@ impl PluginManager {
             handler,
             priority,
             blocking,
+            plugin: None,
             _phantom: std::marker::PhantomData,
         };
 
@@ -623,11 +973,23 @@ impl PluginManager {
             .push(Box::new(typed_handler));
     }
 
+    /// The packet listeners of plugins, which the connections hand their packets to
+    #[must_use]
+    pub const fn packet_listeners(&self) -> &PacketListeners {
+        &self.packet_listeners
+    }
+
+    /// Run the plugin tasks that are due this tick
+    pub async fn tick_tasks(&self, server: &Arc<Server>) {
+        self.scheduler.tick(server).await;
+    }
+
     /// Fire an event to all registered handlers
     pub async fn fire<E: Payload + Send + Sync + 'static>(&self, mut event: E) -> E {
         if let Some(server) = self.server.read().await.as_ref() {
             let handlers = self.handlers.read().await;
             if let Some(handlers) = handlers.get(&E::get_name_static()) {
+                let start = Instant::now();
                 let (blocking, non_blocking): (Vec<_>, Vec<_>) =
                     handlers.iter().partition(|h| h.is_blocking());
 
@@ -643,8 +1005,158 @@ impl PluginManager {
                         .map(|h| h.handle_dyn(server, &event)),
                 )
                 .await;
+                METRICS.observe_event(E::get_name_static(), start.elapsed());
             }
         }
         event
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use pumpkin_util::permission::{Permission, PermissionDefault};
+
+    use super::{
+        BoxFuture, EventHandler, EventPriority, LOADING_PLUGIN, ManagerError, PluginManager,
+        PluginState, TypedEventHandler,
+        api::{
+            events::{Payload, server::server_command::ServerCommandEvent},
+            packets::{Direction, InterceptedPacket, PacketFilter, PacketListener},
+        },
+    };
+    use crate::{PERMISSION_REGISTRY, server::metrics::Edition};
+
+    struct Noop;
+
+    impl EventHandler<ServerCommandEvent> for Noop {}
+
+    impl PacketListener for Noop {
+        fn on_packet<'a>(&'a self, _packet: &'a mut InterceptedPacket) -> BoxFuture<'a, ()> {
+            Box::pin(async {})
+        }
+    }
+
+    /// Registers a bit of everything for a plugin, like its `Context` would
+    async fn register(manager: &PluginManager, plugin: &str) {
+        manager
+            .handlers
+            .write()
+            .await
+            .entry(ServerCommandEvent::get_name_static())
+            .or_default()
+            .push(Box::new(TypedEventHandler {
+                handler: Arc::new(Noop),
+                priority: EventPriority::Normal,
+                blocking: false,
+                plugin: Some(plugin.to_string()),
+                _phantom: std::marker::PhantomData,
+            }));
+        manager.services.write().await.insert(
+            format!("{plugin}:service"),
+            (
+                plugin.to_string(),
+                Arc::new(ServerCommandEvent::new(String::new())),
+            ),
+        );
+        manager
+            .commands
+            .write()
+            .await
+            .insert(plugin.to_string(), vec![format!("{plugin}cmd")]);
+        PERMISSION_REGISTRY
+            .write()
+            .await
+            .register_permission(Permission::new(
+                &format!("{plugin}:use"),
+                "",
+                PermissionDefault::Allow,
+            ))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn forgets_what_the_plugin_registered() {
+        let manager = PluginManager::new();
+        register(&manager, "unloaded").await;
+        register(&manager, "kept").await;
+        let task = manager
+            .scheduler
+            .run_later("unloaded", 10, |_| async {})
+            .await;
+        let filter = || PacketFilter::new(Edition::Java, Direction::Clientbound, &[1]);
+        manager
+            .packet_listeners
+            .register("unloaded", "Noop", filter(), Arc::new(Noop));
+
+        let commands = manager.forget_plugin("unloaded").await;
+        assert_eq!(commands, Some(vec!["unloadedcmd".to_string()]));
+        assert!(task.is_cancelled());
+        assert!(
+            !manager
+                .packet_listeners
+                .is_intercepted(Edition::Java, Direction::Clientbound, 1)
+        );
+
+        let handlers = manager.handlers.read().await;
+        let plugins: Vec<_> = handlers[ServerCommandEvent::get_name_static()]
+            .iter()
+            .map(|handler| handler.get_plugin())
+            .collect();
+        assert_eq!(plugins, [Some("kept")]);
+        let services = manager.services.read().await;
+        assert!(!services.contains_key("unloaded:service"));
+        assert!(services.contains_key("kept:service"));
+        assert!(!manager.commands.read().await.contains_key("unloaded"));
+        let registry = PERMISSION_REGISTRY.read().await;
+        assert!(!registry.has_permission("unloaded:use"));
+        assert!(registry.has_permission("kept:use"));
+        drop(registry);
+
+        // Nothing is left to remove the second time
+        assert_eq!(manager.forget_plugin("unloaded").await, None);
+    }
+
+    #[tokio::test]
+    async fn hands_saved_state_to_the_new_instance_once() {
+        let manager = PluginManager::new();
+        manager.keep_saved_state("reloaded", vec![1, 2]).await;
+        manager.keep_saved_state("failed", vec![3]).await;
+
+        assert_eq!(manager.take_saved_state("reloaded").await, Some(vec![1, 2]));
+        assert_eq!(manager.take_saved_state("reloaded").await, None);
+
+        // A plugin that didn't come back doesn't get its state if it is loaded later on
+        manager.discard_saved_states(["reloaded", "failed"]).await;
+        assert_eq!(manager.take_saved_state("failed").await, None);
+    }
+
+    #[tokio::test]
+    async fn waits_for_queued_plugins_unless_loading() {
+        let manager = PluginManager::default();
+        manager
+            .plugin_states
+            .write()
+            .await
+            .insert("economy".to_string(), PluginState::Queued);
+
+        // It is queued after the plugin waiting in its `on_load`, so it would never load
+        let waiting = LOADING_PLUGIN.scope("shop".to_string(), manager.wait_for_plugin("economy"));
+        assert!(matches!(
+            waiting.await,
+            Err(ManagerError::LoadedAfterWaiter { plugin, .. }) if plugin == "shop"
+        ));
+
+        let (waited, ()) = tokio::join!(manager.wait_for_plugin("economy"), async {
+            tokio::task::yield_now().await;
+            manager
+                .plugin_states
+                .write()
+                .await
+                .insert("economy".to_string(), PluginState::Loaded);
+            manager.state_notify.notify_waiters();
+        });
+        assert!(waited.is_ok());
+    }
+}
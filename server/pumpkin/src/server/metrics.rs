@@ -0,0 +1,433 @@
+//! Server metrics in the OpenMetrics text format, served by `net::metrics_host` for Prometheus
+//! to scrape. Counters that are updated all over the server live in [`METRICS`], everything else
+//! is read from the server when it is scraped.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::{net::ClientPlatform, server::Server};
+
+pub static METRICS: Metrics = Metrics::new();
+
+pub const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Upper bounds of the tick duration buckets in seconds, a tick at 20 TPS has 0.05s
+const TICK_BUCKETS: [f64; 9] = [0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 1.0];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edition {
+    Java,
+    Bedrock,
+}
+
+impl Edition {
+    const ALL: [Self; 2] = [Self::Java, Self::Bedrock];
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Java => "java",
+            Self::Bedrock => "bedrock",
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct EventTiming {
+    calls: u64,
+    nanos: u64,
+}
+
+pub struct Metrics {
+    /// Ticks per bucket of [`TICK_BUCKETS`], the last one counts the ticks slower than all bounds
+    tick_buckets: [AtomicU64; TICK_BUCKETS.len() + 1],
+    tick_nanos: AtomicU64,
+    bytes_received: [AtomicU64; 2],
+    bytes_sent: [AtomicU64; 2],
+    event_timings: Mutex<BTreeMap<&'static str, EventTiming>>,
+    /// Keyed by the plugin and the name of the listener
+    packet_listener_timings: Mutex<BTreeMap<(Arc<str>, Arc<str>), EventTiming>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            tick_buckets: [const { AtomicU64::new(0) }; TICK_BUCKETS.len() + 1],
+            tick_nanos: AtomicU64::new(0),
+            bytes_received: [const { AtomicU64::new(0) }; 2],
+            bytes_sent: [const { AtomicU64::new(0) }; 2],
+            event_timings: Mutex::new(BTreeMap::new()),
+            packet_listener_timings: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn observe_tick(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let bucket = TICK_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(TICK_BUCKETS.len());
+        self.tick_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.tick_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_received(&self, edition: Edition, bytes: usize) {
+        self.bytes_received[edition as usize].fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_sent(&self, edition: Edition, bytes: usize) {
+        self.bytes_sent[edition as usize].fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records how long the plugin handlers of an event took
+    pub fn observe_event(&self, event: &'static str, duration: Duration) {
+        let mut timings = self.event_timings.lock().unwrap();
+        let timing = timings.entry(event).or_default();
+        timing.calls += 1;
+        timing.nanos += duration.as_nanos() as u64;
+    }
+
+    /// Records how long a packet listener of a plugin took for one packet
+    pub fn observe_packet_listener(
+        &self,
+        plugin: &Arc<str>,
+        listener: &Arc<str>,
+        duration: Duration,
+    ) {
+        let mut timings = self.packet_listener_timings.lock().unwrap();
+        let timing = timings
+            .entry((plugin.clone(), listener.clone()))
+            .or_default();
+        timing.calls += 1;
+        timing.nanos += duration.as_nanos() as u64;
+    }
+
+    /// Renders the counters together with a snapshot of the server
+    #[must_use]
+    pub fn render(&self, snapshot: &ServerSnapshot) -> String {
+        let mut out = String::new();
+
+        family(
+            &mut out,
+            "pumpkin_tps",
+            "gauge",
+            "Ticks per second over the last 100 ticks",
+        );
+        sample(&mut out, "pumpkin_tps", "", snapshot.tps);
+        family(
+            &mut out,
+            "pumpkin_mspt",
+            "gauge",
+            "Average milliseconds per tick over the last 100 ticks",
+        );
+        sample(&mut out, "pumpkin_mspt", "", snapshot.mspt);
+
+        family(
+            &mut out,
+            "pumpkin_tick_duration_seconds",
+            "histogram",
+            "How long ticks took",
+        );
+        let mut count = 0;
+        for (bucket, bound) in self.tick_buckets.iter().zip(TICK_BUCKETS) {
+            count += bucket.load(Ordering::Relaxed);
+            let labels = format!("le=\"{bound}\"");
+            sample(
+                &mut out,
+                "pumpkin_tick_duration_seconds_bucket",
+                &labels,
+                count,
+            );
+        }
+        count += self.tick_buckets[TICK_BUCKETS.len()].load(Ordering::Relaxed);
+        sample(
+            &mut out,
+            "pumpkin_tick_duration_seconds_bucket",
+            "le=\"+Inf\"",
+            count,
+        );
+        sample(&mut out, "pumpkin_tick_duration_seconds_count", "", count);
+        let sum = Duration::from_nanos(self.tick_nanos.load(Ordering::Relaxed)).as_secs_f64();
+        sample(&mut out, "pumpkin_tick_duration_seconds_sum", "", sum);
+
+        family(
+            &mut out,
+            "pumpkin_players",
+            "gauge",
+            "Online players per edition",
+        );
+        sample(
+            &mut out,
+            "pumpkin_players",
+            "edition=\"java\"",
+            snapshot.java_players,
+        );
+        sample(
+            &mut out,
+            "pumpkin_players",
+            "edition=\"bedrock\"",
+            snapshot.bedrock_players,
+        );
+
+        for (name, help, counters) in [
+            (
+                "pumpkin_network_received_bytes",
+                "Bytes received from clients, Java packets after decompression and Bedrock datagrams",
+                &self.bytes_received,
+            ),
+            (
+                "pumpkin_network_sent_bytes",
+                "Bytes sent to clients, Java packets before compression and Bedrock datagrams",
+                &self.bytes_sent,
+            ),
+        ] {
+            family(&mut out, name, "counter", help);
+            for edition in Edition::ALL {
+                let labels = format!("edition=\"{}\"", edition.label());
+                let value = counters[edition as usize].load(Ordering::Relaxed);
+                sample(&mut out, &format!("{name}_total"), &labels, value);
+            }
+        }
+
+        type WorldValue = fn(&WorldSnapshot) -> u64;
+        let world_families: [(&str, &str, &str, WorldValue); 5] = [
+            (
+                "pumpkin_world_loaded_chunks",
+                "gauge",
+                "Chunks loaded in memory",
+                |world| world.loaded_chunks as u64,
+            ),
+            (
+                "pumpkin_world_entities",
+                "gauge",
+                "Entities in the world, without players",
+                |world| world.entities as u64,
+            ),
+            (
+                "pumpkin_world_generation_queue",
+                "gauge",
+                "Chunk tasks waiting in the generation queue",
+                |world| world.generation_queue as u64,
+            ),
+            (
+                "pumpkin_world_unloaded_chunks",
+                "counter",
+                "Chunks dropped from memory because nobody watched them anymore",
+                |world| world.unloaded_chunks,
+            ),
+            (
+                "pumpkin_world_unloaded_entity_chunks",
+                "counter",
+                "Entity chunks dropped from memory because nobody watched them anymore",
+                |world| world.unloaded_entity_chunks,
+            ),
+        ];
+        for (name, kind, help, value) in world_families {
+            family(&mut out, name, kind, help);
+            let sample_name = if kind == "counter" {
+                format!("{name}_total")
+            } else {
+                name.to_string()
+            };
+            for world in &snapshot.worlds {
+                let labels = format!("world=\"{}\"", escape_label(&world.name));
+                sample(&mut out, &sample_name, &labels, value(world));
+            }
+        }
+
+        family(
+            &mut out,
+            "pumpkin_plugin_event_seconds",
+            "summary",
+            "How long the plugin handlers of an event took",
+        );
+        for (event, timing) in self.event_timings.lock().unwrap().iter() {
+            let labels = format!("event=\"{}\"", escape_label(event));
+            sample(
+                &mut out,
+                "pumpkin_plugin_event_seconds_count",
+                &labels,
+                timing.calls,
+            );
+            let sum = Duration::from_nanos(timing.nanos).as_secs_f64();
+            sample(&mut out, "pumpkin_plugin_event_seconds_sum", &labels, sum);
+        }
+
+        family(
+            &mut out,
+            "pumpkin_plugin_packet_listener_seconds",
+            "summary",
+            "How long the packet listeners of plugins took",
+        );
+        for ((plugin, listener), timing) in self.packet_listener_timings.lock().unwrap().iter() {
+            let labels = format!(
+                "plugin=\"{}\",listener=\"{}\"",
+                escape_label(plugin),
+                escape_label(listener)
+            );
+            sample(
+                &mut out,
+                "pumpkin_plugin_packet_listener_seconds_count",
+                &labels,
+                timing.calls,
+            );
+            let sum = Duration::from_nanos(timing.nanos).as_secs_f64();
+            sample(
+                &mut out,
+                "pumpkin_plugin_packet_listener_seconds_sum",
+                &labels,
+                sum,
+            );
+        }
+
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+/// What the metrics read from the server when it is scraped
+#[derive(Default)]
+pub struct ServerSnapshot {
+    pub tps: f64,
+    pub mspt: f64,
+    pub java_players: usize,
+    pub bedrock_players: usize,
+    pub worlds: Vec<WorldSnapshot>,
+}
+
+pub struct WorldSnapshot {
+    pub name: String,
+    pub loaded_chunks: usize,
+    pub entities: usize,
+    pub generation_queue: usize,
+    pub unloaded_chunks: u64,
+    pub unloaded_entity_chunks: u64,
+}
+
+impl ServerSnapshot {
+    pub async fn collect(server: &Server) -> Self {
+        let mspt = server.get_average_tick_time_nanos() as f64 / 1_000_000.0;
+        let mut snapshot = Self {
+            tps: if mspt > 0.0 {
+                (1000.0 / mspt).min(f64::from(server.tick_rate_manager.tickrate()))
+            } else {
+                0.0
+            },
+            mspt,
+            ..Default::default()
+        };
+
+        for world in server.worlds.read().await.iter() {
+            for player in world.players.read().await.values() {
+                match player.client {
+                    ClientPlatform::Java(_) => snapshot.java_players += 1,
+                    ClientPlatform::Bedrock(_) => snapshot.bedrock_players += 1,
+                }
+            }
+            let level = &world.level;
+            snapshot.worlds.push(WorldSnapshot {
+                name: world.dimension_type.resource_location().to_string(),
+                loaded_chunks: level.loaded_chunk_count(),
+                entities: world.entities.read().await.len(),
+                generation_queue: level.generation_queue_len.load(Ordering::Relaxed),
+                unloaded_chunks: level.unloaded_chunks.load(Ordering::Relaxed),
+                unloaded_entity_chunks: level.unloaded_entity_chunks.load(Ordering::Relaxed),
+            });
+        }
+        snapshot
+    }
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    let _ = writeln!(out, "# HELP {name} {help}");
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{name} {value}");
+    } else {
+        let _ = writeln!(out, "{name}{{{labels}}} {value}");
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use super::{Edition, Metrics, ServerSnapshot, WorldSnapshot};
+
+    #[test]
+    fn renders_open_metrics() {
+        let metrics = Metrics::new();
+        metrics.observe_tick(Duration::from_millis(3));
+        metrics.observe_tick(Duration::from_millis(40));
+        metrics.observe_tick(Duration::from_secs(2));
+        metrics.add_sent(Edition::Bedrock, 1200);
+        metrics.observe_event("PlayerJoinEvent", Duration::from_millis(500));
+        metrics.observe_packet_listener(
+            &Arc::from("vanish"),
+            &Arc::from("vanish::HidePlayers"),
+            Duration::from_millis(250),
+        );
+
+        let text = metrics.render(&ServerSnapshot {
+            tps: 20.0,
+            mspt: 2.5,
+            java_players: 3,
+            bedrock_players: 1,
+            worlds: vec![WorldSnapshot {
+                name: "minecraft:overworld".to_string(),
+                loaded_chunks: 441,
+                entities: 12,
+                generation_queue: 0,
+                unloaded_chunks: 7,
+                unloaded_entity_chunks: 2,
+            }],
+        });
+        let lines: Vec<_> = text.lines().collect();
+
+        for line in [
+            "pumpkin_tps 20",
+            "pumpkin_tick_duration_seconds_bucket{le=\"0.005\"} 1",
+            "pumpkin_tick_duration_seconds_bucket{le=\"0.05\"} 2",
+            "pumpkin_tick_duration_seconds_bucket{le=\"1\"} 2",
+            "pumpkin_tick_duration_seconds_bucket{le=\"+Inf\"} 3",
+            "pumpkin_tick_duration_seconds_count 3",
+            "pumpkin_tick_duration_seconds_sum 2.043",
+            "pumpkin_players{edition=\"java\"} 3",
+            "pumpkin_network_sent_bytes_total{edition=\"bedrock\"} 1200",
+            "pumpkin_network_sent_bytes_total{edition=\"java\"} 0",
+            "pumpkin_world_loaded_chunks{world=\"minecraft:overworld\"} 441",
+            "pumpkin_world_unloaded_chunks_total{world=\"minecraft:overworld\"} 7",
+            "pumpkin_plugin_event_seconds_count{event=\"PlayerJoinEvent\"} 1",
+            "pumpkin_plugin_event_seconds_sum{event=\"PlayerJoinEvent\"} 0.5",
+            "pumpkin_plugin_packet_listener_seconds_count{plugin=\"vanish\",listener=\"vanish::HidePlayers\"} 1",
+            "pumpkin_plugin_packet_listener_seconds_sum{plugin=\"vanish\",listener=\"vanish::HidePlayers\"} 0.25",
+        ] {
+            assert!(lines.contains(&line), "missing {line} in\n{text}");
+        }
+        assert_eq!(lines.last(), Some(&"# EOF"));
+    }
+}
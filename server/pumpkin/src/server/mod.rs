@@ -1,12 +1,20 @@
+use crate::PLUGIN_MANAGER;
 use crate::block::registry::BlockRegistry;
 use crate::command::commands::default_dispatcher;
 use crate::command::commands::defaultgamemode::DefaultGamemode;
 use crate::data::player_server_data::ServerPlayerData;
+use crate::data::user_cache::remember_user;
 use crate::entity::{EntityBase, NBTStorage};
 use crate::item::registry::ItemRegistry;
+use crate::net::java::handshake::load_supported_versions;
+use crate::net::protection::ConnectionThrottle;
 use crate::net::{ClientPlatform, DisconnectReason, EncryptionError, GameProfile, PlayerConfig};
+use crate::plugin::channels::PluginChannels;
 use crate::plugin::player::player_login::PlayerLoginEvent;
+use crate::plugin::player::player_transfer_join::PlayerTransferJoinEvent;
 use crate::plugin::server::server_broadcast::ServerBroadcastEvent;
+use crate::server::pregen::PregenManager;
+use crate::server::resource_pack::ResourcePackManager;
 use crate::server::tick_rate_manager::ServerTickRateManager;
 use crate::world::custom_bossbar::CustomBossbars;
 use crate::{command::dispatcher::CommandDispatcher, entity::player::Player, world::World};
@@ -18,11 +26,14 @@ use crate::command::CommandSender;
 use pumpkin_macros::send_cancellable;
 use pumpkin_protocol::java::client::login::CEncryptionRequest;
 use pumpkin_protocol::java::client::play::CChangeDifficulty;
+use pumpkin_protocol::java::translation::SupportedVersions;
 use pumpkin_protocol::{ClientPacket, java::client::config::CPluginMessage};
 use pumpkin_registry::{Registry, VanillaDimensionType};
 use pumpkin_util::Difficulty;
 use pumpkin_util::math::vector3::Vector3;
+use pumpkin_util::resource_location::ResourceLocation;
 use pumpkin_util::text::TextComponent;
+use pumpkin_world::chunk::convert::{JOB_FILE_NAME, WorldConverter};
 use pumpkin_world::dimension::Dimension;
 use pumpkin_world::lock::LevelLocker;
 use pumpkin_world::lock::anvil::AnvilLevelLocker;
@@ -45,6 +56,9 @@ use tokio_util::task::TaskTracker;
 
 mod connection_cache;
 mod key_store;
+pub mod metrics;
+pub mod pregen;
+pub mod resource_pack;
 pub mod seasonal_events;
 pub mod tick_rate_manager;
 pub mod ticker;
@@ -77,6 +91,16 @@ pub struct Server {
     pub dimensions: Vec<VanillaDimensionType>,
     /// Caches game registries for efficient access.
     pub cached_registry: Vec<Registry>,
+    /// The protocol versions Java clients may join with, and how to translate for them
+    pub supported_versions: SupportedVersions,
+    /// The resource packs sent to joining players and the ones served by the resource pack host
+    pub resource_packs: ResourcePackManager,
+    /// Limits connections and login attempts per IP
+    pub connection_throttle: Arc<ConnectionThrottle>,
+    /// The cookies plugins want from every Java client while it logs in
+    pub login_cookies: RwLock<HashSet<ResourceLocation>>,
+    /// The plugin channels plugins listen on
+    pub plugin_channels: PluginChannels,
     /// Assigns unique IDs to containers.
     container_id: AtomicU32,
     /// Mojang's public keys, used for chat session signing
@@ -98,6 +122,8 @@ pub struct Server {
     pub aggregated_tick_times_nanos: AtomicI64,
     /// Total number of ticks processed by the server
     pub tick_count: AtomicI32,
+    /// Pre-generates chunks in the background
+    pub pregen: PregenManager,
     /// Random unique Server ID used by Bedrock Edition
     pub server_guid: u64,
     tasks: TaskTracker,
@@ -160,6 +186,38 @@ impl Server {
             }
         };
 
+        // Region conversions requested through `/pumpkin world convert` run before any chunk is loaded
+        match WorldConverter::resume_pending(
+            &world_path,
+            &advanced_config.world.chunk,
+            |progress| {
+                log::info!(
+                    "Converting world: {:.1}% ({}/{} regions)",
+                    progress.percent_done(),
+                    progress.regions_done,
+                    progress.regions_total
+                );
+            },
+        )
+        .await
+        {
+            Ok(Some(progress)) => log::info!(
+                "World conversion finished: kept {} chunks, dropped {} chunks, saved {} KiB",
+                progress.chunks_kept,
+                progress.chunks_dropped,
+                progress.bytes_saved() / 1024
+            ),
+            Ok(None) => {}
+            Err(err) => {
+                log::error!("Failed to convert the world: {err}");
+                log::error!(
+                    "Fix the problem and restart to resume, or delete {} to cancel the conversion",
+                    world_path.join(JOB_FILE_NAME).display()
+                );
+                panic!("World conversion failed! See the logs for more info.");
+            }
+        }
+
         let level_info = level_info.unwrap_or_else(|err| {
             log::warn!("Failed to get level_info, using default instead: {err}");
             LevelData::default(basic_config.seed)
@@ -181,12 +239,25 @@ impl Server {
 
         let tick_rate_manager = Arc::new(ServerTickRateManager::new(basic_config.tps));
 
+        let supported_versions =
+            load_supported_versions(&advanced_config.networking.protocol, probe_root);
+        let resource_packs = ResourcePackManager::new(&advanced_config.resource_pack, probe_root);
+        let connection_throttle = Arc::new(ConnectionThrottle::new(
+            advanced_config.networking.protection.clone(),
+            advanced_config.networking.proxy.enabled,
+        ));
+
         let server = Self {
             basic_config,
             advanced_config,
             config_dir: probe_root.to_path_buf(),
 
             cached_registry: Registry::get_synced(),
+            supported_versions,
+            resource_packs,
+            connection_throttle,
+            login_cookies: RwLock::new(HashSet::new()),
+            plugin_channels: PluginChannels::default(),
             container_id: 0.into(),
             worlds: RwLock::new(vec![]),
             dimensions: vec![
@@ -209,6 +280,7 @@ impl Server {
             tick_times_nanos: Mutex::new([0; 100]),
             aggregated_tick_times_nanos: AtomicI64::new(0),
             tick_count: AtomicI32::new(0),
+            pregen: PregenManager::default(),
             tasks: TaskTracker::new(),
             server_guid: rand::random(),
             mojang_public_keys: Mutex::new(Vec::new()),
@@ -261,6 +333,7 @@ impl Server {
             .expect("Nothing should hold a lock of worlds before server startup") =
             // vec![overworld.into()];
             vec![overworld.into(), nether.into(), end.into()];
+        server.pregen.load(&server).await;
         server
     }
 
@@ -362,6 +435,8 @@ impl Server {
             (default_world, None)
         };
 
+        remember_user(&profile).await;
+
         let mut player = Player::new(
             client,
             profile,
@@ -378,6 +453,19 @@ impl Server {
         // Wrap in Arc after data is loaded
         let player = Arc::new(player);
 
+        if let ClientPlatform::Java(client) = &player.client
+            && client.transferred.load(Ordering::Relaxed)
+        {
+            send_cancellable! {{
+                PlayerTransferJoinEvent::new(player.clone(), TextComponent::translate("multiplayer.disconnect.transfers_disabled", []));
+
+                'cancelled: {
+                    player.kick(DisconnectReason::Kicked, event.kick_message).await;
+                    return None;
+                }
+            }}
+        }
+
         send_cancellable! {{
             PlayerLoginEvent::new(player.clone(), TextComponent::text("You have been kicked from the server"));
             'after: {
@@ -646,6 +734,7 @@ impl Server {
     pub async fn tick(self: &Arc<Self>) {
         if self.tick_rate_manager.runs_normally() || self.tick_rate_manager.is_sprinting() {
             self.tick_worlds().await;
+            PLUGIN_MANAGER.tick_tasks(self).await;
             // Always run player and network ticking, even when game is frozen
         } else {
             self.tick_players_and_network().await;
@@ -0,0 +1,448 @@
+//! Chunk pre-generation.
+//!
+//! A pre-generation walks a square spiral of chunks around a center and loads every chunk through
+//! [`Level::get_chunk`](pumpkin_world::level::Level::get_chunk), which holds a force ticket until
+//! the chunk is fully generated. The number of chunks in flight scales with the tick time that is
+//! left over, so players don't notice the generation running.
+//!
+//! Progress is saved to [`PREGEN_FILE_NAME`] in the folder of the dimension, a restarted server
+//! picks it up again.
+
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use pumpkin_registry::VanillaDimensionType;
+use pumpkin_util::math::vector2::Vector2;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+
+use crate::server::Server;
+use crate::{SHOULD_STOP, STOP_INTERRUPT};
+
+/// The file in a dimension folder that holds its pre-generation.
+pub const PREGEN_FILE_NAME: &str = "pregen.json";
+
+/// How often the driver re-evaluates the tick budget when no chunk finishes.
+const BUDGET_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The persisted part of a pre-generation.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PregenTask {
+    pub center_x: i32,
+    pub center_z: i32,
+    /// Radius in chunks, the generated area is a square with a side of `2 * radius + 1`
+    pub radius: i32,
+    /// All chunks of the spiral before this index are generated
+    pub next_index: u64,
+    pub paused: bool,
+}
+
+impl PregenTask {
+    #[must_use]
+    pub const fn new(center_x: i32, center_z: i32, radius: i32) -> Self {
+        Self {
+            center_x,
+            center_z,
+            radius,
+            next_index: 0,
+            paused: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn total(&self) -> u64 {
+        let side = 2 * self.radius as u64 + 1;
+        side * side
+    }
+
+    fn chunk_at(&self, index: u64) -> Vector2<i32> {
+        let (dx, dz) = spiral_offset(index);
+        Vector2::new(self.center_x + dx, self.center_z + dz)
+    }
+}
+
+/// Returns the offset of the chunk at `index` in a square spiral around the origin.
+///
+/// Ring `k` holds the `8k` chunks between the squares with a side of `2k - 1` and `2k + 1`.
+fn spiral_offset(index: u64) -> (i32, i32) {
+    if index == 0 {
+        return (0, 0);
+    }
+    let ring = index.isqrt().div_ceil(2).max(1) as i64;
+    let position = (index - (2 * ring as u64 - 1).pow(2)) as i64;
+    let side = position / (2 * ring);
+    let along = position % (2 * ring);
+
+    let (x, z) = match side {
+        0 => (ring, -ring + 1 + along),
+        1 => (ring - 1 - along, ring),
+        2 => (-ring, ring - 1 - along),
+        _ => (-ring + 1 + along, -ring),
+    };
+    (x as i32, z as i32)
+}
+
+/// A snapshot of a pre-generation, as shown by `/pregen status` and the metrics.
+#[derive(Serialize, Clone)]
+pub struct PregenStatus {
+    pub dimension: String,
+    pub done: u64,
+    pub total: u64,
+    pub percent: f32,
+    pub paused: bool,
+    pub chunks_per_second: f32,
+}
+
+impl PregenStatus {
+    /// The estimated time until the pre-generation is done at the current rate
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        (self.chunks_per_second > 0.0).then(|| {
+            Duration::from_secs_f32((self.total - self.done) as f32 / self.chunks_per_second)
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PregenError {
+    #[error("A pre-generation is already running in {0}")]
+    AlreadyRunning(String),
+    #[error("There is no pre-generation in {0}")]
+    NotRunning(String),
+}
+
+struct RunningPregen {
+    dimension: VanillaDimensionType,
+    task: PregenTask,
+    /// The next spiral index to hand out
+    cursor: u64,
+    in_flight: BTreeSet<u64>,
+    /// Chunks generated since the pre-generation was (re)started, for the rate
+    generated: u64,
+    started: Instant,
+}
+
+impl RunningPregen {
+    fn new(dimension: VanillaDimensionType, task: PregenTask) -> Self {
+        Self {
+            dimension,
+            cursor: task.next_index,
+            task,
+            in_flight: BTreeSet::new(),
+            generated: 0,
+            started: Instant::now(),
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.cursor >= self.task.total() && self.in_flight.is_empty()
+    }
+
+    fn status(&self) -> PregenStatus {
+        let total = self.task.total();
+        let elapsed = self.started.elapsed().as_secs_f32();
+        PregenStatus {
+            dimension: self.dimension.resource_location().to_string(),
+            done: self.task.next_index,
+            total,
+            percent: self.task.next_index as f32 / total as f32 * 100.0,
+            paused: self.task.paused,
+            chunks_per_second: if elapsed > 0.0 {
+                self.generated as f32 / elapsed
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Keeps track of the pre-generations of all dimensions.
+#[derive(Default)]
+pub struct PregenManager {
+    // A sync lock, so the metrics can read the progress without a runtime
+    running: std::sync::Mutex<Vec<RunningPregen>>,
+    wake: Notify,
+}
+
+impl PregenManager {
+    /// Loads the pre-generations that were interrupted by the last shutdown.
+    pub async fn load(&self, server: &Server) {
+        let mut loaded = Vec::new();
+        for world in server.worlds.read().await.iter() {
+            let path = world.level.level_folder.root_folder.join(PREGEN_FILE_NAME);
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            match serde_json::from_str::<PregenTask>(&content) {
+                Ok(task) => {
+                    log::info!(
+                        "Resuming pre-generation of {} at {}/{} chunks",
+                        world.dimension_type.resource_location(),
+                        task.next_index,
+                        task.total()
+                    );
+                    loaded.push(RunningPregen::new(world.dimension_type, task));
+                }
+                Err(err) => log::error!("Failed to read {}: {err}", path.display()),
+            }
+        }
+        self.running.lock().unwrap().extend(loaded);
+        self.wake.notify_one();
+    }
+
+    /// Starts a new pre-generation in the dimension.
+    pub async fn start(
+        &self,
+        server: &Server,
+        dimension: VanillaDimensionType,
+        task: PregenTask,
+    ) -> Result<(), PregenError> {
+        {
+            let mut running = self.running.lock().unwrap();
+            if running.iter().any(|pregen| pregen.dimension == dimension) {
+                return Err(PregenError::AlreadyRunning(
+                    dimension.resource_location().to_string(),
+                ));
+            }
+            running.push(RunningPregen::new(dimension, task.clone()));
+        }
+        save_task(server, dimension, Some(&task)).await;
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Pauses or resumes the pre-generation of a dimension, or of all dimensions.
+    /// Returns the number of pre-generations that changed.
+    pub async fn set_paused(
+        &self,
+        server: &Server,
+        dimension: Option<VanillaDimensionType>,
+        paused: bool,
+    ) -> usize {
+        let changed = {
+            let mut running = self.running.lock().unwrap();
+            running
+                .iter_mut()
+                .filter(|pregen| dimension.is_none_or(|dimension| pregen.dimension == dimension))
+                .filter(|pregen| pregen.task.paused != paused)
+                .map(|pregen| {
+                    pregen.task.paused = paused;
+                    pregen.generated = 0;
+                    pregen.started = Instant::now();
+                    (pregen.dimension, pregen.task.clone())
+                })
+                .collect::<Vec<_>>()
+        };
+        for (dimension, task) in &changed {
+            save_task(server, *dimension, Some(task)).await;
+        }
+        self.wake.notify_one();
+        changed.len()
+    }
+
+    /// Stops the pre-generation of the dimension, keeping what was generated so far.
+    pub async fn cancel(
+        &self,
+        server: &Server,
+        dimension: VanillaDimensionType,
+    ) -> Result<(), PregenError> {
+        {
+            let mut running = self.running.lock().unwrap();
+            let Some(index) = running
+                .iter()
+                .position(|pregen| pregen.dimension == dimension)
+            else {
+                return Err(PregenError::NotRunning(
+                    dimension.resource_location().to_string(),
+                ));
+            };
+            running.remove(index);
+        }
+        save_task(server, dimension, None).await;
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn status(&self) -> Vec<PregenStatus> {
+        self.running
+            .lock()
+            .unwrap()
+            .iter()
+            .map(RunningPregen::status)
+            .collect()
+    }
+
+    /// How many chunks may be generated at once, depending on the tick time that is left over.
+    fn concurrency_limit(server: &Server) -> usize {
+        let max = server
+            .advanced_config
+            .world
+            .pregen
+            .max_concurrent_chunks
+            .max(1);
+        let budget = server.tick_rate_manager.nanoseconds_per_tick() as f64;
+        let used = server.get_average_tick_time_nanos() as f64;
+        let headroom = (1.0 - used / budget).clamp(0.0, 1.0);
+        // Always keep one chunk going, so a busy server still makes progress
+        ((max as f64 * headroom).ceil() as usize).clamp(1, max)
+    }
+
+    /// Hands out up to `count` chunks that should be generated next.
+    fn next_chunks(&self, count: usize) -> Vec<(VanillaDimensionType, u64, Vector2<i32>)> {
+        let mut running = self.running.lock().unwrap();
+        let mut chunks = Vec::with_capacity(count);
+        for pregen in running.iter_mut().filter(|pregen| !pregen.task.paused) {
+            while chunks.len() < count && pregen.cursor < pregen.task.total() {
+                let index = pregen.cursor;
+                pregen.cursor += 1;
+                pregen.in_flight.insert(index);
+                chunks.push((pregen.dimension, index, pregen.task.chunk_at(index)));
+            }
+        }
+        chunks
+    }
+
+    /// Marks a chunk as generated. Returns the pre-generation if it is done with this chunk.
+    fn complete(&self, dimension: VanillaDimensionType, index: u64) -> Option<PregenTask> {
+        let mut running = self.running.lock().unwrap();
+        let position = running
+            .iter()
+            .position(|pregen| pregen.dimension == dimension)?;
+        let pregen = &mut running[position];
+        if !pregen.in_flight.remove(&index) {
+            // Cancelled and started again in the meantime
+            return None;
+        }
+        pregen.generated += 1;
+        pregen.task.next_index = pregen.in_flight.first().copied().unwrap_or(pregen.cursor);
+
+        pregen.is_finished().then(|| running.remove(position).task)
+    }
+
+    async fn save_all(&self, server: &Server) {
+        let tasks = self
+            .running
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|pregen| (pregen.dimension, pregen.task.clone()))
+            .collect::<Vec<_>>();
+        for (dimension, task) in &tasks {
+            save_task(server, *dimension, Some(task)).await;
+        }
+    }
+}
+
+async fn task_path(server: &Server, dimension: VanillaDimensionType) -> PathBuf {
+    server
+        .get_world_from_dimension(dimension)
+        .await
+        .level
+        .level_folder
+        .root_folder
+        .join(PREGEN_FILE_NAME)
+}
+
+/// Writes the task of the dimension, or removes it if there is none.
+async fn save_task(server: &Server, dimension: VanillaDimensionType, task: Option<&PregenTask>) {
+    let path = task_path(server, dimension).await;
+    let result = match task {
+        Some(task) => serde_json::to_string_pretty(task)
+            .map_err(std::io::Error::other)
+            .and_then(|content| std::fs::write(&path, content)),
+        None => match std::fs::remove_file(&path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        },
+    };
+    if let Err(err) = result {
+        log::error!("Failed to save {}: {err}", path.display());
+    }
+}
+
+/// Drives all pre-generations until the server stops.
+///
+/// IMPORTANT: Run this in a new thread/tokio task.
+pub async fn run(server: &Arc<Server>) {
+    let manager = &server.pregen;
+    let save_interval = Duration::from_secs(server.advanced_config.world.pregen.save_interval);
+    let mut generating = JoinSet::new();
+    let mut last_save = Instant::now();
+
+    while !SHOULD_STOP.load(Ordering::Relaxed) {
+        let free = PregenManager::concurrency_limit(server).saturating_sub(generating.len());
+        for (dimension, index, at) in manager.next_chunks(free) {
+            let level = server
+                .get_world_from_dimension(dimension)
+                .await
+                .level
+                .clone();
+            generating.spawn(async move {
+                level.get_chunk(at).await;
+                (dimension, index)
+            });
+        }
+
+        tokio::select! {
+            Some(result) = generating.join_next() => {
+                if let Ok((dimension, index)) = result
+                    && let Some(task) = manager.complete(dimension, index)
+                {
+                    log::info!(
+                        "Pre-generation of {} finished, generated {} chunks",
+                        dimension.resource_location(),
+                        task.total()
+                    );
+                    save_task(server, dimension, None).await;
+                }
+            }
+            () = manager.wake.notified() => {}
+            () = tokio::time::sleep(BUDGET_INTERVAL) => {}
+            () = STOP_INTERRUPT.notified() => break,
+        }
+
+        if last_save.elapsed() >= save_interval {
+            manager.save_all(server).await;
+            last_save = Instant::now();
+        }
+    }
+
+    // Chunks that were still generating are picked up again after the restart
+    generating.abort_all();
+    manager.save_all(server).await;
+    log::debug!("Pre-generation stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::spiral_offset;
+
+    #[test]
+    fn spiral_covers_square() {
+        let radius = 5;
+        let side = 2 * radius + 1;
+        let offsets = (0..side * side)
+            .map(|index| spiral_offset(index as u64))
+            .collect::<Vec<_>>();
+
+        let unique = offsets.iter().collect::<HashSet<_>>();
+        assert_eq!(unique.len(), offsets.len());
+        assert!(
+            offsets
+                .iter()
+                .all(|(x, z)| x.abs() <= radius && z.abs() <= radius)
+        );
+
+        // Every chunk is next to the one before it
+        for pair in offsets.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert!((a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1);
+        }
+    }
+}
@@ -0,0 +1,383 @@
+//! Resource packs sent to players: the one from the config, the ones in the resource pack folder
+//! and the ones plugins host while the server is running.
+
+use std::{collections::HashMap, fs, io::Read, path::Path, sync::Arc};
+
+use bytes::Bytes;
+use flate2::read::DeflateDecoder;
+use pumpkin_config::resource_pack::{ResourcePackConfig, ResourcePackHostConfig};
+use serde::Deserialize;
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use thiserror::Error;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// A Java resource pack, which the client downloads itself
+pub struct ResourcePack {
+    pub uuid: Uuid,
+    pub url: String,
+    /// Hex encoded SHA-1 of the pack, the client uses it to cache downloads
+    pub sha1: String,
+}
+
+impl ResourcePack {
+    /// A pack hosted somewhere else
+    #[must_use]
+    pub fn external(url: String, sha1: String) -> Self {
+        Self {
+            uuid: Uuid::new_v3(&Uuid::NAMESPACE_DNS, url.as_bytes()),
+            url,
+            sha1,
+        }
+    }
+}
+
+/// A Bedrock resource pack, which is sent in chunks over the game connection
+pub struct BedrockResourcePack {
+    pub uuid: Uuid,
+    pub version: String,
+    pub data: Bytes,
+    pub sha256: [u8; 32],
+}
+
+impl BedrockResourcePack {
+    /// How much of the pack is sent in a single packet
+    pub const CHUNK_SIZE: u32 = 128 * 1024;
+
+    pub fn from_mcpack(data: Bytes) -> Result<Self, ResourcePackError> {
+        let manifest = read_zip_entry(&data, "manifest.json")?;
+        let manifest: Manifest = serde_json::from_slice(&manifest)?;
+        let [major, minor, patch] = manifest.header.version;
+        Ok(Self {
+            uuid: manifest.header.uuid,
+            version: format!("{major}.{minor}.{patch}"),
+            sha256: Sha256::digest(&data).into(),
+            data,
+        })
+    }
+
+    /// The id the client uses to refer to this pack
+    #[must_use]
+    pub fn id(&self) -> String {
+        format!("{}_{}", self.uuid, self.version)
+    }
+
+    #[must_use]
+    pub fn chunk_count(&self) -> u32 {
+        self.data.len().div_ceil(Self::CHUNK_SIZE as usize) as u32
+    }
+
+    /// The chunk at `index` together with its offset in the pack
+    #[must_use]
+    pub fn chunk(&self, index: u32) -> Option<(u64, &[u8])> {
+        let start = index as usize * Self::CHUNK_SIZE as usize;
+        if start >= self.data.len() {
+            return None;
+        }
+        let end = (start + Self::CHUNK_SIZE as usize).min(self.data.len());
+        Some((start as u64, &self.data[start..end]))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ResourcePackError {
+    #[error("Failed to read the pack: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The pack is not a valid zip archive")]
+    InvalidArchive,
+    #[error("The pack has no {0}")]
+    MissingEntry(&'static str),
+    #[error("The pack manifest is invalid: {0}")]
+    InvalidManifest(#[from] serde_json::Error),
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    header: ManifestHeader,
+}
+
+#[derive(Deserialize)]
+struct ManifestHeader {
+    uuid: Uuid,
+    version: [u32; 3],
+}
+
+pub struct ResourcePackManager {
+    host: Option<ResourcePackHostConfig>,
+    java: Vec<Arc<ResourcePack>>,
+    bedrock: Vec<Arc<BedrockResourcePack>>,
+    /// Files served by the HTTP server, by file name
+    hosted: RwLock<HashMap<String, Bytes>>,
+}
+
+impl ResourcePackManager {
+    /// Loads the pack from the config and every pack in the resource pack folder
+    #[must_use]
+    pub fn new(config: &ResourcePackConfig, config_dir: &Path) -> Self {
+        let mut manager = Self {
+            host: config.host.enabled.then(|| config.host.clone()),
+            java: Vec::new(),
+            bedrock: Vec::new(),
+            hosted: RwLock::new(HashMap::new()),
+        };
+        if !config.enabled {
+            return manager;
+        }
+
+        if !config.url.is_empty() {
+            manager.java.push(Arc::new(ResourcePack::external(
+                config.url.clone(),
+                config.sha1.clone(),
+            )));
+        }
+
+        let Ok(entries) = fs::read_dir(config_dir.join(&config.folder)) else {
+            return manager;
+        };
+        let mut paths = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>();
+        // The folder order decides which pack ends up on top
+        paths.sort();
+        for path in paths {
+            let Some(extension) = path.extension().and_then(|extension| extension.to_str()) else {
+                continue;
+            };
+            let data = match fs::read(&path) {
+                Ok(data) => Bytes::from(data),
+                Err(err) => {
+                    log::warn!("Failed to read resource pack {}: {err}", path.display());
+                    continue;
+                }
+            };
+            match extension {
+                "zip" => match manager.host_now(data) {
+                    Some(pack) => {
+                        log::info!("Hosting resource pack {} at {}", path.display(), pack.url);
+                        manager.java.push(pack);
+                    }
+                    None => log::warn!(
+                        "Skipping resource pack {}, resource pack hosting is disabled",
+                        path.display()
+                    ),
+                },
+                "mcpack" => match BedrockResourcePack::from_mcpack(data) {
+                    Ok(pack) => {
+                        log::info!("Loaded Bedrock resource pack {}", path.display());
+                        manager.bedrock.push(Arc::new(pack));
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to load resource pack {}: {err}", path.display());
+                    }
+                },
+                _ => {}
+            }
+        }
+        manager
+    }
+
+    #[must_use]
+    pub const fn host_config(&self) -> Option<&ResourcePackHostConfig> {
+        self.host.as_ref()
+    }
+
+    fn host_now(&mut self, data: Bytes) -> Option<Arc<ResourcePack>> {
+        let (file_name, pack) = self.hosted_pack(&data)?;
+        self.hosted.get_mut().insert(file_name, data);
+        Some(pack)
+    }
+
+    fn hosted_pack(&self, data: &[u8]) -> Option<(String, Arc<ResourcePack>)> {
+        let host = self.host.as_ref()?;
+        let sha1 = hex(&Sha1::digest(data));
+        // Naming files by their hash keeps URLs unique, so clients never use an outdated download
+        let file_name = format!("{sha1}.zip");
+        let pack = ResourcePack::external(host.url_for(&file_name), sha1);
+        Some((file_name, Arc::new(pack)))
+    }
+
+    /// Hosts a pack on the built-in HTTP server, so it can be pushed to players.
+    /// Returns `None` if resource pack hosting is disabled
+    pub async fn host(&self, data: Bytes) -> Option<Arc<ResourcePack>> {
+        let (file_name, pack) = self.hosted_pack(&data)?;
+        self.hosted.write().await.insert(file_name, data);
+        Some(pack)
+    }
+
+    /// A file served by the HTTP server
+    pub async fn hosted_file(&self, file_name: &str) -> Option<Bytes> {
+        self.hosted.read().await.get(file_name).cloned()
+    }
+
+    /// The packs every Java player gets when joining
+    #[must_use]
+    pub fn java_packs(&self) -> &[Arc<ResourcePack>] {
+        &self.java
+    }
+
+    /// The packs every Bedrock player gets when joining
+    #[must_use]
+    pub fn bedrock_packs(&self) -> &[Arc<BedrockResourcePack>] {
+        &self.bedrock
+    }
+
+    /// Looks up a Bedrock pack by the id the client uses, `<uuid>_<version>`
+    #[must_use]
+    pub fn bedrock_pack(&self, id: &str) -> Option<&Arc<BedrockResourcePack>> {
+        let uuid = id.split('_').next()?;
+        self.bedrock
+            .iter()
+            .find(|pack| pack.uuid.to_string() == uuid)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+}
+
+/// Reads a file from a zip archive. Packs are often zipped together with their root folder, so
+/// the least nested file with that name is used
+fn read_zip_entry(archive: &[u8], name: &'static str) -> Result<Vec<u8>, ResourcePackError> {
+    const END_OF_DIRECTORY: &[u8] = &[0x50, 0x4B, 0x05, 0x06];
+    const DIRECTORY_ENTRY: &[u8] = &[0x50, 0x4B, 0x01, 0x02];
+    const LOCAL_HEADER: &[u8] = &[0x50, 0x4B, 0x03, 0x04];
+    const STORED: usize = 0;
+    const DEFLATED: usize = 8;
+
+    let end = archive
+        .windows(4)
+        .rposition(|window| window == END_OF_DIRECTORY)
+        .ok_or(ResourcePackError::InvalidArchive)?;
+    let entry_count = read_u16(archive, end + 10).ok_or(ResourcePackError::InvalidArchive)?;
+    let mut offset = read_u32(archive, end + 16).ok_or(ResourcePackError::InvalidArchive)?;
+
+    // (nesting, method, compressed size, local header offset)
+    let mut found = None::<(usize, usize, usize, usize)>;
+    for _ in 0..entry_count {
+        if archive.get(offset..offset + 4) != Some(DIRECTORY_ENTRY) {
+            return Err(ResourcePackError::InvalidArchive);
+        }
+        let entry = (|| {
+            let method = read_u16(archive, offset + 10)?;
+            let compressed_size = read_u32(archive, offset + 20)?;
+            let name_len = read_u16(archive, offset + 28)?;
+            let extra_len = read_u16(archive, offset + 30)?;
+            let comment_len = read_u16(archive, offset + 32)?;
+            let header_offset = read_u32(archive, offset + 42)?;
+            let file_name = archive.get(offset + 46..offset + 46 + name_len)?;
+            offset += 46 + name_len + extra_len + comment_len;
+            Some((file_name, method, compressed_size, header_offset))
+        })()
+        .ok_or(ResourcePackError::InvalidArchive)?;
+
+        let (file_name, method, compressed_size, header_offset) = entry;
+        let Ok(file_name) = str::from_utf8(file_name) else {
+            continue;
+        };
+        if file_name.rsplit('/').next() != Some(name) {
+            continue;
+        }
+        let nesting = file_name.matches('/').count();
+        if found.is_none_or(|(found_nesting, ..)| nesting < found_nesting) {
+            found = Some((nesting, method, compressed_size, header_offset));
+        }
+    }
+
+    let (_, method, compressed_size, header_offset) =
+        found.ok_or(ResourcePackError::MissingEntry(name))?;
+    if archive.get(header_offset..header_offset + 4) != Some(LOCAL_HEADER) {
+        return Err(ResourcePackError::InvalidArchive);
+    }
+    let data_offset = (|| {
+        let name_len = read_u16(archive, header_offset + 26)?;
+        let extra_len = read_u16(archive, header_offset + 28)?;
+        Some(header_offset + 30 + name_len + extra_len)
+    })()
+    .ok_or(ResourcePackError::InvalidArchive)?;
+    let data = archive
+        .get(data_offset..data_offset + compressed_size)
+        .ok_or(ResourcePackError::InvalidArchive)?;
+
+    match method {
+        STORED => Ok(data.to_vec()),
+        DEFLATED => {
+            let mut decompressed = Vec::new();
+            DeflateDecoder::new(data).read_to_end(&mut decompressed)?;
+            Ok(decompressed)
+        }
+        _ => Err(ResourcePackError::InvalidArchive),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::Bytes;
+
+    use super::{BedrockResourcePack, read_zip_entry};
+
+    /// Builds a zip archive with uncompressed entries
+    fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+        for (name, data) in entries {
+            let header_offset = archive.len() as u32;
+            let mut header = Vec::new();
+            header.extend_from_slice(&[0x50, 0x4B, 0x03, 0x04]);
+            header.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&[0, 0]);
+            archive.extend_from_slice(&header);
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(data);
+
+            directory.extend_from_slice(&[0x50, 0x4B, 0x01, 0x02, 20, 0]);
+            directory.extend_from_slice(&header[4..28]);
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&header_offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_offset = archive.len() as u32;
+        archive.extend_from_slice(&directory);
+        archive.extend_from_slice(&[0x50, 0x4B, 0x05, 0x06, 0, 0, 0, 0]);
+        archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&directory_offset.to_le_bytes());
+        archive.extend_from_slice(&[0, 0]);
+        archive
+    }
+
+    #[test]
+    fn reads_mcpack_manifest() {
+        let manifest = br#"{"format_version": 2, "header": {"name": "Test", "uuid": "a3e0b2c4-61f6-4a4e-9e38-1c0f9b6f1a2d", "version": [1, 2, 3]}}"#;
+        let archive = zip(&[
+            ("pack/textures/blocks/stone.png", b"not a png"),
+            ("pack/subpacks/low/manifest.json", b"{}"),
+            ("pack/manifest.json", manifest),
+        ]);
+        assert_eq!(
+            read_zip_entry(&archive, "manifest.json").unwrap(),
+            manifest.to_vec()
+        );
+
+        let pack = BedrockResourcePack::from_mcpack(Bytes::from(archive)).unwrap();
+        assert_eq!(pack.id(), "a3e0b2c4-61f6-4a4e-9e38-1c0f9b6f1a2d_1.2.3");
+        assert_eq!(pack.chunk_count(), 1);
+        assert_eq!(pack.chunk(0).unwrap().1.len(), pack.data.len());
+        assert!(pack.chunk(1).is_none());
+    }
+}
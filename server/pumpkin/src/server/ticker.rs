@@ -1,4 +1,7 @@
-use crate::{SHOULD_STOP, server::Server};
+use crate::{
+    SHOULD_STOP,
+    server::{Server, metrics::METRICS},
+};
 use std::{
     sync::{Arc, atomic::Ordering},
     time::{Duration, Instant},
@@ -49,8 +52,11 @@ impl Ticker {
             }
 
             // Record the total time this tick took
-            let tick_duration_nanos = tick_start_time.elapsed().as_nanos() as i64;
-            server.update_tick_times(tick_duration_nanos).await;
+            let tick_duration = tick_start_time.elapsed();
+            server
+                .update_tick_times(tick_duration.as_nanos() as i64)
+                .await;
+            METRICS.observe_tick(tick_duration);
 
             // Sleep logic remains the same
             let now = Instant::now();
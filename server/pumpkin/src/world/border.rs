@@ -46,6 +46,14 @@ impl Worldborder {
         }
     }
 
+    /// Whether the block at `x`, `z` is inside the border
+    #[must_use]
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        let radius = self.new_diameter / 2.0;
+        (f64::from(x) + 0.5 - self.center_x).abs() < radius
+            && (f64::from(z) + 0.5 - self.center_z).abs() < radius
+    }
+
     pub async fn init_client(&self, client: &Arc<JavaClient>) {
         client
             .enqueue_packet(&CInitializeWorldBorder::new(
@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use pumpkin_data::{Block, BlockState};
 use pumpkin_util::math::{position::BlockPos, vector3::Vector3};
 
 use crate::{
+    PLUGIN_MANAGER,
     block::{ExplodeArgs, drop_loot},
+    plugin::world::explosion::ExplosionEvent,
     world::loot::LootContextParameters,
 };
 
@@ -73,14 +75,27 @@ impl Explosion {
         map
     }
 
-    /// Returns the removed block count
-    pub async fn explode(&self, world: &Arc<World>) -> u32 {
-        let blocks = self.get_blocks_to_destroy(world).await;
+    /// Returns the removed block count, `None` if a plugin cancelled the explosion
+    pub async fn explode(&self, world: &Arc<World>) -> Option<u32> {
+        let mut blocks = self.get_blocks_to_destroy(world).await;
+        blocks.retain(|_, (_, state)| !state.is_air());
+
+        let event = ExplosionEvent::new(
+            world.clone(),
+            self.pos,
+            self.power,
+            blocks.keys().copied().collect(),
+        );
+        let event = PLUGIN_MANAGER.fire(event).await;
+        if event.cancelled {
+            return None;
+        }
+        // Blocks a plugin added are ignored, the explosion never reached them
+        let kept: HashSet<BlockPos> = event.blocks.into_iter().collect();
+        blocks.retain(|pos, _| kept.contains(pos));
+
         // TODO: Entity damage, fire
         for (pos, (block, state)) in &blocks {
-            if state.is_air() {
-                continue;
-            }
             let pumpkin_block = world.block_registry.get_pumpkin_block(block);
 
             world.set_block_state(pos, 0, BlockFlags::NOTIFY_ALL).await;
@@ -103,6 +118,6 @@ impl Explosion {
                     .await;
             }
         }
-        blocks.len() as u32
+        Some(blocks.len() as u32)
     }
 }
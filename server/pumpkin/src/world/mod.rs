@@ -20,7 +20,7 @@ use crate::{
     block::{
         self,
         registry::BlockRegistry,
-        {OnNeighborUpdateArgs, OnScheduledTickArgs},
+        {OnNeighborUpdateArgs, OnScheduledTickArgs, RandomTickArgs},
     },
     command::client_suggestions,
     entity::{Entity, EntityBase, player::Player, r#type::from_type},
@@ -28,7 +28,11 @@ use crate::{
     net::ClientPlatform,
     plugin::{
         block::block_break::BlockBreakEvent,
-        player::{player_join::PlayerJoinEvent, player_leave::PlayerLeaveEvent},
+        entity::entity_spawn::EntitySpawnEvent,
+        player::{
+            player_join::PlayerJoinEvent, player_leave::PlayerLeaveEvent,
+            player_respawn::PlayerRespawnEvent,
+        },
     },
     server::Server,
 };
@@ -596,19 +600,25 @@ impl World {
             entity.get_entity().age.fetch_add(1, Relaxed);
 
             entity.tick(entity.clone(), server).await;
-            for player in self.players.read().await.values() {
-                if player
-                    .living_entity
-                    .entity
-                    .bounding_box
-                    .load()
-                    // This is vanilla, but TODO: change this when is in a vehicle
-                    .expand(1.0, 0.5, 1.0)
-                    .intersects(&entity.get_entity().bounding_box.load())
-                {
-                    entity.on_player_collision(player).await;
-                    break;
-                }
+            // Don't hold the lock while colliding, picking up items fires plugin events
+            let colliding = self
+                .players
+                .read()
+                .await
+                .values()
+                .find(|player| {
+                    player
+                        .living_entity
+                        .entity
+                        .bounding_box
+                        .load()
+                        // This is vanilla, but TODO: change this when is in a vehicle
+                        .expand(1.0, 0.5, 1.0)
+                        .intersects(&entity.get_entity().bounding_box.load())
+                })
+                .cloned();
+            if let Some(player) = colliding {
+                entity.on_player_collision(&player).await;
             }
         }
 
@@ -667,6 +677,12 @@ impl World {
         if level_time.world_age % 300 == 0 {
             self.level.should_save.store(true, Relaxed);
             self.level.level_channel.notify();
+
+            let level = self.level.clone();
+            self.level.spawn_task(async move {
+                level.save_poi().await;
+                level.save_persistent_data();
+            });
         }
 
         let mut weather = self.weather.lock().await;
@@ -690,6 +706,7 @@ impl World {
     }
 
     pub async fn tick_chunks(self: &Arc<Self>) {
+        self.level.init_loaded_poi();
         let tick_data = self.level.get_tick_data().await;
         for scheduled_tick in tick_data.block_ticks {
             let block = self.get_block(&scheduled_tick.position).await;
@@ -712,6 +729,28 @@ impl World {
             }
         }
 
+        // Forced chunks are surrounded by fully loaded chunks, so their random ticks never have
+        // to wait on a chunk load
+        for scheduled_tick in tick_data.random_ticks.iter().filter(|scheduled_tick| {
+            self.level.is_chunk_forced(
+                &scheduled_tick
+                    .position
+                    .chunk_and_chunk_relative_position()
+                    .0,
+            )
+        }) {
+            let block = self.get_block(&scheduled_tick.position).await;
+            if let Some(pumpkin_block) = self.block_registry.get_pumpkin_block(block) {
+                pumpkin_block
+                    .random_tick(RandomTickArgs {
+                        world: self,
+                        block,
+                        position: &scheduled_tick.position,
+                    })
+                    .await;
+            }
+        }
+
         /* TODO: Fix this deadlock
         for scheduled_tick in tick_data.random_ticks {
             let block = self.get_block(&scheduled_tick.position).await;
@@ -1466,6 +1505,7 @@ impl World {
 
         log::debug!("Sending player teleport to {}", player.gameprofile.name);
         player.request_teleport(position, yaw, pitch).await;
+        player.movement.reset(player.position()).await;
 
         player.living_entity.entity.last_pos.store(position);
 
@@ -1776,7 +1816,9 @@ impl World {
 
     pub async fn explode(self: &Arc<Self>, position: Vector3<f64>, power: f32) {
         let explosion = Explosion::new(power, position);
-        let block_count = explosion.explode(self).await;
+        let Some(block_count) = explosion.explode(self).await else {
+            return;
+        };
         let particle = if power < 2.0 {
             Particle::Explosion
         } else {
@@ -1840,7 +1882,7 @@ impl World {
 
         player.hunger_manager.restart();
 
-        let info = &self.level_info.read().await;
+        let info = self.level_info.read().await;
         if !info.game_rules.keep_inventory {
             player.set_experience(0, 0.0, 0).await;
         }
@@ -1863,6 +1905,12 @@ impl World {
                 info.spawn_angle,
             )
         };
+        drop(info);
+
+        let event = PLUGIN_MANAGER
+            .fire(PlayerRespawnEvent::new(player.clone(), position, yaw))
+            .await;
+        let (position, yaw) = (event.position, event.yaw);
 
         log::debug!("Sending player teleport to {}", player.gameprofile.name);
         player.request_teleport(position, yaw, pitch).await;
@@ -2315,6 +2363,13 @@ impl World {
 
     /// Adds an entity to the world.
     pub async fn spawn_entity(&self, entity: Arc<dyn EntityBase>) {
+        let event = PLUGIN_MANAGER
+            .fire(EntitySpawnEvent::new(entity.clone()))
+            .await;
+        if event.cancelled {
+            return;
+        }
+
         let base_entity = entity.get_entity();
         self.broadcast_packet_all(&base_entity.create_spawn_packet())
             .await;
@@ -2567,6 +2622,10 @@ impl World {
             .insert(*position, block_state_id);
         drop(chunk);
 
+        self.level
+            .update_poi(*position, replaced_block_state_id, block_state_id)
+            .await;
+
         let old_block = Block::from_state_id(replaced_block_state_id);
         let new_block = Block::from_state_id(block_state_id);
 
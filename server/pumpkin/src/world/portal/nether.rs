@@ -6,8 +6,9 @@ use pumpkin_data::{
     tag,
     tag::Taggable,
 };
-use pumpkin_util::math::position::BlockPos;
-use pumpkin_world::world::BlockFlags;
+use pumpkin_registry::VanillaDimensionType;
+use pumpkin_util::math::{position::BlockPos, vector3::Vector3};
+use pumpkin_world::{BlockStateId, poi::PoiType, world::BlockFlags};
 
 use crate::world::World;
 
@@ -30,6 +31,15 @@ impl NetherPortal {
 
     const FRAME_BLOCK: Block = Block::OBSIDIAN;
 
+    /// How far around the destination vanilla looks for an existing portal
+    const OVERWORLD_SEARCH_RADIUS: i32 = 128;
+    const NETHER_SEARCH_RADIUS: i32 = 16;
+    /// How far from the destination a new portal may be built
+    const CREATE_RADIUS: i32 = 16;
+    /// Portals built on arrival have the smallest possible inside
+    const CREATED_WIDTH: i32 = 2;
+    const CREATED_HEIGHT: i32 = 3;
+
     #[must_use]
     pub fn is_valid(&self) -> bool {
         self.width >= Self::MIN_WIDTH
@@ -242,6 +252,213 @@ impl NetherPortal {
         true
     }
 
+    /// Where an entity travelling to `target` in `world` comes out: the closest nether portal in
+    /// search range, or a newly built one when there is none.
+    ///
+    /// `axis` is the axis of the portal the entity entered, new portals are built along it.
+    pub async fn find_or_create_destination(
+        world: &Arc<World>,
+        target: BlockPos,
+        axis: HorizontalAxis,
+    ) -> Vector3<f64> {
+        let pos = match Self::find_destination(world, target).await {
+            Some(pos) => pos,
+            None => Self::create_destination(world, target, axis).await,
+        };
+        pos.to_f64().add_raw(0.5, 0.0, 0.5)
+    }
+
+    /// Looks up the closest portal block around `target` in the point of interest storage,
+    /// returning the lowest portal block of its column
+    async fn find_destination(world: &Arc<World>, target: BlockPos) -> Option<BlockPos> {
+        let radius = if world.dimension_type == VanillaDimensionType::TheNether {
+            Self::NETHER_SEARCH_RADIUS
+        } else {
+            Self::OVERWORLD_SEARCH_RADIUS
+        };
+        let folder = &world.level.level_folder;
+        let mut portals = world
+            .level
+            .poi
+            .in_square(folder, target, radius, |poi_type| {
+                *poi_type == PoiType::NETHER_PORTAL
+            })
+            .await;
+        {
+            let border = world.worldborder.lock().await;
+            portals.retain(|record| border.contains(record.pos.0.x, record.pos.0.z));
+        }
+        portals.sort_by_key(|record| (record.pos.squared_distance(target), record.pos.0.y));
+
+        for record in portals {
+            if world.get_block(&record.pos).await != &Block::NETHER_PORTAL {
+                // The portal is gone without us noticing, e.g. the world was edited offline
+                world.level.poi.remove(folder, &record.pos).await;
+                continue;
+            }
+            let mut pos = record.pos;
+            while world.get_block(&pos.down()).await == &Block::NETHER_PORTAL {
+                pos = pos.down();
+            }
+            return Some(pos);
+        }
+        None
+    }
+
+    /// Builds a portal near `target`, preferring a spot on the ground with room for the frame,
+    /// returning the lower corner of its inside
+    async fn create_destination(
+        world: &Arc<World>,
+        target: BlockPos,
+        axis: HorizontalAxis,
+    ) -> BlockPos {
+        let direction = if axis == HorizontalAxis::X {
+            BlockDirection::East
+        } else {
+            BlockDirection::South
+        };
+        let min_y = world.min_y + 1;
+        let max_y = i32::from(world.generation_settings().shape.max_y()) - Self::CREATED_HEIGHT - 2;
+
+        let mut found = None;
+        let columns = BlockPos::iterate_outwards(
+            BlockPos::new(target.0.x, 0, target.0.z),
+            Self::CREATE_RADIUS,
+            0,
+            Self::CREATE_RADIUS,
+        );
+        for column in columns {
+            if !world
+                .worldborder
+                .lock()
+                .await
+                .contains(column.0.x, column.0.z)
+            {
+                continue;
+            }
+            for y in Self::ground_positions(world, column, min_y, max_y).await {
+                let pos = BlockPos::new(column.0.x, y, column.0.z);
+                if !Self::can_host_frame(world, pos, direction).await {
+                    continue;
+                }
+                let closer = found.is_none_or(|best: BlockPos| {
+                    (pos.0.y - target.0.y).abs() < (best.0.y - target.0.y).abs()
+                });
+                if closer {
+                    found = Some(pos);
+                }
+            }
+            if found.is_some() {
+                break;
+            }
+        }
+
+        let lower_corner = match found {
+            Some(pos) => pos,
+            None => {
+                // No room anywhere, so like vanilla we make some on a small obsidian platform
+                let pos = BlockPos::new(target.0.x, target.0.y.clamp(min_y, max_y), target.0.z);
+                let side = direction.rotate_clockwise();
+                for i in -1..=Self::CREATED_WIDTH {
+                    for k in -1..=1 {
+                        let column = pos
+                            .offset_dir(direction.to_offset(), i)
+                            .offset_dir(side.to_offset(), k);
+                        Self::place(world, column.down(), Block::OBSIDIAN.default_state.id).await;
+                        for j in 0..=Self::CREATED_HEIGHT {
+                            Self::place(
+                                world,
+                                column.offset_dir(BlockDirection::Up.to_offset(), j),
+                                Block::AIR.default_state.id,
+                            )
+                            .await;
+                        }
+                    }
+                }
+                pos
+            }
+        };
+
+        for i in -1..=Self::CREATED_WIDTH {
+            for j in -1..=Self::CREATED_HEIGHT {
+                let is_frame =
+                    i == -1 || i == Self::CREATED_WIDTH || j == -1 || j == Self::CREATED_HEIGHT;
+                if is_frame {
+                    let pos = lower_corner
+                        .offset_dir(direction.to_offset(), i)
+                        .offset_dir(BlockDirection::Up.to_offset(), j);
+                    Self::place(world, pos, Self::FRAME_BLOCK.default_state.id).await;
+                }
+            }
+        }
+        Self {
+            axis,
+            found_portal_blocks: 0,
+            negative_direction: direction,
+            lower_conor: lower_corner,
+            width: Self::CREATED_WIDTH as u32,
+            height: Self::CREATED_HEIGHT as u32,
+        }
+        .create(world)
+        .await;
+
+        lower_corner
+    }
+
+    /// The heights in `column` between `min_y` and `max_y` with air on top of a solid block, from
+    /// the top down. The column is read from its chunk in one go, as most positions fail this
+    /// check and looking each of them up through the world is slow
+    async fn ground_positions(world: &World, column: BlockPos, min_y: i32, max_y: i32) -> Vec<i32> {
+        let (chunk_pos, relative) = column.chunk_and_chunk_relative_position();
+        let chunk = world.level.get_chunk(chunk_pos).await;
+        let chunk = chunk.read().await;
+        let state_at = |y: i32| {
+            chunk
+                .section
+                .get_block_absolute_y(relative.x as usize, y, relative.z as usize)
+                .map_or(Block::VOID_AIR.default_state, BlockState::from_id)
+        };
+
+        let mut positions = Vec::new();
+        let mut above = state_at(max_y);
+        for y in (min_y - 1..max_y).rev() {
+            let state = state_at(y);
+            if above.is_air() && state.is_solid() {
+                positions.push(y + 1);
+            }
+            above = state;
+        }
+        positions
+    }
+
+    /// Whether a portal with its inside's lower corner at `pos` stands on the ground with nothing
+    /// in the way of its frame, `pos` itself is expected to be air on top of a solid block
+    async fn can_host_frame(world: &World, pos: BlockPos, direction: BlockDirection) -> bool {
+        for i in -1..=Self::CREATED_WIDTH {
+            let column = pos.offset_dir(direction.to_offset(), i);
+            if !world.get_block_state(&column.down()).await.is_solid() {
+                return false;
+            }
+            for j in 0..=Self::CREATED_HEIGHT {
+                let pos = column.offset_dir(BlockDirection::Up.to_offset(), j);
+                if !world.get_block_state(&pos).await.is_air() {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    async fn place(world: &Arc<World>, pos: BlockPos, state: BlockStateId) {
+        world
+            .set_block_state(
+                &pos,
+                state,
+                BlockFlags::NOTIFY_LISTENERS | BlockFlags::FORCE_STATE,
+            )
+            .await;
+    }
+
     /// What is allowed to be inside the Portal frame
     fn valid_state_inside_portal(block: &Block, state: &BlockState) -> bool {
         state.is_air()